@@ -0,0 +1,84 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use txasm::fee_calculator::FeeStrategy;
+use txasm::instruction::{AccountMeta, RawInstruction};
+use std::io::Cursor;
+use txasm::perf::{self, TransactionView};
+use txasm::simd;
+use txasm::{CompiledTransaction, PriorityFeeCalculator, TransactionBuilder};
+
+fn sample_transaction() -> CompiledTransaction {
+    let mut builder = TransactionBuilder::new()
+        .payer([1u8; 32])
+        .recent_blockhash([9u8; 32]);
+    for i in 0..8u8 {
+        let accounts = vec![
+            AccountMeta::new([1u8; 32], true, true),
+            AccountMeta::new([10 + i; 32], false, true),
+            AccountMeta::new([40 + i; 32], false, false),
+        ];
+        builder = builder.add_instruction(RawInstruction::new([2u8; 32], accounts, vec![i; 64]));
+    }
+    builder.build_unsigned().unwrap()
+}
+
+fn bench_deserialize(c: &mut Criterion) {
+    let bytes = sample_transaction().serialize().unwrap();
+    c.bench_function("deserialize", |b| {
+        b.iter(|| CompiledTransaction::deserialize(black_box(&bytes)).unwrap())
+    });
+    c.bench_function("view_parse", |b| {
+        b.iter(|| TransactionView::parse(black_box(&bytes)).unwrap())
+    });
+}
+
+fn bench_fees(c: &mut Criterion) {
+    let tx = sample_transaction();
+    let bytes = tx.serialize().unwrap();
+    let view = TransactionView::parse(&bytes).unwrap();
+    let calculator = PriorityFeeCalculator::new();
+    c.bench_function("estimate_fee", |b| {
+        b.iter(|| calculator.estimate_fee(black_box(&tx), FeeStrategy::Medium).unwrap())
+    });
+    c.bench_function("estimate_fee_view", |b| {
+        b.iter(|| perf::estimate_fee(black_box(&view), &calculator, FeeStrategy::Medium).unwrap())
+    });
+}
+
+/// Key regions of a block's worth of transactions (2,000 x 24 keys)
+fn block_key_regions() -> Vec<Vec<u8>> {
+    (0..2_000u32)
+        .map(|tx| (0..24u32).flat_map(|key| [(tx + key) as u8; 32]).collect())
+        .collect()
+}
+
+fn bench_block_decode(c: &mut Criterion) {
+    let regions = block_key_regions();
+    c.bench_function("block_keys_bulk", |b| {
+        b.iter(|| {
+            for region in &regions {
+                black_box(simd::decode_keys(&mut Cursor::new(&region[..]), 24).unwrap());
+            }
+        })
+    });
+    c.bench_function("block_keys_scalar", |b| {
+        b.iter(|| {
+            for region in &regions {
+                black_box(simd::scalar::decode_keys(&mut Cursor::new(&region[..]), 24).unwrap());
+            }
+        })
+    });
+
+    let keys: Vec<[u8; 32]> = (0..64u8).map(|i| [i; 32]).collect();
+    let signatures: Vec<[u8; 64]> = (0..64u8).map(|i| [i % 2; 64]).collect();
+    c.bench_function("find_key", |b| b.iter(|| simd::find_key(black_box(&keys), &[63u8; 32])));
+    c.bench_function("find_key_scalar", |b| {
+        b.iter(|| simd::scalar::find_key(black_box(&keys), &[63u8; 32]))
+    });
+    c.bench_function("zero_slots", |b| b.iter(|| simd::zero_slots(black_box(&signatures))));
+    c.bench_function("zero_slots_scalar", |b| {
+        b.iter(|| simd::scalar::zero_slots(black_box(&signatures)))
+    });
+}
+
+criterion_group!(benches, bench_deserialize, bench_fees, bench_block_decode);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use txasm::instruction::{AccountMeta, RawInstruction};
+use txasm::TransactionBuilder;
+
+fn sample_builder() -> TransactionBuilder {
+    let mut builder = TransactionBuilder::new()
+        .payer([1u8; 32])
+        .recent_blockhash([9u8; 32]);
+    for i in 0..8u8 {
+        let accounts = vec![
+            AccountMeta::new([1u8; 32], true, true),
+            AccountMeta::new([10 + i; 32], false, true),
+            AccountMeta::new([40 + i; 32], false, false),
+        ];
+        builder = builder.add_instruction(RawInstruction::new([2u8; 32], accounts, vec![i; 64]));
+    }
+    builder
+}
+
+fn bench_compile(c: &mut Criterion) {
+    let builder = sample_builder();
+    c.bench_function("compile", |b| {
+        b.iter(|| black_box(builder.clone()).compile().unwrap())
+    });
+
+    let tx = builder.clone().build_unsigned().unwrap();
+    c.bench_function("serialize", |b| b.iter(|| black_box(&tx).serialize().unwrap()));
+
+    let mut scratch = Vec::new();
+    c.bench_function("serialize_into", |b| {
+        b.iter(|| black_box(&tx).serialize_into(&mut scratch).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_compile);
+criterion_main!(benches);
@@ -109,7 +109,7 @@ fn example_fee_calculation() -> Result<(), Box<dyn std::error::Error>> {
         ("Medium", txasm::fee_calculator::FeeStrategy::Medium),
         ("High", txasm::fee_calculator::FeeStrategy::High),
     ] {
-        let estimate = calculator.estimate_fee(&transaction, strategy);
+        let estimate = calculator.estimate_fee(&transaction, strategy)?;
         println!("  {}: {} lamports", name, estimate.total_cost);
         println!("    Base fee: {}", estimate.base_fee);
         println!("    Priority fee: {} microlamports/CU", estimate.priority_fee_per_cu);
@@ -152,7 +152,11 @@ fn example_optimization() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("Transaction Analysis Report:");
     println!("  - Initial Size: {} bytes", analysis.total_size);
-    println!("  - Efficiency Score: {}/100", optimizer.calculate_efficiency_score(&transaction));
+    let efficiency = optimizer.efficiency_report(&transaction);
+    println!("  - Efficiency Score: {}/100", efficiency.score);
+    for deduction in &efficiency.deductions {
+        println!("    -{} {}", deduction.points(), deduction.suggestion());
+    }
 
     if !analysis.suggestions.is_empty() {
         println!("  Optimization Suggestions:");
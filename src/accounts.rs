@@ -0,0 +1,347 @@
+//! Account state decoders
+//!
+//! Parses the bincode layouts of stake and vote accounts straight from raw
+//! account bytes. Only the fields dashboards read are kept; everything else
+//! is skipped over, and lengths read from the data are checked against the
+//! bytes remaining before anything is allocated.
+
+use crate::error::{Result, TxAsmError};
+use crate::serialization::{decode_pubkey, decode_u64, decode_u8};
+use std::io::Cursor;
+
+/// Stake program address
+pub const STAKE_PROGRAM_ID: [u8; 32] = solana_sdk::stake::program::ID.to_bytes();
+
+/// Vote program address
+pub const VOTE_PROGRAM_ID: [u8; 32] = solana_sdk::vote::program::ID.to_bytes();
+
+/// Entries in a vote account's prior-voters ring buffer
+const MAX_PRIOR_VOTERS: usize = 32;
+
+/// Stake account lockup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lockup {
+    pub unix_timestamp: i64,
+    pub epoch: u64,
+    pub custodian: [u8; 32],
+}
+
+/// Authorities and reserve shared by initialized and delegated stake accounts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StakeMeta {
+    pub rent_exempt_reserve: u64,
+    pub staker: [u8; 32],
+    pub withdrawer: [u8; 32],
+    pub lockup: Lockup,
+}
+
+/// Delegation of a stake account to a vote account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StakeDelegation {
+    pub voter: [u8; 32],
+    /// Delegated lamports
+    pub stake: u64,
+    pub activation_epoch: u64,
+    /// `u64::MAX` while not deactivating
+    pub deactivation_epoch: u64,
+    pub credits_observed: u64,
+}
+
+/// Decoded stake account state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StakeState {
+    Uninitialized,
+    Initialized(StakeMeta),
+    Stake {
+        meta: StakeMeta,
+        delegation: StakeDelegation,
+        flags: u8,
+    },
+    RewardsPool,
+}
+
+impl StakeState {
+    /// Decode stake account data
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(data);
+        match decode_u32(&mut cursor)? {
+            0 => Ok(StakeState::Uninitialized),
+            1 => Ok(StakeState::Initialized(decode_meta(&mut cursor)?)),
+            2 => {
+                let meta = decode_meta(&mut cursor)?;
+                let voter = decode_pubkey(&mut cursor)?;
+                let stake = decode_u64(&mut cursor)?;
+                let activation_epoch = decode_u64(&mut cursor)?;
+                let deactivation_epoch = decode_u64(&mut cursor)?;
+                // Deprecated warmup/cooldown rate (f64)
+                decode_u64(&mut cursor)?;
+                let credits_observed = decode_u64(&mut cursor)?;
+                // Accounts written before stake flags existed end here
+                let flags = decode_u8(&mut cursor).unwrap_or(0);
+                Ok(StakeState::Stake {
+                    meta,
+                    delegation: StakeDelegation {
+                        voter,
+                        stake,
+                        activation_epoch,
+                        deactivation_epoch,
+                        credits_observed,
+                    },
+                    flags,
+                })
+            }
+            3 => Ok(StakeState::RewardsPool),
+            tag => Err(TxAsmError::DecodingError(format!("Unknown stake state {}", tag))),
+        }
+    }
+
+    pub fn meta(&self) -> Option<&StakeMeta> {
+        match self {
+            StakeState::Initialized(meta) | StakeState::Stake { meta, .. } => Some(meta),
+            _ => None,
+        }
+    }
+
+    pub fn delegation(&self) -> Option<&StakeDelegation> {
+        match self {
+            StakeState::Stake { delegation, .. } => Some(delegation),
+            _ => None,
+        }
+    }
+}
+
+/// One tower vote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoteLockout {
+    pub slot: u64,
+    pub confirmation_count: u32,
+    /// Slots between the voted slot and the vote landing (0 for old layouts)
+    pub latency: u8,
+}
+
+/// Credits earned by a vote account in one epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochCredits {
+    pub epoch: u64,
+    pub credits: u64,
+    pub prev_credits: u64,
+}
+
+/// Decoded vote account state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteState {
+    pub node_pubkey: [u8; 32],
+    pub authorized_withdrawer: [u8; 32],
+    pub commission: u8,
+    pub votes: Vec<VoteLockout>,
+    pub root_slot: Option<u64>,
+    /// (epoch, voter) pairs, ascending by epoch
+    pub authorized_voters: Vec<(u64, [u8; 32])>,
+    pub epoch_credits: Vec<EpochCredits>,
+    /// (slot, unix timestamp) of the last timestamped vote
+    pub last_timestamp: (u64, i64),
+}
+
+impl VoteState {
+    /// Decode vote account data (1.14.11 or current layout)
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(data);
+        let with_latency = match decode_u32(&mut cursor)? {
+            0 => {
+                return Err(TxAsmError::DecodingError(
+                    "Vote state 0.23.5 layout is not supported".to_string(),
+                ))
+            }
+            1 => false,
+            2 => true,
+            tag => return Err(TxAsmError::DecodingError(format!("Unknown vote state version {}", tag))),
+        };
+
+        let node_pubkey = decode_pubkey(&mut cursor)?;
+        let authorized_withdrawer = decode_pubkey(&mut cursor)?;
+        let commission = decode_u8(&mut cursor)?;
+
+        let vote_size = if with_latency { 13 } else { 12 };
+        let num_votes = decode_len(&mut cursor, vote_size)?;
+        let mut votes = Vec::with_capacity(num_votes);
+        for _ in 0..num_votes {
+            let latency = if with_latency { decode_u8(&mut cursor)? } else { 0 };
+            votes.push(VoteLockout {
+                slot: decode_u64(&mut cursor)?,
+                confirmation_count: decode_u32(&mut cursor)?,
+                latency,
+            });
+        }
+
+        let root_slot = match decode_u8(&mut cursor)? {
+            0 => None,
+            _ => Some(decode_u64(&mut cursor)?),
+        };
+
+        let num_voters = decode_len(&mut cursor, 40)?;
+        let mut authorized_voters = Vec::with_capacity(num_voters);
+        for _ in 0..num_voters {
+            authorized_voters.push((decode_u64(&mut cursor)?, decode_pubkey(&mut cursor)?));
+        }
+
+        // Prior voters ring buffer: (pubkey, start epoch, end epoch) entries, index, is_empty
+        skip(&mut cursor, MAX_PRIOR_VOTERS * 48 + 8 + 1)?;
+
+        let num_credits = decode_len(&mut cursor, 24)?;
+        let mut epoch_credits = Vec::with_capacity(num_credits);
+        for _ in 0..num_credits {
+            epoch_credits.push(EpochCredits {
+                epoch: decode_u64(&mut cursor)?,
+                credits: decode_u64(&mut cursor)?,
+                prev_credits: decode_u64(&mut cursor)?,
+            });
+        }
+
+        let last_timestamp = (decode_u64(&mut cursor)?, decode_u64(&mut cursor)? as i64);
+
+        Ok(VoteState {
+            node_pubkey,
+            authorized_withdrawer,
+            commission,
+            votes,
+            root_slot,
+            authorized_voters,
+            epoch_credits,
+            last_timestamp,
+        })
+    }
+
+    /// Voter authorized for `epoch`
+    pub fn authorized_voter(&self, epoch: u64) -> Option<[u8; 32]> {
+        self.authorized_voters
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= epoch)
+            .map(|(_, voter)| *voter)
+    }
+
+    /// Total credits earned, as of the latest epoch
+    pub fn credits(&self) -> u64 {
+        self.epoch_credits.last().map_or(0, |c| c.credits)
+    }
+}
+
+fn decode_meta(cursor: &mut Cursor<&[u8]>) -> Result<StakeMeta> {
+    Ok(StakeMeta {
+        rent_exempt_reserve: decode_u64(cursor)?,
+        staker: decode_pubkey(cursor)?,
+        withdrawer: decode_pubkey(cursor)?,
+        lockup: Lockup {
+            unix_timestamp: decode_u64(cursor)? as i64,
+            epoch: decode_u64(cursor)?,
+            custodian: decode_pubkey(cursor)?,
+        },
+    })
+}
+
+fn decode_u32(cursor: &mut Cursor<&[u8]>) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    for byte in &mut bytes {
+        *byte = decode_u8(cursor)?;
+    }
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// bincode u64 collection length, checked against the bytes left
+fn decode_len(cursor: &mut Cursor<&[u8]>, element_size: usize) -> Result<usize> {
+    let len = decode_u64(cursor)?;
+    let remaining = cursor.get_ref().len() - cursor.position() as usize;
+    match usize::try_from(len).ok().and_then(|n| n.checked_mul(element_size)) {
+        Some(needed) if needed <= remaining => Ok(len as usize),
+        _ => Err(TxAsmError::DecodingError(format!(
+            "Length {} exceeds remaining {} bytes",
+            len, remaining
+        ))),
+    }
+}
+
+fn skip(cursor: &mut Cursor<&[u8]>, len: usize) -> Result<()> {
+    let position = cursor.position() as usize;
+    let available = cursor.get_ref().len();
+    if position + len > available {
+        return Err(TxAsmError::BufferTooSmall {
+            needed: position + len,
+            available,
+        });
+    }
+    cursor.set_position((position + len) as u64);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::stake::stake_flags::StakeFlags;
+    use solana_sdk::stake::state::{Authorized, Delegation, Meta, Stake, StakeStateV2};
+    use solana_sdk::vote::state::{VoteInit, VoteState as SdkVoteState, VoteStateVersions};
+
+    #[test]
+    fn test_decode_stake_state() {
+        let meta = Meta {
+            rent_exempt_reserve: 2_282_880,
+            authorized: Authorized {
+                staker: Pubkey::new_from_array([1u8; 32]),
+                withdrawer: Pubkey::new_from_array([2u8; 32]),
+            },
+            ..Meta::default()
+        };
+        let stake = Stake {
+            delegation: Delegation::new(&Pubkey::new_from_array([3u8; 32]), 5_000_000_000, 400),
+            credits_observed: 77,
+        };
+        let mut data = bincode::serialize(&StakeStateV2::Stake(meta, stake, StakeFlags::empty())).unwrap();
+        data.resize(StakeStateV2::size_of(), 0);
+
+        let state = StakeState::decode(&data).unwrap();
+        assert_eq!(state.meta().unwrap().withdrawer, [2u8; 32]);
+        let delegation = state.delegation().unwrap();
+        assert_eq!(delegation.voter, [3u8; 32]);
+        assert_eq!(delegation.stake, 5_000_000_000);
+        assert_eq!(delegation.activation_epoch, 400);
+        assert_eq!(delegation.deactivation_epoch, u64::MAX);
+        assert_eq!(delegation.credits_observed, 77);
+
+        let initialized = bincode::serialize(&StakeStateV2::Initialized(meta)).unwrap();
+        assert!(matches!(StakeState::decode(&initialized).unwrap(), StakeState::Initialized(_)));
+        assert!(StakeState::decode(&[9, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_decode_vote_state() {
+        let node = Pubkey::new_from_array([1u8; 32]);
+        let voter = Pubkey::new_from_array([2u8; 32]);
+        let init = VoteInit {
+            node_pubkey: node,
+            authorized_voter: voter,
+            authorized_withdrawer: Pubkey::new_from_array([3u8; 32]),
+            commission: 7,
+        };
+        let clock = solana_sdk::clock::Clock { epoch: 5, ..Default::default() };
+        let mut sdk_state = SdkVoteState::new(&init, &clock);
+        for slot in 1..=3 {
+            sdk_state.process_next_vote_slot(slot, 5, slot, true, true);
+        }
+        sdk_state.increment_credits(5, 10);
+
+        let mut data = vec![0u8; SdkVoteState::size_of()];
+        SdkVoteState::serialize(&VoteStateVersions::new_current(sdk_state), &mut data).unwrap();
+
+        let state = VoteState::decode(&data).unwrap();
+        assert_eq!(state.node_pubkey, [1u8; 32]);
+        assert_eq!(state.commission, 7);
+        assert_eq!(state.votes.len(), 3);
+        assert_eq!(state.votes[2].slot, 3);
+        assert_eq!(state.authorized_voter(6), Some([2u8; 32]));
+        assert_eq!(state.credits(), state.epoch_credits.last().unwrap().credits);
+
+        assert!(VoteState::decode(&data[..80]).is_err());
+        data[0] = 0;
+        assert!(VoteState::decode(&data).is_err());
+    }
+}
@@ -0,0 +1,385 @@
+//! Transaction archive container
+//!
+//! A simple stream format for recording serialized transactions together
+//! with their metadata, so production traffic can be captured once and
+//! replayed in tests.
+//!
+//! Layout: the 4-byte magic `TXAR`, a format version byte and a flags byte,
+//! followed by records. With the `zstd` feature and `FLAG_ZSTD` set,
+//! everything after the 6-byte preamble is one zstd stream. Each record is
+//! a little-endian u32 length followed by:
+//!
+//! | Field        | Encoding                                    |
+//! |--------------|---------------------------------------------|
+//! | presence     | u8 bitmask (slot, fee paid, label)          |
+//! | slot         | u64 LE, if present                          |
+//! | fee paid     | u64 LE lamports, if present                 |
+//! | label        | compact-u16 length + UTF-8, if present      |
+//! | transaction  | remaining bytes                             |
+
+use crate::error::{Result, TxAsmError};
+use crate::serialization::{decode_compact_u16, decode_u64, encode_compact_u16};
+use crate::transaction::CompiledTransaction;
+use std::io::{Cursor, Read, Write};
+
+/// Magic bytes opening every archive
+pub const ARCHIVE_MAGIC: [u8; 4] = *b"TXAR";
+
+/// Current archive format version
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Flag: records are zstd-compressed
+pub const FLAG_ZSTD: u8 = 0x01;
+
+/// Largest record a reader will accept
+pub const MAX_RECORD_LEN: usize = 1 << 20;
+
+const HAS_SLOT: u8 = 0x01;
+const HAS_FEE_PAID: u8 = 0x02;
+const HAS_LABEL: u8 = 0x04;
+
+/// One archived transaction with its metadata
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ArchiveRecord {
+    pub slot: Option<u64>,
+    pub label: Option<String>,
+    /// Fee actually paid, in lamports
+    pub fee_paid: Option<u64>,
+    /// Serialized transaction bytes
+    pub transaction: Vec<u8>,
+}
+
+impl ArchiveRecord {
+    pub fn new(transaction: Vec<u8>) -> Self {
+        Self {
+            transaction,
+            ..Default::default()
+        }
+    }
+
+    /// Record a compiled transaction
+    pub fn from_transaction(transaction: &CompiledTransaction) -> Result<Self> {
+        Ok(Self::new(transaction.serialize()?))
+    }
+
+    pub fn slot(mut self, slot: u64) -> Self {
+        self.slot = Some(slot);
+        self
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn fee_paid(mut self, lamports: u64) -> Self {
+        self.fee_paid = Some(lamports);
+        self
+    }
+
+    /// Decode the archived bytes as a legacy transaction
+    pub fn decode_transaction(&self) -> Result<CompiledTransaction> {
+        CompiledTransaction::deserialize(&self.transaction)
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) -> Result<()> {
+        let mut presence = 0;
+        if self.slot.is_some() {
+            presence |= HAS_SLOT;
+        }
+        if self.fee_paid.is_some() {
+            presence |= HAS_FEE_PAID;
+        }
+        if self.label.is_some() {
+            presence |= HAS_LABEL;
+        }
+        out.push(presence);
+        if let Some(slot) = self.slot {
+            out.extend_from_slice(&slot.to_le_bytes());
+        }
+        if let Some(fee) = self.fee_paid {
+            out.extend_from_slice(&fee.to_le_bytes());
+        }
+        if let Some(label) = &self.label {
+            let len = u16::try_from(label.len()).map_err(|_| {
+                TxAsmError::SerializationError(format!("Label of {} bytes is too long", label.len()))
+            })?;
+            encode_compact_u16(len, out)?;
+            out.extend_from_slice(label.as_bytes());
+        }
+        out.extend_from_slice(&self.transaction);
+        Ok(())
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        let mut presence = [0u8; 1];
+        cursor.read_exact(&mut presence)?;
+        let presence = presence[0];
+
+        let slot = (presence & HAS_SLOT != 0)
+            .then(|| decode_u64(&mut cursor))
+            .transpose()?;
+        let fee_paid = (presence & HAS_FEE_PAID != 0)
+            .then(|| decode_u64(&mut cursor))
+            .transpose()?;
+        let label = if presence & HAS_LABEL != 0 {
+            let len = decode_compact_u16(&mut cursor)? as usize;
+            let mut label = vec![0u8; len];
+            cursor.read_exact(&mut label)?;
+            Some(String::from_utf8(label).map_err(|e| TxAsmError::DecodingError(e.to_string()))?)
+        } else {
+            None
+        };
+
+        let transaction = bytes[cursor.position() as usize..].to_vec();
+        Ok(Self {
+            slot,
+            label,
+            fee_paid,
+            transaction,
+        })
+    }
+}
+
+enum Sink<W: Write> {
+    Plain(W),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Sink<W> {
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Sink::Plain(w) => w,
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(w) => w,
+        }
+    }
+}
+
+/// Streaming archive writer
+pub struct ArchiveWriter<W: Write> {
+    sink: Sink<W>,
+    scratch: Vec<u8>,
+    records: usize,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    /// Start an uncompressed archive
+    pub fn new(mut inner: W) -> Result<Self> {
+        write_preamble(&mut inner, 0)?;
+        Ok(Self::with_sink(Sink::Plain(inner)))
+    }
+
+    /// Start a zstd-compressed archive at the given compression level
+    #[cfg(feature = "zstd")]
+    pub fn compressed(mut inner: W, level: i32) -> Result<Self> {
+        write_preamble(&mut inner, FLAG_ZSTD)?;
+        Ok(Self::with_sink(Sink::Zstd(zstd::Encoder::new(inner, level)?)))
+    }
+
+    fn with_sink(sink: Sink<W>) -> Self {
+        Self {
+            sink,
+            scratch: Vec::new(),
+            records: 0,
+        }
+    }
+
+    /// Append a record
+    pub fn write(&mut self, record: &ArchiveRecord) -> Result<()> {
+        self.scratch.clear();
+        record.encode(&mut self.scratch)?;
+        if self.scratch.len() > MAX_RECORD_LEN {
+            return Err(TxAsmError::SerializationError(format!(
+                "Record of {} bytes exceeds {} byte limit",
+                self.scratch.len(),
+                MAX_RECORD_LEN
+            )));
+        }
+
+        let len = (self.scratch.len() as u32).to_le_bytes();
+        let writer = self.sink.writer();
+        writer.write_all(&len)?;
+        writer.write_all(&self.scratch)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Number of records written so far
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Flush any buffered output and return the underlying writer
+    pub fn finish(self) -> Result<W> {
+        match self.sink {
+            Sink::Plain(mut w) => {
+                w.flush()?;
+                Ok(w)
+            }
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(w) => Ok(w.finish()?),
+        }
+    }
+}
+
+fn write_preamble<W: Write>(writer: &mut W, flags: u8) -> Result<()> {
+    writer.write_all(&ARCHIVE_MAGIC)?;
+    writer.write_all(&[ARCHIVE_VERSION, flags])?;
+    Ok(())
+}
+
+enum Source<R: Read> {
+    Plain(R),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Decoder<'static, std::io::BufReader<R>>),
+}
+
+/// Streaming archive reader, yielding records in write order
+pub struct ArchiveReader<R: Read> {
+    source: Source<R>,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    /// Open an archive, validating its preamble
+    pub fn new(mut inner: R) -> Result<Self> {
+        let mut preamble = [0u8; 6];
+        inner.read_exact(&mut preamble)?;
+        if preamble[..4] != ARCHIVE_MAGIC {
+            return Err(TxAsmError::DeserializationError(
+                "Not a transaction archive".to_string(),
+            ));
+        }
+        if preamble[4] != ARCHIVE_VERSION {
+            return Err(TxAsmError::DeserializationError(format!(
+                "Unsupported archive version {}",
+                preamble[4]
+            )));
+        }
+
+        let source = if preamble[5] & FLAG_ZSTD != 0 {
+            #[cfg(feature = "zstd")]
+            {
+                Source::Zstd(zstd::Decoder::new(inner)?)
+            }
+            #[cfg(not(feature = "zstd"))]
+            {
+                return Err(TxAsmError::DeserializationError(
+                    "Archive is zstd-compressed; enable the `zstd` feature".to_string(),
+                ));
+            }
+        } else {
+            Source::Plain(inner)
+        };
+
+        Ok(Self { source })
+    }
+
+    /// Whether the archive body is zstd-compressed
+    pub fn is_compressed(&self) -> bool {
+        !matches!(self.source, Source::Plain(_))
+    }
+
+    fn reader(&mut self) -> &mut dyn Read {
+        match &mut self.source {
+            Source::Plain(r) => r,
+            #[cfg(feature = "zstd")]
+            Source::Zstd(r) => r,
+        }
+    }
+
+    /// Read the next record, or `None` at a clean end of stream
+    pub fn next_record(&mut self) -> Result<Option<ArchiveRecord>> {
+        let mut len = [0u8; 4];
+        let mut filled = 0;
+        while filled < len.len() {
+            let n = self.reader().read(&mut len[filled..])?;
+            if n == 0 {
+                if filled == 0 {
+                    return Ok(None);
+                }
+                return Err(TxAsmError::DeserializationError(
+                    "Truncated record length".to_string(),
+                ));
+            }
+            filled += n;
+        }
+
+        let len = u32::from_le_bytes(len) as usize;
+        if len > MAX_RECORD_LEN {
+            return Err(TxAsmError::DeserializationError(format!(
+                "Record of {} bytes exceeds {} byte limit",
+                len, MAX_RECORD_LEN
+            )));
+        }
+        let mut body = vec![0u8; len];
+        self.reader().read_exact(&mut body)?;
+        ArchiveRecord::decode(&body).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for ArchiveReader<R> {
+    type Item = Result<ArchiveRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_records() -> Vec<ArchiveRecord> {
+        vec![
+            ArchiveRecord::new(vec![1, 2, 3]).slot(42).label("swap").fee_paid(5000),
+            ArchiveRecord::new(vec![4; 300]),
+            ArchiveRecord::new(vec![]).label("empty"),
+        ]
+    }
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        for record in sample_records() {
+            writer.write(&record).unwrap();
+        }
+        assert_eq!(writer.records(), 3);
+        let bytes = writer.finish().unwrap();
+        assert_eq!(bytes[..4], ARCHIVE_MAGIC);
+
+        let reader = ArchiveReader::new(Cursor::new(bytes)).unwrap();
+        assert!(!reader.is_compressed());
+        let records: Vec<_> = reader.collect::<Result<_>>().unwrap();
+        assert_eq!(records, sample_records());
+    }
+
+    #[test]
+    fn test_archive_rejects_truncation() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        writer.write(&sample_records()[1]).unwrap();
+        let mut bytes = writer.finish().unwrap();
+        bytes.truncate(bytes.len() - 1);
+
+        let mut reader = ArchiveReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.next_record().is_err());
+        assert!(ArchiveReader::new(Cursor::new(b"NOPE\x01\x00".to_vec())).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_archive_zstd_roundtrip() {
+        let mut writer = ArchiveWriter::compressed(Vec::new(), 3).unwrap();
+        for record in sample_records() {
+            writer.write(&record).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let reader = ArchiveReader::new(Cursor::new(bytes)).unwrap();
+        assert!(reader.is_compressed());
+        let records: Vec<_> = reader.collect::<Result<_>>().unwrap();
+        assert_eq!(records, sample_records());
+    }
+}
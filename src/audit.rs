@@ -0,0 +1,292 @@
+//! Append-only audit log of build, sign and submit events
+//!
+//! Each [`AuditRecord`] captures who acted, the exact serialized
+//! transaction (base64), its message hash, which signers had signed, the
+//! fee and any policy results. [`AuditLog`] numbers the records, stamps
+//! them with the Unix time the caller passes (as with the
+//! [`FeeBudget`](crate::fee_budget::FeeBudget)), and chains each to the
+//! previous record's hash before handing it to an [`AuditSink`]. Deleting,
+//! reordering or editing a record breaks the chain, which
+//! [`verify_chain`] detects.
+//!
+//! [`JsonLines`] writes one JSON record per line to any `Write`, such as an
+//! append-mode file; closures and `Vec<AuditRecord>` are sinks too.
+
+use crate::error::{Result, TxAsmError};
+use crate::policy::Policy;
+use crate::serialization::encode_hex;
+use crate::transaction::CompiledTransaction;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufRead, Write};
+
+/// What happened to the transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    Build,
+    Sign,
+    Submit,
+}
+
+/// One entry of the log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Position in the log, from 0
+    pub sequence: u64,
+    /// Unix time in seconds
+    pub timestamp: u64,
+    pub event: AuditEvent,
+    /// Operator or service that acted
+    pub actor: Option<String>,
+    /// Hex SHA-256 of the message bytes
+    pub message_hash: String,
+    /// Base58 keys whose signature slots are filled
+    pub signers: Vec<String>,
+    pub fee_lamports: Option<u64>,
+    /// Policy violations; `None` when no policy was evaluated, empty when
+    /// all passed
+    pub policy_violations: Option<Vec<String>>,
+    /// Base64 serialized transaction
+    pub transaction: String,
+    /// Free text, e.g. the submission result
+    pub detail: Option<String>,
+    /// Hex SHA-256 of the previous record; zeros for the first
+    pub prev_hash: String,
+}
+
+impl AuditRecord {
+    /// Record `event` for `transaction`; the log fills in sequence,
+    /// timestamp and chain hash
+    pub fn new(event: AuditEvent, transaction: &CompiledTransaction) -> Result<Self> {
+        let signers = transaction
+            .required_signers()
+            .iter()
+            .zip(&transaction.signatures)
+            .filter(|(_, signature)| **signature != [0u8; 64])
+            .map(|(key, _)| bs58::encode(key).into_string())
+            .collect();
+        Ok(Self {
+            sequence: 0,
+            timestamp: 0,
+            event,
+            actor: None,
+            message_hash: encode_hex(&Sha256::digest(transaction.message_bytes()?)),
+            signers,
+            fee_lamports: None,
+            policy_violations: None,
+            transaction: BASE64.encode(transaction.serialize()?),
+            detail: None,
+            prev_hash: encode_hex(&[0u8; 32]),
+        })
+    }
+
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    pub fn fee(mut self, lamports: u64) -> Self {
+        self.fee_lamports = Some(lamports);
+        self
+    }
+
+    /// Evaluate `policy` against the recorded transaction and keep the result
+    pub fn policy(mut self, policy: &dyn Policy) -> Result<Self> {
+        let violations = policy.evaluate(&self.decode_transaction()?.message);
+        self.policy_violations
+            .get_or_insert_with(Vec::new)
+            .extend(violations.iter().map(|v| v.to_string()));
+        Ok(self)
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// The exact transaction bytes recorded
+    pub fn decode_transaction(&self) -> Result<CompiledTransaction> {
+        let bytes = BASE64
+            .decode(&self.transaction)
+            .map_err(|e| TxAsmError::DecodingError(e.to_string()))?;
+        CompiledTransaction::deserialize(&bytes)
+    }
+
+    /// Hash the next record chains to
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let json = serde_json::to_vec(self).map_err(|e| TxAsmError::SerializationError(e.to_string()))?;
+        Ok(Sha256::digest(json).into())
+    }
+}
+
+/// Where records are written
+pub trait AuditSink {
+    fn append(&mut self, record: &AuditRecord) -> Result<()>;
+}
+
+impl<F> AuditSink for F
+where
+    F: FnMut(&AuditRecord) -> Result<()>,
+{
+    fn append(&mut self, record: &AuditRecord) -> Result<()> {
+        self(record)
+    }
+}
+
+impl AuditSink for Vec<AuditRecord> {
+    fn append(&mut self, record: &AuditRecord) -> Result<()> {
+        self.push(record.clone());
+        Ok(())
+    }
+}
+
+/// One JSON record per line
+pub struct JsonLines<W: Write>(pub W);
+
+impl<W: Write> AuditSink for JsonLines<W> {
+    fn append(&mut self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(record).map_err(|e| TxAsmError::SerializationError(e.to_string()))?;
+        line.push(b'\n');
+        self.0
+            .write_all(&line)
+            .and_then(|_| self.0.flush())
+            .map_err(|e| TxAsmError::SerializationError(e.to_string()))
+    }
+}
+
+/// Read records written by [`JsonLines`]
+pub fn read_json_lines(reader: impl BufRead) -> Result<Vec<AuditRecord>> {
+    reader
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|line| {
+            let line = line.map_err(|e| TxAsmError::DeserializationError(e.to_string()))?;
+            serde_json::from_str(&line).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+        })
+        .collect()
+}
+
+/// Sequencing and hash-chaining front end to a sink
+pub struct AuditLog<S: AuditSink> {
+    sink: S,
+    next_sequence: u64,
+    prev_hash: [u8; 32],
+}
+
+impl<S: AuditSink> AuditLog<S> {
+    /// Start a new log
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            next_sequence: 0,
+            prev_hash: [0u8; 32],
+        }
+    }
+
+    /// Continue a log whose last record is `last`, e.g. after a restart
+    pub fn resume(sink: S, last: &AuditRecord) -> Result<Self> {
+        Ok(Self {
+            sink,
+            next_sequence: last.sequence + 1,
+            prev_hash: last.hash()?,
+        })
+    }
+
+    /// Stamp, chain and write `record`, returning it as written
+    pub fn append(&mut self, mut record: AuditRecord, now: u64) -> Result<AuditRecord> {
+        record.sequence = self.next_sequence;
+        record.timestamp = now;
+        record.prev_hash = encode_hex(&self.prev_hash);
+        let hash = record.hash()?;
+        self.sink.append(&record)?;
+        self.next_sequence += 1;
+        self.prev_hash = hash;
+        Ok(record)
+    }
+
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+}
+
+/// Check that `records` are consecutive and each links to its predecessor
+///
+/// The first record may start mid-log; only links within the slice are
+/// checked.
+pub fn verify_chain(records: &[AuditRecord]) -> Result<()> {
+    for pair in records.windows(2) {
+        let (prev, record) = (&pair[0], &pair[1]);
+        if record.sequence != prev.sequence + 1 {
+            return Err(TxAsmError::InvalidTransaction(format!(
+                "Audit record {} follows record {}",
+                record.sequence, prev.sequence
+            )));
+        }
+        if record.prev_hash != encode_hex(&prev.hash()?) {
+            return Err(TxAsmError::InvalidTransaction(format!(
+                "Audit record {} does not chain to record {}",
+                record.sequence, prev.sequence
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::AccessList;
+    use crate::testkit;
+    use solana_sdk::signature::Signer;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_log_records_lifecycle() {
+        let tx = testkit::simple_transfer_tx(1);
+        let mut unsigned = tx.clone();
+        unsigned.signatures = vec![[0u8; 64]];
+
+        let mut log = AuditLog::new(JsonLines(Vec::new()));
+        let policy = AccessList::new().block_account(testkit::pubkey(2));
+        let built = AuditRecord::new(AuditEvent::Build, &unsigned).unwrap().actor("ops").fee(5_000);
+        log.append(built.policy(&policy).unwrap(), 100).unwrap();
+        log.append(AuditRecord::new(AuditEvent::Sign, &tx).unwrap().actor("hsm-1"), 101).unwrap();
+        log.append(AuditRecord::new(AuditEvent::Submit, &tx).unwrap().detail("confirmed"), 102).unwrap();
+
+        let records = read_json_lines(Cursor::new(log.into_sink().0)).unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(records[0].signers.is_empty());
+        assert_eq!(records[0].policy_violations.as_ref().unwrap().len(), 1);
+        assert_eq!(records[1].signers, vec![bs58::encode(testkit::keypair(1).pubkey().to_bytes()).into_string()]);
+        assert_eq!(records[2].timestamp, 102);
+        assert_eq!(records[1].message_hash, records[2].message_hash);
+        assert_eq!(records[2].decode_transaction().unwrap(), tx);
+        verify_chain(&records).unwrap();
+    }
+
+    #[test]
+    fn test_chain_detects_tampering() {
+        let tx = testkit::simple_transfer_tx(1);
+        let mut log = AuditLog::new(Vec::new());
+        for now in 0..3 {
+            log.append(AuditRecord::new(AuditEvent::Build, &tx).unwrap(), now).unwrap();
+        }
+        let mut records = log.into_sink();
+
+        // Resuming continues the chain
+        let mut resumed = AuditLog::resume(Vec::new(), records.last().unwrap()).unwrap();
+        records.push(resumed.append(AuditRecord::new(AuditEvent::Sign, &tx).unwrap(), 3).unwrap());
+        verify_chain(&records).unwrap();
+
+        records[1].fee_lamports = Some(1);
+        assert!(verify_chain(&records).is_err());
+        records.remove(1);
+        assert!(verify_chain(&records).is_err());
+    }
+}
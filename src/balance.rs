@@ -0,0 +1,219 @@
+//! Balance previews from decoded transfers
+//!
+//! [`BalancePreview`] decodes the value-moving System and SPL Token
+//! instructions of a message (transfers, account creation funding and
+//! token transfers, checked or not) into [`Transfer`]s, and nets them into
+//! per-account deltas. It needs no chain state, so it runs before signing:
+//! wallets show the deltas, and the spending-limit
+//! [policy](crate::policy::SpendingLimits) caps them.
+//!
+//! Unchecked token transfers do not name their mint. Register the source
+//! token account's mint with [`BalancePreview::token_account`]; otherwise
+//! the asset is identified by the source token account itself.
+
+use crate::error::Result;
+use crate::transaction::CompiledMessage;
+use std::collections::{BTreeMap, HashMap};
+
+const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+const TOKEN: [u8; 32] = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").to_bytes();
+const TOKEN_2022: [u8; 32] = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb").to_bytes();
+
+/// What a transfer moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Asset {
+    /// Lamports
+    Sol,
+    /// Tokens of a mint, or of an unregistered source token account
+    Token([u8; 32]),
+}
+
+/// One decoded movement of value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub instruction: usize,
+    pub asset: Asset,
+    /// Lamport holder or source token account
+    pub from: [u8; 32],
+    /// Lamport recipient or destination token account
+    pub to: [u8; 32],
+    /// Lamports or raw token units
+    pub amount: u64,
+}
+
+/// Decodes transfers, knowing the mints of token accounts
+#[derive(Debug, Clone, Default)]
+pub struct BalancePreview {
+    token_accounts: HashMap<[u8; 32], [u8; 32]>,
+}
+
+impl BalancePreview {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token account `account` holds tokens of `mint`
+    pub fn token_account(mut self, account: [u8; 32], mint: [u8; 32]) -> Self {
+        self.token_accounts.insert(account, mint);
+        self
+    }
+
+    /// Every transfer in `message`, in instruction order
+    ///
+    /// Fails if any instruction references an index past the key list,
+    /// since what it moves cannot be known.
+    pub fn transfers(&self, message: &CompiledMessage) -> Result<Vec<Transfer>> {
+        let mut transfers = Vec::new();
+        for ix in message.walk() {
+            let ix = ix?;
+            let accounts: Vec<[u8; 32]> = ix.accounts.iter().map(|account| account.key).collect();
+            let decoded = match *ix.program_id {
+                SYSTEM_PROGRAM_ID => decode_system(&accounts, ix.data),
+                TOKEN | TOKEN_2022 => self.decode_token(&accounts, ix.data),
+                _ => None,
+            };
+            if let Some((asset, from, to, amount)) = decoded {
+                transfers.push(Transfer {
+                    instruction: ix.index,
+                    asset,
+                    from,
+                    to,
+                    amount,
+                });
+            }
+        }
+        Ok(transfers)
+    }
+
+    /// Net change of every account touched by a transfer
+    pub fn changes(&self, message: &CompiledMessage) -> Result<BalanceChanges> {
+        let transfers = self.transfers(message)?;
+        let mut deltas = BTreeMap::new();
+        for transfer in &transfers {
+            *deltas.entry((transfer.from, transfer.asset)).or_default() -= transfer.amount as i128;
+            *deltas.entry((transfer.to, transfer.asset)).or_default() += transfer.amount as i128;
+        }
+        Ok(BalanceChanges { transfers, deltas })
+    }
+
+    fn decode_token(&self, accounts: &[[u8; 32]], data: &[u8]) -> Option<(Asset, [u8; 32], [u8; 32], u64)> {
+        let (&tag, rest) = data.split_first()?;
+        let amount = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+        match tag {
+            // Transfer: [source, destination, owner]
+            3 => {
+                let source = *accounts.first()?;
+                let mint = self.token_accounts.get(&source).copied().unwrap_or(source);
+                Some((Asset::Token(mint), source, *accounts.get(1)?, amount))
+            }
+            // TransferChecked: [source, mint, destination, owner]
+            12 => Some((Asset::Token(*accounts.get(1)?), *accounts.first()?, *accounts.get(2)?, amount)),
+            _ => None,
+        }
+    }
+}
+
+fn decode_system(accounts: &[[u8; 32]], data: &[u8]) -> Option<(Asset, [u8; 32], [u8; 32], u64)> {
+    let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    let lamports = u64::from_le_bytes(data.get(4..12)?.try_into().ok()?);
+    match tag {
+        // CreateAccount: [funder, new account]; Transfer: [from, to]
+        0 | 2 => Some((Asset::Sol, *accounts.first()?, *accounts.get(1)?, lamports)),
+        // TransferWithSeed: [from, base, to]
+        11 => Some((Asset::Sol, *accounts.first()?, *accounts.get(2)?, lamports)),
+        _ => None,
+    }
+}
+
+/// Result of [`BalancePreview::changes`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BalanceChanges {
+    pub transfers: Vec<Transfer>,
+    deltas: BTreeMap<([u8; 32], Asset), i128>,
+}
+
+impl BalanceChanges {
+    /// Net change of `account`'s `asset`; negative when it pays out
+    pub fn delta(&self, account: &[u8; 32], asset: Asset) -> i128 {
+        self.deltas.get(&(*account, asset)).copied().unwrap_or_default()
+    }
+
+    /// Every non-zero net change
+    pub fn deltas(&self) -> impl Iterator<Item = ([u8; 32], Asset, i128)> + '_ {
+        self.deltas
+            .iter()
+            .filter(|(_, delta)| **delta != 0)
+            .map(|((account, asset), delta)| (*account, *asset, *delta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::testkit;
+    use crate::transaction::TransactionBuilder;
+
+    #[test]
+    fn test_sol_transfers_net_out() {
+        let payer = testkit::pubkey(1);
+        let (a, b) = (testkit::pubkey(2), testkit::pubkey(3));
+        let message = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(payer, a, 500))
+            .add_instruction(testkit::transfer_instruction(payer, b, 300))
+            .add_instruction(testkit::transfer_instruction(a, b, 200))
+            .compile()
+            .unwrap();
+        let changes = BalancePreview::new().changes(&message).unwrap();
+        assert_eq!(changes.transfers.len(), 3);
+        assert_eq!(changes.transfers[2].instruction, 2);
+        assert_eq!(changes.delta(&payer, Asset::Sol), -800);
+        assert_eq!(changes.delta(&a, Asset::Sol), 300);
+        assert_eq!(changes.delta(&b, Asset::Sol), 500);
+        assert_eq!(changes.deltas().count(), 3);
+    }
+
+    #[test]
+    fn test_token_transfers_resolve_mints() {
+        let (owner, source, destination, mint) =
+            (testkit::pubkey(1), testkit::address(2), testkit::address(3), testkit::address(4));
+        let unchecked = InstructionEncoder::new(TOKEN)
+            .writable(source, false)
+            .writable(destination, false)
+            .signer(owner, false)
+            .append_u8(3)
+            .append_u64(1_000)
+            .build();
+        let checked = InstructionEncoder::new(TOKEN_2022)
+            .writable(source, false)
+            .readonly(mint)
+            .writable(destination, false)
+            .signer(owner, false)
+            .append_u8(12)
+            .append_u64(50)
+            .append_u8(6)
+            .build();
+        let message = TransactionBuilder::new()
+            .payer(owner)
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(unchecked)
+            .add_instruction(checked)
+            .compile()
+            .unwrap();
+
+        let unknown = BalancePreview::new().transfers(&message).unwrap();
+        assert_eq!(unknown[0].asset, Asset::Token(source));
+        let changes = BalancePreview::new().token_account(source, mint).changes(&message).unwrap();
+        assert_eq!(changes.delta(&destination, Asset::Token(mint)), 1_050);
+        assert_eq!(changes.delta(&owner, Asset::Sol), 0);
+    }
+
+    #[test]
+    fn test_unresolvable_instruction_fails() {
+        let mut message = testkit::simple_transfer_tx(1).message;
+        message.instructions[0].account_indices[1] = 9;
+        assert!(BalancePreview::new().transfers(&message).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Parallel batch signing and serialization
+//!
+//! Airdrop and liquidation engines often produce thousands of transactions
+//! per blockhash window. These helpers fan the work out over the rayon
+//! thread pool and report errors per item, in input order, so one bad
+//! transaction doesn't sink the whole batch.
+//!
+//! Enabled with the `rayon` feature.
+
+use crate::error::{Result, TxAsmError};
+use crate::transaction::CompiledTransaction;
+use rayon::prelude::*;
+use solana_sdk::signature::Signer;
+
+/// Sign every transaction with `signer`, placing the signature in its slot
+///
+/// Returns one result per transaction. Transactions that don't require
+/// `signer` fail with a `SignatureError` and are left untouched.
+pub fn sign_all<S>(transactions: &mut [CompiledTransaction], signer: &S) -> Vec<Result<()>>
+where
+    S: Signer + Sync + ?Sized,
+{
+    let pubkey = signer.pubkey().to_bytes();
+    transactions
+        .par_iter_mut()
+        .map(|tx| {
+            let message = tx.message_bytes()?;
+            let signature = signer
+                .try_sign_message(&message)
+                .map_err(|e| TxAsmError::SignatureError(e.to_string()))?;
+            let mut sig_array = [0u8; 64];
+            sig_array.copy_from_slice(signature.as_ref());
+            tx.merge_signature(&pubkey, sig_array)
+        })
+        .collect()
+}
+
+/// Serialize every transaction, one result per transaction
+pub fn serialize_all(transactions: &[CompiledTransaction]) -> Vec<Result<Vec<u8>>> {
+    transactions
+        .par_iter()
+        .map(|tx| tx.serialize())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use solana_sdk::signature::Keypair;
+
+    fn unsigned(payer: [u8; 32], tag: u8) -> CompiledTransaction {
+        let instruction = InstructionEncoder::new([2u8; 32]).append_u8(tag).build();
+        TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sign_all_reports_per_item() {
+        let signer = Keypair::new();
+        let payer = signer.pubkey().to_bytes();
+        let mut batch = vec![unsigned(payer, 1), unsigned([9u8; 32], 2), unsigned(payer, 3)];
+
+        let results = sign_all(&mut batch, &signer);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(TxAsmError::SignatureError(_))));
+        assert!(results[2].is_ok());
+
+        let message = batch[2].message_bytes().unwrap();
+        assert_eq!(batch[2].signatures[0], <[u8; 64]>::from(signer.sign_message(&message)));
+        assert_eq!(batch[1].signatures[0], [0u8; 64]);
+    }
+
+    #[test]
+    fn test_serialize_all_matches_sequential() {
+        let batch: Vec<_> = (0..32).map(|i| unsigned([1u8; 32], i)).collect();
+        let results = serialize_all(&batch);
+        for (tx, bytes) in batch.iter().zip(results) {
+            assert_eq!(bytes.unwrap(), tx.serialize().unwrap());
+        }
+    }
+}
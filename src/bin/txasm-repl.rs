@@ -0,0 +1,28 @@
+//! Interactive transaction assembler: `cargo run --features repl --bin txasm-repl`
+
+use std::io::{self, BufRead, Write};
+use txasm::repl::Session;
+
+fn main() -> io::Result<()> {
+    let mut session = Session::new();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    println!("txasm repl, `help` for commands, `quit` to exit");
+    loop {
+        write!(stdout, "txasm> ")?;
+        stdout.flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        match line.trim() {
+            "quit" | "exit" => break,
+            line => match session.eval(line) {
+                Ok(output) if output.is_empty() => {}
+                Ok(output) => println!("{}", output),
+                Err(err) => println!("error: {}", err),
+            },
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,6 @@
+//! Generates Kotlin/Swift bindings from the compiled library:
+//! `cargo run --features uniffi --bin uniffi-bindgen generate --library <lib> --language kotlin`
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
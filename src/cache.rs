@@ -0,0 +1,242 @@
+//! Precompiled message cache
+//!
+//! Compiling a message builds a key map, sorts the accounts and resolves
+//! every instruction's indices. None of that depends on the blockhash or on
+//! instruction data, so builds that only change those (a new blockhash, a
+//! different u64 amount) can reuse the layout and the serialized
+//! header/account-key prefix of an earlier build.
+
+use crate::error::Result;
+use crate::instruction::RawInstruction;
+use crate::serialization::{encode_len, encode_pubkey, encode_u8, ByteSerialize};
+use crate::transaction::{AccountLayout, CompiledMessage, TransactionBuilder};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Default number of distinct layouts kept before the cache is reset
+const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Cache hit/miss counters
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+struct CacheEntry {
+    payer: [u8; 32],
+    additional_signers: Vec<[u8; 32]>,
+    /// Program and account metas per instruction, used to confirm a fingerprint match
+    shape: Vec<RawInstruction>,
+    layout: AccountLayout,
+    /// Serialized header and account keys
+    prefix: Vec<u8>,
+}
+
+impl CacheEntry {
+    fn matches(&self, payer: &[u8; 32], additional_signers: &[[u8; 32]], instructions: &[RawInstruction]) -> bool {
+        self.payer == *payer
+            && self.additional_signers == additional_signers
+            && self.shape.len() == instructions.len()
+            && self
+                .shape
+                .iter()
+                .zip(instructions)
+                .all(|(a, b)| a.program_id == b.program_id && a.accounts == b.accounts)
+    }
+}
+
+/// Memoizes account layouts keyed by payer, programs and account metas
+pub struct CompileCache {
+    entries: HashMap<u64, CacheEntry>,
+    max_entries: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl CompileCache {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_MAX_ENTRIES)
+    }
+
+    /// Create a cache holding at most `max_entries` layouts
+    pub fn with_capacity(max_entries: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries: max_entries.max(1),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Compile the builder's message, reusing a cached layout when possible
+    pub fn compile(&mut self, builder: &TransactionBuilder) -> Result<CompiledMessage> {
+        builder.role_warnings()?;
+        let (payer, recent_blockhash) = builder.require_parts()?;
+        let entry = self.entry(payer, &builder.additional_signers, &builder.instructions)?;
+        let message = entry.layout.to_message(recent_blockhash, &builder.instructions);
+        builder.check_policies(&message)?;
+        Ok(message)
+    }
+
+    /// Serialize the builder's message into `out`, a reusable scratch
+    /// buffer whose contents are replaced, reusing the cached prefix
+    pub fn serialize_message(&mut self, builder: &TransactionBuilder, out: &mut Vec<u8>) -> Result<()> {
+        out.clear();
+        builder.role_warnings()?;
+        let (payer, recent_blockhash) = builder.require_parts()?;
+        let entry = self.entry(payer, &builder.additional_signers, &builder.instructions)?;
+        if !builder.policies.is_empty() {
+            builder.check_policies(&entry.layout.to_message(recent_blockhash, &builder.instructions))?;
+        }
+
+        out.extend_from_slice(&entry.prefix);
+        encode_pubkey(&recent_blockhash, out)?;
+        encode_len("instructions", builder.instructions.len(), out)?;
+        for ((program_id_index, account_indices), instruction) in
+            entry.layout.indices.iter().zip(&builder.instructions)
+        {
+            encode_u8(*program_id_index, out)?;
+            encode_len("instruction accounts", account_indices.len(), out)?;
+            out.extend_from_slice(account_indices);
+            encode_len("instruction data", instruction.data.len(), out)?;
+            out.extend_from_slice(&instruction.data);
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn entry(
+        &mut self,
+        payer: [u8; 32],
+        additional_signers: &[[u8; 32]],
+        instructions: &[RawInstruction],
+    ) -> Result<&CacheEntry> {
+        let fingerprint = fingerprint(&payer, additional_signers, instructions);
+
+        let hit = self
+            .entries
+            .get(&fingerprint)
+            .is_some_and(|entry| entry.matches(&payer, additional_signers, instructions));
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            let layout = AccountLayout::build(payer, additional_signers, instructions)?;
+            let mut prefix = Vec::with_capacity(3 + 3 + layout.account_keys.len() * 32);
+            layout.header.serialize_bytes(&mut prefix)?;
+            encode_len("account keys", layout.account_keys.len(), &mut prefix)?;
+            for key in &layout.account_keys {
+                encode_pubkey(key, &mut prefix)?;
+            }
+            let shape = instructions
+                .iter()
+                .map(|ix| RawInstruction::new(ix.program_id, ix.accounts.clone(), Vec::new()))
+                .collect();
+
+            if self.entries.len() >= self.max_entries {
+                self.entries.clear();
+            }
+            self.entries.insert(
+                fingerprint,
+                CacheEntry {
+                    payer,
+                    additional_signers: additional_signers.to_vec(),
+                    shape,
+                    layout,
+                    prefix,
+                },
+            );
+        }
+        Ok(&self.entries[&fingerprint])
+    }
+}
+
+impl Default for CompileCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hash of everything that determines the account layout
+fn fingerprint(payer: &[u8; 32], additional_signers: &[[u8; 32]], instructions: &[RawInstruction]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    payer.hash(&mut hasher);
+    additional_signers.hash(&mut hasher);
+    instructions.len().hash(&mut hasher);
+    for ix in instructions {
+        ix.program_id.hash(&mut hasher);
+        ix.accounts.len().hash(&mut hasher);
+        for account in &ix.accounts {
+            account.pubkey.hash(&mut hasher);
+            account.is_signer.hash(&mut hasher);
+            account.is_writable.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+
+    fn builder(amount: u64, blockhash: [u8; 32]) -> TransactionBuilder {
+        let instruction = InstructionEncoder::new([2u8; 32])
+            .signer([1u8; 32], true)
+            .writable([5u8; 32], false)
+            .append_u64(amount)
+            .build();
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash(blockhash)
+            .add_instruction(instruction)
+    }
+
+    #[test]
+    fn test_cache_matches_compile() {
+        let mut cache = CompileCache::new();
+        // One scratch buffer, starting dirty, reused across calls
+        let mut direct = vec![0xff; 7];
+        for amount in [1u64, 2, 3] {
+            let cached = cache.compile(&builder(amount, [amount as u8; 32])).unwrap();
+            let fresh = builder(amount, [amount as u8; 32]).compile().unwrap();
+
+            let mut cached_bytes = Vec::new();
+            let mut fresh_bytes = Vec::new();
+            cached.serialize_bytes(&mut cached_bytes).unwrap();
+            fresh.serialize_bytes(&mut fresh_bytes).unwrap();
+            assert_eq!(cached_bytes, fresh_bytes);
+
+            cache.serialize_message(&builder(amount, [amount as u8; 32]), &mut direct).unwrap();
+            assert_eq!(direct, fresh_bytes);
+        }
+        assert_eq!(cache.stats(), CacheStats { hits: 5, misses: 1, entries: 1 });
+    }
+
+    #[test]
+    fn test_cache_misses_on_account_change() {
+        let mut cache = CompileCache::with_capacity(1);
+        cache.compile(&builder(1, [3u8; 32])).unwrap();
+
+        let other = builder(1, [3u8; 32])
+            .add_instruction(InstructionEncoder::new([9u8; 32]).readonly([6u8; 32]).build());
+        let message = cache.compile(&other).unwrap();
+        assert_eq!(message.instructions.len(), 2);
+        assert_eq!(cache.stats().misses, 2);
+        assert_eq!(cache.stats().entries, 1);
+    }
+}
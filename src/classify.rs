@@ -0,0 +1,199 @@
+//! Cheap recognition of consensus traffic
+//!
+//! Most transactions in a block are validator votes. Block-level analytics
+//! usually want to skip them, and a full decode is wasted on them.
+//! [`classify_bytes`] reads a serialized transaction in place with
+//! [`TransactionView`] and settles its [`TransactionKind`] from the program
+//! and the first four bytes of instruction data alone.
+//!
+//! A vote is recognised the way the runtime recognises a simple vote
+//! transaction: at most two signatures, no lookup tables, and exactly one
+//! instruction, which casts a vote. Other vote program instructions
+//! (authorizing, withdrawing, changing commission) are vote account
+//! management.
+
+use crate::accounts::VOTE_PROGRAM_ID;
+use crate::error::Result;
+use crate::fee_calculator::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+use crate::perf::TransactionView;
+use crate::transaction::CompiledTransaction;
+use crate::versioned::{is_versioned, CompiledTransactionV0};
+
+/// What a transaction is for, as far as consensus is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    /// A validator casting a vote
+    Vote,
+    /// Only vote program instructions that manage vote accounts
+    VoteAccount,
+    Regular,
+}
+
+impl TransactionKind {
+    /// Whether the transaction is consensus plumbing rather than user activity
+    pub fn is_consensus(&self) -> bool {
+        !matches!(self, TransactionKind::Regular)
+    }
+}
+
+/// Name of a vote program instruction from its bincode tag
+pub fn vote_instruction_name(data: &[u8]) -> Option<&'static str> {
+    let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    Some(match tag {
+        0 => "InitializeAccount",
+        1 => "Authorize",
+        2 => "Vote",
+        3 => "Withdraw",
+        4 => "UpdateValidatorIdentity",
+        5 => "UpdateCommission",
+        6 => "VoteSwitch",
+        7 => "AuthorizeChecked",
+        8 => "UpdateVoteState",
+        9 => "UpdateVoteStateSwitch",
+        10 => "AuthorizeWithSeed",
+        11 => "AuthorizeCheckedWithSeed",
+        12 => "CompactUpdateVoteState",
+        13 => "CompactUpdateVoteStateSwitch",
+        14 => "TowerSync",
+        15 => "TowerSyncSwitch",
+        _ => return None,
+    })
+}
+
+/// Whether vote program `data` casts a vote
+pub fn is_vote_cast(data: &[u8]) -> bool {
+    matches!(
+        vote_instruction_name(data),
+        Some(
+            "Vote"
+                | "VoteSwitch"
+                | "UpdateVoteState"
+                | "UpdateVoteStateSwitch"
+                | "CompactUpdateVoteState"
+                | "CompactUpdateVoteStateSwitch"
+                | "TowerSync"
+                | "TowerSyncSwitch"
+        )
+    )
+}
+
+/// Classify from signature count, lookup use and each instruction's
+/// program and data
+fn classify_parts<'a>(
+    num_signatures: usize,
+    uses_lookups: bool,
+    instructions: impl Iterator<Item = (Option<&'a [u8; 32]>, &'a [u8])>,
+) -> TransactionKind {
+    let mut count = 0;
+    let mut casts = false;
+    let mut vote_only = true;
+    for (program, data) in instructions {
+        count += 1;
+        match program {
+            Some(program) if *program == VOTE_PROGRAM_ID => casts |= is_vote_cast(data),
+            Some(program) if *program == COMPUTE_BUDGET_PROGRAM_ID => {}
+            _ => vote_only = false,
+        }
+    }
+    if count == 1 && casts && num_signatures <= 2 && !uses_lookups {
+        TransactionKind::Vote
+    } else if count > 0 && vote_only && !casts {
+        TransactionKind::VoteAccount
+    } else {
+        TransactionKind::Regular
+    }
+}
+
+/// Classify a compiled legacy transaction
+pub fn classify(transaction: &CompiledTransaction) -> TransactionKind {
+    let message = &transaction.message;
+    classify_parts(
+        transaction.signatures.len(),
+        false,
+        message
+            .instructions
+            .iter()
+            .map(|ix| (message.account_keys.get(ix.program_id_index as usize), &ix.data[..])),
+    )
+}
+
+/// Classify a compiled V0 transaction
+pub fn classify_v0(transaction: &CompiledTransactionV0) -> TransactionKind {
+    let message = &transaction.message;
+    classify_parts(
+        transaction.signatures.len(),
+        !message.address_table_lookups.is_empty(),
+        message
+            .instructions
+            .iter()
+            .map(|ix| (message.account_keys.get(ix.program_id_index as usize), &ix.data[..])),
+    )
+}
+
+/// Classify serialized transaction bytes
+///
+/// Legacy transactions, which is how validators send votes, are read in
+/// place without allocating; versioned ones are decoded first.
+pub fn classify_bytes(bytes: &[u8]) -> Result<TransactionKind> {
+    if is_versioned(bytes)? {
+        return Ok(classify_v0(&CompiledTransactionV0::deserialize(bytes)?));
+    }
+    let view = TransactionView::parse(bytes)?;
+    Ok(classify_parts(
+        view.num_signatures(),
+        false,
+        view.instructions()
+            .map(|ix| (view.account_key(ix.program_id_index as usize), ix.data)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::testkit;
+    use crate::transaction::TransactionBuilder;
+
+    fn vote_program_tx(tag: u32) -> CompiledTransaction {
+        let validator = testkit::pubkey(1);
+        TransactionBuilder::new()
+            .payer(validator)
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(
+                InstructionEncoder::new(VOTE_PROGRAM_ID)
+                    .writable(testkit::address(2), false)
+                    .signer(validator, false)
+                    .append_u32(tag)
+                    .append_data(&[0u8; 16])
+                    .build(),
+            )
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_classify_votes() {
+        let vote = vote_program_tx(14);
+        assert_eq!(classify(&vote), TransactionKind::Vote);
+        assert_eq!(classify_bytes(&vote.serialize().unwrap()).unwrap(), TransactionKind::Vote);
+        assert_eq!(classify(&vote_program_tx(3)), TransactionKind::VoteAccount);
+        assert!(classify(&vote_program_tx(5)).is_consensus());
+
+        let transfer = testkit::simple_transfer_tx(1);
+        assert_eq!(classify_bytes(&transfer.serialize().unwrap()).unwrap(), TransactionKind::Regular);
+        assert!(!TransactionKind::Regular.is_consensus());
+    }
+
+    #[test]
+    fn test_vote_shape_is_strict() {
+        // A vote bundled with another instruction is not a simple vote
+        let mut bundled = vote_program_tx(14);
+        bundled.message.instructions.push(bundled.message.instructions[0].clone());
+        assert_eq!(classify(&bundled), TransactionKind::Regular);
+
+        let (v0, _) = crate::convert::legacy_to_v0(&vote_program_tx(12), &[]).unwrap();
+        assert_eq!(classify_bytes(&v0.serialize().unwrap()).unwrap(), TransactionKind::Vote);
+        assert_eq!(vote_instruction_name(&14u32.to_le_bytes()), Some("TowerSync"));
+        assert_eq!(vote_instruction_name(&99u32.to_le_bytes()), None);
+    }
+}
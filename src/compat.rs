@@ -0,0 +1,371 @@
+//! Byte-exact comparison against web3.js output
+//!
+//! Legacy messages built by web3.js and by txasm hold the same information,
+//! but the bytes can still differ. The usual cause is account order: both
+//! group keys as writable signers, readonly signers, writable, then
+//! readonly, with the fee payer first. Within a group, txasm sorts keys by
+//! their bytes, while web3.js sorts them by their base58 strings.
+//! [`compare_with`] decodes the web3.js bytes, lines up the two
+//! [layouts](crate::layout) by field name and explains each field that
+//! differs.
+
+use crate::error::Result;
+use crate::layout::Span;
+use crate::transaction::{CompiledMessage, CompiledTransaction};
+use crate::versioned::is_versioned;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// One field whose bytes differ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Dotted layout name, e.g. `"instructions[0].account_indices"`
+    pub field: String,
+    /// Position in txasm's bytes, if txasm has the field
+    pub ours: Option<Span>,
+    /// Position in the web3.js bytes, if web3.js has the field
+    pub theirs: Option<Span>,
+    pub reason: String,
+}
+
+/// Result of [`compare_with`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CompatReport {
+    /// First byte at which the two encodings differ
+    pub first_offset: Option<usize>,
+    pub divergences: Vec<Divergence>,
+}
+
+impl CompatReport {
+    pub fn is_identical(&self) -> bool {
+        self.first_offset.is_none()
+    }
+}
+
+impl fmt::Display for CompatReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.first_offset {
+            None => return write!(f, "byte-identical"),
+            Some(offset) => writeln!(f, "bytes differ from offset {}", offset)?,
+        }
+        for divergence in &self.divergences {
+            writeln!(f, "  {}: {}", divergence.field, divergence.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Diff `js_serialized` (web3.js `Transaction.serialize()` output) against
+/// `ours` field by field
+pub fn compare_with(js_serialized: &[u8], ours: &CompiledTransaction) -> Result<CompatReport> {
+    let our_bytes = ours.serialize()?;
+    let mut report = CompatReport {
+        first_offset: first_difference(&our_bytes, js_serialized),
+        divergences: Vec::new(),
+    };
+    if report.is_identical() {
+        return Ok(report);
+    }
+    let whole = |reason: String| Divergence {
+        field: "transaction".to_string(),
+        ours: None,
+        theirs: None,
+        reason,
+    };
+
+    if is_versioned(js_serialized).unwrap_or(false) {
+        report.divergences.push(whole(
+            "web3.js produced a versioned message; compare it with a CompiledTransactionV0".to_string(),
+        ));
+        return Ok(report);
+    }
+    let theirs = match CompiledTransaction::deserialize(js_serialized) {
+        Ok(theirs) => theirs,
+        Err(e) => {
+            report.divergences.push(whole(format!("web3.js bytes do not decode: {}", e)));
+            return Ok(report);
+        }
+    };
+    let their_layout = theirs.layout();
+
+    // Same content re-encoded differently means a length prefix was not
+    // minimal, or bytes follow the transaction
+    let reencoded = theirs.serialize()?;
+    if reencoded != js_serialized {
+        if js_serialized.starts_with(&reencoded) {
+            report.divergences.push(whole(format!(
+                "{} trailing bytes after the web3.js transaction",
+                js_serialized.len() - reencoded.len()
+            )));
+        } else {
+            let offset = first_difference(&reencoded, js_serialized).unwrap_or_default();
+            report.divergences.push(Divergence {
+                field: their_layout.field_at(offset).unwrap_or_else(|| "transaction".to_string()),
+                ours: None,
+                theirs: None,
+                reason: format!("non-canonical compact-u16 length at byte {} of the web3.js bytes", offset),
+            });
+        }
+        if ours == &theirs {
+            return Ok(report);
+        }
+    }
+
+    let our_layout = ours.layout();
+    let their_fields: HashMap<String, Span> = their_layout.fields().into_iter().collect();
+    let mut seen = HashSet::new();
+    let explain = Explainer { ours, theirs: &theirs };
+    for (field, span) in our_layout.fields() {
+        seen.insert(field.clone());
+        let their_span = their_fields.get(&field).copied();
+        if let Some(their_span) = their_span {
+            if our_bytes[span.range()] == reencoded[their_span.range()] {
+                continue;
+            }
+        }
+        report.divergences.push(Divergence {
+            reason: explain.field(&field),
+            field,
+            ours: Some(span),
+            theirs: their_span,
+        });
+    }
+    for (field, span) in their_layout.fields() {
+        if !seen.contains(&field) {
+            report.divergences.push(Divergence {
+                reason: explain.field(&field),
+                field,
+                ours: None,
+                theirs: Some(span),
+            });
+        }
+    }
+    Ok(report)
+}
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    if a == b {
+        return None;
+    }
+    Some(a.iter().zip(b).position(|(x, y)| x != y).unwrap_or(a.len().min(b.len())))
+}
+
+fn key(bytes: &[u8; 32]) -> String {
+    bs58::encode(bytes).into_string()
+}
+
+struct Explainer<'a> {
+    ours: &'a CompiledTransaction,
+    theirs: &'a CompiledTransaction,
+}
+
+impl Explainer<'_> {
+    fn field(&self, field: &str) -> String {
+        let (ours, theirs) = (&self.ours.message, &self.theirs.message);
+        if let Some(rest) = field.strip_prefix("instructions[") {
+            let (index, name) = rest.split_once("].").unwrap_or((rest, ""));
+            return self.instruction(index.parse().unwrap_or_default(), name);
+        }
+        if let Some(index) = indexed(field, "signatures[") {
+            return self.signature(index);
+        }
+        if let Some(index) = indexed(field, "account_keys[") {
+            return self.account_key(index);
+        }
+        match field {
+            "signatures_len" => format!(
+                "{} signature slots here, {} in web3.js",
+                self.ours.signatures.len(),
+                self.theirs.signatures.len()
+            ),
+            "header" => header(ours, theirs),
+            "account_keys_len" => {
+                let (our_keys, their_keys) = (key_set(ours), key_set(theirs));
+                let missing: Vec<_> = our_keys.difference(&their_keys).map(key).collect();
+                let extra: Vec<_> = their_keys.difference(&our_keys).map(key).collect();
+                format!(
+                    "{} keys here, {} in web3.js; only here: [{}]; only in web3.js: [{}]",
+                    ours.account_keys.len(),
+                    theirs.account_keys.len(),
+                    missing.join(", "),
+                    extra.join(", ")
+                )
+            }
+            "recent_blockhash" => format!(
+                "blockhash {} here, {} in web3.js",
+                key(&ours.recent_blockhash),
+                key(&theirs.recent_blockhash)
+            ),
+            "instructions_len" => format!(
+                "{} instructions here, {} in web3.js",
+                ours.instructions.len(),
+                theirs.instructions.len()
+            ),
+            _ => "bytes differ".to_string(),
+        }
+    }
+
+    fn signature(&self, index: usize) -> String {
+        let (ours, theirs) = (self.ours.signatures.get(index), self.theirs.signatures.get(index));
+        match (ours, theirs) {
+            (_, Some(sig)) if sig == &[0u8; 64] => "not signed in the web3.js bytes".to_string(),
+            (Some(sig), _) if sig == &[0u8; 64] => "not signed here".to_string(),
+            (Some(_), Some(_)) if self.ours.message == self.theirs.message => {
+                "signed by a different key over the same message".to_string()
+            }
+            (Some(_), Some(_)) => "signs a different message".to_string(),
+            _ => "slot exists on one side only".to_string(),
+        }
+    }
+
+    fn account_key(&self, index: usize) -> String {
+        let (ours, theirs) = (&self.ours.message, &self.theirs.message);
+        match (ours.account_keys.get(index), theirs.account_keys.get(index)) {
+            (Some(a), Some(b)) if key_set(ours) == key_set(theirs) => {
+                format!(
+                    "same keys in a different order: {} ({}) here, {} ({}) in web3.js; within a group txasm sorts by key bytes and web3.js by base58 string",
+                    key(a),
+                    role(ours, index),
+                    key(b),
+                    role(theirs, index)
+                )
+            }
+            (Some(a), _) if !theirs.account_keys.contains(a) => format!("{} is not in the web3.js message", key(a)),
+            (_, Some(b)) if !ours.account_keys.contains(b) => format!("{} is only in the web3.js message", key(b)),
+            _ => "key sets differ, shifting later keys".to_string(),
+        }
+    }
+
+    fn instruction(&self, index: usize, name: &str) -> String {
+        let (ours, theirs) = (&self.ours.message, &self.theirs.message);
+        let (Some(a), Some(b)) = (ours.instructions.get(index), theirs.instructions.get(index)) else {
+            return "instruction exists on one side only".to_string();
+        };
+        let resolve = |m: &CompiledMessage, i: u8| m.account_keys.get(i as usize).copied();
+        match name {
+            "program_id_index" if resolve(ours, a.program_id_index) == resolve(theirs, b.program_id_index) => {
+                "same program at a different key index (account order differs)".to_string()
+            }
+            "program_id_index" => "invokes a different program".to_string(),
+            "accounts_len" => format!(
+                "{} account indices here, {} in web3.js; web3.js keeps duplicate metas",
+                a.account_indices.len(),
+                b.account_indices.len()
+            ),
+            "account_indices" => {
+                let our_keys: Vec<_> = a.account_indices.iter().map(|i| resolve(ours, *i)).collect();
+                let their_keys: Vec<_> = b.account_indices.iter().map(|i| resolve(theirs, *i)).collect();
+                if our_keys == their_keys {
+                    "same accounts at different key indices (account order differs)".to_string()
+                } else {
+                    "references different accounts".to_string()
+                }
+            }
+            "data_len" => format!("{} data bytes here, {} in web3.js", a.data.len(), b.data.len()),
+            "data" => format!(
+                "data differs from byte {}",
+                first_difference(&a.data, &b.data).unwrap_or_default()
+            ),
+            _ => "bytes differ".to_string(),
+        }
+    }
+}
+
+fn indexed(field: &str, prefix: &str) -> Option<usize> {
+    field.strip_prefix(prefix)?.strip_suffix(']')?.parse().ok()
+}
+
+fn key_set(message: &CompiledMessage) -> HashSet<[u8; 32]> {
+    message.account_keys.iter().copied().collect()
+}
+
+fn role(message: &CompiledMessage, index: usize) -> &'static str {
+    match (message.is_signer(index), message.is_writable(index)) {
+        (true, true) => "writable signer",
+        (true, false) => "readonly signer",
+        (false, true) => "writable",
+        (false, false) => "readonly",
+    }
+}
+
+fn header(ours: &CompiledMessage, theirs: &CompiledMessage) -> String {
+    let (a, b) = (&ours.header, &theirs.header);
+    let mut counts = Vec::new();
+    for (name, x, y) in [
+        ("required signatures", a.num_required_signatures, b.num_required_signatures),
+        ("readonly signed", a.num_readonly_signed_accounts, b.num_readonly_signed_accounts),
+        ("readonly unsigned", a.num_readonly_unsigned_accounts, b.num_readonly_unsigned_accounts),
+    ] {
+        if x != y {
+            counts.push(format!("{} {} here, {} in web3.js", name, x, y));
+        }
+    }
+    format!(
+        "{}; an account's signer or writable flag differs (both merge duplicate metas by OR-ing the flags)",
+        counts.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::testkit;
+    use crate::transaction::TransactionBuilder;
+
+    fn two_writable_tx() -> CompiledTransaction {
+        TransactionBuilder::new()
+            .payer(testkit::pubkey(1))
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(
+                InstructionEncoder::new(testkit::address(9))
+                    .writable(testkit::address(1), false)
+                    .writable(testkit::address(2), false)
+                    .build(),
+            )
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_identical_and_field_differences() {
+        let tx = testkit::simple_transfer_tx(1);
+        let bytes = tx.serialize().unwrap();
+        assert!(compare_with(&bytes, &tx).unwrap().is_identical());
+        assert_eq!(compare_with(&bytes, &tx).unwrap().to_string(), "byte-identical");
+
+        let mut ours = tx.clone();
+        ours.message.recent_blockhash = testkit::blockhash(2);
+        let report = compare_with(&bytes, &ours).unwrap();
+        assert_eq!(report.first_offset, Some(tx.layout().recent_blockhash.offset));
+        assert_eq!(report.divergences.len(), 1);
+        assert_eq!(report.divergences[0].field, "recent_blockhash");
+
+        let mut bytes = tx.serialize().unwrap();
+        bytes.push(0);
+        assert!(compare_with(&bytes, &tx).unwrap().divergences[0].reason.contains("trailing"));
+    }
+
+    #[test]
+    fn test_explains_order_and_encoding() {
+        // The same writable accounts, sorted the other way round
+        let tx = two_writable_tx();
+        let mut reordered = tx.clone();
+        reordered.message.account_keys.swap(1, 2);
+        for index in &mut reordered.message.instructions[0].account_indices {
+            *index = 3 - *index;
+        }
+        let report = compare_with(&reordered.serialize().unwrap(), &tx).unwrap();
+        assert!(report.divergences[0].reason.starts_with("same keys in a different order"));
+        assert!(report.divergences[0].reason.contains("(writable)"));
+        assert_eq!(report.divergences.len(), 3);
+        assert!(report.divergences[2].reason.contains("order differs"));
+
+        // A two-byte encoding of a signature count of 1
+        let mut bytes = tx.serialize().unwrap();
+        bytes.splice(0..1, [0x81, 0x00]);
+        let report = compare_with(&bytes, &tx).unwrap();
+        assert_eq!(report.divergences.len(), 1);
+        assert!(report.divergences[0].reason.starts_with("non-canonical compact-u16"));
+    }
+}
@@ -0,0 +1,222 @@
+//! Compute unit limits learned from observed executions
+//!
+//! [`ComputeUnitEstimator`] keeps a running average of the compute units
+//! each program consumed, fed from simulation or confirmation results with
+//! [`ComputeUnitEstimator::learn`] or straight from program logs with
+//! [`ComputeUnitEstimator::learn_from_logs`]. A service that feeds back its
+//! own history gets limits that track what its instructions actually cost
+//! rather than a fixed constant. The learned averages round-trip through
+//! JSON so they survive restarts.
+
+use crate::cost_model::{DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT};
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+use crate::serialization::parse_pubkey;
+use crate::transaction::CompiledMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Compute units charged for each compute budget instruction
+const COMPUTE_BUDGET_INSTRUCTION_UNITS: u64 = 150;
+
+/// What has been observed for one program
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LearnedUnits {
+    pub samples: u64,
+    /// Running average, weighted towards the last `window` samples
+    pub mean: f64,
+    pub max: u32,
+}
+
+/// Per-program compute unit averages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComputeUnitEstimator {
+    /// Keyed by base58 program ID
+    programs: BTreeMap<String, LearnedUnits>,
+    /// Headroom added to learned averages, e.g. 0.2 for +20%
+    margin: f64,
+    /// Samples after which older observations start to fade
+    window: u64,
+    /// Units assumed for programs with no history
+    fallback: u32,
+}
+
+impl Default for ComputeUnitEstimator {
+    fn default() -> Self {
+        Self {
+            programs: BTreeMap::new(),
+            margin: 0.2,
+            window: 100,
+            fallback: DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT as u32,
+        }
+    }
+}
+
+impl ComputeUnitEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn margin(mut self, margin: f64) -> Self {
+        self.margin = margin.max(0.0);
+        self
+    }
+
+    /// Weight recent behaviour: the average moves by at least `1/window`
+    /// of each new sample's difference, so it keeps adapting
+    pub fn window(mut self, window: u64) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    pub fn fallback(mut self, units: u32) -> Self {
+        self.fallback = units;
+        self
+    }
+
+    /// Record that one invocation of `program_id` consumed `observed_cus`
+    pub fn learn(&mut self, program_id: [u8; 32], observed_cus: u32) {
+        let key = bs58::encode(program_id).into_string();
+        let entry = self.programs.entry(key).or_insert(LearnedUnits {
+            samples: 0,
+            mean: 0.0,
+            max: 0,
+        });
+        entry.samples += 1;
+        let weight = entry.samples.min(self.window) as f64;
+        entry.mean += (observed_cus as f64 - entry.mean) / weight;
+        entry.max = entry.max.max(observed_cus);
+    }
+
+    /// Learn from `Program <id> consumed <n> of <m> compute units` log lines
+    ///
+    /// Only top-level invocations are recorded, since a CPI's units are
+    /// already included in its caller's. Returns the number learned.
+    pub fn learn_from_logs<S: AsRef<str>>(&mut self, logs: &[S]) -> usize {
+        let mut depth = 0usize;
+        let mut learned = 0;
+        for line in logs {
+            let Some(rest) = line.as_ref().strip_prefix("Program ") else {
+                continue;
+            };
+            let mut words = rest.split_whitespace();
+            let (Some(program), Some(event)) = (words.next(), words.next()) else {
+                continue;
+            };
+            match event {
+                "invoke" => {
+                    depth = words
+                        .next()
+                        .and_then(|d| d.trim_matches(|c| c == '[' || c == ']').parse().ok())
+                        .unwrap_or(depth + 1);
+                }
+                "success" | "failed:" => depth = depth.saturating_sub(1),
+                "consumed" if depth <= 1 => {
+                    let units = words.next().and_then(|n| n.parse().ok());
+                    if let (Ok(program), Some(units)) = (parse_pubkey(program), units) {
+                        self.learn(program, units);
+                        learned += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        learned
+    }
+
+    /// Learned history of `program_id`
+    pub fn learned(&self, program_id: &[u8; 32]) -> Option<&LearnedUnits> {
+        self.programs.get(&bs58::encode(program_id).into_string())
+    }
+
+    /// Expected units for one invocation of `program_id`, margin included
+    pub fn estimate_program(&self, program_id: &[u8; 32]) -> u32 {
+        match self.learned(program_id) {
+            Some(learned) => (learned.mean * (1.0 + self.margin)).ceil() as u32,
+            None => self.fallback,
+        }
+    }
+
+    /// Compute unit limit to request for `message`
+    pub fn estimate(&self, message: &CompiledMessage) -> u32 {
+        let total: u64 = message
+            .instructions
+            .iter()
+            .filter_map(|ix| message.account_keys.get(ix.program_id_index as usize))
+            .map(|program| {
+                if *program == COMPUTE_BUDGET_PROGRAM_ID {
+                    COMPUTE_BUDGET_INSTRUCTION_UNITS
+                } else {
+                    self.estimate_program(program) as u64
+                }
+            })
+            .sum();
+        total.min(MAX_COMPUTE_UNIT_LIMIT) as u32
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| TxAsmError::SerializationError(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::compute_budget;
+    use crate::instruction::RawInstruction;
+    use crate::testkit;
+    use crate::transaction::TransactionBuilder;
+
+    #[test]
+    fn test_learned_estimate() {
+        let mut estimator = ComputeUnitEstimator::new().margin(0.1).window(2);
+        let program = testkit::address(1);
+        assert_eq!(estimator.estimate_program(&program), 200_000);
+
+        estimator.learn(program, 1_000);
+        estimator.learn(program, 3_000);
+        estimator.learn(program, 3_000);
+        // Window of 2: 1000 -> 2000 -> 2500
+        assert_eq!(estimator.learned(&program).unwrap().mean, 2_500.0);
+        assert_eq!(estimator.estimate_program(&program), 2_750);
+
+        let message = TransactionBuilder::new()
+            .payer(testkit::pubkey(1))
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(RawInstruction::new(
+                COMPUTE_BUDGET_PROGRAM_ID,
+                vec![],
+                compute_budget::create_compute_unit_limit_instruction(10_000),
+            ))
+            .add_instruction(RawInstruction::new(program, vec![], vec![]))
+            .compile()
+            .unwrap();
+        assert_eq!(estimator.estimate(&message), 2_900);
+
+        let restored = ComputeUnitEstimator::from_json(&estimator.to_json().unwrap()).unwrap();
+        assert_eq!(restored, estimator);
+    }
+
+    #[test]
+    fn test_learn_from_logs_skips_cpi() {
+        let outer = bs58::encode(testkit::address(1)).into_string();
+        let inner = bs58::encode(testkit::address(2)).into_string();
+        let logs = vec![
+            format!("Program {} invoke [1]", outer),
+            format!("Program {} invoke [2]", inner),
+            format!("Program {} consumed 900 of 190000 compute units", inner),
+            format!("Program {} success", inner),
+            format!("Program {} consumed 5000 of 200000 compute units", outer),
+            format!("Program {} success", outer),
+            "Program log: not a consumption line".to_string(),
+        ];
+        let mut estimator = ComputeUnitEstimator::new();
+        assert_eq!(estimator.learn_from_logs(&logs), 1);
+        assert_eq!(estimator.learned(&testkit::address(1)).unwrap().max, 5_000);
+        assert!(estimator.learned(&testkit::address(2)).is_none());
+    }
+}
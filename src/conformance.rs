@@ -0,0 +1,245 @@
+//! Cross-language conformance test vectors
+//!
+//! Ports of the encoder to other languages need the same inputs and the
+//! same expected bytes. A [`TestVector`] records a transaction's inputs
+//! (base58 keys, hex data) next to the message and transaction bytes txasm
+//! produces for them (hex). A [`VectorSuite`] is the canonical JSON file:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "vectors": [{
+//!     "name": "transfer",
+//!     "payer": "…",
+//!     "recent_blockhash": "…",
+//!     "instructions": [{
+//!       "program_id": "11111111111111111111111111111111",
+//!       "accounts": [{"pubkey": "…", "is_signer": true, "is_writable": true}],
+//!       "data": "02000000e803000000000000"
+//!     }],
+//!     "signatures": ["…"],
+//!     "expected_message": "…",
+//!     "expected_transaction": "…"
+//!   }]
+//! }
+//! ```
+//!
+//! `signatures` may be empty for unsigned vectors, whose signature slots
+//! are zero. Ed25519 signing is deterministic, so signed vectors also pin
+//! down the signature bytes.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::{AccountMeta, RawInstruction};
+use crate::serialization::{encode_hex, parse_hex, parse_pubkey};
+use crate::snapshot::AccountSnapshot;
+use crate::transaction::TransactionBuilder;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Keypair;
+
+/// Current vector format version
+pub const VECTOR_FORMAT_VERSION: u32 = 1;
+
+/// An instruction of a vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorInstruction {
+    pub program_id: String,
+    pub accounts: Vec<AccountSnapshot>,
+    /// Hex instruction data
+    pub data: String,
+}
+
+/// Inputs of one transaction and the bytes they must encode to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub payer: String,
+    pub recent_blockhash: String,
+    pub instructions: Vec<VectorInstruction>,
+    /// Hex signatures in signer order; empty when unsigned
+    #[serde(default)]
+    pub signatures: Vec<String>,
+    pub expected_message: String,
+    pub expected_transaction: String,
+}
+
+impl TestVector {
+    /// Record what `builder` compiles to, signed by `signers` (none for an
+    /// unsigned vector)
+    pub fn new(name: impl Into<String>, builder: &TransactionBuilder, signers: &[&Keypair]) -> Result<Self> {
+        let transaction = if signers.is_empty() {
+            builder.clone().build_unsigned()?
+        } else {
+            builder.clone().build_and_sign(signers)?
+        };
+        let message = &transaction.message;
+        let encode = |key: &[u8; 32]| bs58::encode(key).into_string();
+        Ok(Self {
+            name: name.into(),
+            payer: encode(&message.account_keys[0]),
+            recent_blockhash: encode(&message.recent_blockhash),
+            instructions: builder
+                .instructions()
+                .iter()
+                .map(|ix| VectorInstruction {
+                    program_id: encode(&ix.program_id),
+                    accounts: ix
+                        .accounts
+                        .iter()
+                        .map(|meta| AccountSnapshot {
+                            pubkey: encode(&meta.pubkey),
+                            is_signer: meta.is_signer,
+                            is_writable: meta.is_writable,
+                        })
+                        .collect(),
+                    data: encode_hex(&ix.data),
+                })
+                .collect(),
+            signatures: if signers.is_empty() {
+                Vec::new()
+            } else {
+                transaction.signatures.iter().map(|s| encode_hex(s)).collect()
+            },
+            expected_message: encode_hex(&transaction.message_bytes()?),
+            expected_transaction: encode_hex(&transaction.serialize()?),
+        })
+    }
+
+    /// The builder described by the vector's inputs
+    pub fn builder(&self) -> Result<TransactionBuilder> {
+        let mut builder = TransactionBuilder::new()
+            .payer(parse_pubkey(&self.payer)?)
+            .recent_blockhash(parse_pubkey(&self.recent_blockhash)?);
+        for (index, ix) in self.instructions.iter().enumerate() {
+            let accounts = ix
+                .accounts
+                .iter()
+                .map(|meta| Ok(AccountMeta::new(parse_pubkey(&meta.pubkey)?, meta.is_signer, meta.is_writable)))
+                .collect::<Result<Vec<_>>>()?;
+            let data = hex_field(&ix.data, &format!("instruction {} data", index))?;
+            builder = builder.add_instruction(RawInstruction::new(parse_pubkey(&ix.program_id)?, accounts, data));
+        }
+        Ok(builder)
+    }
+
+    /// Encode the inputs and compare with the expected bytes
+    pub fn check(&self) -> Result<()> {
+        let mut transaction = self.builder()?.build_unsigned()?;
+        compare("message", &transaction.message_bytes()?, &hex_field(&self.expected_message, "expected_message")?)?;
+        if !self.signatures.is_empty() {
+            transaction.signatures = self
+                .signatures
+                .iter()
+                .map(|s| {
+                    hex_field(s, "signature")?
+                        .try_into()
+                        .map_err(|_| TxAsmError::DecodingError("Signature is not 64 bytes".to_string()))
+                })
+                .collect::<Result<_>>()?;
+        }
+        compare(
+            "transaction",
+            &transaction.serialize()?,
+            &hex_field(&self.expected_transaction, "expected_transaction")?,
+        )
+    }
+}
+
+fn hex_field(text: &str, what: &str) -> Result<Vec<u8>> {
+    parse_hex(text).ok_or_else(|| TxAsmError::DecodingError(format!("Invalid hex in {}", what)))
+}
+
+fn compare(what: &str, actual: &[u8], expected: &[u8]) -> Result<()> {
+    if actual == expected {
+        return Ok(());
+    }
+    let offset = actual.iter().zip(expected).position(|(a, e)| a != e).unwrap_or(actual.len().min(expected.len()));
+    Err(TxAsmError::EncodingError(format!(
+        "{} differs at byte {} ({} bytes, expected {})",
+        what,
+        offset,
+        actual.len(),
+        expected.len()
+    )))
+}
+
+/// A canonical JSON file of test vectors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorSuite {
+    pub version: u32,
+    pub vectors: Vec<TestVector>,
+}
+
+impl VectorSuite {
+    pub fn new(vectors: Vec<TestVector>) -> Self {
+        Self {
+            version: VECTOR_FORMAT_VERSION,
+            vectors,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| TxAsmError::SerializationError(e.to_string()))
+    }
+
+    /// Parse a suite, rejecting versions newer than this crate understands
+    pub fn from_json(json: &str) -> Result<Self> {
+        let suite: Self = serde_json::from_str(json).map_err(|e| TxAsmError::DeserializationError(e.to_string()))?;
+        if suite.version > VECTOR_FORMAT_VERSION {
+            return Err(TxAsmError::DeserializationError(format!(
+                "Vector format version {} is newer than supported version {}",
+                suite.version, VECTOR_FORMAT_VERSION
+            )));
+        }
+        Ok(suite)
+    }
+
+    /// Check every vector, returning `"name: error"` for each failure
+    pub fn failures(&self) -> Vec<String> {
+        self.vectors
+            .iter()
+            .filter_map(|vector| vector.check().err().map(|e| format!("{}: {}", vector.name, e)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit;
+    use solana_sdk::signature::Signer;
+
+    #[test]
+    fn test_vectors_round_trip_through_json() {
+        let payer = testkit::keypair(1);
+        let builder = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(payer.pubkey().to_bytes(), testkit::pubkey(2), 1_000));
+        let suite = VectorSuite::new(vec![
+            TestVector::new("unsigned transfer", &builder, &[]).unwrap(),
+            TestVector::new("signed transfer", &builder, &[&payer]).unwrap(),
+        ]);
+        assert!(suite.vectors[0].signatures.is_empty());
+        assert_eq!(suite.vectors[1].instructions[0].data, "02000000e803000000000000");
+
+        let restored = VectorSuite::from_json(&suite.to_json().unwrap()).unwrap();
+        assert_eq!(restored, suite);
+        assert!(restored.failures().is_empty());
+    }
+
+    #[test]
+    fn test_mismatch_reports_offset() {
+        let builder = TransactionBuilder::new()
+            .payer(testkit::pubkey(1))
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(testkit::pubkey(1), testkit::pubkey(2), 1));
+        let mut vector = TestVector::new("transfer", &builder, &[]).unwrap();
+        vector.instructions[0].data = "02000000ff00000000000000".to_string();
+        let failures = VectorSuite::new(vec![vector]).failures();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].contains("message differs at byte 142"), "{}", failures[0]);
+
+        let future = r#"{"version": 2, "vectors": []}"#;
+        assert!(VectorSuite::from_json(future).is_err());
+    }
+}
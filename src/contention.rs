@@ -0,0 +1,178 @@
+//! Write-lock contention across pending transactions
+//!
+//! The runtime executes transactions in parallel unless they lock the same
+//! account and at least one of them writes it. [`analyze`] finds the accounts
+//! a batch contends on and groups the transactions that conflict, directly or
+//! through a chain of shared accounts, so searchers can see which flows are
+//! serialized and split them across accounts or price only the transactions
+//! that actually compete.
+
+use crate::transaction::CompiledTransaction;
+use std::collections::HashMap;
+
+/// Lock usage of one contended account
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountContention {
+    pub account: [u8; 32],
+    /// Batch indices write-locking the account
+    pub writers: Vec<usize>,
+    /// Batch indices read-locking the account
+    pub readers: Vec<usize>,
+}
+
+impl AccountContention {
+    /// Transactions serialized on this account
+    pub fn contenders(&self) -> usize {
+        self.writers.len() + self.readers.len()
+    }
+}
+
+/// Result of [`analyze`]
+#[derive(Debug, Clone, Default)]
+pub struct ContentionReport {
+    /// Accounts locked by more than one transaction with at least one
+    /// writer, most write-contended first
+    pub accounts: Vec<AccountContention>,
+    /// Sets of batch indices that cannot all execute in parallel, each
+    /// sorted, ordered by their first index
+    pub groups: Vec<Vec<usize>>,
+    /// Batch indices that conflict with no other transaction
+    pub independent: Vec<usize>,
+}
+
+impl ContentionReport {
+    /// Whether every transaction can execute in parallel
+    pub fn is_parallel(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The conflict group containing batch index `index`
+    pub fn group_of(&self, index: usize) -> Option<&[usize]> {
+        self.groups
+            .iter()
+            .find(|group| group.binary_search(&index).is_ok())
+            .map(Vec::as_slice)
+    }
+}
+
+/// Group `batch` by shared write-locked accounts
+pub fn analyze(batch: &[CompiledTransaction]) -> ContentionReport {
+    let mut locks: HashMap<[u8; 32], AccountContention> = HashMap::new();
+    for (index, transaction) in batch.iter().enumerate() {
+        let message = &transaction.message;
+        for (position, key) in message.account_keys.iter().enumerate() {
+            let entry = locks.entry(*key).or_insert_with(|| AccountContention {
+                account: *key,
+                writers: Vec::new(),
+                readers: Vec::new(),
+            });
+            if message.is_writable(position) {
+                entry.writers.push(index);
+            } else {
+                entry.readers.push(index);
+            }
+        }
+    }
+
+    let mut accounts: Vec<AccountContention> = locks
+        .into_values()
+        .filter(|lock| !lock.writers.is_empty() && lock.contenders() > 1)
+        .collect();
+    accounts.sort_by(|a, b| {
+        b.writers
+            .len()
+            .cmp(&a.writers.len())
+            .then(b.readers.len().cmp(&a.readers.len()))
+            .then(a.account.cmp(&b.account))
+    });
+
+    // Union the transactions locking each contended account
+    let mut parent: Vec<usize> = (0..batch.len()).collect();
+    for lock in &accounts {
+        let mut members = lock.writers.iter().chain(&lock.readers);
+        let first = *members.next().expect("contended accounts have a writer");
+        for &other in members {
+            let (a, b) = (find(&mut parent, first), find(&mut parent, other));
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut by_root: HashMap<usize, Vec<usize>> = HashMap::new();
+    for index in 0..batch.len() {
+        let root = find(&mut parent, index);
+        by_root.entry(root).or_default().push(index);
+    }
+    let mut report = ContentionReport {
+        accounts,
+        ..Default::default()
+    };
+    for (_, group) in by_root {
+        match group.as_slice() {
+            [single] => report.independent.push(*single),
+            _ => report.groups.push(group),
+        }
+    }
+    report.groups.sort_by_key(|group| group[0]);
+    report.independent.sort_unstable();
+    report
+}
+
+fn find(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+
+    fn tx(payer: u8, writable: &[u8], readonly: &[u8]) -> CompiledTransaction {
+        let mut encoder = InstructionEncoder::new([2u8; 32]);
+        for key in writable {
+            encoder = encoder.writable([*key; 32], false);
+        }
+        for key in readonly {
+            encoder = encoder.readonly([*key; 32]);
+        }
+        TransactionBuilder::new()
+            .payer([payer; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(encoder.build())
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_groups_conflicting_transactions() {
+        let batch = vec![
+            tx(10, &[50], &[]),     // writes pool
+            tx(11, &[50, 60], &[]), // writes pool and vault
+            tx(12, &[], &[60]),     // reads vault: conflicts with 1
+            tx(13, &[70], &[80]),   // independent
+            tx(14, &[], &[80]),     // shares a read lock only
+        ];
+        let report = analyze(&batch);
+
+        assert_eq!(report.groups, vec![vec![0, 1, 2]]);
+        assert_eq!(report.independent, vec![3, 4]);
+        assert_eq!(report.group_of(2), Some(&[0, 1, 2][..]));
+        assert_eq!(report.group_of(4), None);
+
+        let accounts: Vec<[u8; 32]> = report.accounts.iter().map(|a| a.account).collect();
+        assert_eq!(accounts, vec![[50u8; 32], [60u8; 32]]);
+        assert_eq!(report.accounts[1].readers, vec![2]);
+    }
+
+    #[test]
+    fn test_disjoint_batch_is_parallel() {
+        let report = analyze(&[tx(10, &[50], &[2]), tx(11, &[51], &[2])]);
+        assert!(report.is_parallel());
+        assert!(report.accounts.is_empty());
+        assert!(analyze(&[]).is_parallel());
+    }
+}
@@ -0,0 +1,406 @@
+//! Conversion between legacy and version 0 transactions
+//!
+//! Account indices are re-mapped between static and table-based addressing.
+//! Signers and invoked programs always stay static. Because the message bytes
+//! change, existing signatures are kept only if they still verify against the
+//! converted message; all others are zeroed and counted in the report.
+
+use crate::error::{Result, TxAsmError};
+use crate::transaction::{
+    CompiledInstruction, CompiledMessage, CompiledTransaction, MessageHeader, MAX_ACCOUNT_KEYS,
+};
+use crate::versioned::{
+    AddressLookupTable, CompiledMessageV0, CompiledTransactionV0, LoadedAddresses,
+    MessageAddressTableLookup,
+};
+use solana_sdk::signature::Signature;
+use std::collections::HashSet;
+
+/// Summary of a conversion
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionReport {
+    pub original_size: usize,
+    pub converted_size: usize,
+    /// Converted minus original size (negative means smaller)
+    pub size_delta: i64,
+    /// Accounts moved into (or out of) lookup tables
+    pub accounts_moved: usize,
+    /// Signatures still valid over the converted message
+    pub signatures_preserved: usize,
+    /// Non-empty signatures that no longer verify and were zeroed
+    pub signatures_cleared: usize,
+}
+
+/// Remap compiled instructions through an old → new index table
+fn remap_instructions(
+    instructions: &[CompiledInstruction],
+    new_index: &[u8],
+) -> Result<Vec<CompiledInstruction>> {
+    let lookup = |index: u8| {
+        new_index.get(index as usize).copied().ok_or_else(|| {
+            TxAsmError::InvalidInstruction(format!("Account index {} out of range", index))
+        })
+    };
+    instructions
+        .iter()
+        .map(|ix| {
+            Ok(CompiledInstruction {
+                program_id_index: lookup(ix.program_id_index)?,
+                account_indices: ix
+                    .account_indices
+                    .iter()
+                    .map(|&i| lookup(i))
+                    .collect::<Result<_>>()?,
+                data: ix.data.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Keep signatures that verify over `message_bytes`, zero the rest
+fn carry_signatures(
+    signatures: &[[u8; 64]],
+    signer_keys: &[[u8; 32]],
+    message_bytes: &[u8],
+) -> (Vec<[u8; 64]>, usize, usize) {
+    let mut preserved = 0;
+    let mut cleared = 0;
+    let carried = signer_keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| match signatures.get(i) {
+            Some(sig) if *sig != [0u8; 64] => {
+                if Signature::from(*sig).verify(key, message_bytes) {
+                    preserved += 1;
+                    *sig
+                } else {
+                    cleared += 1;
+                    [0u8; 64]
+                }
+            }
+            _ => [0u8; 64],
+        })
+        .collect();
+    (carried, preserved, cleared)
+}
+
+/// Convert a legacy transaction to V0, moving eligible accounts into the given tables
+///
+/// Each non-signer, non-program account found in a table is loaded from the
+/// first table that contains it; everything else stays static.
+pub fn legacy_to_v0(
+    tx: &CompiledTransaction,
+    lookup_tables: &[AddressLookupTable],
+) -> Result<(CompiledTransactionV0, ConversionReport)> {
+    let message = &tx.message;
+    let num_keys = message.account_keys.len();
+
+    // (table position, index in table) for every account moved out of the static list
+    let mut placement: Vec<Option<(usize, u8)>> = vec![None; num_keys];
+    for (i, key) in message.account_keys.iter().enumerate() {
+        if message.is_signer(i) || message.is_program(i) {
+            continue;
+        }
+        placement[i] = lookup_tables
+            .iter()
+            .enumerate()
+            .find_map(|(t, table)| table.index_of(key).map(|j| (t, j)));
+    }
+
+    let mut static_keys = Vec::new();
+    let mut num_readonly_unsigned = 0u8;
+    let mut lookups: Vec<MessageAddressTableLookup> = lookup_tables
+        .iter()
+        .map(|t| MessageAddressTableLookup {
+            account_key: t.key,
+            writable_indexes: Vec::new(),
+            readonly_indexes: Vec::new(),
+        })
+        .collect();
+
+    for (i, key) in message.account_keys.iter().enumerate() {
+        match placement[i] {
+            None => {
+                if !message.is_signer(i) && !message.is_writable(i) {
+                    num_readonly_unsigned += 1;
+                }
+                static_keys.push(*key);
+            }
+            Some((t, j)) if message.is_writable(i) => lookups[t].writable_indexes.push(j),
+            Some((t, j)) => lookups[t].readonly_indexes.push(j),
+        }
+    }
+
+    // Loaded accounts are addressed after the static keys: all writable, then all readonly
+    let mut new_index = vec![0u8; num_keys];
+    let mut next_static = 0usize;
+    let num_static = static_keys.len();
+    let num_loaded_writable: usize = lookups.iter().map(|l| l.writable_indexes.len()).sum();
+    let mut writable_cursor = vec![0usize; lookups.len()];
+    let mut readonly_cursor = vec![0usize; lookups.len()];
+    let writable_base: Vec<usize> = lookups
+        .iter()
+        .scan(num_static, |acc, l| {
+            let base = *acc;
+            *acc += l.writable_indexes.len();
+            Some(base)
+        })
+        .collect();
+    let readonly_base: Vec<usize> = lookups
+        .iter()
+        .scan(num_static + num_loaded_writable, |acc, l| {
+            let base = *acc;
+            *acc += l.readonly_indexes.len();
+            Some(base)
+        })
+        .collect();
+
+    for i in 0..num_keys {
+        let index = match placement[i] {
+            None => {
+                next_static += 1;
+                next_static - 1
+            }
+            Some((t, _)) if message.is_writable(i) => {
+                writable_cursor[t] += 1;
+                writable_base[t] + writable_cursor[t] - 1
+            }
+            Some((t, _)) => {
+                readonly_cursor[t] += 1;
+                readonly_base[t] + readonly_cursor[t] - 1
+            }
+        };
+        new_index[i] = u8::try_from(index).map_err(|_| TxAsmError::AccountIndexOverflow {
+            what: "account index",
+            value: index,
+            max: u8::MAX as usize,
+        })?;
+    }
+
+    lookups.retain(|l| !l.writable_indexes.is_empty() || !l.readonly_indexes.is_empty());
+
+    let converted_message = CompiledMessageV0 {
+        header: MessageHeader::new(
+            message.header.num_required_signatures,
+            message.header.num_readonly_signed_accounts,
+            num_readonly_unsigned,
+        ),
+        account_keys: static_keys,
+        recent_blockhash: message.recent_blockhash,
+        instructions: remap_instructions(&message.instructions, &new_index)?,
+        address_table_lookups: lookups,
+    };
+
+    let mut converted = CompiledTransactionV0 {
+        message: converted_message,
+        signatures: Vec::new(),
+    };
+    let message_bytes = converted.message_bytes()?;
+    let num_signers = message.header.num_required_signatures as usize;
+    let (signatures, signatures_preserved, signatures_cleared) = carry_signatures(
+        &tx.signatures,
+        &converted.message.account_keys[..num_signers.min(num_static)],
+        &message_bytes,
+    );
+    converted.signatures = signatures;
+
+    let original_size = tx.size();
+    let converted_size = converted.size();
+    let report = ConversionReport {
+        original_size,
+        converted_size,
+        size_delta: converted_size as i64 - original_size as i64,
+        accounts_moved: num_keys - num_static,
+        signatures_preserved,
+        signatures_cleared,
+    };
+    Ok((converted, report))
+}
+
+/// Convert a V0 transaction back to legacy using the addresses its lookups resolve to
+pub fn v0_to_legacy(
+    tx: &CompiledTransactionV0,
+    resolved_addresses: &LoadedAddresses,
+) -> Result<(CompiledTransaction, ConversionReport)> {
+    let message = &tx.message;
+    let num_loaded_writable: usize = message
+        .address_table_lookups
+        .iter()
+        .map(|l| l.writable_indexes.len())
+        .sum();
+    if resolved_addresses.writable.len() != num_loaded_writable
+        || resolved_addresses.len() != message.num_loaded_accounts()
+    {
+        return Err(TxAsmError::AccountError(
+            "Resolved addresses do not match the message lookups".to_string(),
+        ));
+    }
+
+    let num_static = message.account_keys.len();
+    let all_keys: Vec<[u8; 32]> = message
+        .account_keys
+        .iter()
+        .chain(resolved_addresses.writable.iter())
+        .chain(resolved_addresses.readonly.iter())
+        .copied()
+        .collect();
+    if all_keys.len() > MAX_ACCOUNT_KEYS {
+        return Err(TxAsmError::AccountIndexOverflow {
+            what: "account keys",
+            value: all_keys.len(),
+            max: MAX_ACCOUNT_KEYS,
+        });
+    }
+    let mut seen = HashSet::new();
+    if let Some(dup) = all_keys.iter().find(|k| !seen.insert(**k)) {
+        return Err(TxAsmError::AccountError(format!(
+            "Duplicate account {} across static and loaded keys",
+            bs58::encode(dup).into_string()
+        )));
+    }
+
+    // Legacy order: signers, writable non-signers, readonly non-signers
+    let num_signers = message.header.num_required_signatures as usize;
+    let static_range = num_signers..num_static;
+    let loaded_writable = num_static..num_static + num_loaded_writable;
+    let loaded_readonly = num_static + num_loaded_writable..all_keys.len();
+    let order: Vec<usize> = (0..num_signers.min(num_static))
+        .chain(static_range.clone().filter(|&i| message.is_writable(i)))
+        .chain(loaded_writable)
+        .chain(static_range.filter(|&i| !message.is_writable(i)))
+        .chain(loaded_readonly)
+        .collect();
+
+    let mut new_index = vec![0u8; all_keys.len()];
+    for (new, &old) in order.iter().enumerate() {
+        new_index[old] = new as u8;
+    }
+
+    let num_readonly_unsigned = order
+        .iter()
+        .filter(|&&i| i >= num_signers && !message.is_writable(i))
+        .count() as u8;
+
+    let converted_message = CompiledMessage {
+        header: MessageHeader::new(
+            message.header.num_required_signatures,
+            message.header.num_readonly_signed_accounts,
+            num_readonly_unsigned,
+        ),
+        account_keys: order.iter().map(|&i| all_keys[i]).collect(),
+        recent_blockhash: message.recent_blockhash,
+        instructions: remap_instructions(&message.instructions, &new_index)?,
+    };
+
+    let mut converted = CompiledTransaction {
+        message: converted_message,
+        signatures: Vec::new(),
+    };
+    let message_bytes = converted.message_bytes()?;
+    let (signatures, signatures_preserved, signatures_cleared) = carry_signatures(
+        &tx.signatures,
+        converted.required_signers(),
+        &message_bytes,
+    );
+    converted.signatures = signatures;
+
+    let original_size = tx.size();
+    let converted_size = converted.size();
+    let report = ConversionReport {
+        original_size,
+        converted_size,
+        size_delta: converted_size as i64 - original_size as i64,
+        accounts_moved: resolved_addresses.len(),
+        signatures_preserved,
+        signatures_cleared,
+    };
+    Ok((converted, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+
+    fn sample_tx() -> CompiledTransaction {
+        let instruction = InstructionEncoder::new([2u8; 32])
+            .writable([10u8; 32], false)
+            .readonly([11u8; 32])
+            .writable([12u8; 32], false)
+            .append_u8(7)
+            .build();
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_legacy_to_v0_moves_table_accounts() {
+        let tx = sample_tx();
+        let tables = [AddressLookupTable::new([9u8; 32], vec![[11u8; 32], [10u8; 32]])];
+        let (v0, report) = legacy_to_v0(&tx, &tables).unwrap();
+
+        assert_eq!(report.accounts_moved, 2);
+        assert_eq!(v0.message.account_keys.len(), 3);
+        assert_eq!(v0.message.address_table_lookups[0].writable_indexes, vec![1]);
+        assert_eq!(v0.message.address_table_lookups[0].readonly_indexes, vec![0]);
+        assert_eq!(report.size_delta, v0.size() as i64 - tx.size() as i64);
+
+        // Instruction still references the same keys in the same roles
+        let loaded = v0.message.resolve(&tables).unwrap();
+        let keys: Vec<[u8; 32]> = v0
+            .message
+            .account_keys
+            .iter()
+            .chain(loaded.writable.iter())
+            .chain(loaded.readonly.iter())
+            .copied()
+            .collect();
+        let ix = &v0.message.instructions[0];
+        assert_eq!(keys[ix.program_id_index as usize], [2u8; 32]);
+        let referenced: Vec<[u8; 32]> = ix.account_indices.iter().map(|&i| keys[i as usize]).collect();
+        assert_eq!(referenced, vec![[10u8; 32], [11u8; 32], [12u8; 32]]);
+        assert!(v0.message.is_writable(ix.account_indices[0] as usize));
+        assert!(!v0.message.is_writable(ix.account_indices[1] as usize));
+    }
+
+    #[test]
+    fn test_v0_roundtrip_to_legacy() {
+        let tx = sample_tx();
+        let tables = [AddressLookupTable::new([9u8; 32], vec![[10u8; 32], [11u8; 32]])];
+        let (v0, _) = legacy_to_v0(&tx, &tables).unwrap();
+        let loaded = v0.message.resolve(&tables).unwrap();
+        let (legacy, report) = v0_to_legacy(&v0, &loaded).unwrap();
+
+        // Static writable keys precede loaded ones, so order may differ but roles must not
+        let ix = &legacy.message.instructions[0];
+        let keys = &legacy.message.account_keys;
+        assert_eq!(keys[ix.program_id_index as usize], [2u8; 32]);
+        let referenced: Vec<([u8; 32], bool)> = ix
+            .account_indices
+            .iter()
+            .map(|&i| (keys[i as usize], legacy.message.is_writable(i as usize)))
+            .collect();
+        assert_eq!(
+            referenced,
+            vec![([10u8; 32], true), ([11u8; 32], false), ([12u8; 32], true)]
+        );
+        assert_eq!(legacy.message.header.num_readonly_unsigned_accounts, 2);
+        assert_eq!(legacy.size(), tx.size());
+        assert_eq!(report.accounts_moved, 2);
+        assert!(v0_to_legacy(&v0, &LoadedAddresses::default()).is_err());
+    }
+
+    #[test]
+    fn test_conversion_clears_stale_signatures() {
+        let mut tx = sample_tx();
+        tx.signatures[0] = [1u8; 64];
+        let (v0, report) = legacy_to_v0(&tx, &[]).unwrap();
+        assert_eq!(report.signatures_cleared, 1);
+        assert_eq!(v0.signatures, vec![[0u8; 64]]);
+    }
+}
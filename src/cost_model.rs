@@ -0,0 +1,250 @@
+//! Block cost model
+//!
+//! Mirrors the validator's cost tracker closely enough to size batches
+//! before submission: every transaction is charged for signatures, write
+//! locks, instruction data and requested compute, and a block caps both the
+//! total and the units charged against any one writable account.
+
+use crate::convert::v0_to_legacy;
+use crate::error::Result;
+use crate::fee_calculator::compute_budget;
+use crate::transaction::CompiledTransaction;
+use crate::versioned::{CompiledTransactionV0, LoadedAddresses};
+use std::collections::HashMap;
+
+/// Cost units a block may hold
+pub const MAX_BLOCK_UNITS: u64 = 48_000_000;
+
+/// Cost units a block may charge against a single writable account
+pub const MAX_WRITABLE_ACCOUNT_UNITS: u64 = 12_000_000;
+
+/// Cost units per signature
+pub const SIGNATURE_COST: u64 = 720;
+
+/// Cost units per write-locked account
+pub const WRITE_LOCK_UNITS: u64 = 300;
+
+/// Instruction data bytes per cost unit
+pub const INSTRUCTION_DATA_BYTES_COST: u64 = 4;
+
+/// Compute units charged per instruction without an explicit limit
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+
+/// Upper bound on a transaction's compute unit limit
+pub const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// Cost units charged for one transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionCost {
+    pub signature_cost: u64,
+    pub write_lock_cost: u64,
+    pub data_bytes_cost: u64,
+    pub execution_cost: u64,
+    pub writable_accounts: Vec<[u8; 32]>,
+}
+
+impl TransactionCost {
+    pub fn total(&self) -> u64 {
+        self.signature_cost + self.write_lock_cost + self.data_bytes_cost + self.execution_cost
+    }
+}
+
+/// Compute the block cost of `transaction`
+///
+/// Execution cost is the `SetComputeUnitLimit` request if present, otherwise
+/// the default limit per non-compute-budget instruction.
+pub fn transaction_cost(transaction: &CompiledTransaction) -> TransactionCost {
+    let message = &transaction.message;
+    let writable_accounts: Vec<[u8; 32]> = message
+        .account_keys
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| message.is_writable(*i))
+        .map(|(_, key)| *key)
+        .collect();
+
+    let data_bytes: u64 = message.instructions.iter().map(|i| i.data.len() as u64).sum();
+
+    let budget = compute_budget::requested_budget(message);
+    let execution_cost = budget
+        .unit_limit
+        .map(u64::from)
+        .unwrap_or(budget.num_program_instructions as u64 * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+
+    TransactionCost {
+        signature_cost: message.header.num_required_signatures as u64 * SIGNATURE_COST,
+        write_lock_cost: writable_accounts.len() as u64 * WRITE_LOCK_UNITS,
+        data_bytes_cost: data_bytes / INSTRUCTION_DATA_BYTES_COST,
+        execution_cost,
+        writable_accounts,
+    }
+}
+
+/// Compute the block cost of a V0 transaction whose lookups resolve to `loaded`
+///
+/// Loaded writable accounts take write locks like static ones, so they are
+/// charged and listed in `writable_accounts`.
+pub fn transaction_cost_v0(transaction: &CompiledTransactionV0, loaded: &LoadedAddresses) -> Result<TransactionCost> {
+    let (legacy, _) = v0_to_legacy(transaction, loaded)?;
+    Ok(transaction_cost(&legacy))
+}
+
+/// Block capacity used by [`fits_in_block_with`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockLimits {
+    pub max_block_units: u64,
+    pub max_writable_account_units: u64,
+}
+
+impl Default for BlockLimits {
+    fn default() -> Self {
+        Self {
+            max_block_units: MAX_BLOCK_UNITS,
+            max_writable_account_units: MAX_WRITABLE_ACCOUNT_UNITS,
+        }
+    }
+}
+
+/// Why a transaction would not fit in the block
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpillReason {
+    /// The block's total cost limit would be exceeded
+    BlockLimit,
+    /// The per-account limit for this writable account would be exceeded
+    AccountLimit([u8; 32]),
+}
+
+/// Result of packing a batch into one block
+#[derive(Debug, Clone, Default)]
+pub struct BlockFit {
+    /// Batch indices that fit, in order
+    pub included: Vec<usize>,
+    /// Batch indices that would spill into the next block
+    pub spilled: Vec<(usize, SpillReason)>,
+    /// Cost units used by the included transactions
+    pub block_units: u64,
+    /// Cost units charged per writable account
+    pub account_units: HashMap<[u8; 32], u64>,
+}
+
+impl BlockFit {
+    pub fn all_fit(&self) -> bool {
+        self.spilled.is_empty()
+    }
+}
+
+/// Pack `batch` into one block with mainnet limits
+pub fn fits_in_block(batch: &[CompiledTransaction]) -> BlockFit {
+    fits_in_block_with(batch, &BlockLimits::default())
+}
+
+/// Pack `batch` into one block, in order, with the given limits
+pub fn fits_in_block_with(batch: &[CompiledTransaction], limits: &BlockLimits) -> BlockFit {
+    let mut fit = BlockFit::default();
+    for (index, transaction) in batch.iter().enumerate() {
+        let cost = transaction_cost(transaction);
+        let total = cost.total();
+
+        if fit.block_units + total > limits.max_block_units {
+            fit.spilled.push((index, SpillReason::BlockLimit));
+            continue;
+        }
+        let contended = cost.writable_accounts.iter().find(|key| {
+            fit.account_units.get(*key).copied().unwrap_or(0) + total
+                > limits.max_writable_account_units
+        });
+        if let Some(key) = contended {
+            fit.spilled.push((index, SpillReason::AccountLimit(*key)));
+            continue;
+        }
+
+        fit.block_units += total;
+        for key in cost.writable_accounts {
+            *fit.account_units.entry(key).or_insert(0) += total;
+        }
+        fit.included.push(index);
+    }
+    fit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+    use crate::instruction::{InstructionEncoder, RawInstruction};
+    use crate::transaction::TransactionBuilder;
+
+    fn swap(payer: [u8; 32], pool: [u8; 32], cu_limit: u32) -> CompiledTransaction {
+        let limit = RawInstruction::new(
+            COMPUTE_BUDGET_PROGRAM_ID,
+            vec![],
+            compute_budget::create_compute_unit_limit_instruction(cu_limit),
+        );
+        let swap = InstructionEncoder::new([2u8; 32])
+            .writable(pool, false)
+            .append_u64(1)
+            .build();
+        TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([3u8; 32])
+            .add_instruction(limit)
+            .add_instruction(swap)
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_transaction_cost() {
+        let cost = transaction_cost(&swap([1u8; 32], [5u8; 32], 100_000));
+        assert_eq!(cost.signature_cost, SIGNATURE_COST);
+        assert_eq!(cost.write_lock_cost, 2 * WRITE_LOCK_UNITS);
+        assert_eq!(cost.execution_cost, 100_000);
+        assert_eq!(cost.data_bytes_cost, (5 + 8) / INSTRUCTION_DATA_BYTES_COST);
+    }
+
+    #[test]
+    fn test_v0_cost_counts_loaded_write_locks() {
+        use crate::convert::legacy_to_v0;
+        use crate::fee_calculator::{FeeStrategy, PriorityFeeCalculator};
+        use crate::versioned::AddressLookupTable;
+
+        let legacy = swap([1u8; 32], [5u8; 32], 100_000);
+        let tables = [AddressLookupTable::new([9u8; 32], vec![[5u8; 32]])];
+        let (v0, _) = legacy_to_v0(&legacy, &tables).unwrap();
+        let loaded = v0.message.resolve(&tables).unwrap();
+        assert_eq!(loaded.writable, vec![[5u8; 32]]);
+
+        let cost = transaction_cost_v0(&v0, &loaded).unwrap();
+        assert_eq!(cost, transaction_cost(&legacy));
+        assert!(cost.writable_accounts.contains(&[5u8; 32]));
+
+        let calculator = PriorityFeeCalculator::new();
+        assert_eq!(
+            calculator.estimate_compute_units_v0(&v0),
+            calculator.estimate_compute_units(&legacy)
+        );
+        let estimate = calculator.estimate_fee_v0(&v0, &loaded, FeeStrategy::High).unwrap();
+        assert_eq!(estimate.total_cost, calculator.estimate_fee(&legacy, FeeStrategy::High).unwrap().total_cost);
+        assert!(calculator.estimate_fee_v0(&v0, &LoadedAddresses::default(), FeeStrategy::High).is_err());
+    }
+
+    #[test]
+    fn test_hot_account_spills() {
+        let limits = BlockLimits {
+            max_block_units: 1_000_000,
+            max_writable_account_units: 250_000,
+        };
+        let batch = vec![
+            swap([1u8; 32], [5u8; 32], 100_000),
+            swap([6u8; 32], [5u8; 32], 100_000),
+            swap([7u8; 32], [5u8; 32], 100_000),
+            swap([8u8; 32], [9u8; 32], 100_000),
+        ];
+
+        let fit = fits_in_block_with(&batch, &limits);
+        assert_eq!(fit.included, vec![0, 1, 3]);
+        assert_eq!(fit.spilled, vec![(2, SpillReason::AccountLimit([5u8; 32]))]);
+        assert!(fits_in_block(&batch).all_fit());
+    }
+}
@@ -0,0 +1,225 @@
+//! Cross-program invocation depth and size estimates
+//!
+//! The runtime caps how deep invocations nest and how many instructions,
+//! inner ones included, a transaction may execute. Neither is visible in
+//! the compiled message. A [`CpiRegistry`] records, per program, which
+//! programs one invocation calls in turn, as known to the user. With it,
+//! [`CpiRegistry::estimate`] walks each top-level instruction's call tree
+//! and [`CpiEstimate::suggestions`] flags transactions likely to hit a
+//! limit. Attach a registry with
+//! [`TransactionOptimizer::with_cpi_registry`](crate::optimizer::TransactionOptimizer::with_cpi_registry)
+//! to have [`analyze`](crate::optimizer::TransactionOptimizer::analyze)
+//! report them.
+//!
+//! Programs missing from the registry are assumed to make no calls.
+
+use crate::optimizer::{Severity, Suggestion, MAX_TX_ACCOUNT_LOCKS};
+use crate::transaction::CompiledMessage;
+use std::collections::{BTreeSet, HashMap};
+
+/// Deepest invoke stack, counting the top-level instruction as height 1
+pub const MAX_INVOKE_STACK_HEIGHT: usize = 5;
+
+/// Instructions a transaction may execute, inner instructions included
+pub const MAX_INSTRUCTION_TRACE_LENGTH: usize = 64;
+
+/// Known fan-out of each program
+#[derive(Debug, Clone, Default)]
+pub struct CpiRegistry {
+    programs: HashMap<[u8; 32], Vec<[u8; 32]>>,
+}
+
+impl CpiRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// One invocation of `program_id` invokes each of `invokes`, in order
+    /// (list a program twice if it is called twice)
+    pub fn program(mut self, program_id: [u8; 32], invokes: Vec<[u8; 32]>) -> Self {
+        self.programs.insert(program_id, invokes);
+        self
+    }
+
+    /// Walk the call tree of every instruction of `message`
+    pub fn estimate(&self, message: &CompiledMessage) -> CpiEstimate {
+        let mut estimate = CpiEstimate {
+            trace_length: message.instructions.len(),
+            ..Default::default()
+        };
+        let mut reached = BTreeSet::new();
+        for (index, ix) in message.instructions.iter().enumerate() {
+            let Some(program) = message.account_keys.get(ix.program_id_index as usize) else {
+                continue;
+            };
+            let (inner, height) = self.walk(program, 1, &mut reached);
+            estimate.inner_instructions += inner;
+            if height > estimate.max_stack_height {
+                estimate.max_stack_height = height;
+                estimate.deepest_instruction = Some(index);
+            }
+        }
+        estimate.trace_length += estimate.inner_instructions;
+        estimate.missing_programs = reached
+            .into_iter()
+            .filter(|program| !message.account_keys.contains(program))
+            .collect();
+        estimate.loaded_accounts = message.account_keys.len() + estimate.missing_programs.len();
+        estimate
+    }
+
+    /// Inner instructions below `program` at `height`, and the deepest
+    /// height reached; recursion stops one level past the limit so cycles
+    /// terminate
+    fn walk(&self, program: &[u8; 32], height: usize, reached: &mut BTreeSet<[u8; 32]>) -> (usize, usize) {
+        let mut inner = 0;
+        let mut deepest = height;
+        if height > MAX_INVOKE_STACK_HEIGHT {
+            return (inner, deepest);
+        }
+        for callee in self.programs.get(program).into_iter().flatten() {
+            reached.insert(*callee);
+            let (below, callee_height) = self.walk(callee, height + 1, reached);
+            inner += 1 + below;
+            deepest = deepest.max(callee_height);
+        }
+        (inner, deepest)
+    }
+}
+
+/// Result of [`CpiRegistry::estimate`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpiEstimate {
+    /// Inner instructions expected across the transaction
+    pub inner_instructions: usize,
+    /// Top-level plus inner instructions
+    pub trace_length: usize,
+    /// Deepest invoke stack; top-level instructions are height 1
+    pub max_stack_height: usize,
+    /// Top-level instruction reaching `max_stack_height`
+    pub deepest_instruction: Option<usize>,
+    /// Programs reached through CPI that the message does not load
+    pub missing_programs: Vec<[u8; 32]>,
+    /// Message accounts plus the missing programs
+    pub loaded_accounts: usize,
+}
+
+impl CpiEstimate {
+    /// Findings for [`TransactionAnalysis`](crate::optimizer::TransactionAnalysis)
+    pub fn suggestions(&self) -> Vec<Suggestion> {
+        let mut suggestions = Vec::new();
+        let critical = |code, message: String| Suggestion {
+            code,
+            severity: Severity::Critical,
+            message,
+            estimated_savings_bytes: 0,
+            estimated_savings_lamports: 0,
+        };
+
+        let instruction = self.deepest_instruction.unwrap_or_default();
+        if self.max_stack_height > MAX_INVOKE_STACK_HEIGHT {
+            suggestions.push(critical(
+                "cpi-depth",
+                format!(
+                    "Instruction {} nests invocations past the {}-level limit",
+                    instruction, MAX_INVOKE_STACK_HEIGHT
+                ),
+            ));
+        } else if self.max_stack_height == MAX_INVOKE_STACK_HEIGHT {
+            suggestions.push(Suggestion {
+                severity: Severity::Warning,
+                ..critical(
+                    "cpi-depth",
+                    format!("Instruction {} reaches the invoke depth limit; any deeper call fails", instruction),
+                )
+            });
+        }
+        if self.trace_length > MAX_INSTRUCTION_TRACE_LENGTH {
+            suggestions.push(critical(
+                "cpi-trace-length",
+                format!(
+                    "About {} instructions including CPIs, over the {} limit; split the transaction",
+                    self.trace_length, MAX_INSTRUCTION_TRACE_LENGTH
+                ),
+            ));
+        }
+        for program in &self.missing_programs {
+            suggestions.push(critical(
+                "cpi-missing-program",
+                format!("Program {} is invoked through CPI but not loaded", bs58::encode(program).into_string()),
+            ));
+        }
+        if self.loaded_accounts > MAX_TX_ACCOUNT_LOCKS {
+            suggestions.push(critical(
+                "loaded-accounts",
+                format!(
+                    "{} accounts loaded, over the {} account limit",
+                    self.loaded_accounts, MAX_TX_ACCOUNT_LOCKS
+                ),
+            ));
+        }
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::optimizer::TransactionOptimizer;
+    use crate::testkit;
+    use crate::transaction::{CompiledTransaction, TransactionBuilder};
+
+    fn call(programs: &[u64]) -> CompiledTransaction {
+        let (first, rest) = programs.split_first().unwrap();
+        let ix = rest
+            .iter()
+            .fold(InstructionEncoder::new(testkit::address(*first)), |ix, program| ix.readonly(testkit::address(*program)));
+        TransactionBuilder::new()
+            .payer(testkit::pubkey(1))
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(ix.build())
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_estimate_inner_instructions_and_depth() {
+        let p = testkit::address;
+        // Router -> (AMM -> token, token), token
+        let registry = CpiRegistry::new()
+            .program(p(1), vec![p(2), p(3)])
+            .program(p(2), vec![p(3), p(3)]);
+        let estimate = registry.estimate(&call(&[1, 2, 3]).message);
+        assert_eq!(estimate.inner_instructions, 4);
+        assert_eq!(estimate.trace_length, 5);
+        assert_eq!(estimate.max_stack_height, 3);
+        assert!(estimate.missing_programs.is_empty());
+        assert!(estimate.suggestions().is_empty());
+
+        // Token program not passed in
+        let estimate = registry.estimate(&call(&[1, 2]).message);
+        assert_eq!(estimate.missing_programs, vec![p(3)]);
+        assert_eq!(estimate.suggestions()[0].code, "cpi-missing-program");
+    }
+
+    #[test]
+    fn test_analysis_flags_depth_and_cycles() {
+        let p = testkit::address;
+        let chain = CpiRegistry::new()
+            .program(p(1), vec![p(2)])
+            .program(p(2), vec![p(3)])
+            .program(p(3), vec![p(4)])
+            .program(p(4), vec![p(5)]);
+        let tx = call(&[1, 2, 3, 4, 5]);
+        let estimate = chain.estimate(&tx.message);
+        assert_eq!(estimate.max_stack_height, 5);
+        assert_eq!(estimate.suggestions()[0].severity, Severity::Warning);
+
+        // A call cycle nests past the limit
+        let cyclic = chain.program(p(5), vec![p(1)]);
+        let analysis = TransactionOptimizer::default().with_cpi_registry(cyclic).analyze(&tx);
+        let depth = analysis.suggestions.iter().find(|s| s.code == "cpi-depth").unwrap();
+        assert_eq!(depth.severity, Severity::Critical);
+    }
+}
@@ -0,0 +1,166 @@
+//! Swap instruction registry
+//!
+//! Swap layouts differ per venue but are all "fixed program, fixed account
+//! order, discriminator plus a few amounts". A [`DexRegistry`] maps venue
+//! names to [`TemplateInstruction`]s, registered in code or from JSON
+//! config, so new venues need no crate changes:
+//!
+//! ```json
+//! [{
+//!   "name": "raydium-v4",
+//!   "program_id": "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+//!   "accounts": [
+//!     {"name": "token_program", "key": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"},
+//!     {"name": "amm", "writable": true},
+//!     {"name": "owner", "signer": true}
+//!   ],
+//!   "data": [{"bytes": [9]}, {"u64": "amount_in"}, {"u64": "minimum_out"}]
+//! }]
+//! ```
+//!
+//! Accounts without a `key` are placeholders bound by name at swap time.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::RawInstruction;
+use crate::serialization::parse_pubkey;
+use crate::template::{DataField, TemplateBindings, TemplateInstruction, TemplateKey};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// One account slot of a venue's swap instruction
+#[derive(Debug, Clone, Deserialize)]
+pub struct VenueAccount {
+    /// Binding name when `key` is unset
+    pub name: String,
+    /// Fixed base58 address
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub signer: bool,
+    #[serde(default)]
+    pub writable: bool,
+}
+
+/// Config form of a venue's swap instruction
+#[derive(Debug, Clone, Deserialize)]
+pub struct VenueConfig {
+    pub name: String,
+    /// Base58 program address
+    pub program_id: String,
+    pub accounts: Vec<VenueAccount>,
+    pub data: Vec<DataField>,
+}
+
+impl VenueConfig {
+    /// Convert to a template, decoding fixed addresses
+    pub fn to_template(&self) -> Result<TemplateInstruction> {
+        let mut template = TemplateInstruction::new(TemplateKey::Fixed(parse_pubkey(&self.program_id)?));
+        for account in &self.accounts {
+            let key = match &account.key {
+                Some(key) => TemplateKey::Fixed(parse_pubkey(key)?),
+                None => TemplateKey::Placeholder(account.name.clone()),
+            };
+            template = template.account(key, account.signer, account.writable);
+        }
+        template.data = self.data.clone();
+        Ok(template)
+    }
+}
+
+/// Venue name to swap template registry
+#[derive(Debug, Clone, Default)]
+pub struct DexRegistry {
+    venues: BTreeMap<String, TemplateInstruction>,
+}
+
+impl DexRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a JSON array of [`VenueConfig`]s
+    pub fn from_json(json: &str) -> Result<Self> {
+        let configs: Vec<VenueConfig> =
+            serde_json::from_str(json).map_err(|e| TxAsmError::DeserializationError(e.to_string()))?;
+        let mut registry = Self::new();
+        for config in &configs {
+            registry.register_config(config)?;
+        }
+        Ok(registry)
+    }
+
+    /// Register (or replace) a venue's swap template
+    pub fn register(&mut self, venue: impl Into<String>, template: TemplateInstruction) -> &mut Self {
+        self.venues.insert(venue.into(), template);
+        self
+    }
+
+    /// Register a venue from its config form
+    pub fn register_config(&mut self, config: &VenueConfig) -> Result<&mut Self> {
+        let template = config.to_template()?;
+        Ok(self.register(config.name.clone(), template))
+    }
+
+    /// Registered venue names, sorted
+    pub fn venues(&self) -> impl Iterator<Item = &str> {
+        self.venues.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, venue: &str) -> Option<&TemplateInstruction> {
+        self.venues.get(venue)
+    }
+
+    /// Build `venue`'s swap instruction from pool accounts and amounts
+    pub fn swap(&self, venue: &str, bindings: &TemplateBindings) -> Result<RawInstruction> {
+        self.venues
+            .get(venue)
+            .ok_or_else(|| TxAsmError::InvalidInstruction(format!("Unknown venue '{}'", venue)))?
+            .resolve(bindings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"[{
+        "name": "raydium-v4",
+        "program_id": "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+        "accounts": [
+            {"name": "token_program", "key": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"},
+            {"name": "amm", "writable": true},
+            {"name": "owner", "signer": true}
+        ],
+        "data": [{"bytes": [9]}, {"u64": "amount_in"}, {"u64": "minimum_out"}]
+    }]"#;
+
+    #[test]
+    fn test_swap_from_config() {
+        let registry = DexRegistry::from_json(CONFIG).unwrap();
+        assert_eq!(registry.venues().collect::<Vec<_>>(), vec!["raydium-v4"]);
+
+        let bindings = TemplateBindings::new()
+            .key("amm", [5u8; 32])
+            .key("owner", [1u8; 32])
+            .value("amount_in", 1_000)
+            .value("minimum_out", 990);
+        let ix = registry.swap("raydium-v4", &bindings).unwrap();
+
+        assert_eq!(ix.program_id, parse_pubkey("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8").unwrap());
+        assert_eq!(ix.accounts[0].pubkey, parse_pubkey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap());
+        assert!(ix.accounts[1].is_writable && !ix.accounts[1].is_signer);
+        assert!(ix.accounts[2].is_signer);
+        assert_eq!(ix.data[0], 9);
+        assert_eq!(&ix.data[1..9], &1_000u64.to_le_bytes());
+        assert_eq!(&ix.data[9..], &990u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_swap_errors() {
+        let registry = DexRegistry::from_json(CONFIG).unwrap();
+        let partial = TemplateBindings::new().key("amm", [5u8; 32]);
+        assert!(registry.swap("raydium-v4", &partial).is_err());
+        assert!(registry.swap("orca", &partial).is_err());
+        assert!(DexRegistry::from_json(r#"[{"name":"x","program_id":"bad!","accounts":[],"data":[]}]"#).is_err());
+    }
+}
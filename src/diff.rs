@@ -0,0 +1,192 @@
+//! Account list diffs between messages
+//!
+//! [`account_diff`] compares the accounts two messages lock, by key, and
+//! reports what was added, removed or given a different signer/writable role.
+//! Index moves alone are not differences: an optimizer pass or a V0
+//! conversion may reorder keys freely, but it must not change the set of
+//! accounts or their roles. V0 messages are compared together with the
+//! addresses their lookups resolve to.
+
+use crate::transaction::CompiledMessage;
+use crate::versioned::{CompiledMessageV0, LoadedAddresses};
+use std::collections::{HashMap, HashSet};
+
+/// An account's position and role in a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountRole {
+    pub index: usize,
+    pub key: [u8; 32],
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Messages whose accounts can be listed with their roles
+pub trait MessageAccounts {
+    /// Every account in index order
+    fn account_roles(&self) -> Vec<AccountRole>;
+}
+
+impl MessageAccounts for CompiledMessage {
+    fn account_roles(&self) -> Vec<AccountRole> {
+        (0..self.account_keys.len()).filter_map(|index| self.account_role(index)).collect()
+    }
+}
+
+/// A V0 message with its resolved lookup addresses
+impl MessageAccounts for (&CompiledMessageV0, &LoadedAddresses) {
+    fn account_roles(&self) -> Vec<AccountRole> {
+        let (message, loaded) = *self;
+        message
+            .account_keys
+            .iter()
+            .chain(&loaded.writable)
+            .chain(&loaded.readonly)
+            .enumerate()
+            .map(|(index, key)| AccountRole {
+                index,
+                key: *key,
+                is_signer: message.is_signer(index),
+                is_writable: message.is_writable(index),
+            })
+            .collect()
+    }
+}
+
+/// An account present in both messages with different roles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoleChange {
+    pub before: AccountRole,
+    pub after: AccountRole,
+}
+
+/// Differences between the account lists of two messages
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountDiff {
+    /// Accounts only in the second message, with their indices there
+    pub added: Vec<AccountRole>,
+    /// Accounts only in the first message, with their indices there
+    pub removed: Vec<AccountRole>,
+    pub role_changed: Vec<RoleChange>,
+    /// Accounts in both messages at different indices but with the same role
+    pub moved: usize,
+}
+
+impl AccountDiff {
+    /// Whether both messages lock the same accounts with the same roles
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.role_changed.is_empty()
+    }
+}
+
+impl std::fmt::Display for AccountDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let role = |r: &AccountRole| match (r.is_signer, r.is_writable) {
+            (true, true) => "signer, writable",
+            (true, false) => "signer, readonly",
+            (false, true) => "writable",
+            (false, false) => "readonly",
+        };
+        for r in &self.added {
+            writeln!(f, "+ [{}] {} ({})", r.index, bs58::encode(r.key).into_string(), role(r))?;
+        }
+        for r in &self.removed {
+            writeln!(f, "- [{}] {} ({})", r.index, bs58::encode(r.key).into_string(), role(r))?;
+        }
+        for change in &self.role_changed {
+            writeln!(
+                f,
+                "~ [{} -> {}] {} ({} -> {})",
+                change.before.index,
+                change.after.index,
+                bs58::encode(change.before.key).into_string(),
+                role(&change.before),
+                role(&change.after)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare the accounts of `a` and `b` by key
+pub fn account_diff(a: &impl MessageAccounts, b: &impl MessageAccounts) -> AccountDiff {
+    let before = a.account_roles();
+    let after = b.account_roles();
+    let after_by_key: HashMap<[u8; 32], AccountRole> = after.iter().map(|r| (r.key, *r)).collect();
+
+    let mut diff = AccountDiff::default();
+    for old in &before {
+        match after_by_key.get(&old.key) {
+            None => diff.removed.push(*old),
+            Some(new) if (old.is_signer, old.is_writable) != (new.is_signer, new.is_writable) => {
+                diff.role_changed.push(RoleChange { before: *old, after: *new })
+            }
+            Some(new) if old.index != new.index => diff.moved += 1,
+            Some(_) => {}
+        }
+    }
+    let before_keys: HashSet<[u8; 32]> = before.iter().map(|r| r.key).collect();
+    diff.added = after.into_iter().filter(|r| !before_keys.contains(&r.key)).collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::legacy_to_v0;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use crate::versioned::AddressLookupTable;
+
+    fn message(accounts: &[([u8; 32], bool)]) -> CompiledMessage {
+        let mut encoder = InstructionEncoder::new([2u8; 32]);
+        for (key, writable) in accounts {
+            encoder = if *writable { encoder.writable(*key, false) } else { encoder.readonly(*key) };
+        }
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(encoder.build())
+            .build_unsigned()
+            .unwrap()
+            .message
+    }
+
+    #[test]
+    fn test_account_diff() {
+        let a = message(&[([10u8; 32], true), ([11u8; 32], false), ([12u8; 32], true)]);
+        let b = message(&[([10u8; 32], false), ([11u8; 32], false), ([13u8; 32], true)]);
+        let diff = account_diff(&a, &b);
+
+        assert_eq!(diff.added.iter().map(|r| r.key).collect::<Vec<_>>(), vec![[13u8; 32]]);
+        assert_eq!(diff.removed.iter().map(|r| r.key).collect::<Vec<_>>(), vec![[12u8; 32]]);
+        assert_eq!(diff.role_changed.len(), 1);
+        let change = diff.role_changed[0];
+        assert_eq!(change.before.key, [10u8; 32]);
+        assert!(change.before.is_writable && !change.after.is_writable);
+        assert!(diff.to_string().contains("(writable -> readonly)"));
+        assert!(account_diff(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn test_v0_conversion_preserves_accounts() {
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(
+                InstructionEncoder::new([2u8; 32])
+                    .readonly([10u8; 32])
+                    .writable([11u8; 32], false)
+                    .writable([12u8; 32], false)
+                    .build(),
+            )
+            .build_unsigned()
+            .unwrap();
+        let tables = [AddressLookupTable::new([9u8; 32], vec![[10u8; 32], [11u8; 32]])];
+        let (v0, _) = legacy_to_v0(&tx, &tables).unwrap();
+        let loaded = v0.message.resolve(&tables).unwrap();
+
+        let diff = account_diff(&tx.message, &(&v0.message, &loaded));
+        assert!(diff.is_empty(), "{}", diff);
+        assert!(diff.moved > 0);
+    }
+}
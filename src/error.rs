@@ -40,11 +40,28 @@ pub enum TxAsmError {
         available: usize,
     },
 
+    #[error("Account index overflow: {what} is {value}, max {max}")]
+    AccountIndexOverflow {
+        what: &'static str,
+        value: usize,
+        max: usize,
+    },
+
+    #[error("Length overflow: {what} has {len} entries, max {max}")]
+    LengthOverflow {
+        what: &'static str,
+        len: usize,
+        max: usize,
+    },
+
     #[error("Invalid public key: {0}")]
     InvalidPublicKey(String),
 
     #[error("Solana SDK error: {0}")]
     SolanaError(String),
+
+    #[error("Submission failed: {0}")]
+    Submit(#[from] crate::submit::SubmitError),
 }
 
 impl From<std::io::Error> for TxAsmError {
@@ -0,0 +1,197 @@
+//! Transaction expiry by last valid block height
+//!
+//! A transaction can land only while the block height is at or below the
+//! last valid block height returned with its blockhash by
+//! `getLatestBlockhash`. [`ExpiryTracker`] records that height for each
+//! built transaction, keyed by its signature, so retry logic can ask whether
+//! resubmitting still has a chance before sending again.
+//!
+//! Entries are keyed by the fee payer's signature, which does not exist until
+//! the message is signed. [`ExpiryTracker::build_and_track`] supplies the
+//! height at build time by compiling, signing and recording in one step;
+//! [`ExpiryTracker::track`] records transactions signed elsewhere.
+
+use crate::error::{Result, TxAsmError};
+use crate::transaction::{CompiledTransaction, TransactionBuilder};
+use solana_sdk::signature::Keypair;
+use std::collections::HashMap;
+
+/// Lifetime of one tracked transaction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Expiry {
+    pub blockhash: [u8; 32],
+    pub last_valid_block_height: u64,
+}
+
+impl Expiry {
+    pub fn is_expired(&self, current_height: u64) -> bool {
+        current_height > self.last_valid_block_height
+    }
+
+    /// Blocks left in which the transaction can still land, `None` once expired
+    pub fn expires_in(&self, current_height: u64) -> Option<u64> {
+        self.last_valid_block_height.checked_sub(current_height)
+    }
+}
+
+/// Expiry of built transactions, keyed by their first signature
+#[derive(Debug, Clone, Default)]
+pub struct ExpiryTracker {
+    entries: HashMap<[u8; 64], Expiry>,
+}
+
+impl ExpiryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track a signed transaction whose blockhash is valid through
+    /// `last_valid_block_height`, returning its signature
+    ///
+    /// Re-tracking a signature replaces its entry.
+    pub fn track(&mut self, transaction: &CompiledTransaction, last_valid_block_height: u64) -> Result<[u8; 64]> {
+        let signature = match transaction.signatures.first() {
+            Some(signature) if *signature != [0u8; 64] => *signature,
+            _ => {
+                return Err(TxAsmError::SignatureError(
+                    "Only signed transactions can be tracked".to_string(),
+                ))
+            }
+        };
+        self.entries.insert(
+            signature,
+            Expiry {
+                blockhash: transaction.message.recent_blockhash,
+                last_valid_block_height,
+            },
+        );
+        Ok(signature)
+    }
+
+    /// Build and sign `builder`, tracking the result as valid through
+    /// `last_valid_block_height`
+    ///
+    /// Nothing is recorded if building or signing fails.
+    pub fn build_and_track(
+        &mut self,
+        builder: TransactionBuilder,
+        signers: &[&Keypair],
+        last_valid_block_height: u64,
+    ) -> Result<CompiledTransaction> {
+        let transaction = builder.build_and_sign(signers)?;
+        self.track(&transaction, last_valid_block_height)?;
+        Ok(transaction)
+    }
+
+    pub fn get(&self, signature: &[u8; 64]) -> Option<&Expiry> {
+        self.entries.get(signature)
+    }
+
+    /// Whether `signature` has expired; `None` if untracked
+    pub fn is_expired(&self, signature: &[u8; 64], current_height: u64) -> Option<bool> {
+        self.get(signature).map(|expiry| expiry.is_expired(current_height))
+    }
+
+    /// Blocks `signature` has left; `None` if untracked or expired
+    pub fn expires_in(&self, signature: &[u8; 64], current_height: u64) -> Option<u64> {
+        self.get(signature)?.expires_in(current_height)
+    }
+
+    /// Stop tracking `signature`, e.g. once confirmed
+    pub fn remove(&mut self, signature: &[u8; 64]) -> Option<Expiry> {
+        self.entries.remove(signature)
+    }
+
+    /// Remove and return every expired signature
+    pub fn prune(&mut self, current_height: u64) -> Vec<[u8; 64]> {
+        let expired: Vec<[u8; 64]> = self
+            .entries
+            .iter()
+            .filter(|(_, expiry)| expiry.is_expired(current_height))
+            .map(|(signature, _)| *signature)
+            .collect();
+        for signature in &expired {
+            self.entries.remove(signature);
+        }
+        expired
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use solana_sdk::signature::Signer;
+
+    fn signed(blockhash: [u8; 32]) -> CompiledTransaction {
+        let payer = Keypair::new();
+        TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash(blockhash)
+            .add_instruction(InstructionEncoder::new([2u8; 32]).build())
+            .build_and_sign(&[&payer])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_expiry_window() {
+        let mut tracker = ExpiryTracker::new();
+        let signature = tracker.track(&signed([3u8; 32]), 1_000).unwrap();
+
+        assert_eq!(tracker.get(&signature).unwrap().blockhash, [3u8; 32]);
+        assert_eq!(tracker.expires_in(&signature, 990), Some(10));
+        assert_eq!(tracker.expires_in(&signature, 1_000), Some(0));
+        assert_eq!(tracker.is_expired(&signature, 1_000), Some(false));
+        assert_eq!(tracker.is_expired(&signature, 1_001), Some(true));
+        assert_eq!(tracker.expires_in(&signature, 1_001), None);
+        assert_eq!(tracker.is_expired(&[9u8; 64], 0), None);
+
+        let unsigned = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).build())
+            .build_unsigned()
+            .unwrap();
+        assert!(tracker.track(&unsigned, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_prune_expired() {
+        let mut tracker = ExpiryTracker::new();
+        let old = tracker.track(&signed([3u8; 32]), 100).unwrap();
+        let fresh = tracker.track(&signed([4u8; 32]), 250).unwrap();
+
+        assert_eq!(tracker.prune(200), vec![old]);
+        assert_eq!(tracker.len(), 1);
+        assert!(tracker.remove(&fresh).is_some());
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn test_build_and_track() {
+        let payer = Keypair::new();
+        let builder = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([5u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).build());
+
+        let mut tracker = ExpiryTracker::new();
+        let transaction = tracker.build_and_track(builder, &[&payer], 500).unwrap();
+        let expiry = tracker.get(&transaction.signatures[0]).unwrap();
+        assert_eq!(expiry.blockhash, [5u8; 32]);
+        assert_eq!(expiry.last_valid_block_height, 500);
+
+        let empty = TransactionBuilder::new().payer_pubkey(&payer.pubkey()).recent_blockhash([5u8; 32]);
+        assert!(tracker.build_and_track(empty, &[&Keypair::new()], 500).is_err());
+        assert_eq!(tracker.len(), 1);
+    }
+}
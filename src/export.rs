@@ -0,0 +1,205 @@
+//! CSV/Parquet export of transaction analyses
+//!
+//! Flattens a [`TransactionAnalysis`] and, optionally, a [`FeeEstimate`]
+//! into one [`ExportRow`] per transaction so size and fee distributions can
+//! be studied with ordinary data tooling. CSV output needs the `csv`
+//! feature and Parquet output the `parquet` feature.
+
+#[cfg(any(feature = "csv", feature = "parquet"))]
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::FeeEstimate;
+use crate::optimizer::TransactionAnalysis;
+use serde::Serialize;
+#[cfg(any(feature = "csv", feature = "parquet"))]
+use std::io::Write;
+
+/// One exported transaction
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ExportRow {
+    pub label: Option<String>,
+    pub total_size: u64,
+    pub signature_bytes: u64,
+    pub account_bytes: u64,
+    pub instruction_data_bytes: u64,
+    pub num_signatures: u64,
+    pub num_accounts: u64,
+    pub num_instructions: u64,
+    pub num_suggestions: u64,
+    pub base_fee: Option<u64>,
+    pub priority_fee_per_cu: Option<u64>,
+    pub estimated_compute_units: Option<u64>,
+    pub total_cost: Option<u64>,
+}
+
+impl ExportRow {
+    pub fn new(analysis: &TransactionAnalysis) -> Self {
+        Self {
+            label: None,
+            total_size: analysis.total_size as u64,
+            signature_bytes: analysis.signature_bytes as u64,
+            account_bytes: analysis.account_bytes as u64,
+            instruction_data_bytes: analysis.instruction_data_bytes as u64,
+            num_signatures: analysis.num_signatures as u64,
+            num_accounts: analysis.num_accounts as u64,
+            num_instructions: analysis.num_instructions as u64,
+            num_suggestions: analysis.suggestions.len() as u64,
+            ..Default::default()
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Attach the fee estimate columns
+    pub fn fee(mut self, estimate: &FeeEstimate) -> Self {
+        self.base_fee = Some(estimate.base_fee);
+        self.priority_fee_per_cu = Some(estimate.priority_fee_per_cu);
+        self.estimated_compute_units = Some(estimate.estimated_compute_units as u64);
+        self.total_cost = Some(estimate.total_cost);
+        self
+    }
+}
+
+impl From<&TransactionAnalysis> for ExportRow {
+    fn from(analysis: &TransactionAnalysis) -> Self {
+        Self::new(analysis)
+    }
+}
+
+/// Write `rows` as CSV with a header line; missing values are empty fields
+#[cfg(feature = "csv")]
+pub fn write_csv<W: Write>(rows: &[ExportRow], writer: W) -> Result<()> {
+    let mut csv = csv::Writer::from_writer(writer);
+    for row in rows {
+        csv.serialize(row)
+            .map_err(|e| TxAsmError::SerializationError(e.to_string()))?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+/// Numeric columns shared by the Parquet schema and writer, in order
+#[cfg(feature = "parquet")]
+type Column = (&'static str, bool, fn(&ExportRow) -> Option<u64>);
+
+#[cfg(feature = "parquet")]
+const NUMERIC_COLUMNS: &[Column] = &[
+    ("total_size", true, |r| Some(r.total_size)),
+    ("signature_bytes", true, |r| Some(r.signature_bytes)),
+    ("account_bytes", true, |r| Some(r.account_bytes)),
+    ("instruction_data_bytes", true, |r| Some(r.instruction_data_bytes)),
+    ("num_signatures", true, |r| Some(r.num_signatures)),
+    ("num_accounts", true, |r| Some(r.num_accounts)),
+    ("num_instructions", true, |r| Some(r.num_instructions)),
+    ("num_suggestions", true, |r| Some(r.num_suggestions)),
+    ("base_fee", false, |r| r.base_fee),
+    ("priority_fee_per_cu", false, |r| r.priority_fee_per_cu),
+    ("estimated_compute_units", false, |r| r.estimated_compute_units),
+    ("total_cost", false, |r| r.total_cost),
+];
+
+/// Write `rows` as a single-row-group Parquet file
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: Write + Send>(rows: &[ExportRow], writer: W) -> Result<()> {
+    use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::sync::Arc;
+
+    let err = |e: parquet::errors::ParquetError| TxAsmError::SerializationError(e.to_string());
+
+    let mut schema = String::from("message transaction_analysis {\n  OPTIONAL BYTE_ARRAY label (UTF8);\n");
+    for (name, required, _) in NUMERIC_COLUMNS {
+        let repetition = if *required { "REQUIRED" } else { "OPTIONAL" };
+        schema.push_str(&format!("  {} INT64 {};\n", repetition, name));
+    }
+    schema.push('}');
+    let schema = Arc::new(parse_message_type(&schema).map_err(err)?);
+
+    let props = Arc::new(WriterProperties::builder().build());
+    let mut file = SerializedFileWriter::new(writer, schema, props).map_err(err)?;
+    let mut row_group = file.next_row_group().map_err(err)?;
+
+    // Label column
+    let labels: Vec<ByteArray> = rows
+        .iter()
+        .filter_map(|r| r.label.as_deref().map(ByteArray::from))
+        .collect();
+    let levels: Vec<i16> = rows.iter().map(|r| r.label.is_some() as i16).collect();
+    let mut column = row_group.next_column().map_err(err)?.expect("label column");
+    column
+        .typed::<ByteArrayType>()
+        .write_batch(&labels, Some(&levels), None)
+        .map_err(err)?;
+    column.close().map_err(err)?;
+
+    for (_, required, get) in NUMERIC_COLUMNS {
+        let values: Vec<i64> = rows.iter().filter_map(|r| get(r).map(|v| v as i64)).collect();
+        let levels: Vec<i16> = rows.iter().map(|r| get(r).is_some() as i16).collect();
+        let mut column = row_group.next_column().map_err(err)?.expect("numeric column");
+        column
+            .typed::<Int64Type>()
+            .write_batch(&values, (!required).then_some(&levels[..]), None)
+            .map_err(err)?;
+        column.close().map_err(err)?;
+    }
+
+    row_group.close().map_err(err)?;
+    file.close().map_err(err)?;
+    Ok(())
+}
+
+#[cfg(all(test, any(feature = "csv", feature = "parquet")))]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::{FeeStrategy, PriorityFeeCalculator};
+    use crate::instruction::InstructionEncoder;
+    use crate::optimizer::{OptimizationStrategy, TransactionOptimizer};
+    use crate::transaction::TransactionBuilder;
+
+    fn sample_rows() -> Vec<ExportRow> {
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).append_u64(9).build())
+            .build_unsigned()
+            .unwrap();
+        let analysis = TransactionOptimizer::new(OptimizationStrategy::Balanced).analyze(&tx);
+        let estimate = PriorityFeeCalculator::new().estimate_fee(&tx, FeeStrategy::Medium).unwrap();
+        vec![
+            ExportRow::new(&analysis).label("swap, fast").fee(&estimate),
+            ExportRow::from(&analysis),
+        ]
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_export() {
+        let rows = sample_rows();
+        let mut out = Vec::new();
+        write_csv(&rows, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("label,total_size,"));
+        assert!(lines[1].starts_with(&format!("\"swap, fast\",{},", rows[0].total_size)));
+        assert!(lines[2].ends_with(",,,,"));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_export() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let path = std::env::temp_dir().join(format!("txasm-export-{}.parquet", std::process::id()));
+        write_parquet(&sample_rows(), std::fs::File::create(&path).unwrap()).unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+        assert_eq!(reader.metadata().file_metadata().schema_descr().num_columns(), 13);
+    }
+}
@@ -0,0 +1,148 @@
+//! Fee spend accounting against a periodic budget
+//!
+//! [`FeeBudget`] adds up the fees automated senders actually paid, as
+//! reported by confirmations, against a per-period lamport budget. Before
+//! each submission [`FeeBudget::check`] vetoes transactions the remaining
+//! budget cannot cover and downgrades any [`FeePolicy`] to
+//! [`FeeStrategy::Low`] once the budget runs low.
+//!
+//! Periods are aligned to multiples of their length since the Unix epoch
+//! (UTC midnight for [`FeeBudget::daily`]). Like the scheduler, the budget is
+//! driven by the caller, which passes the current Unix time in seconds.
+
+use crate::fee_calculator::{FeePolicy, FeeStrategy};
+use std::time::Duration;
+
+/// Verdict on a submission priced by a policy `P`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetDecision<P = FeeStrategy> {
+    /// Send with the policy as given
+    Allow(P),
+    /// Send at [`FeeStrategy::Low`]: the budget is nearly spent
+    Downgrade,
+    /// Hold the submission: the estimate exceeds what is left this period
+    Veto { remaining: u64, estimated_fee: u64 },
+}
+
+/// Lamport budget per period with spend tracking
+#[derive(Debug, Clone)]
+pub struct FeeBudget {
+    limit: u64,
+    period_secs: u64,
+    /// Fraction of `limit` below which policies are forced to `Low`
+    low_watermark: f64,
+    period_start: u64,
+    spent: u64,
+}
+
+impl FeeBudget {
+    /// `limit` lamports per `period`, forcing `Low` under 20% remaining
+    pub fn new(limit: u64, period: Duration) -> Self {
+        Self {
+            limit,
+            period_secs: period.as_secs().max(1),
+            low_watermark: 0.2,
+            period_start: 0,
+            spent: 0,
+        }
+    }
+
+    /// `limit` lamports per UTC day
+    pub fn daily(limit: u64) -> Self {
+        Self::new(limit, Duration::from_secs(86_400))
+    }
+
+    /// Force `Low` once less than `fraction` of the budget remains
+    pub fn low_watermark(mut self, fraction: f64) -> Self {
+        self.low_watermark = fraction.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Record a fee paid by a confirmed transaction
+    pub fn record(&mut self, fee_lamports: u64, now: u64) {
+        self.roll(now);
+        self.spent = self.spent.saturating_add(fee_lamports);
+    }
+
+    /// Lamports spent in the period containing `now`
+    pub fn spent(&mut self, now: u64) -> u64 {
+        self.roll(now);
+        self.spent
+    }
+
+    /// Lamports left in the period containing `now`
+    pub fn remaining(&mut self, now: u64) -> u64 {
+        self.roll(now);
+        self.limit.saturating_sub(self.spent)
+    }
+
+    /// Decide whether a transaction estimated to cost `estimated_fee`
+    /// lamports may be sent with `policy`
+    pub fn check<P: FeePolicy>(&mut self, policy: P, estimated_fee: u64, now: u64) -> BudgetDecision<P> {
+        let remaining = self.remaining(now);
+        if estimated_fee > remaining {
+            return BudgetDecision::Veto { remaining, estimated_fee };
+        }
+        let after = (remaining - estimated_fee) as f64;
+        if after < self.limit as f64 * self.low_watermark {
+            BudgetDecision::Downgrade
+        } else {
+            BudgetDecision::Allow(policy)
+        }
+    }
+
+    /// Start a new period once `now` has left the current one
+    fn roll(&mut self, now: u64) {
+        let start = now - now % self.period_secs;
+        if start > self.period_start {
+            self.period_start = start;
+            self.spent = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86_400;
+
+    #[test]
+    fn test_budget_downgrades_then_vetoes() {
+        let mut budget = FeeBudget::daily(100_000);
+        let now = 20_000 * DAY + 3_600;
+
+        assert_eq!(budget.check(FeeStrategy::High, 10_000, now), BudgetDecision::Allow(FeeStrategy::High));
+        budget.record(70_000, now);
+        // 30k left: spending 15k would leave 15k, under the 20k watermark
+        assert_eq!(budget.check(FeeStrategy::High, 15_000, now), BudgetDecision::Downgrade);
+        assert_eq!(budget.check(FeeStrategy::High, 5_000, now), BudgetDecision::Allow(FeeStrategy::High));
+        assert_eq!(
+            budget.check(FeeStrategy::Medium, 40_000, now),
+            BudgetDecision::Veto { remaining: 30_000, estimated_fee: 40_000 }
+        );
+    }
+
+    #[test]
+    fn test_budget_resets_each_period() {
+        let mut budget = FeeBudget::new(1_000, Duration::from_secs(3_600)).low_watermark(0.0);
+        budget.record(900, 7_200);
+        assert_eq!(budget.remaining(10_799), 100);
+        assert_eq!(budget.remaining(10_800), 1_000);
+        assert_eq!(budget.spent(10_800), 0);
+        assert_eq!(budget.check(FeeStrategy::High, 1_000, 10_800), BudgetDecision::Allow(FeeStrategy::High));
+    }
+
+    #[test]
+    fn test_budget_checks_custom_policy() {
+        let mut budget = FeeBudget::new(1_000, Duration::from_secs(3_600));
+        let flat = |_: &crate::transaction::CompiledMessage| 5_000;
+        assert!(matches!(budget.check(flat, 100, 0), BudgetDecision::Allow(_)));
+        assert!(matches!(budget.check(flat, 900, 0), BudgetDecision::Downgrade));
+        assert!(matches!(budget.check(flat, 1_001, 0), BudgetDecision::Veto { .. }));
+    }
+}
@@ -3,8 +3,17 @@
 //! This module provides utilities for calculating and optimizing transaction fees,
 //! including priority fees and compute unit optimizations.
 
+use crate::convert::v0_to_legacy;
 use crate::error::{Result, TxAsmError};
-use crate::transaction::CompiledTransaction;
+use crate::rpc_fees::FeeSource;
+use crate::timing::LeaderWindow;
+use crate::transaction::{CompiledMessage, CompiledTransaction};
+use crate::versioned::{CompiledTransactionV0, LoadedAddresses};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 /// Priority fee calculation strategies
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -19,6 +28,194 @@ pub enum FeeStrategy {
     Custom(u64),
 }
 
+impl FeeStrategy {
+    /// The fixed presets, cheapest first
+    pub const PRESETS: [FeeStrategy; 3] = [FeeStrategy::Low, FeeStrategy::Medium, FeeStrategy::High];
+
+    /// CU price of the preset in microlamports
+    pub fn micro_lamports_per_cu(&self) -> u64 {
+        match self {
+            FeeStrategy::Low => 1,           // 1 microlamport per CU
+            FeeStrategy::Medium => 100,      // 100 microlamports per CU
+            FeeStrategy::High => 1000,       // 1000 microlamports per CU
+            FeeStrategy::Custom(fee) => *fee,
+        }
+    }
+}
+
+/// Chooses the compute unit price for a transaction
+///
+/// [`FeeStrategy`] covers the fixed presets. Implement this trait, or pass a
+/// closure over the message, to price dynamically by time of day, by the
+/// accounts a transaction writes, or by what remains of a budget.
+pub trait FeePolicy {
+    /// CU price in microlamports for `message`
+    fn micro_lamports_per_cu(&self, message: &CompiledMessage) -> u64;
+
+    /// CU price when `leader` is expected to include `message`
+    ///
+    /// Leaders run different schedulers, so some are worth pricing
+    /// differently. txasm has no per-validator behaviour of its own: the
+    /// default ignores the leader, and [`PerLeader`] adds an adjustment.
+    fn micro_lamports_per_cu_for_leader(&self, message: &CompiledMessage, leader: &UpcomingLeader) -> u64 {
+        let _ = leader;
+        self.micro_lamports_per_cu(message)
+    }
+
+    /// CU price with live market prices available through `quote`
+    ///
+    /// A [`FeeSource`] quotes each [`FeeStrategy`] from provider data, so a
+    /// policy can pick the tier it wants per message. The default ignores the
+    /// market; presets return their own quote.
+    fn micro_lamports_per_cu_quoted(
+        &self,
+        message: &CompiledMessage,
+        quote: &dyn Fn(FeeStrategy) -> Result<u64>,
+    ) -> Result<u64> {
+        let _ = quote;
+        Ok(self.micro_lamports_per_cu(message))
+    }
+
+    /// CU price if it does not depend on the message
+    ///
+    /// Lets callers holding only serialized bytes, such as
+    /// [`perf::estimate_fee`](crate::perf::estimate_fee), skip decoding.
+    fn fixed_micro_lamports_per_cu(&self) -> Option<u64> {
+        None
+    }
+}
+
+impl FeePolicy for FeeStrategy {
+    fn micro_lamports_per_cu(&self, _message: &CompiledMessage) -> u64 {
+        FeeStrategy::micro_lamports_per_cu(self)
+    }
+
+    fn micro_lamports_per_cu_quoted(
+        &self,
+        _message: &CompiledMessage,
+        quote: &dyn Fn(FeeStrategy) -> Result<u64>,
+    ) -> Result<u64> {
+        quote(*self)
+    }
+
+    fn fixed_micro_lamports_per_cu(&self) -> Option<u64> {
+        Some(FeeStrategy::micro_lamports_per_cu(self))
+    }
+}
+
+impl<F> FeePolicy for F
+where
+    F: Fn(&CompiledMessage) -> u64,
+{
+    fn micro_lamports_per_cu(&self, message: &CompiledMessage) -> u64 {
+        self(message)
+    }
+}
+
+/// Forward every method through a pointer to a policy
+macro_rules! forward_fee_policy {
+    ($($pointer:ty),*) => {$(
+        impl FeePolicy for $pointer {
+            fn micro_lamports_per_cu(&self, message: &CompiledMessage) -> u64 {
+                (**self).micro_lamports_per_cu(message)
+            }
+
+            fn micro_lamports_per_cu_for_leader(&self, message: &CompiledMessage, leader: &UpcomingLeader) -> u64 {
+                (**self).micro_lamports_per_cu_for_leader(message, leader)
+            }
+
+            fn micro_lamports_per_cu_quoted(
+                &self,
+                message: &CompiledMessage,
+                quote: &dyn Fn(FeeStrategy) -> Result<u64>,
+            ) -> Result<u64> {
+                (**self).micro_lamports_per_cu_quoted(message, quote)
+            }
+
+            fn fixed_micro_lamports_per_cu(&self) -> Option<u64> {
+                (**self).fixed_micro_lamports_per_cu()
+            }
+        }
+    )*};
+}
+
+forward_fee_policy!(&dyn FeePolicy, Box<dyn FeePolicy>, Arc<dyn FeePolicy>);
+
+/// The validator expected to include a transaction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpcomingLeader {
+    pub identity: [u8; 32],
+    /// First slot of its window
+    pub slot: u64,
+    /// Activated stake in lamports, when known
+    pub activated_stake: Option<u64>,
+    /// Total activated stake of the cluster, when known
+    pub total_stake: Option<u64>,
+}
+
+impl UpcomingLeader {
+    pub fn new(identity: [u8; 32], slot: u64) -> Self {
+        Self {
+            identity,
+            slot,
+            activated_stake: None,
+            total_stake: None,
+        }
+    }
+
+    /// The leader of a window from the [`timing`](crate::timing) schedule
+    pub fn from_window(window: &LeaderWindow) -> Self {
+        Self::new(window.leader, window.start_slot)
+    }
+
+    pub fn stake(mut self, activated_stake: u64, total_stake: u64) -> Self {
+        self.activated_stake = Some(activated_stake);
+        self.total_stake = Some(total_stake);
+        self
+    }
+
+    /// Share of the cluster's stake, from 0.0 to 1.0
+    pub fn stake_share(&self) -> Option<f64> {
+        match (self.activated_stake, self.total_stake) {
+            (Some(stake), Some(total)) if total > 0 => Some(stake as f64 / total as f64),
+            _ => None,
+        }
+    }
+}
+
+/// A policy whose price `adjust` rewrites per leader
+///
+/// `adjust` receives the base policy's price and the upcoming leader, e.g.
+/// to pay more to leaders whose scheduler favours high bidders.
+pub struct PerLeader<P, F> {
+    base: P,
+    adjust: F,
+}
+
+impl<P, F> PerLeader<P, F>
+where
+    P: FeePolicy,
+    F: Fn(u64, &UpcomingLeader) -> u64,
+{
+    pub fn new(base: P, adjust: F) -> Self {
+        Self { base, adjust }
+    }
+}
+
+impl<P, F> FeePolicy for PerLeader<P, F>
+where
+    P: FeePolicy,
+    F: Fn(u64, &UpcomingLeader) -> u64,
+{
+    fn micro_lamports_per_cu(&self, message: &CompiledMessage) -> u64 {
+        self.base.micro_lamports_per_cu(message)
+    }
+
+    fn micro_lamports_per_cu_for_leader(&self, message: &CompiledMessage, leader: &UpcomingLeader) -> u64 {
+        (self.adjust)(self.base.micro_lamports_per_cu_for_leader(message, leader), leader)
+    }
+}
+
 /// Fee estimation data
 #[derive(Debug, Clone)]
 pub struct FeeEstimate {
@@ -32,27 +229,226 @@ pub struct FeeEstimate {
     pub total_cost: u64,
 }
 
+impl FeeEstimate {
+    /// Fiat-denominated view of the estimate at `feed`'s current SOL price
+    pub fn display_in(&self, feed: &impl PriceFeed) -> Result<FiatFeeEstimate> {
+        let price = feed.sol_price()?;
+        if !price.is_finite() || price < 0.0 {
+            return Err(TxAsmError::FeeCalculationError(format!("Invalid SOL price {}", price)));
+        }
+        let currency = feed.currency();
+        let fiat = |lamports: u64| FiatAmount {
+            amount: lamports as f64 / LAMPORTS_PER_SOL as f64 * price,
+            currency: currency.to_string(),
+        };
+        Ok(FiatFeeEstimate {
+            base_fee: fiat(self.base_fee),
+            priority_fee: fiat(self.total_cost.saturating_sub(self.base_fee)),
+            total: fiat(self.total_cost),
+        })
+    }
+}
+
+/// Source of the SOL price in a fiat currency
+///
+/// Implemented for closures returning a USD price.
+pub trait PriceFeed {
+    /// Price of one SOL
+    fn sol_price(&self) -> Result<f64>;
+
+    /// ISO 4217 code of the price's currency
+    fn currency(&self) -> &str {
+        "USD"
+    }
+}
+
+impl<F> PriceFeed for F
+where
+    F: Fn() -> Result<f64>,
+{
+    fn sol_price(&self) -> Result<f64> {
+        self()
+    }
+}
+
+/// Constant price, for reports and tests
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedPrice {
+    pub price: f64,
+    pub currency: String,
+}
+
+impl FixedPrice {
+    pub fn new(price: f64, currency: &str) -> Self {
+        Self {
+            price,
+            currency: currency.to_string(),
+        }
+    }
+}
+
+impl PriceFeed for FixedPrice {
+    fn sol_price(&self) -> Result<f64> {
+        Ok(self.price)
+    }
+
+    fn currency(&self) -> &str {
+        &self.currency
+    }
+}
+
+/// An amount in a fiat currency
+///
+/// Displays with cents, or with two significant digits below one cent
+/// (`$0.0021`), using the currency's symbol when it has a common one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatAmount {
+    pub amount: f64,
+    pub currency: String,
+}
+
+impl std::fmt::Display for FiatAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let decimals = if self.amount == 0.0 || self.amount.abs() >= 0.01 {
+            2
+        } else {
+            (1 - self.amount.abs().log10().floor() as i32).clamp(2, 12) as usize
+        };
+        let symbol = match self.currency.as_str() {
+            "USD" => Some("$"),
+            "EUR" => Some("€"),
+            "GBP" => Some("£"),
+            "JPY" => Some("¥"),
+            _ => None,
+        };
+        match symbol {
+            Some(symbol) => write!(f, "{}{:.*}", symbol, decimals, self.amount),
+            None => write!(f, "{:.*} {}", decimals, self.amount, self.currency),
+        }
+    }
+}
+
+/// [`FeeEstimate`] converted to fiat
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiatFeeEstimate {
+    pub base_fee: FiatAmount,
+    pub priority_fee: FiatAmount,
+    pub total: FiatAmount,
+}
+
+/// Recent CU prices (microlamports) paid by transactions write-locking each account
+#[derive(Debug, Clone, Default)]
+pub struct AccountFeeHistory {
+    samples: HashMap<[u8; 32], Vec<u64>>,
+}
+
+impl AccountFeeHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one observed CU price for `account`
+    pub fn record(&mut self, account: [u8; 32], micro_lamports_per_cu: u64) {
+        self.samples.entry(account).or_default().push(micro_lamports_per_cu);
+    }
+
+    /// Record many observed CU prices for `account`
+    pub fn extend(&mut self, account: [u8; 32], prices: impl IntoIterator<Item = u64>) {
+        self.samples.entry(account).or_default().extend(prices);
+    }
+
+    /// Nearest-rank percentile of the prices seen for `account`
+    pub fn percentile(&self, account: &[u8; 32], percentile: u8) -> Option<u64> {
+        nearest_rank(self.samples.get(account)?, percentile)
+    }
+}
+
+/// Nearest-rank percentile of `samples`, `None` if empty
+pub(crate) fn nearest_rank(samples: &[u64], percentile: u8) -> Option<u64> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let rank = (percentile.min(100) as usize * sorted.len()).div_ceil(100);
+    Some(sorted[rank.saturating_sub(1)])
+}
+
+/// Priority fee driven by the hottest writable account
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountFeeRecommendation {
+    /// Recommended CU price in microlamports
+    pub micro_lamports_per_cu: u64,
+    /// Writable account whose history set the price, if any had history
+    pub hottest_account: Option<[u8; 32]>,
+}
+
+/// Bounded random increase applied to recommended CU prices
+///
+/// Competitors that mirror a deterministic percentile can outbid it by one
+/// microlamport every time. Raising each price by a random share of up to
+/// `max_fraction` makes the bid unpredictable. Draws are weighted towards
+/// small raises (density falls linearly to zero at `max_fraction`), so the
+/// average overpayment is a third of the bound.
+#[derive(Debug)]
+pub struct FeeJitter {
+    max_fraction: f64,
+    rng: Mutex<StdRng>,
+}
+
+impl FeeJitter {
+    /// Raise prices by up to `max_fraction` of themselves, e.g. 0.1 for 10%
+    pub fn up_to(max_fraction: f64) -> Self {
+        Self {
+            max_fraction: max_fraction.max(0.0),
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    /// Use a fixed seed so the sequence of prices is reproducible
+    pub fn seed(self, seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            ..self
+        }
+    }
+
+    /// `price` raised by a random amount within the bound
+    pub fn apply(&self, price: u64) -> u64 {
+        let draw: f64 = self.rng.lock().unwrap_or_else(|e| e.into_inner()).gen();
+        // Inverse CDF of the triangular distribution with its mode at 0
+        let fraction = self.max_fraction * (1.0 - (1.0 - draw).sqrt());
+        price.saturating_add((price as f64 * fraction).round() as u64)
+    }
+}
+
 /// Priority fee calculator with various strategies
 pub struct PriorityFeeCalculator {
     /// Base fee per signature (typically 5000 lamports on Solana)
     base_fee_per_signature: u64,
+    jitter: Option<FeeJitter>,
 }
 
 impl PriorityFeeCalculator {
     /// Create a new fee calculator with default Solana base fee
     pub fn new() -> Self {
-        Self {
-            base_fee_per_signature: 5000,
-        }
+        Self::with_base_fee(5000)
     }
 
     /// Create with custom base fee
     pub fn with_base_fee(base_fee_per_signature: u64) -> Self {
         Self {
             base_fee_per_signature,
+            jitter: None,
         }
     }
 
+    /// Apply `jitter` to the CU price of every estimate
+    pub fn with_jitter(mut self, jitter: FeeJitter) -> Self {
+        self.jitter = Some(jitter);
+        self
+    }
+
     /// Calculate base transaction fee based on number of signatures
     pub fn calculate_base_fee(&self, num_signatures: usize) -> u64 {
         self.base_fee_per_signature * num_signatures as u64
@@ -61,13 +457,6 @@ impl PriorityFeeCalculator {
     /// Estimate compute units based on transaction size and complexity
     /// This is a heuristic estimation - actual compute units depend on program logic
     pub fn estimate_compute_units(&self, transaction: &CompiledTransaction) -> u32 {
-        let base_cu = 200; // Base compute units
-        let per_instruction = 1000; // Compute units per instruction
-        let per_account = 100; // Compute units per account
-        let per_data_byte = 1; // Compute units per byte of instruction data
-
-        let num_instructions = transaction.message.instructions.len() as u32;
-        let num_accounts = transaction.message.account_keys.len() as u32;
         let total_data_bytes: u32 = transaction
             .message
             .instructions
@@ -75,44 +464,130 @@ impl PriorityFeeCalculator {
             .map(|i| i.data.len() as u32)
             .sum();
 
+        self.estimate_compute_units_for(
+            transaction.message.instructions.len() as u32,
+            transaction.message.account_keys.len() as u32,
+            total_data_bytes,
+        )
+    }
+
+    /// Estimate compute units for a V0 transaction, counting the accounts
+    /// its lookups load alongside the static keys
+    pub fn estimate_compute_units_v0(&self, transaction: &CompiledTransactionV0) -> u32 {
+        let message = &transaction.message;
+        let total_data_bytes: u32 = message.instructions.iter().map(|i| i.data.len() as u32).sum();
+
+        self.estimate_compute_units_for(
+            message.instructions.len() as u32,
+            message.total_accounts() as u32,
+            total_data_bytes,
+        )
+    }
+
+    /// Compute unit heuristic from the transaction's shape alone
+    pub(crate) fn estimate_compute_units_for(
+        &self,
+        num_instructions: u32,
+        num_accounts: u32,
+        total_data_bytes: u32,
+    ) -> u32 {
+        let base_cu = 200; // Base compute units
+        let per_instruction = 1000; // Compute units per instruction
+        let per_account = 100; // Compute units per account
+        let per_data_byte = 1; // Compute units per byte of instruction data
+
         base_cu
             + (per_instruction * num_instructions)
             + (per_account * num_accounts)
             + (per_data_byte * total_data_bytes)
     }
 
-    /// Calculate priority fee based on strategy
-    pub fn get_priority_fee(&self, strategy: FeeStrategy) -> u64 {
-        match strategy {
-            FeeStrategy::Low => 1,           // 1 microlamport per CU
-            FeeStrategy::Medium => 100,      // 100 microlamports per CU
-            FeeStrategy::High => 1000,       // 1000 microlamports per CU
-            FeeStrategy::Custom(fee) => fee,
-        }
+    /// CU price `policy` sets for `message`
+    pub fn get_priority_fee(&self, message: &CompiledMessage, policy: impl FeePolicy) -> u64 {
+        policy.micro_lamports_per_cu(message)
     }
 
-    /// Calculate total fee estimate for a transaction
+    /// Calculate total fee estimate for a transaction, priced by `policy`
     pub fn estimate_fee(
         &self,
         transaction: &CompiledTransaction,
-        strategy: FeeStrategy,
-    ) -> FeeEstimate {
-        let num_signatures = transaction.signatures.len();
+        policy: impl FeePolicy,
+    ) -> Result<FeeEstimate> {
+        self.fee_for(
+            transaction.signatures.len(),
+            self.estimate_compute_units(transaction),
+            policy.micro_lamports_per_cu(&transaction.message),
+        )
+    }
+
+    /// Estimate the fee for inclusion by `leader`
+    pub fn estimate_fee_for_leader(
+        &self,
+        transaction: &CompiledTransaction,
+        policy: impl FeePolicy,
+        leader: &UpcomingLeader,
+    ) -> Result<FeeEstimate> {
+        self.fee_for(
+            transaction.signatures.len(),
+            self.estimate_compute_units(transaction),
+            policy.micro_lamports_per_cu_for_leader(&transaction.message, leader),
+        )
+    }
+
+    /// Calculate total fee estimate for a V0 transaction
+    ///
+    /// `loaded` are the addresses its lookups resolve to (see
+    /// [`CompiledMessageV0::resolve`](crate::versioned::CompiledMessageV0::resolve)),
+    /// so `policy` sees every account the transaction locks.
+    pub fn estimate_fee_v0(
+        &self,
+        transaction: &CompiledTransactionV0,
+        loaded: &LoadedAddresses,
+        policy: impl FeePolicy,
+    ) -> Result<FeeEstimate> {
+        let (legacy, _) = v0_to_legacy(transaction, loaded)?;
+        self.fee_for(
+            transaction.signatures.len(),
+            self.estimate_compute_units_v0(transaction),
+            policy.micro_lamports_per_cu(&legacy.message),
+        )
+    }
+
+    /// Assemble an estimate from a signature count and compute units
+    ///
+    /// Fails if the fee does not fit in a u64 of lamports, which only an
+    /// absurd CU price can cause.
+    pub(crate) fn fee_for(
+        &self,
+        num_signatures: usize,
+        estimated_compute_units: u32,
+        priority_fee_per_cu: u64,
+    ) -> Result<FeeEstimate> {
         let base_fee = self.calculate_base_fee(num_signatures);
-        let estimated_compute_units = self.estimate_compute_units(transaction);
-        let priority_fee_per_cu = self.get_priority_fee(strategy);
+        let priority_fee_per_cu = match &self.jitter {
+            Some(jitter) => jitter.apply(priority_fee_per_cu),
+            None => priority_fee_per_cu,
+        };
 
         // Convert microlamports to lamports (divide by 1,000,000)
-        let priority_fee_lamports =
-            (estimated_compute_units as u64 * priority_fee_per_cu) / 1_000_000;
-        let total_cost = base_fee + priority_fee_lamports;
+        let overflow = || {
+            TxAsmError::FeeCalculationError(format!(
+                "Fee for {} CUs at {} microlamports per CU overflows u64 lamports",
+                estimated_compute_units, priority_fee_per_cu
+            ))
+        };
+        let priority_fee_lamports = (estimated_compute_units as u64)
+            .checked_mul(priority_fee_per_cu)
+            .ok_or_else(overflow)?
+            / 1_000_000;
+        let total_cost = base_fee.checked_add(priority_fee_lamports).ok_or_else(overflow)?;
 
-        FeeEstimate {
+        Ok(FeeEstimate {
             base_fee,
             priority_fee_per_cu,
             estimated_compute_units,
             total_cost,
-        }
+        })
     }
 
     /// Calculate the optimal priority fee based on network conditions
@@ -136,7 +611,90 @@ impl PriorityFeeCalculator {
             _ => FeeStrategy::High,
         };
 
-        Ok(self.estimate_fee(transaction, strategy))
+        self.estimate_fee(transaction, strategy)
+    }
+
+    /// Recommend a CU price from per-account fee histories
+    ///
+    /// Takes the given percentile of each writable account's history and
+    /// returns the maximum, so a transaction touching a hot market is priced
+    /// for that market. Returns a price of 0 when no writable account has
+    /// history; callers can fall back to a global strategy.
+    pub fn recommend_for_accounts(
+        &self,
+        transaction: &CompiledTransaction,
+        history: &AccountFeeHistory,
+        percentile: u8,
+    ) -> Result<AccountFeeRecommendation> {
+        if percentile > 100 {
+            return Err(TxAsmError::FeeCalculationError(
+                "Percentile must be between 0 and 100".to_string(),
+            ));
+        }
+
+        let message = &transaction.message;
+        let hottest = message
+            .account_keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| message.is_writable(*i))
+            .filter_map(|(_, key)| history.percentile(key, percentile).map(|p| (p, *key)))
+            .max_by_key(|(price, _)| *price);
+
+        Ok(AccountFeeRecommendation {
+            micro_lamports_per_cu: hottest.map_or(0, |(price, _)| price),
+            hottest_account: hottest.map(|(_, key)| key),
+        })
+    }
+
+    /// Estimate the fee using the per-account recommendation as CU price
+    pub fn estimate_fee_for_accounts(
+        &self,
+        transaction: &CompiledTransaction,
+        history: &AccountFeeHistory,
+        percentile: u8,
+    ) -> Result<FeeEstimate> {
+        let recommendation = self.recommend_for_accounts(transaction, history, percentile)?;
+        self.estimate_fee(
+            transaction,
+            FeeStrategy::Custom(recommendation.micro_lamports_per_cu),
+        )
+    }
+
+    /// Estimate the fee at the CU price `policy` settles on with `source`
+    /// quoting the transaction's writable accounts
+    pub fn estimate_fee_from_source(
+        &self,
+        transaction: &CompiledTransaction,
+        source: &dyn FeeSource,
+        policy: impl FeePolicy,
+    ) -> Result<FeeEstimate> {
+        let price = source.priority_fee(&transaction.message, &policy)?;
+        self.estimate_fee(transaction, FeeStrategy::Custom(price))
+    }
+
+    /// CU price needed to rank above the `target_position`-th highest competitor
+    ///
+    /// `observed_competitor_fees` are CU prices (microlamports) of competing
+    /// transactions for the same writable accounts. Position 1 is the top
+    /// bidder, so `price_to_outrank(fees, 1)` outbids everyone. Returns 0 when
+    /// there are fewer than `target_position` competitors.
+    pub fn price_to_outrank(
+        &self,
+        observed_competitor_fees: &[u64],
+        target_position: usize,
+    ) -> Result<u64> {
+        if target_position == 0 {
+            return Err(TxAsmError::FeeCalculationError(
+                "Target position is 1-based".to_string(),
+            ));
+        }
+
+        let mut sorted = observed_competitor_fees.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(sorted
+            .get(target_position - 1)
+            .map_or(0, |price| price.saturating_add(1)))
     }
 
     /// Recommend fee strategy based on urgency
@@ -150,34 +708,81 @@ impl PriorityFeeCalculator {
     }
 
     /// Calculate cost per byte for the transaction
-    pub fn cost_per_byte(&self, transaction: &CompiledTransaction, strategy: FeeStrategy) -> f64 {
-        let estimate = self.estimate_fee(transaction, strategy);
+    pub fn cost_per_byte(&self, transaction: &CompiledTransaction, policy: impl FeePolicy) -> Result<f64> {
+        let estimate = self.estimate_fee(transaction, policy)?;
         let size = transaction.size() as f64;
-        estimate.total_cost as f64 / size
+        Ok(estimate.total_cost as f64 / size)
     }
 
-    /// Compare costs across different strategies
-    pub fn compare_strategies(
+    /// Compare costs across policies, in the order given
+    ///
+    /// Pass [`FeeStrategy::PRESETS`] for the built-in presets, or
+    /// `&dyn FeePolicy` references to mix presets with custom policies.
+    pub fn compare_strategies<P: FeePolicy>(
         &self,
         transaction: &CompiledTransaction,
-    ) -> Vec<(FeeStrategy, FeeEstimate)> {
-        vec![
-            (
-                FeeStrategy::Low,
-                self.estimate_fee(transaction, FeeStrategy::Low),
-            ),
-            (
-                FeeStrategy::Medium,
-                self.estimate_fee(transaction, FeeStrategy::Medium),
-            ),
-            (
-                FeeStrategy::High,
-                self.estimate_fee(transaction, FeeStrategy::High),
-            ),
-        ]
+        policies: impl IntoIterator<Item = P>,
+    ) -> Result<Vec<(P, FeeEstimate)>> {
+        policies
+            .into_iter()
+            .map(|policy| {
+                let estimate = self.estimate_fee(transaction, &policy as &dyn FeePolicy)?;
+                Ok((policy, estimate))
+            })
+            .collect()
+    }
+
+    /// Estimate every transaction of a batch with one policy and summarize
+    /// the total cost
+    pub fn estimate_batch(
+        &self,
+        transactions: &[CompiledTransaction],
+        policy: impl FeePolicy,
+    ) -> Result<BatchFeeEstimate> {
+        let estimates: Vec<FeeEstimate> = transactions
+            .iter()
+            .map(|transaction| self.estimate_fee(transaction, &policy as &dyn FeePolicy))
+            .collect::<Result<_>>()?;
+        let costs: Vec<u64> = estimates.iter().map(|e| e.total_cost).collect();
+        let total_base_fee = estimates.iter().map(|e| e.base_fee).sum();
+        let total_cost = costs
+            .iter()
+            .try_fold(0u64, |total, &cost| total.checked_add(cost))
+            .ok_or_else(|| TxAsmError::FeeCalculationError("Batch fee overflows u64 lamports".to_string()))?;
+
+        Ok(BatchFeeEstimate {
+            total_base_fee,
+            total_priority_fee: total_cost - total_base_fee,
+            total_cost,
+            total_compute_units: estimates.iter().map(|e| e.estimated_compute_units as u64).sum(),
+            min_cost: costs.iter().copied().min().unwrap_or(0),
+            max_cost: costs.iter().copied().max().unwrap_or(0),
+            mean_cost: if costs.is_empty() { 0.0 } else { total_cost as f64 / costs.len() as f64 },
+            median_cost: nearest_rank(&costs, 50).unwrap_or(0),
+            p90_cost: nearest_rank(&costs, 90).unwrap_or(0),
+            estimates,
+        })
     }
 }
 
+/// Fee estimates for a batch of transactions, with totals in lamports
+#[derive(Debug, Clone)]
+pub struct BatchFeeEstimate {
+    /// One estimate per transaction, in input order
+    pub estimates: Vec<FeeEstimate>,
+    pub total_base_fee: u64,
+    pub total_priority_fee: u64,
+    pub total_cost: u64,
+    pub total_compute_units: u64,
+    pub min_cost: u64,
+    pub max_cost: u64,
+    pub mean_cost: f64,
+    /// Nearest-rank median per-transaction cost
+    pub median_cost: u64,
+    /// Nearest-rank 90th percentile per-transaction cost
+    pub p90_cost: u64,
+}
+
 impl Default for PriorityFeeCalculator {
     fn default() -> Self {
         Self::new()
@@ -199,7 +804,8 @@ pub enum TransactionUrgency {
 
 /// Helper function to create compute budget instructions
 pub mod compute_budget {
-    
+    use crate::instruction::ConstData;
+    use crate::transaction::CompiledMessage;
 
     /// Compute budget program ID
     pub const COMPUTE_BUDGET_PROGRAM_ID: [u8; 32] = [
@@ -211,16 +817,70 @@ pub mod compute_budget {
 
     /// Create compute unit limit instruction data
     pub fn create_compute_unit_limit_instruction(units: u32) -> Vec<u8> {
-        let mut data = vec![0x02]; // SetComputeUnitLimit discriminator
-        data.extend_from_slice(&units.to_le_bytes());
-        data
+        compute_unit_limit_data(units).to_vec()
     }
 
     /// Create compute unit price instruction data (priority fee)
     pub fn create_compute_unit_price_instruction(microlamports: u64) -> Vec<u8> {
-        let mut data = vec![0x03]; // SetComputeUnitPrice discriminator
-        data.extend_from_slice(&microlamports.to_le_bytes());
-        data
+        compute_unit_price_data(microlamports).to_vec()
+    }
+
+    /// `SetComputeUnitLimit` data, usable in `const` items
+    pub const fn compute_unit_limit_data(units: u32) -> [u8; 5] {
+        ConstData::new().u8(0x02).u32(units).finish()
+    }
+
+    /// `SetComputeUnitPrice` data, usable in `const` items
+    pub const fn compute_unit_price_data(microlamports: u64) -> [u8; 9] {
+        ConstData::new().u8(0x03).u64(microlamports).finish()
+    }
+
+    /// Parse `SetComputeUnitLimit` instruction data
+    pub fn parse_compute_unit_limit(data: &[u8]) -> Option<u32> {
+        match data {
+            [0x02, units @ ..] => units.try_into().ok().map(u32::from_le_bytes),
+            _ => None,
+        }
+    }
+
+    /// Parse `SetComputeUnitPrice` instruction data
+    pub fn parse_compute_unit_price(data: &[u8]) -> Option<u64> {
+        match data {
+            [0x03, price @ ..] => price.try_into().ok().map(u64::from_le_bytes),
+            _ => None,
+        }
+    }
+
+    /// Compute budget requested by a message's compute budget instructions
+    #[derive(Debug, Clone, Copy, Default, PartialEq)]
+    pub struct RequestedBudget {
+        pub unit_limit: Option<u32>,
+        /// CU price in microlamports
+        pub unit_price: Option<u64>,
+        /// Instructions not addressed to the compute budget program
+        pub num_program_instructions: usize,
+    }
+
+    /// Collect the compute budget requested by `message`
+    pub fn requested_budget(message: &CompiledMessage) -> RequestedBudget {
+        let mut budget = RequestedBudget::default();
+        for instruction in &message.instructions {
+            let is_compute_budget = message
+                .account_keys
+                .get(instruction.program_id_index as usize)
+                .is_some_and(|key| *key == COMPUTE_BUDGET_PROGRAM_ID);
+            if !is_compute_budget {
+                budget.num_program_instructions += 1;
+                continue;
+            }
+            if let Some(units) = parse_compute_unit_limit(&instruction.data) {
+                budget.unit_limit = Some(units);
+            }
+            if let Some(price) = parse_compute_unit_price(&instruction.data) {
+                budget.unit_price = Some(price);
+            }
+        }
+        budget
     }
 }
 
@@ -240,10 +900,16 @@ mod tests {
     #[test]
     fn test_priority_fee_strategies() {
         let calculator = PriorityFeeCalculator::new();
-        assert_eq!(calculator.get_priority_fee(FeeStrategy::Low), 1);
-        assert_eq!(calculator.get_priority_fee(FeeStrategy::Medium), 100);
-        assert_eq!(calculator.get_priority_fee(FeeStrategy::High), 1000);
-        assert_eq!(calculator.get_priority_fee(FeeStrategy::Custom(500)), 500);
+        let message = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).build())
+            .compile()
+            .unwrap();
+        assert_eq!(calculator.get_priority_fee(&message, FeeStrategy::Low), 1);
+        assert_eq!(calculator.get_priority_fee(&message, FeeStrategy::Medium), 100);
+        assert_eq!(calculator.get_priority_fee(&message, FeeStrategy::High), 1000);
+        assert_eq!(calculator.get_priority_fee(&message, FeeStrategy::Custom(500)), 500);
     }
 
     #[test]
@@ -265,7 +931,7 @@ mod tests {
             .build_unsigned()
             .unwrap();
 
-        let estimate = calculator.estimate_fee(&tx, FeeStrategy::Medium);
+        let estimate = calculator.estimate_fee(&tx, FeeStrategy::Medium).unwrap();
         assert!(estimate.base_fee > 0);
         assert!(estimate.estimated_compute_units > 0);
         assert!(estimate.total_cost >= estimate.base_fee);
@@ -297,5 +963,210 @@ mod tests {
         let price_data = compute_budget::create_compute_unit_price_instruction(1000);
         assert_eq!(price_data[0], 0x03);
         assert_eq!(price_data.len(), 9);
+
+        assert_eq!(compute_budget::parse_compute_unit_limit(&limit_data), Some(200_000));
+        assert_eq!(compute_budget::parse_compute_unit_price(&price_data), Some(1000));
+        assert_eq!(compute_budget::parse_compute_unit_price(&limit_data), None);
+    }
+
+    #[test]
+    fn test_recommend_for_hot_writable_account() {
+        let calculator = PriorityFeeCalculator::new();
+        let payer = [1u8; 32];
+        let pool = [5u8; 32];
+        let oracle = [6u8; 32];
+
+        let instruction = InstructionEncoder::new([2u8; 32])
+            .writable(pool, false)
+            .readonly(oracle)
+            .append_u8(1)
+            .build();
+        let tx = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap();
+
+        let mut history = AccountFeeHistory::new();
+        history.extend(payer, [10, 20, 30]);
+        history.extend(pool, [1_000, 50_000, 90_000, 100_000]);
+        // Readonly accounts don't contend for write locks
+        history.extend(oracle, [1_000_000]);
+
+        let recommendation = calculator.recommend_for_accounts(&tx, &history, 75).unwrap();
+        assert_eq!(recommendation.micro_lamports_per_cu, 90_000);
+        assert_eq!(recommendation.hottest_account, Some(pool));
+
+        let empty = calculator
+            .recommend_for_accounts(&tx, &AccountFeeHistory::new(), 50)
+            .unwrap();
+        assert_eq!(empty.micro_lamports_per_cu, 0);
+        assert!(calculator.recommend_for_accounts(&tx, &history, 101).is_err());
+    }
+
+    #[test]
+    fn test_price_to_outrank() {
+        let calculator = PriorityFeeCalculator::new();
+        let competitors = [500, 20_000, 1_500, 20_000, 8_000];
+
+        assert_eq!(calculator.price_to_outrank(&competitors, 1).unwrap(), 20_001);
+        assert_eq!(calculator.price_to_outrank(&competitors, 3).unwrap(), 8_001);
+        assert_eq!(calculator.price_to_outrank(&competitors, 6).unwrap(), 0);
+        assert_eq!(calculator.price_to_outrank(&[u64::MAX], 1).unwrap(), u64::MAX);
+        assert!(calculator.price_to_outrank(&competitors, 0).is_err());
+    }
+
+    #[test]
+    fn test_fee_display_in_fiat() {
+        let estimate = FeeEstimate {
+            base_fee: 5_000,
+            priority_fee_per_cu: 50_000,
+            estimated_compute_units: 200_000,
+            total_cost: 15_000,
+        };
+        let usd = estimate.display_in(&|| Ok(140.0)).unwrap();
+        assert_eq!(usd.total.to_string(), "$0.0021");
+        assert_eq!(usd.base_fee.to_string(), "$0.00070");
+        assert!((usd.priority_fee.amount - 0.0014).abs() < 1e-12);
+
+        let chf = estimate.display_in(&FixedPrice::new(150_000.0, "CHF")).unwrap();
+        assert_eq!(chf.total.to_string(), "2.25 CHF");
+        assert!(estimate.display_in(&|| Ok(f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_estimate_batch() {
+        let calculator = PriorityFeeCalculator::new();
+        let transactions: Vec<CompiledTransaction> = (1..=4u8)
+            .map(|n| {
+                let mut builder = TransactionBuilder::new().payer([1u8; 32]).recent_blockhash([3u8; 32]);
+                for i in 0..n {
+                    builder = builder.add_instruction(InstructionEncoder::new([2u8; 32]).signer([10 + i; 32], false).build());
+                }
+                builder.build_unsigned().unwrap()
+            })
+            .collect();
+
+        let batch = calculator.estimate_batch(&transactions, FeeStrategy::High).unwrap();
+        assert_eq!(batch.estimates.len(), 4);
+        let singles: Vec<u64> = transactions
+            .iter()
+            .map(|tx| calculator.estimate_fee(tx, FeeStrategy::High).unwrap().total_cost)
+            .collect();
+        assert_eq!(batch.total_cost, singles.iter().sum::<u64>());
+        assert_eq!(batch.total_base_fee, 5_000 * (2 + 3 + 4 + 5));
+        assert_eq!(batch.total_base_fee + batch.total_priority_fee, batch.total_cost);
+        assert_eq!((batch.min_cost, batch.max_cost), (singles[0], singles[3]));
+        assert_eq!(batch.median_cost, singles[1]);
+        assert_eq!(batch.p90_cost, singles[3]);
+
+        let empty = calculator.estimate_batch(&[], FeeStrategy::Low).unwrap();
+        assert_eq!((empty.total_cost, empty.max_cost, empty.mean_cost), (0, 0, 0.0));
+    }
+
+    #[test]
+    fn test_dynamic_fee_policy() {
+        let calculator = PriorityFeeCalculator::new();
+        let hot = [9u8; 32];
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable(hot, false).build())
+            .build_unsigned()
+            .unwrap();
+
+        // Outbid on transactions writing the hot account, otherwise stay cheap
+        let by_account = |message: &CompiledMessage| {
+            if message.account_keys.contains(&hot) { 20_000 } else { 1 }
+        };
+        assert_eq!(calculator.estimate_fee(&tx, by_account).unwrap().priority_fee_per_cu, 20_000);
+
+        let boxed: Box<dyn FeePolicy> = Box::new(FeeStrategy::Medium);
+        let policies: [&dyn FeePolicy; 3] = [&FeeStrategy::Low, &by_account, &boxed];
+        let estimates = calculator.compare_strategies(&tx, policies).unwrap();
+        let prices: Vec<u64> = estimates.iter().map(|(_, e)| e.priority_fee_per_cu).collect();
+        assert_eq!(prices, vec![1, 20_000, 100]);
+        assert_eq!(
+            calculator.estimate_batch(std::slice::from_ref(&tx), by_account).unwrap().total_cost,
+            estimates[1].1.total_cost
+        );
+        assert_eq!(calculator.get_priority_fee(&tx.message, by_account), 20_000);
+
+        let presets = calculator.compare_strategies(&tx, FeeStrategy::PRESETS).unwrap();
+        assert_eq!(presets[2].0, FeeStrategy::High);
+        assert_eq!(presets[2].1.priority_fee_per_cu, 1_000);
+    }
+
+    #[test]
+    fn test_fee_jitter_is_bounded_and_seedable() {
+        let prices: Vec<u64> = {
+            let jitter = FeeJitter::up_to(0.2).seed(7);
+            (0..1_000).map(|_| jitter.apply(10_000)).collect()
+        };
+        assert!(prices.iter().all(|p| (10_000..=12_000).contains(p)));
+        let mean = prices.iter().sum::<u64>() as f64 / prices.len() as f64;
+        assert!((10_550.0..10_800.0).contains(&mean), "mean {}", mean);
+
+        let jitter = FeeJitter::up_to(0.2).seed(7);
+        assert_eq!(jitter.apply(10_000), prices[0]);
+        assert_eq!(FeeJitter::up_to(0.0).apply(10_000), 10_000);
+
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).build())
+            .build_unsigned()
+            .unwrap();
+        let calculator = PriorityFeeCalculator::new().with_jitter(FeeJitter::up_to(0.2).seed(7));
+        let estimate = calculator.estimate_fee(&tx, FeeStrategy::Custom(10_000)).unwrap();
+        assert_eq!(estimate.priority_fee_per_cu, prices[0]);
+    }
+
+    #[test]
+    fn test_per_leader_pricing() {
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).build())
+            .build_unsigned()
+            .unwrap();
+        let picky = [7u8; 32];
+        let policy = PerLeader::new(FeeStrategy::Custom(1_000), |price, leader: &UpcomingLeader| {
+            if leader.identity == picky {
+                price * 2
+            } else if leader.stake_share().is_some_and(|share| share > 0.05) {
+                price + 100
+            } else {
+                price
+            }
+        });
+
+        let calculator = PriorityFeeCalculator::new();
+        let estimate = |leader: UpcomingLeader| {
+            calculator.estimate_fee_for_leader(&tx, &policy as &dyn FeePolicy, &leader).unwrap().priority_fee_per_cu
+        };
+        assert_eq!(estimate(UpcomingLeader::new(picky, 100)), 2_000);
+        assert_eq!(estimate(UpcomingLeader::new([8u8; 32], 100).stake(10, 100)), 1_100);
+        assert_eq!(estimate(UpcomingLeader::new([8u8; 32], 100)), 1_000);
+        // Without a leader the base price applies, and plain policies ignore it
+        assert_eq!(calculator.estimate_fee(&tx, &policy as &dyn FeePolicy).unwrap().priority_fee_per_cu, 1_000);
+        let window = LeaderWindow { leader: picky, start_slot: 100, end_slot: 103 };
+        let leader = UpcomingLeader::from_window(&window);
+        assert_eq!(FeeStrategy::Low.micro_lamports_per_cu_for_leader(&tx.message, &leader), 1);
+    }
+
+    #[test]
+    fn test_overflowing_price_is_an_error() {
+        let tx = crate::testkit::simple_transfer_tx(1);
+        let calculator = PriorityFeeCalculator::new();
+        let outbid = calculator.price_to_outrank(&[u64::MAX - 1], 1).unwrap();
+        let err = calculator.estimate_fee(&tx, FeeStrategy::Custom(outbid)).unwrap_err();
+        assert!(matches!(err, TxAsmError::FeeCalculationError(_)));
+
+        // Jitter saturates the price rather than wrapping it
+        let jittered = PriorityFeeCalculator::new().with_jitter(FeeJitter::up_to(1.0).seed(7));
+        assert!(jittered.estimate_fee(&tx, FeeStrategy::Custom(u64::MAX / 2)).is_err());
+        assert!(calculator.estimate_batch(&[tx.clone(), tx], FeeStrategy::Custom(u64::MAX / 4)).is_err());
     }
 }
@@ -0,0 +1,88 @@
+//! Helius priority fee adapter
+//!
+//! Implements [`FeeSource`] over Helius' `getPriorityFeeEstimate`, asking
+//! for every priority level so one call serves every [`FeeStrategy`].
+//!
+//! Enabled with the `helius` feature.
+
+use crate::error::Result;
+use crate::fee_calculator::{FeePolicy, FeeStrategy};
+use crate::rpc_fees::{encode_accounts, writable_accounts, parse_priority_fee_estimate, FeeSource, PriorityFeeLevels, RpcTransport};
+use crate::transaction::CompiledMessage;
+use serde_json::json;
+
+/// [`FeeSource`] backed by Helius' `getPriorityFeeEstimate`
+pub struct HeliusFeeSource<T> {
+    transport: T,
+}
+
+impl<T: RpcTransport> HeliusFeeSource<T> {
+    /// `transport` posts to a Helius RPC URL (including the API key)
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    /// JSON-RPC request body for `accounts`
+    pub fn request(accounts: &[[u8; 32]]) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "txasm",
+            "method": "getPriorityFeeEstimate",
+            "params": [{
+                "accountKeys": encode_accounts(accounts),
+                "options": { "includeAllPriorityFeeLevels": true },
+            }],
+        })
+        .to_string()
+    }
+
+    /// All priority levels for `accounts`
+    pub fn levels(&self, accounts: &[[u8; 32]]) -> Result<PriorityFeeLevels> {
+        let response = self.transport.post(&Self::request(accounts))?;
+        let estimate = parse_priority_fee_estimate(&response)?;
+        match (estimate.levels, estimate.estimate) {
+            (Some(levels), _) => Ok(levels),
+            // Older deployments ignore the option; use the single estimate throughout
+            (None, Some(price)) => Ok(PriorityFeeLevels {
+                min: price,
+                low: price,
+                medium: price,
+                high: price,
+                very_high: price,
+                unsafe_max: price,
+            }),
+            (None, None) => unreachable!("parse_priority_fee_estimate requires one of them"),
+        }
+    }
+}
+
+impl<T: RpcTransport> FeeSource for HeliusFeeSource<T> {
+    fn preset_fee(&self, accounts: &[[u8; 32]], strategy: FeeStrategy) -> Result<u64> {
+        Ok(self.levels(accounts)?.level(strategy))
+    }
+
+    /// One request serves every tier `policy` asks about
+    fn priority_fee(&self, message: &CompiledMessage, policy: &dyn FeePolicy) -> Result<u64> {
+        self.levels(&writable_accounts(message))?.price_for(message, policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helius_fee_source() {
+        let transport = |body: &str| -> Result<String> {
+            assert!(body.contains("getPriorityFeeEstimate"));
+            assert!(body.contains(&bs58::encode([5u8; 32]).into_string()));
+            Ok(r#"{"jsonrpc":"2.0","result":{"priorityFeeLevels":{
+                "min":0,"low":10,"medium":250.5,"high":4000,"veryHigh":90000,"unsafeMax":1000000
+            }},"id":"txasm"}"#
+                .to_string())
+        };
+        let source = HeliusFeeSource::new(transport);
+        assert_eq!(source.preset_fee(&[[5u8; 32]], FeeStrategy::Medium).unwrap(), 251);
+        assert_eq!(source.preset_fee(&[[5u8; 32]], FeeStrategy::High).unwrap(), 4000);
+    }
+}
@@ -0,0 +1,561 @@
+//! Anchor IDL account resolution
+//!
+//! Account order is the most common integration bug with Anchor programs:
+//! the instruction compiles, signs and fails on chain with a constraint
+//! error. An [`Idl`] loaded from the program's JSON IDL lists every
+//! instruction's accounts in order, so [`Idl::account_metas`] can build the
+//! metas from bindings by name instead:
+//!
+//! ```
+//! use txasm::{accounts, idl::Idl};
+//!
+//! let idl = Idl::from_json(r#"{
+//!   "address": "11111111111111111111111111111111",
+//!   "instructions": [{"name": "burn", "accounts": [
+//!     {"name": "mint", "writable": true},
+//!     {"name": "owner", "signer": true}
+//!   ]}]
+//! }"#).unwrap();
+//! let metas = idl.account_metas("burn", &accounts! { "owner" => [2u8; 32], "mint" => [3u8; 32] }).unwrap();
+//! assert_eq!(metas[0].pubkey, [3u8; 32]);
+//! ```
+//!
+//! Accounts with a fixed `address` or a documented `pda` need no binding;
+//! PDA seeds may refer to other accounts of the instruction or, through
+//! [`IdlAccounts::arg`], to instruction arguments. Both the current
+//! (`writable`/`signer`) and pre-0.30 (`isMut`/`isSigner`) IDL formats load.
+//!
+//! [`verify`] runs the other direction as a pre-submission contract test:
+//! it checks a compiled transaction's instructions for the program against
+//! the accounts and argument sizes the IDL declares.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::AccountMeta;
+use crate::serialization::parse_pubkey;
+use crate::transaction::CompiledTransaction;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Named account keys (and argument seeds) for one instruction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdlAccounts {
+    keys: HashMap<String, [u8; 32]>,
+    args: HashMap<String, Vec<u8>>,
+}
+
+impl IdlAccounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind the account named `name` in the IDL
+    pub fn bind(mut self, name: impl Into<String>, key: impl Into<[u8; 32]>) -> Self {
+        self.keys.insert(name.into(), key.into());
+        self
+    }
+
+    /// Seed bytes for an instruction argument used in a PDA derivation
+    pub fn arg(mut self, name: impl Into<String>, seed: impl Into<Vec<u8>>) -> Self {
+        self.args.insert(name.into(), seed.into());
+        self
+    }
+}
+
+/// A seed of a documented PDA
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlSeed {
+    /// Fixed bytes, given as a byte array or (pre-0.30) a string
+    Const { value: Value },
+    /// Key of another account of the instruction
+    Account { path: String },
+    /// Value of an instruction argument
+    Arg { path: String },
+}
+
+/// A PDA derivation documented in the IDL
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdlPda {
+    pub seeds: Vec<IdlSeed>,
+    /// Deriving program when not the IDL's own
+    #[serde(default)]
+    pub program: Option<IdlSeed>,
+}
+
+/// An account of an instruction, or a group of them
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdlAccountItem {
+    pub name: String,
+    #[serde(default, alias = "isMut")]
+    pub writable: bool,
+    #[serde(default, alias = "isSigner")]
+    pub signer: bool,
+    #[serde(default, alias = "isOptional")]
+    pub optional: bool,
+    /// Fixed base58 address
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub pda: Option<IdlPda>,
+    /// Members of a composite account group, flattened in order
+    #[serde(default)]
+    pub accounts: Option<Vec<IdlAccountItem>>,
+}
+
+/// A named instruction argument
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    /// Type as written in the IDL, e.g. `"u64"` or `{"vec": "u8"}`
+    #[serde(rename = "type")]
+    pub ty: Value,
+}
+
+impl IdlField {
+    /// Borsh size of the argument: `(minimum, exact)`, where `exact` is
+    /// false for strings, vectors, options and user-defined types
+    pub fn encoded_size(&self) -> (usize, bool) {
+        borsh_size(&self.ty)
+    }
+}
+
+fn borsh_size(ty: &Value) -> (usize, bool) {
+    match ty {
+        Value::String(name) => match name.as_str() {
+            "bool" | "u8" | "i8" => (1, true),
+            "u16" | "i16" => (2, true),
+            "u32" | "i32" | "f32" => (4, true),
+            "u64" | "i64" | "f64" => (8, true),
+            "u128" | "i128" => (16, true),
+            "pubkey" | "publicKey" => (32, true),
+            // string and bytes carry a u32 length prefix
+            _ => (4, false),
+        },
+        Value::Object(map) => {
+            if let Some(Value::Array(array)) = map.get("array") {
+                let (size, exact) = array.first().map_or((0, false), borsh_size);
+                let len = array.get(1).and_then(Value::as_u64).unwrap_or_default() as usize;
+                (size * len, exact && array.get(1).is_some_and(Value::is_u64))
+            } else if map.contains_key("option") || map.contains_key("coption") {
+                (1, false)
+            } else if map.contains_key("vec") {
+                (4, false)
+            } else {
+                (0, false)
+            }
+        }
+        _ => (0, false),
+    }
+}
+
+/// An instruction as described in the IDL
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IdlInstruction {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+    pub accounts: Vec<IdlAccountItem>,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+}
+
+impl IdlInstruction {
+    /// Leading data bytes that select this instruction
+    ///
+    /// Older IDLs omit it; Anchor then uses the first 8 bytes of
+    /// `sha256("global:<snake_case name>")`.
+    pub fn discriminator(&self) -> Vec<u8> {
+        if let Some(discriminator) = &self.discriminator {
+            return discriminator.clone();
+        }
+        let mut name = String::with_capacity(self.name.len() + 4);
+        for c in self.name.chars() {
+            if c.is_ascii_uppercase() {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        }
+        Sha256::digest(format!("global:{}", name).as_bytes())[..8].to_vec()
+    }
+
+    /// Data length: `(minimum, exact)`, discriminator included
+    pub fn data_size(&self) -> (usize, bool) {
+        self.args
+            .iter()
+            .map(IdlField::encoded_size)
+            .fold((self.discriminator().len(), true), |(total, all_exact), (size, exact)| {
+                (total + size, all_exact && exact)
+            })
+    }
+
+    /// Accounts in the order the program expects them
+    pub fn flat_accounts(&self) -> Vec<&IdlAccountItem> {
+        fn flatten<'a>(items: &'a [IdlAccountItem], out: &mut Vec<&'a IdlAccountItem>) {
+            for item in items {
+                match &item.accounts {
+                    Some(group) => flatten(group, out),
+                    None => out.push(item),
+                }
+            }
+        }
+        let mut accounts = Vec::new();
+        flatten(&self.accounts, &mut accounts);
+        accounts
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+struct IdlMetadata {
+    #[serde(default)]
+    address: Option<String>,
+}
+
+/// An Anchor IDL, as far as instructions and their accounts go
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Idl {
+    /// Program address (0.30+)
+    #[serde(default)]
+    address: Option<String>,
+    /// Holds the program address in older IDLs
+    #[serde(default)]
+    metadata: IdlMetadata,
+    pub instructions: Vec<IdlInstruction>,
+}
+
+impl Idl {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| TxAsmError::DeserializationError(format!("Invalid IDL: {}", e)))
+    }
+
+    /// The program the IDL describes
+    pub fn program_id(&self) -> Result<[u8; 32]> {
+        let address = self.address.as_ref().or(self.metadata.address.as_ref()).ok_or_else(|| {
+            TxAsmError::InvalidInstruction("IDL does not record the program address".to_string())
+        })?;
+        parse_pubkey(address)
+    }
+
+    pub fn instruction(&self, name: &str) -> Result<&IdlInstruction> {
+        self.instructions
+            .iter()
+            .find(|ix| ix.name == name)
+            .ok_or_else(|| TxAsmError::InvalidInstruction(format!("IDL has no instruction '{}'", name)))
+    }
+
+    /// Ordered account metas for instruction `name`
+    ///
+    /// Each account takes its binding, else its fixed address, else its
+    /// documented PDA. A missing optional account is passed as the program
+    /// ID, as Anchor expects. Bindings that name no account of the
+    /// instruction are rejected, which catches typos.
+    pub fn account_metas(&self, name: &str, bindings: &IdlAccounts) -> Result<Vec<AccountMeta>> {
+        let instruction = self.instruction(name)?;
+        let accounts = instruction.flat_accounts();
+        if let Some(unknown) = bindings.keys.keys().find(|bound| accounts.iter().all(|a| a.name != **bound)) {
+            return Err(TxAsmError::AccountError(format!(
+                "Instruction '{}' has no account '{}'",
+                name, unknown
+            )));
+        }
+
+        let mut resolved: HashMap<&str, [u8; 32]> = HashMap::new();
+        let mut metas = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let key = match bindings.keys.get(&account.name) {
+                Some(key) => *key,
+                None => match (&account.address, &account.pda) {
+                    (Some(address), _) => parse_pubkey(address)?,
+                    (None, Some(pda)) => self.derive(pda, &resolved, bindings)?,
+                    (None, None) if account.optional => {
+                        metas.push(AccountMeta::new_readonly(self.program_id()?, false));
+                        continue;
+                    }
+                    (None, None) => {
+                        return Err(TxAsmError::AccountError(format!(
+                            "Instruction '{}' is missing account '{}'",
+                            name, account.name
+                        )))
+                    }
+                },
+            };
+            resolved.insert(&account.name, key);
+            metas.push(AccountMeta::new(key, account.signer, account.writable));
+        }
+        Ok(metas)
+    }
+
+    fn derive(&self, pda: &IdlPda, resolved: &HashMap<&str, [u8; 32]>, bindings: &IdlAccounts) -> Result<[u8; 32]> {
+        let seed_bytes = |seed: &IdlSeed| -> Result<Vec<u8>> {
+            match seed {
+                IdlSeed::Const { value } => match value {
+                    Value::String(text) => Ok(text.as_bytes().to_vec()),
+                    value => serde_json::from_value(value.clone())
+                        .map_err(|e| TxAsmError::DeserializationError(format!("Invalid const seed: {}", e))),
+                },
+                IdlSeed::Account { path } => resolved
+                    .get(path.as_str())
+                    .or_else(|| bindings.keys.get(path))
+                    .map(|key| key.to_vec())
+                    .ok_or_else(|| {
+                        TxAsmError::AccountError(format!("PDA seed needs account '{}', which is resolved later", path))
+                    }),
+                IdlSeed::Arg { path } => bindings
+                    .args
+                    .get(path)
+                    .cloned()
+                    .ok_or_else(|| TxAsmError::AccountError(format!("PDA seed needs argument '{}'", path))),
+            }
+        };
+
+        let seeds = pda.seeds.iter().map(seed_bytes).collect::<Result<Vec<_>>>()?;
+        let program = match &pda.program {
+            Some(seed) => seed_bytes(seed)?
+                .try_into()
+                .map_err(|_| TxAsmError::InvalidPublicKey("PDA program is not 32 bytes".to_string()))?,
+            None => self.program_id()?,
+        };
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        Ok(Pubkey::find_program_address(&seeds, &Pubkey::new_from_array(program))
+            .0
+            .to_bytes())
+    }
+}
+
+/// A way an instruction departs from its IDL declaration
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdlMismatch {
+    /// Data matches no declared discriminator
+    UnknownInstruction { instruction: usize },
+    /// Fewer accounts than declared; extra ones are remaining accounts
+    MissingAccounts { instruction: usize, name: String, expected: usize, found: usize },
+    NotSigner { instruction: usize, account: String },
+    NotWritable { instruction: usize, account: String },
+    /// Data length outside what the declared args encode to
+    DataLength { instruction: usize, name: String, expected: usize, exact: bool, found: usize },
+}
+
+impl fmt::Display for IdlMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdlMismatch::UnknownInstruction { instruction } => {
+                write!(f, "Instruction {}: discriminator matches no IDL instruction", instruction)
+            }
+            IdlMismatch::MissingAccounts { instruction, name, expected, found } => write!(
+                f,
+                "Instruction {} ({}): {} accounts, IDL declares {}",
+                instruction, name, found, expected
+            ),
+            IdlMismatch::NotSigner { instruction, account } => {
+                write!(f, "Instruction {}: account '{}' must sign", instruction, account)
+            }
+            IdlMismatch::NotWritable { instruction, account } => {
+                write!(f, "Instruction {}: account '{}' must be writable", instruction, account)
+            }
+            IdlMismatch::DataLength { instruction, name, expected, exact, found } => write!(
+                f,
+                "Instruction {} ({}): {} data bytes, args encode to {}{}",
+                instruction,
+                name,
+                found,
+                if *exact { "" } else { "at least " },
+                expected
+            ),
+        }
+    }
+}
+
+/// Result of [`verify`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdlReport {
+    /// Instructions that invoke the IDL's program
+    pub checked: usize,
+    pub mismatches: Vec<IdlMismatch>,
+}
+
+impl IdlReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Check every instruction invoking `idl`'s program against its declaration
+///
+/// Accounts must carry at least the declared signer and writable flags;
+/// an optional account passed as the program ID is not checked.
+pub fn verify(transaction: &CompiledTransaction, idl: &Idl) -> Result<IdlReport> {
+    let program_id = idl.program_id()?;
+    let message = &transaction.message;
+    let mut report = IdlReport::default();
+
+    for (index, ix) in message.instructions.iter().enumerate() {
+        if message.account_keys.get(ix.program_id_index as usize) != Some(&program_id) {
+            continue;
+        }
+        report.checked += 1;
+        let Some(declared) = idl.instructions.iter().find(|d| ix.data.starts_with(&d.discriminator())) else {
+            report.mismatches.push(IdlMismatch::UnknownInstruction { instruction: index });
+            continue;
+        };
+
+        let accounts = declared.flat_accounts();
+        if ix.account_indices.len() < accounts.len() {
+            report.mismatches.push(IdlMismatch::MissingAccounts {
+                instruction: index,
+                name: declared.name.clone(),
+                expected: accounts.len(),
+                found: ix.account_indices.len(),
+            });
+        }
+        for (account, &key_index) in accounts.iter().zip(ix.account_indices.iter()) {
+            let key_index = key_index as usize;
+            if account.optional && message.account_keys.get(key_index) == Some(&program_id) {
+                continue;
+            }
+            if account.signer && !message.is_signer(key_index) {
+                report.mismatches.push(IdlMismatch::NotSigner { instruction: index, account: account.name.clone() });
+            }
+            if account.writable && !message.is_writable(key_index) {
+                report.mismatches.push(IdlMismatch::NotWritable { instruction: index, account: account.name.clone() });
+            }
+        }
+
+        let (expected, exact) = declared.data_size();
+        let found = ix.data.len();
+        if found < expected || (exact && found != expected) {
+            report.mismatches.push(IdlMismatch::DataLength {
+                instruction: index,
+                name: declared.name.clone(),
+                expected,
+                exact,
+                found,
+            });
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit;
+
+    const PROGRAM: &str = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS";
+
+    fn idl() -> Idl {
+        Idl::from_json(&format!(
+            r#"{{
+              "address": "{}",
+              "instructions": [{{
+                "name": "deposit",
+                "accounts": [
+                  {{"name": "user", "writable": true, "signer": true}},
+                  {{"name": "vault", "writable": true, "pda": {{"seeds": [
+                    {{"kind": "const", "value": [118, 97, 117, 108, 116]}},
+                    {{"kind": "account", "path": "user"}},
+                    {{"kind": "arg", "path": "nonce"}}
+                  ]}}}},
+                  {{"name": "token", "accounts": [
+                    {{"name": "mint"}},
+                    {{"name": "referrer", "optional": true}}
+                  ]}},
+                  {{"name": "system_program", "address": "11111111111111111111111111111111"}}
+                ],
+                "args": [{{"name": "nonce", "type": "u8"}}]
+              }}]
+            }}"#,
+            PROGRAM
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_account_metas_resolve_pdas_and_groups() {
+        let idl = idl();
+        let (user, mint) = (testkit::pubkey(1), testkit::address(2));
+        let metas = idl
+            .account_metas("deposit", &crate::accounts! { "mint" => mint, "user" => user }.arg("nonce", [7]))
+            .unwrap();
+
+        let program = Pubkey::new_from_array(idl.program_id().unwrap());
+        let (vault, _) = Pubkey::find_program_address(&[b"vault", &user, &[7]], &program);
+        let keys: Vec<_> = metas.iter().map(|m| m.pubkey).collect();
+        assert_eq!(keys, vec![user, vault.to_bytes(), mint, program.to_bytes(), [0u8; 32]]);
+        assert!(metas[0].is_signer && metas[1].is_writable && !metas[2].is_writable);
+    }
+
+    #[test]
+    fn test_account_metas_report_mistakes() {
+        let idl = idl();
+        let user = testkit::pubkey(1);
+        let typo = crate::accounts! { "user" => user, "mnit" => testkit::address(2) }.arg("nonce", [7]);
+        assert!(idl.account_metas("deposit", &typo).unwrap_err().to_string().contains("'mnit'"));
+
+        let missing = crate::accounts! { "user" => user }.arg("nonce", [7]);
+        assert!(idl.account_metas("deposit", &missing).unwrap_err().to_string().contains("'mint'"));
+        let no_arg = crate::accounts! { "user" => user, "mint" => testkit::address(2) };
+        assert!(idl.account_metas("deposit", &no_arg).unwrap_err().to_string().contains("'nonce'"));
+        assert!(idl.account_metas("withdraw", &IdlAccounts::new()).is_err());
+
+        // Pre-0.30 format
+        let legacy = Idl::from_json(&format!(
+            r#"{{"metadata": {{"address": "{}"}}, "instructions": [{{"name": "init",
+                "accounts": [{{"name": "payer", "isMut": true, "isSigner": true}}], "args": []}}]}}"#,
+            PROGRAM
+        ))
+        .unwrap();
+        let metas = legacy.account_metas("init", &crate::accounts! { "payer" => user }).unwrap();
+        assert_eq!(metas, vec![AccountMeta::new(user, true, true)]);
+    }
+
+    #[test]
+    fn test_verify_against_idl() {
+        let idl = idl();
+        let user = testkit::pubkey(1);
+        let program = idl.program_id().unwrap();
+        let bindings = crate::accounts! { "user" => user, "mint" => testkit::address(2) }.arg("nonce", [7]);
+        let deposit = idl.instruction("deposit").unwrap();
+        let mut data = deposit.discriminator();
+        data.push(7);
+        let instruction = crate::instruction::RawInstruction::new(
+            program,
+            idl.account_metas("deposit", &bindings).unwrap(),
+            data,
+        );
+        let build = |instruction| {
+            crate::transaction::TransactionBuilder::new()
+                .payer(user)
+                .recent_blockhash(testkit::blockhash(1))
+                .add_instruction(instruction)
+                .build_unsigned()
+                .unwrap()
+        };
+
+        let report = verify(&build(instruction.clone()), &idl).unwrap();
+        assert_eq!(report.checked, 1);
+        assert!(report.is_ok(), "{:?}", report);
+
+        let mut broken = instruction.clone();
+        broken.accounts[2].is_writable = false;
+        broken.accounts[1].is_writable = false;
+        broken.accounts.truncate(4);
+        broken.data.to_mut().push(0);
+        let report = verify(&build(broken), &idl).unwrap();
+        assert_eq!(
+            report.mismatches.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "Instruction 0 (deposit): 4 accounts, IDL declares 5",
+                "Instruction 0: account 'vault' must be writable",
+                "Instruction 0 (deposit): 10 data bytes, args encode to 9",
+            ]
+        );
+
+        let mut unknown = instruction;
+        unknown.data = vec![0; 9].into();
+        let report = verify(&build(unknown), &idl).unwrap();
+        assert_eq!(report.mismatches, vec![IdlMismatch::UnknownInstruction { instruction: 0 }]);
+    }
+}
@@ -0,0 +1,225 @@
+//! Plugin decoders and lints over compiled instructions
+//!
+//! Third-party crates describe their own programs by implementing
+//! [`InstructionInspector`]. [`Inspectors`] walks a message once and calls
+//! every interested inspector for each instruction, each account it
+//! references and its data, in that order. Inspectors report through
+//! [`Notes`], which tags each note with the inspector and instruction.
+//!
+//! ```ignore
+//! struct MyProgramLint;
+//!
+//! impl InstructionInspector for MyProgramLint {
+//!     fn name(&self) -> &str { "my-program" }
+//!     fn accepts(&self, program_id: &[u8; 32]) -> bool { *program_id == MY_PROGRAM_ID }
+//!     fn on_data(&mut self, _ix: &InstructionContext<'_>, data: &[u8], notes: &mut Notes) {
+//!         if data.first() == Some(&7) { notes.note("deprecated CloseV1; use CloseV2"); }
+//!     }
+//! }
+//!
+//! let notes = Inspectors::new().with(MyProgramLint).run(&tx.message);
+//! ```
+
+use crate::diff::AccountRole;
+use crate::transaction::CompiledMessage;
+
+/// The instruction being inspected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionContext<'a> {
+    /// Position in the message
+    pub index: usize,
+    pub program_id: &'a [u8; 32],
+    /// Number of accounts the instruction references
+    pub num_accounts: usize,
+}
+
+/// A finding reported by an inspector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectorNote {
+    pub inspector: String,
+    /// Instruction the note is about, if any
+    pub instruction: Option<usize>,
+    pub message: String,
+}
+
+/// Where inspectors write their notes
+#[derive(Debug, Default)]
+pub struct Notes {
+    inspector: String,
+    instruction: Option<usize>,
+    notes: Vec<InspectorNote>,
+}
+
+impl Notes {
+    /// Record a note about the current instruction
+    pub fn note(&mut self, message: impl Into<String>) {
+        self.notes.push(InspectorNote {
+            inspector: self.inspector.clone(),
+            instruction: self.instruction,
+            message: message.into(),
+        });
+    }
+
+    fn enter(&mut self, inspector: &str, instruction: Option<usize>) {
+        inspector.clone_into(&mut self.inspector);
+        self.instruction = instruction;
+    }
+}
+
+/// Callbacks invoked while walking a message; every callback defaults to
+/// doing nothing
+pub trait InstructionInspector {
+    /// Label attached to this inspector's notes
+    fn name(&self) -> &str;
+
+    /// Whether to be called for instructions of `program_id`
+    fn accepts(&self, _program_id: &[u8; 32]) -> bool {
+        true
+    }
+
+    /// Start of an instruction
+    fn on_instruction(&mut self, _ix: &InstructionContext<'_>, _notes: &mut Notes) {}
+
+    /// The account at `position` in the instruction's account list
+    fn on_account(&mut self, _ix: &InstructionContext<'_>, _position: usize, _account: &AccountRole, _notes: &mut Notes) {}
+
+    /// The instruction's data, after its accounts
+    fn on_data(&mut self, _ix: &InstructionContext<'_>, _data: &[u8], _notes: &mut Notes) {}
+
+    /// After the last instruction, for message-wide findings
+    fn on_finish(&mut self, _notes: &mut Notes) {}
+}
+
+/// A closure inspects each instruction's data
+impl<F> InstructionInspector for F
+where
+    F: FnMut(&InstructionContext<'_>, &[u8], &mut Notes),
+{
+    fn name(&self) -> &str {
+        "closure"
+    }
+
+    fn on_data(&mut self, ix: &InstructionContext<'_>, data: &[u8], notes: &mut Notes) {
+        self(ix, data, notes)
+    }
+}
+
+/// Registered inspectors, run together in one pass
+#[derive(Default)]
+pub struct Inspectors {
+    inspectors: Vec<Box<dyn InstructionInspector>>,
+}
+
+impl Inspectors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, inspector: impl InstructionInspector + 'static) -> Self {
+        self.inspectors.push(Box::new(inspector));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.inspectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inspectors.is_empty()
+    }
+
+    /// Walk `message`, returning every note in the order reported
+    ///
+    /// Instructions whose program index or account indices fall outside
+    /// the key list are skipped.
+    pub fn run(&mut self, message: &CompiledMessage) -> Vec<InspectorNote> {
+        let mut notes = Notes::default();
+        for ix in message.walk().flatten() {
+            let context = InstructionContext {
+                index: ix.index,
+                program_id: ix.program_id,
+                num_accounts: ix.accounts.len(),
+            };
+            for inspector in self.inspectors.iter_mut().filter(|i| i.accepts(ix.program_id)) {
+                notes.enter(inspector.name(), Some(ix.index));
+                inspector.on_instruction(&context, &mut notes);
+                for (position, account) in ix.accounts.iter().enumerate() {
+                    inspector.on_account(&context, position, account, &mut notes);
+                }
+                inspector.on_data(&context, ix.data, &mut notes);
+            }
+        }
+        for inspector in &mut self.inspectors {
+            notes.enter(inspector.name(), None);
+            inspector.on_finish(&mut notes);
+        }
+        notes.notes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit;
+
+    const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+
+    /// Flags transfers and counts writable accounts across the message
+    #[derive(Default)]
+    struct TransferLint {
+        writable: usize,
+    }
+
+    impl InstructionInspector for TransferLint {
+        fn name(&self) -> &str {
+            "transfer-lint"
+        }
+
+        fn accepts(&self, program_id: &[u8; 32]) -> bool {
+            *program_id == SYSTEM_PROGRAM_ID
+        }
+
+        fn on_account(&mut self, _ix: &InstructionContext<'_>, _position: usize, account: &AccountRole, _notes: &mut Notes) {
+            self.writable += account.is_writable as usize;
+        }
+
+        fn on_data(&mut self, _ix: &InstructionContext<'_>, data: &[u8], notes: &mut Notes) {
+            if data.starts_with(&2u32.to_le_bytes()) {
+                notes.note("system transfer");
+            }
+        }
+
+        fn on_finish(&mut self, notes: &mut Notes) {
+            notes.note(format!("{} writable", self.writable));
+        }
+    }
+
+    #[test]
+    fn test_inspectors_receive_callbacks() {
+        let tx = testkit::simple_transfer_tx(1);
+        let notes = Inspectors::new().with(TransferLint::default()).run(&tx.message);
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].inspector, "transfer-lint");
+        assert_eq!(notes[0].instruction, Some(0));
+        assert_eq!(notes[0].message, "system transfer");
+        assert_eq!(notes[1].instruction, None);
+        assert_eq!(notes[1].message, "2 writable");
+    }
+
+    #[test]
+    fn test_closure_and_filtering() {
+        let tx = testkit::memo_tx(1, 3);
+        let mut inspectors = Inspectors::new()
+            .with(TransferLint::default())
+            .with(|ix: &InstructionContext<'_>, data: &[u8], notes: &mut Notes| {
+                notes.note(format!("{} accounts, {} bytes", ix.num_accounts, data.len()))
+            });
+        assert_eq!(inspectors.len(), 2);
+        let notes = inspectors.run(&tx.message);
+        // Three memos for the closure; the transfer lint only finishes
+        assert_eq!(notes.len(), 4);
+        assert_eq!(notes[2].instruction, Some(2));
+        assert_eq!(notes[2].message, "1 accounts, 6 bytes");
+        assert_eq!(notes[3].message, "0 writable");
+    }
+}
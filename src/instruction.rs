@@ -5,10 +5,12 @@
 
 use crate::error::{Result, TxAsmError};
 use crate::serialization::{
-    ByteSerialize, encode_compact_u16, decode_compact_u16,
+    ByteSerialize, encode_len, decode_compact_u16,
     encode_pubkey, encode_u8, decode_u8,
 };
+use crate::token::TokenAmount;
 use solana_sdk::pubkey::Pubkey;
+use std::borrow::Cow;
 use std::io::Cursor;
 
 /// Account metadata for an instruction
@@ -74,16 +76,21 @@ pub struct RawInstruction {
     pub program_id: [u8; 32],
     /// Account keys required by this instruction
     pub accounts: Vec<AccountMeta>,
-    /// Instruction data (opaque bytes)
-    pub data: Vec<u8>,
+    /// Instruction data (opaque bytes), owned or borrowed from a static
+    pub data: Cow<'static, [u8]>,
 }
 
 impl RawInstruction {
-    pub fn new(program_id: [u8; 32], accounts: Vec<AccountMeta>, data: Vec<u8>) -> Self {
+    /// Create an instruction from owned (`Vec<u8>`) or static (`&'static [u8]`) data
+    pub fn new(
+        program_id: [u8; 32],
+        accounts: Vec<AccountMeta>,
+        data: impl Into<Cow<'static, [u8]>>,
+    ) -> Self {
         Self {
             program_id,
             accounts,
-            data,
+            data: data.into(),
         }
     }
 
@@ -101,7 +108,7 @@ impl RawInstruction {
         Self {
             program_id: program_id.to_bytes(),
             accounts,
-            data: data.to_vec(),
+            data: Cow::Owned(data.to_vec()),
         }
     }
 
@@ -119,13 +126,13 @@ impl ByteSerialize for RawInstruction {
         encode_u8(0, writer)?;
         
         // Encode accounts
-        encode_compact_u16(self.accounts.len() as u16, writer)?;
+        encode_len("instruction accounts", self.accounts.len(), writer)?;
         for _account in &self.accounts {
             encode_u8(0, writer)?; // Account index placeholder
         }
         
         // Encode data
-        encode_compact_u16(self.data.len() as u16, writer)?;
+        encode_len("instruction data", self.data.len(), writer)?;
         writer.extend_from_slice(&self.data);
         
         Ok(())
@@ -224,6 +231,17 @@ impl InstructionEncoder {
         self
     }
 
+    /// Append a token amount's raw value, as `Transfer` expects it
+    pub fn append_token_amount(self, amount: TokenAmount) -> Self {
+        self.append_u64(amount.raw)
+    }
+
+    /// Append a token amount's raw value and decimals, as `TransferChecked`
+    /// and the other `*Checked` instructions expect them
+    pub fn append_token_amount_checked(self, amount: TokenAmount) -> Self {
+        self.append_u64(amount.raw).append_u8(amount.decimals)
+    }
+
     /// Build the final instruction
     pub fn build(self) -> RawInstruction {
         RawInstruction::new(self.program_id, self.accounts, self.data)
@@ -238,6 +256,86 @@ impl InstructionEncoder {
     }
 }
 
+/// Compile-time encoder for fixed-size instruction data
+///
+/// Every method is a `const fn`, so static data can be assembled into a
+/// `const` array and passed to [`RawInstruction::new`] as `&CONST` without
+/// any runtime encoding or allocation:
+///
+/// ```
+/// use txasm::instruction::{ConstData, RawInstruction};
+///
+/// const SWAP: [u8; 17] = ConstData::new().u8(9).u64(1_000).u64(990).finish();
+/// let ix = RawInstruction::new([1u8; 32], vec![], &SWAP);
+/// assert!(matches!(ix.data, std::borrow::Cow::Borrowed(_)));
+/// ```
+///
+/// Writing past `N`, or finishing short of it, panics, which in a `const`
+/// context is a compile error.
+#[derive(Debug, Clone, Copy)]
+pub struct ConstData<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ConstData<N> {
+    pub const fn new() -> Self {
+        Self { bytes: [0; N], len: 0 }
+    }
+
+    /// Append raw bytes
+    pub const fn bytes(mut self, bytes: &[u8]) -> Self {
+        assert!(self.len + bytes.len() <= N, "ConstData overflow");
+        let mut i = 0;
+        while i < bytes.len() {
+            self.bytes[self.len + i] = bytes[i];
+            i += 1;
+        }
+        self.len += bytes.len();
+        self
+    }
+
+    pub const fn u8(self, value: u8) -> Self {
+        self.bytes(&[value])
+    }
+
+    /// Append a little-endian u16
+    pub const fn u16(self, value: u16) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    /// Append a little-endian u32
+    pub const fn u32(self, value: u32) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    /// Append a little-endian u64
+    pub const fn u64(self, value: u64) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    /// Append a little-endian i64
+    pub const fn i64(self, value: i64) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub const fn pubkey(self, key: &[u8; 32]) -> Self {
+        self.bytes(key)
+    }
+
+    /// The encoded data; every byte must have been written
+    pub const fn finish(self) -> [u8; N] {
+        assert!(self.len == N, "ConstData not fully written");
+        self.bytes
+    }
+}
+
+impl<const N: usize> Default for ConstData<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Instruction decoder for parsing raw instruction bytes
 pub struct InstructionDecoder;
 
@@ -345,4 +443,36 @@ mod tests {
         
         assert!(!bytes.is_empty());
     }
+
+    #[test]
+    fn test_const_data_matches_encoder() {
+        const DATA: [u8; 55] = ConstData::new().u8(3).u16(7).u32(8).u64(9).i64(-1).pubkey(&[4u8; 32]).finish();
+        let runtime = InstructionEncoder::new([0u8; 32])
+            .append_u8(3)
+            .append_data(&7u16.to_le_bytes())
+            .append_u32(8)
+            .append_u64(9)
+            .append_data(&(-1i64).to_le_bytes())
+            .append_data(&[4u8; 32])
+            .build();
+        assert_eq!(&DATA[..], &runtime.data[..]);
+
+        let ix = RawInstruction::new([0u8; 32], vec![], &DATA);
+        assert!(matches!(ix.data, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_append_token_amount() {
+        let amount = TokenAmount::from_ui("2.5", 6).unwrap();
+        // spl-token TransferChecked: tag 12, amount u64, decimals u8
+        let ix = InstructionEncoder::new([0u8; 32])
+            .append_u8(12)
+            .append_token_amount_checked(amount)
+            .build();
+        assert_eq!(&ix.data[1..9], &2_500_000u64.to_le_bytes());
+        assert_eq!(ix.data[9], 6);
+
+        let ix = InstructionEncoder::new([0u8; 32]).append_u8(3).append_token_amount(amount).build();
+        assert_eq!(ix.data.len(), 9);
+    }
 }
@@ -0,0 +1,79 @@
+//! Conversions to and from `solana-sdk` transaction types
+//!
+//! Tooling built on the SDK, such as `solana-program-test`'s `BanksClient`
+//! or `RpcClient`, takes `Transaction` or `VersionedTransaction`. The wire
+//! format is shared, so conversion goes through the serialized bytes and the
+//! result is byte-identical, signatures included. The `program-test`
+//! feature builds on this in `program_test`.
+
+use crate::error::{Result, TxAsmError};
+use crate::transaction::CompiledTransaction;
+use crate::versioned::CompiledTransactionV0;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+impl CompiledTransaction {
+    pub fn to_sdk_transaction(&self) -> Result<Transaction> {
+        from_wire(&self.serialize()?)
+    }
+
+    pub fn to_versioned_transaction(&self) -> Result<VersionedTransaction> {
+        from_wire(&self.serialize()?)
+    }
+
+    pub fn from_sdk_transaction(transaction: &Transaction) -> Result<Self> {
+        Self::deserialize(&to_wire(transaction)?)
+    }
+}
+
+impl CompiledTransactionV0 {
+    pub fn to_versioned_transaction(&self) -> Result<VersionedTransaction> {
+        from_wire(&self.serialize()?)
+    }
+
+    /// Fails for legacy transactions; use
+    /// [`CompiledTransaction::from_sdk_transaction`] for those
+    pub fn from_versioned_transaction(transaction: &VersionedTransaction) -> Result<Self> {
+        Self::deserialize(&to_wire(transaction)?)
+    }
+}
+
+fn from_wire<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    bincode::deserialize(bytes).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+}
+
+fn to_wire<T: serde::Serialize>(value: &T) -> Result<Vec<u8>> {
+    bincode::serialize(value).map_err(|e| TxAsmError::SerializationError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::legacy_to_v0;
+    use crate::testkit;
+    use crate::versioned::AddressLookupTable;
+
+    #[test]
+    fn test_legacy_roundtrip_keeps_signatures() {
+        let tx = testkit::simple_transfer_tx(3);
+        let sdk = tx.to_sdk_transaction().unwrap();
+        sdk.verify().unwrap();
+        assert_eq!(sdk.message.account_keys[0].to_bytes(), testkit::pubkey(3));
+
+        let back = CompiledTransaction::from_sdk_transaction(&sdk).unwrap();
+        assert_eq!(back.serialize().unwrap(), tx.serialize().unwrap());
+        assert!(tx.to_versioned_transaction().unwrap().verify_with_results().iter().all(|ok| *ok));
+    }
+
+    #[test]
+    fn test_v0_roundtrip() {
+        let tx = testkit::simple_transfer_tx(4);
+        let tables = [AddressLookupTable::new(testkit::address(1), vec![testkit::pubkey(5)])];
+        let (v0, _) = legacy_to_v0(&tx, &tables).unwrap();
+
+        let sdk = v0.to_versioned_transaction().unwrap();
+        assert_eq!(sdk.message.address_table_lookups().map(|l| l.len()), Some(1));
+        let back = CompiledTransactionV0::from_versioned_transaction(&sdk).unwrap();
+        assert_eq!(back.serialize().unwrap(), v0.serialize().unwrap());
+        assert!(CompiledTransactionV0::from_versioned_transaction(&tx.to_versioned_transaction().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Instructions sysvar introspection helpers
+//!
+//! Programs that read the Instructions sysvar (to check that an ed25519
+//! precompile ran at a known index, for example) need byte offsets that are
+//! only known after compilation. This module rebuilds the sysvar exactly as
+//! the runtime lays it out, locates every instruction in the serialized
+//! message, and builds ed25519 precompile instructions whose offsets point
+//! either into themselves or into another instruction's data.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::RawInstruction;
+use crate::serialization::{checked_len, compact_u16_len, ByteSerialize};
+use crate::transaction::CompiledMessage;
+
+/// Instructions sysvar address
+pub const INSTRUCTIONS_SYSVAR_ID: [u8; 32] = solana_sdk::sysvar::instructions::ID.to_bytes();
+
+/// Ed25519 signature verification precompile address
+pub const ED25519_PROGRAM_ID: [u8; 32] = solana_sdk::ed25519_program::ID.to_bytes();
+
+/// `instruction_index` value meaning "the precompile instruction itself"
+pub const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Serialized size of one [`Ed25519SignatureOffsets`]
+pub const ED25519_OFFSETS_SIZE: usize = 14;
+
+/// Where one instruction sits inside a byte buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionOffsets {
+    /// First byte of the instruction
+    pub start: usize,
+    /// First byte of the instruction data
+    pub data_offset: usize,
+    pub data_len: usize,
+}
+
+/// Byte offsets of every instruction in the serialized message
+///
+/// Add `transaction.size() - message.byte_size()` for offsets into the
+/// full serialized transaction.
+pub fn message_offsets(message: &CompiledMessage) -> Vec<InstructionOffsets> {
+    let keys = message.account_keys.len();
+    let mut offset = 3 + compact_u16_len(keys) + keys * 32 + 32 + compact_u16_len(message.instructions.len());
+
+    message
+        .instructions
+        .iter()
+        .map(|ix| {
+            let start = offset;
+            let accounts = ix.account_indices.len();
+            let data_offset = start + 1 + compact_u16_len(accounts) + accounts + compact_u16_len(ix.data.len());
+            offset += ix.byte_size();
+            InstructionOffsets {
+                start,
+                data_offset,
+                data_len: ix.data.len(),
+            }
+        })
+        .collect()
+}
+
+/// Instructions sysvar contents for a message
+#[derive(Debug, Clone)]
+pub struct InstructionsSysvar {
+    /// Sysvar account data, including the trailing current-index slot
+    pub data: Vec<u8>,
+    /// Location of each instruction within `data`
+    pub offsets: Vec<InstructionOffsets>,
+}
+
+impl InstructionsSysvar {
+    /// Serialize `message`'s instructions the way the runtime does
+    ///
+    /// Account flags come from the message header; the runtime additionally
+    /// demotes reserved and program accounts to readonly.
+    pub fn build(message: &CompiledMessage) -> Result<Self> {
+        let count = message.instructions.len();
+        let mut data = Vec::with_capacity(2 + count * 72);
+        data.extend_from_slice(&checked_len("instructions", count)?.to_le_bytes());
+        data.resize(2 + 2 * count, 0);
+
+        let key = |index: u8| {
+            message.account_keys.get(index as usize).ok_or_else(|| {
+                TxAsmError::InvalidInstruction(format!("Account index {} out of range", index))
+            })
+        };
+
+        let mut offsets = Vec::with_capacity(count);
+        for (i, ix) in message.instructions.iter().enumerate() {
+            let start = data.len();
+            data[2 + 2 * i..4 + 2 * i].copy_from_slice(&checked_len("sysvar offset", start)?.to_le_bytes());
+
+            data.extend_from_slice(&checked_len("instruction accounts", ix.account_indices.len())?.to_le_bytes());
+            for &index in ix.account_indices.iter() {
+                let flags = message.is_signer(index as usize) as u8
+                    | (message.is_writable(index as usize) as u8) << 1;
+                data.push(flags);
+                data.extend_from_slice(key(index)?);
+            }
+            data.extend_from_slice(key(ix.program_id_index)?);
+            data.extend_from_slice(&checked_len("instruction data", ix.data.len())?.to_le_bytes());
+            let data_offset = data.len();
+            data.extend_from_slice(&ix.data);
+
+            offsets.push(InstructionOffsets {
+                start,
+                data_offset,
+                data_len: ix.data.len(),
+            });
+        }
+        // Slot the runtime fills with the executing instruction's index
+        data.extend_from_slice(&[0, 0]);
+
+        Ok(Self { data, offsets })
+    }
+}
+
+/// One signature entry of an ed25519 precompile instruction
+///
+/// Each offset is relative to the data of the instruction named by the
+/// matching `*_instruction_index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ed25519SignatureOffsets {
+    pub signature_offset: u16,
+    pub signature_instruction_index: u16,
+    pub public_key_offset: u16,
+    pub public_key_instruction_index: u16,
+    pub message_data_offset: u16,
+    pub message_data_size: u16,
+    pub message_instruction_index: u16,
+}
+
+impl Ed25519SignatureOffsets {
+    pub fn to_bytes(&self) -> [u8; ED25519_OFFSETS_SIZE] {
+        let mut bytes = [0u8; ED25519_OFFSETS_SIZE];
+        let fields = [
+            self.signature_offset,
+            self.signature_instruction_index,
+            self.public_key_offset,
+            self.public_key_instruction_index,
+            self.message_data_offset,
+            self.message_data_size,
+            self.message_instruction_index,
+        ];
+        for (chunk, field) in bytes.chunks_exact_mut(2).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// Ed25519 precompile instruction from explicit offsets and trailing data
+pub fn ed25519_instruction_with_offsets(offsets: &[Ed25519SignatureOffsets], trailing: &[u8]) -> Result<RawInstruction> {
+    let count = u8::try_from(offsets.len()).map_err(|_| {
+        TxAsmError::InvalidInstruction(format!("{} signatures exceed 255", offsets.len()))
+    })?;
+    let mut data = Vec::with_capacity(2 + offsets.len() * ED25519_OFFSETS_SIZE + trailing.len());
+    data.extend_from_slice(&[count, 0]);
+    for entry in offsets {
+        data.extend_from_slice(&entry.to_bytes());
+    }
+    data.extend_from_slice(trailing);
+    Ok(RawInstruction::new(ED25519_PROGRAM_ID, vec![], data))
+}
+
+/// Self-contained ed25519 precompile instruction verifying one signature
+pub fn ed25519_instruction(pubkey: &[u8; 32], signature: &[u8; 64], message: &[u8]) -> Result<RawInstruction> {
+    let public_key_offset = 2 + ED25519_OFFSETS_SIZE;
+    let signature_offset = public_key_offset + 32;
+    let message_data_offset = signature_offset + 64;
+    let offsets = Ed25519SignatureOffsets {
+        signature_offset: signature_offset as u16,
+        signature_instruction_index: CURRENT_INSTRUCTION,
+        public_key_offset: public_key_offset as u16,
+        public_key_instruction_index: CURRENT_INSTRUCTION,
+        message_data_offset: message_data_offset as u16,
+        message_data_size: checked_len("ed25519 message", message.len())?,
+        message_instruction_index: CURRENT_INSTRUCTION,
+    };
+
+    let mut trailing = Vec::with_capacity(96 + message.len());
+    trailing.extend_from_slice(pubkey);
+    trailing.extend_from_slice(signature);
+    trailing.extend_from_slice(message);
+    ed25519_instruction_with_offsets(&[offsets], &trailing)
+}
+
+/// Ed25519 precompile instruction whose pubkey, signature and message all
+/// live in the data of instruction `instruction_index`
+///
+/// Offsets are relative to that instruction's data, so they are valid for
+/// any message that keeps the instruction at the same index.
+pub fn ed25519_instruction_referencing(
+    instruction_index: u16,
+    public_key_offset: u16,
+    signature_offset: u16,
+    message_data_offset: u16,
+    message_data_size: u16,
+) -> Result<RawInstruction> {
+    let offsets = Ed25519SignatureOffsets {
+        signature_offset,
+        signature_instruction_index: instruction_index,
+        public_key_offset,
+        public_key_instruction_index: instruction_index,
+        message_data_offset,
+        message_data_size,
+        message_instruction_index: instruction_index,
+    };
+    ed25519_instruction_with_offsets(&[offsets], &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::sysvar::instructions::{construct_instructions_data, BorrowedAccountMeta, BorrowedInstruction};
+
+    #[test]
+    fn test_offsets_match_runtime_layout() {
+        let keypair = Keypair::new();
+        let signature: [u8; 64] = keypair.sign_message(b"claim").into();
+        let verify = ed25519_instruction(&keypair.pubkey().to_bytes(), &signature, b"claim").unwrap();
+        let claim = InstructionEncoder::new([2u8; 32])
+            .writable([5u8; 32], false)
+            .readonly(INSTRUCTIONS_SYSVAR_ID)
+            .append_u64(42)
+            .build();
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(verify)
+            .add_instruction(claim)
+            .build_unsigned()
+            .unwrap();
+        let message = &tx.message;
+
+        let bytes = tx.message_bytes().unwrap();
+        for (offsets, ix) in message_offsets(message).iter().zip(&message.instructions) {
+            assert_eq!(bytes[offsets.start], ix.program_id_index);
+            assert_eq!(&bytes[offsets.data_offset..offsets.data_offset + offsets.data_len], &ix.data[..]);
+        }
+
+        let keys: Vec<Pubkey> = message.account_keys.iter().map(|k| Pubkey::new_from_array(*k)).collect();
+        let instructions: Vec<Instruction> = message
+            .instructions
+            .iter()
+            .map(|ix| Instruction {
+                program_id: keys[ix.program_id_index as usize],
+                accounts: ix
+                    .account_indices
+                    .iter()
+                    .map(|&i| AccountMeta {
+                        pubkey: keys[i as usize],
+                        is_signer: message.is_signer(i as usize),
+                        is_writable: message.is_writable(i as usize),
+                    })
+                    .collect(),
+                data: ix.data.clone(),
+            })
+            .collect();
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|meta| BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: &ix.data,
+            })
+            .collect();
+
+        let sysvar = InstructionsSysvar::build(message).unwrap();
+        assert_eq!(sysvar.data, construct_instructions_data(&borrowed));
+        let claim_data = sysvar.offsets[1];
+        assert_eq!(&sysvar.data[claim_data.data_offset..claim_data.data_offset + 8], &42u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_ed25519_instruction_verifies() {
+        let keypair = Keypair::new();
+        let signature: [u8; 64] = keypair.sign_message(b"claim").into();
+        let ours = ed25519_instruction(&keypair.pubkey().to_bytes(), &signature, b"claim").unwrap();
+
+        assert_eq!(ours.program_id, solana_sdk::ed25519_program::id().to_bytes());
+        let feature_set = Default::default();
+        assert!(solana_sdk::ed25519_instruction::verify(&ours.data, &[&ours.data], &feature_set).is_ok());
+
+        let referencing = ed25519_instruction_referencing(1, 0, 32, 96, 5).unwrap();
+        assert_eq!(&referencing.data[2..4], &32u16.to_le_bytes());
+        assert_eq!(&referencing.data[4..6], &1u16.to_le_bytes());
+    }
+}
@@ -0,0 +1,384 @@
+//! Byte layout of a serialized transaction
+//!
+//! [`CompiledTransaction::layout`] computes where every field lands in the
+//! serialized bytes without serializing, for in-place patching, annotated
+//! hex dumps and binary diffs. [`TransactionPatcher`] uses it to rewrite
+//! same-length fields of a serialized transaction directly.
+
+use crate::error::{Result, TxAsmError};
+use crate::serialization::compact_u16_len;
+use crate::transaction::CompiledTransaction;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A field's position in the serialized transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Span {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.len
+    }
+
+    pub fn contains(&self, offset: usize) -> bool {
+        self.range().contains(&offset)
+    }
+}
+
+/// Positions of one instruction's fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionLayout {
+    /// The whole instruction
+    pub span: Span,
+    pub program_id_index: Span,
+    pub accounts_len: Span,
+    pub account_indices: Span,
+    pub data_len: Span,
+    pub data: Span,
+}
+
+/// Positions of every field of a serialized legacy transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionLayout {
+    pub signatures_len: Span,
+    pub signatures: Vec<Span>,
+    /// The signed message, from header to the last instruction
+    pub message: Span,
+    pub header: Span,
+    pub account_keys_len: Span,
+    pub account_keys: Vec<Span>,
+    pub recent_blockhash: Span,
+    pub instructions_len: Span,
+    pub instructions: Vec<InstructionLayout>,
+    pub total_len: usize,
+}
+
+impl TransactionLayout {
+    /// Every leaf field with a dotted name (`"instructions[1].data"`), in
+    /// byte order
+    pub fn fields(&self) -> Vec<(String, Span)> {
+        let mut fields = vec![("signatures_len".to_string(), self.signatures_len)];
+        fields.extend(self.signatures.iter().enumerate().map(|(i, s)| (format!("signatures[{}]", i), *s)));
+        fields.push(("header".to_string(), self.header));
+        fields.push(("account_keys_len".to_string(), self.account_keys_len));
+        fields.extend(self.account_keys.iter().enumerate().map(|(i, s)| (format!("account_keys[{}]", i), *s)));
+        fields.push(("recent_blockhash".to_string(), self.recent_blockhash));
+        fields.push(("instructions_len".to_string(), self.instructions_len));
+        for (i, ix) in self.instructions.iter().enumerate() {
+            for (name, span) in [
+                ("program_id_index", ix.program_id_index),
+                ("accounts_len", ix.accounts_len),
+                ("account_indices", ix.account_indices),
+                ("data_len", ix.data_len),
+                ("data", ix.data),
+            ] {
+                fields.push((format!("instructions[{}].{}", i, name), span));
+            }
+        }
+        fields
+    }
+
+    /// Name of the leaf field containing byte `offset`
+    pub fn field_at(&self, offset: usize) -> Option<String> {
+        self.fields()
+            .into_iter()
+            .find(|(_, span)| span.contains(offset))
+            .map(|(name, _)| name)
+    }
+}
+
+/// Same-length in-place edits of a serialized transaction
+///
+/// Every edit that changes a message byte zeroes all signature slots, since
+/// each signature covers the whole message. For hot paths, compute the
+/// layout once per template and reuse it with [`Self::with_layout`].
+pub struct TransactionPatcher<'a> {
+    bytes: &'a mut [u8],
+    layout: Cow<'a, TransactionLayout>,
+}
+
+impl<'a> TransactionPatcher<'a> {
+    /// Patcher over `bytes`, decoding them once to find the layout
+    pub fn new(bytes: &'a mut [u8]) -> Result<Self> {
+        let layout = CompiledTransaction::deserialize(bytes)?.layout();
+        check_len(bytes, &layout)?;
+        Ok(Self {
+            bytes,
+            layout: Cow::Owned(layout),
+        })
+    }
+
+    /// Patcher over `bytes` using a layout computed earlier for the same shape
+    pub fn with_layout(bytes: &'a mut [u8], layout: &'a TransactionLayout) -> Result<Self> {
+        check_len(bytes, layout)?;
+        Ok(Self {
+            bytes,
+            layout: Cow::Borrowed(layout),
+        })
+    }
+
+    pub fn layout(&self) -> &TransactionLayout {
+        &self.layout
+    }
+
+    pub fn set_recent_blockhash(&mut self, blockhash: &[u8; 32]) -> &mut Self {
+        let span = self.layout.recent_blockhash;
+        self.write(span, blockhash);
+        self
+    }
+
+    /// Replace the account key at `index`, refusing a key the message
+    /// already holds at another index
+    pub fn set_account_key(&mut self, index: usize, key: &[u8; 32]) -> Result<&mut Self> {
+        let span = *self.layout.account_keys.get(index).ok_or_else(|| {
+            TxAsmError::InvalidTransaction(format!("Account key {} out of range", index))
+        })?;
+        let existing = self
+            .layout
+            .account_keys
+            .iter()
+            .position(|other| &self.bytes[other.range()] == key);
+        if let Some(existing) = existing.filter(|&existing| existing != index) {
+            return Err(TxAsmError::AccountError(format!(
+                "{} is already account {} of the message",
+                bs58::encode(key).into_string(),
+                existing
+            )));
+        }
+        self.write(span, key);
+        Ok(self)
+    }
+
+    /// Overwrite `bytes.len()` bytes of instruction data at `offset`
+    pub fn set_data(&mut self, instruction: usize, offset: usize, bytes: &[u8]) -> Result<&mut Self> {
+        let data = self
+            .layout
+            .instructions
+            .get(instruction)
+            .ok_or_else(|| TxAsmError::InvalidInstruction(format!("Instruction {} out of range", instruction)))?
+            .data;
+        if offset.checked_add(bytes.len()).is_none_or(|end| end > data.len) {
+            return Err(TxAsmError::InvalidInstruction(format!(
+                "Patch of {} bytes at {} exceeds instruction {}'s {} data bytes",
+                bytes.len(),
+                offset,
+                instruction,
+                data.len
+            )));
+        }
+        self.write(
+            Span {
+                offset: data.offset + offset,
+                len: bytes.len(),
+            },
+            bytes,
+        );
+        Ok(self)
+    }
+
+    /// Overwrite a little-endian u64 (an amount, typically) in instruction data
+    pub fn set_u64(&mut self, instruction: usize, offset: usize, value: u64) -> Result<&mut Self> {
+        self.set_data(instruction, offset, &value.to_le_bytes())
+    }
+
+    /// Place a signature in `slot`, e.g. after re-signing the patched message
+    pub fn set_signature(&mut self, slot: usize, signature: &[u8; 64]) -> Result<&mut Self> {
+        let span = *self.layout.signatures.get(slot).ok_or_else(|| {
+            TxAsmError::SignatureError(format!("Signature slot {} out of range", slot))
+        })?;
+        self.bytes[span.range()].copy_from_slice(signature);
+        Ok(self)
+    }
+
+    /// The signed message bytes, as currently patched
+    pub fn message(&self) -> &[u8] {
+        &self.bytes[self.layout.message.range()]
+    }
+
+    fn write(&mut self, span: Span, bytes: &[u8]) {
+        let target = &mut self.bytes[span.range()];
+        if target == bytes {
+            return;
+        }
+        target.copy_from_slice(bytes);
+        for signature in &self.layout.signatures {
+            self.bytes[signature.range()].fill(0);
+        }
+    }
+}
+
+fn check_len(bytes: &[u8], layout: &TransactionLayout) -> Result<()> {
+    if bytes.len() != layout.total_len {
+        return Err(TxAsmError::InvalidTransaction(format!(
+            "Buffer is {} bytes but the layout describes {}",
+            bytes.len(),
+            layout.total_len
+        )));
+    }
+    Ok(())
+}
+
+/// Cursor handing out consecutive spans
+struct Spans(usize);
+
+impl Spans {
+    fn next(&mut self, len: usize) -> Span {
+        let span = Span { offset: self.0, len };
+        self.0 += len;
+        span
+    }
+}
+
+impl CompiledTransaction {
+    /// Byte positions of every field in [`Self::serialize`]'s output
+    pub fn layout(&self) -> TransactionLayout {
+        let message = &self.message;
+        let mut spans = Spans(0);
+
+        let signatures_len = spans.next(compact_u16_len(self.signatures.len()));
+        let signatures = self.signatures.iter().map(|_| spans.next(64)).collect();
+        let message_start = spans.0;
+        let header = spans.next(3);
+        let account_keys_len = spans.next(compact_u16_len(message.account_keys.len()));
+        let account_keys = message.account_keys.iter().map(|_| spans.next(32)).collect();
+        let recent_blockhash = spans.next(32);
+        let instructions_len = spans.next(compact_u16_len(message.instructions.len()));
+        let instructions = message
+            .instructions
+            .iter()
+            .map(|ix| {
+                let start = spans.0;
+                let program_id_index = spans.next(1);
+                let accounts_len = spans.next(compact_u16_len(ix.account_indices.len()));
+                let account_indices = spans.next(ix.account_indices.len());
+                let data_len = spans.next(compact_u16_len(ix.data.len()));
+                let data = spans.next(ix.data.len());
+                InstructionLayout {
+                    span: Span {
+                        offset: start,
+                        len: spans.0 - start,
+                    },
+                    program_id_index,
+                    accounts_len,
+                    account_indices,
+                    data_len,
+                    data,
+                }
+            })
+            .collect();
+
+        TransactionLayout {
+            signatures_len,
+            signatures,
+            message: Span {
+                offset: message_start,
+                len: spans.0 - message_start,
+            },
+            header,
+            account_keys_len,
+            account_keys,
+            recent_blockhash,
+            instructions_len,
+            instructions,
+            total_len: spans.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+
+    fn sample() -> CompiledTransaction {
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(7).build())
+            .add_instruction(InstructionEncoder::new([4u8; 32]).data(vec![9; 200]).build())
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_layout_matches_serialization() {
+        let tx = sample();
+        let bytes = tx.serialize().unwrap();
+        let layout = tx.layout();
+
+        assert_eq!(layout.total_len, bytes.len());
+        assert_eq!(&bytes[layout.message.range()], &tx.message_bytes().unwrap()[..]);
+        for (span, key) in layout.account_keys.iter().zip(&tx.message.account_keys) {
+            assert_eq!(&bytes[span.range()], key);
+        }
+        assert_eq!(&bytes[layout.recent_blockhash.range()], &[3u8; 32]);
+        for (ix_layout, ix) in layout.instructions.iter().zip(&tx.message.instructions) {
+            assert_eq!(bytes[ix_layout.program_id_index.offset], ix.program_id_index);
+            assert_eq!(&bytes[ix_layout.account_indices.range()], &ix.account_indices[..]);
+            assert_eq!(&bytes[ix_layout.data.range()], &ix.data[..]);
+        }
+        // 200 bytes of data needs a two-byte length prefix
+        assert_eq!(layout.instructions[1].data_len.len, 2);
+
+        // Leaf fields tile the whole transaction
+        let fields = layout.fields();
+        assert!(fields.windows(2).all(|w| w[0].1.offset + w[0].1.len == w[1].1.offset));
+        assert_eq!(fields.last().map(|(_, s)| s.offset + s.len), Some(bytes.len()));
+    }
+
+    #[test]
+    fn test_patch_in_place() {
+        let tx = sample();
+        let mut bytes = tx.serialize().unwrap();
+        let layout = tx.layout();
+
+        let amount = layout.instructions[0].data;
+        bytes[amount.range()].copy_from_slice(&42u64.to_le_bytes());
+        let patched = CompiledTransaction::deserialize(&bytes).unwrap();
+        assert_eq!(patched.message.instructions[0].data, 42u64.to_le_bytes());
+
+        assert_eq!(layout.field_at(amount.offset).as_deref(), Some("instructions[0].data"));
+        assert_eq!(layout.field_at(0).as_deref(), Some("signatures_len"));
+        assert_eq!(layout.field_at(bytes.len()), None);
+    }
+
+    #[test]
+    fn test_patcher_clears_signatures() {
+        use solana_sdk::signature::{Keypair, Signer};
+
+        let payer = Keypair::new();
+        let template = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(7).build())
+            .build_and_sign(&[&payer])
+            .unwrap();
+        let layout = template.layout();
+        let mut bytes = template.serialize().unwrap();
+
+        let mut patcher = TransactionPatcher::with_layout(&mut bytes, &layout).unwrap();
+        // Unchanged bytes keep the signature
+        patcher.set_recent_blockhash(&[3u8; 32]);
+        assert!(CompiledTransaction::deserialize(patcher.bytes).unwrap().is_fully_signed());
+
+        patcher.set_recent_blockhash(&[4u8; 32]).set_u64(0, 0, 1_000).unwrap();
+        assert!(patcher.set_u64(0, 1, 1).is_err());
+        assert!(patcher.set_account_key(9, &[0u8; 32]).is_err());
+        assert!(patcher.set_data(0, usize::MAX, &[1]).is_err());
+        // Keys stay unique; rewriting a key in place is fine
+        assert!(matches!(patcher.set_account_key(1, &payer.pubkey().to_bytes()), Err(TxAsmError::AccountError(_))));
+        assert!(patcher.set_account_key(1, &[5u8; 32]).is_ok());
+        let signature: [u8; 64] = payer.sign_message(patcher.message()).into();
+        patcher.set_signature(0, &signature).unwrap();
+
+        let patched = CompiledTransaction::deserialize(&bytes).unwrap();
+        assert_eq!(patched.message.recent_blockhash, [4u8; 32]);
+        assert_eq!(patched.message.instructions[0].data, 1_000u64.to_le_bytes());
+        assert!(patched.verify_strict().is_ok());
+        assert!(TransactionPatcher::with_layout(&mut bytes[1..], &layout).is_err());
+    }
+}
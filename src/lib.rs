@@ -3,12 +3,97 @@
 //! A comprehensive library for constructing Solana transactions at the byte level,
 //! providing maximum control over transaction encoding, optimization, and fee calculation.
 
+mod macros;
+
 pub mod serialization;
 pub mod instruction;
+pub mod token;
 pub mod transaction;
+pub mod layout;
+pub mod compat;
+pub mod diff;
+pub mod render;
+pub mod versioned;
+pub mod convert;
+pub mod interop;
+pub mod template;
+pub mod spec;
+pub mod cache;
+pub mod snapshot;
+pub mod conformance;
+pub mod storage;
+pub mod perf;
+pub mod simd;
+pub mod pool;
+pub mod introspection;
+pub mod programs;
+pub mod dex;
+pub mod idl;
+pub mod accounts;
+pub mod archive;
+pub mod replay;
 pub mod fee_calculator;
+pub mod rpc_fees;
+pub mod submit;
+pub mod fee_budget;
+pub mod compute_units;
+pub mod cost_model;
+pub mod contention;
+pub mod stats;
+pub mod scheduler;
+pub mod timing;
+pub mod expiry;
+pub mod workflow;
+pub mod preflight;
+pub mod policy;
+pub mod balance;
+pub mod audit;
 pub mod optimizer;
+pub mod cpi;
+pub mod export;
+pub mod summary;
+pub mod classify;
+pub mod inspect;
 pub mod error;
+pub mod siws;
+pub mod sigverify;
+pub mod relay;
+
+#[cfg(feature = "rayon")]
+pub mod batch;
+
+#[cfg(feature = "helius")]
+pub mod helius;
+
+#[cfg(feature = "triton")]
+pub mod triton;
+
+#[cfg(feature = "protobuf")]
+pub mod proto;
+
+#[cfg(feature = "yellowstone")]
+pub mod yellowstone;
+
+#[cfg(feature = "stream")]
+pub mod stream;
+
+#[cfg(feature = "repl")]
+pub mod repl;
+
+#[cfg(feature = "program-test")]
+pub mod program_test;
+
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+
+#[cfg(feature = "python")]
+pub mod python;
+
+#[cfg(feature = "uniffi")]
+pub mod mobile;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!("txasm");
 
 pub use error::TxAsmError;
 pub use transaction::{TransactionBuilder, CompiledTransaction};
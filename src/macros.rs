@@ -0,0 +1,172 @@
+//! Declarative instruction and transaction macros
+//!
+//! [`ix!`](crate::ix) and [`tx!`](crate::tx) expand to plain
+//! [`InstructionEncoder`](crate::instruction::InstructionEncoder) and
+//! [`TransactionBuilder`](crate::transaction::TransactionBuilder) calls, so
+//! every account role and data byte stays explicit:
+//!
+//! ```
+//! use txasm::{ix, tx};
+//!
+//! let (program, payer, mint, amount) = ([1u8; 32], [2u8; 32], [3u8; 32], 500u64);
+//! let burn = ix!(program = program, accounts = [ws payer, w mint], data = { u8 8, u64 amount });
+//! let tx = tx!(payer, [9u8; 32], [burn]).build_unsigned().unwrap();
+//! assert_eq!(tx.message.instructions[0].data.len(), 9);
+//! ```
+//!
+//! Account roles: `r` readonly, `w` writable, `s` readonly signer, `ws`
+//! writable signer. Data fields: `u8`, `u16`, `u32`, `u64`, `i64`, `bool`,
+//! `pubkey`, `bytes` (anything `AsRef<[u8]>`), `token` and `token_checked`
+//! ([`TokenAmount`](crate::token::TokenAmount) without and with decimals).
+//!
+//! [`accounts!`](crate::accounts) binds Anchor IDL accounts by name.
+
+/// Build a [`RawInstruction`](crate::instruction::RawInstruction)
+///
+/// `ix!(program = ID, accounts = [ws payer, r mint], data = { u8 3, u64 amount })`;
+/// `data` may be omitted.
+#[macro_export]
+macro_rules! ix {
+    (
+        program = $program:expr,
+        accounts = [$($role:ident $key:expr),* $(,)?]
+        $(, data = { $($kind:ident $value:expr),* $(,)? })?
+        $(,)?
+    ) => {{
+        let encoder = $crate::instruction::InstructionEncoder::new($program);
+        $(let encoder = $crate::__ix_account!(encoder, $role, $key);)*
+        $($(let encoder = $crate::__ix_data!(encoder, $kind, $value);)*)?
+        encoder.build()
+    }};
+}
+
+/// Build a [`TransactionBuilder`](crate::transaction::TransactionBuilder)
+///
+/// `tx!(payer, blockhash, [ix1, ix2])`; the result can be compiled, signed
+/// or extended further.
+#[macro_export]
+macro_rules! tx {
+    ($payer:expr, $blockhash:expr, [$($instruction:expr),* $(,)?] $(,)?) => {
+        $crate::transaction::TransactionBuilder::new()
+            .payer($payer)
+            .recent_blockhash($blockhash)
+            $(.add_instruction($instruction))*
+    };
+}
+
+/// Build [`IdlAccounts`](crate::idl::IdlAccounts) bindings by name
+///
+/// `accounts! { "payer" => payer, "mint" => mint }`, for
+/// [`Idl::account_metas`](crate::idl::Idl::account_metas).
+#[macro_export]
+macro_rules! accounts {
+    ($($name:expr => $key:expr),* $(,)?) => {
+        $crate::idl::IdlAccounts::new()$(.bind($name, $key))*
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ix_account {
+    ($encoder:expr, r, $key:expr) => {
+        $encoder.readonly($key)
+    };
+    ($encoder:expr, w, $key:expr) => {
+        $encoder.writable($key, false)
+    };
+    ($encoder:expr, s, $key:expr) => {
+        $encoder.signer($key, false)
+    };
+    ($encoder:expr, ws, $key:expr) => {
+        $encoder.signer($key, true)
+    };
+    ($encoder:expr, $role:ident, $key:expr) => {
+        compile_error!(concat!("unknown account role `", stringify!($role), "`, expected r, w, s or ws"))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ix_data {
+    ($encoder:expr, u8, $value:expr) => {
+        $encoder.append_u8($value)
+    };
+    ($encoder:expr, u16, $value:expr) => {
+        $encoder.append_data(&u16::to_le_bytes($value))
+    };
+    ($encoder:expr, u32, $value:expr) => {
+        $encoder.append_u32($value)
+    };
+    ($encoder:expr, u64, $value:expr) => {
+        $encoder.append_u64($value)
+    };
+    ($encoder:expr, i64, $value:expr) => {
+        $encoder.append_data(&i64::to_le_bytes($value))
+    };
+    ($encoder:expr, bool, $value:expr) => {
+        $encoder.append_u8(<bool as Into<u8>>::into($value))
+    };
+    ($encoder:expr, pubkey, $value:expr) => {
+        $encoder.append_data(&<[u8; 32]>::from($value))
+    };
+    ($encoder:expr, bytes, $value:expr) => {
+        $encoder.append_data(::core::convert::AsRef::<[u8]>::as_ref(&$value))
+    };
+    ($encoder:expr, token, $value:expr) => {
+        $encoder.append_token_amount($value)
+    };
+    ($encoder:expr, token_checked, $value:expr) => {
+        $encoder.append_token_amount_checked($value)
+    };
+    ($encoder:expr, $kind:ident, $value:expr) => {
+        compile_error!(concat!("unknown data field `", stringify!($kind), "`"))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instruction::{AccountMeta, InstructionEncoder};
+    use crate::token::TokenAmount;
+    use crate::transaction::TransactionBuilder;
+
+    #[test]
+    fn test_ix_matches_encoder() {
+        let amount = TokenAmount::new(1_500, 2);
+        let ours = crate::ix!(
+            program = [1u8; 32],
+            accounts = [ws [2u8; 32], w [3u8; 32], r [4u8; 32], s [5u8; 32]],
+            data = { u8 12, token_checked amount, u16 7, bool true, pubkey [6u8; 32], bytes vec![9, 9] },
+        );
+        let expected = InstructionEncoder::new([1u8; 32])
+            .signer([2u8; 32], true)
+            .writable([3u8; 32], false)
+            .readonly([4u8; 32])
+            .signer([5u8; 32], false)
+            .append_u8(12)
+            .append_token_amount_checked(amount)
+            .append_data(&7u16.to_le_bytes())
+            .append_u8(1)
+            .append_data(&[6u8; 32])
+            .append_data(&[9, 9])
+            .build();
+        assert_eq!(ours.accounts, expected.accounts);
+        assert_eq!(ours.data, expected.data);
+
+        let no_data = crate::ix!(program = [1u8; 32], accounts = [r [4u8; 32]]);
+        assert_eq!(no_data.accounts, vec![AccountMeta::new_readonly([4u8; 32], false)]);
+        assert!(no_data.data.is_empty());
+    }
+
+    #[test]
+    fn test_tx_matches_builder() {
+        let ix = crate::ix!(program = [1u8; 32], accounts = [w [3u8; 32]], data = { u64 5 });
+        let ours = crate::tx!([2u8; 32], [7u8; 32], [ix.clone(), ix.clone()]).build_unsigned().unwrap();
+        let expected = TransactionBuilder::new()
+            .payer([2u8; 32])
+            .recent_blockhash([7u8; 32])
+            .add_instructions(vec![ix.clone(), ix])
+            .build_unsigned()
+            .unwrap();
+        assert_eq!(ours.serialize().unwrap(), expected.serialize().unwrap());
+    }
+}
@@ -0,0 +1,253 @@
+//! UniFFI bindings for mobile wallets
+//!
+//! Exposes the builder, decoder and fee calculator through UniFFI so Kotlin
+//! and Swift bindings can be generated from the compiled library instead of
+//! reimplementing compact-u16 and message layout per platform.
+//!
+//! Enabled with the `uniffi` feature. Generate bindings with
+//! `cargo run --features uniffi --bin uniffi-bindgen generate --library <lib> --language swift`.
+
+use crate::error::TxAsmError;
+use crate::fee_calculator::{FeeStrategy, PriorityFeeCalculator};
+use crate::instruction::{AccountMeta, RawInstruction};
+use crate::transaction::{CompiledTransaction, TransactionBuilder};
+use std::sync::{Arc, Mutex};
+
+/// Error surfaced to foreign code
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum MobileError {
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    TxAsm(String),
+}
+
+impl From<TxAsmError> for MobileError {
+    fn from(err: TxAsmError) -> Self {
+        MobileError::TxAsm(err.to_string())
+    }
+}
+
+fn to_key(bytes: &[u8]) -> Result<[u8; 32], MobileError> {
+    bytes.try_into().map_err(|_| {
+        MobileError::InvalidInput(format!("Expected 32-byte key, got {} bytes", bytes.len()))
+    })
+}
+
+/// Account metadata for an instruction
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileAccountMeta {
+    pub pubkey: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Instruction to be compiled into a transaction
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileInstruction {
+    pub program_id: Vec<u8>,
+    pub accounts: Vec<MobileAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl TryFrom<MobileInstruction> for RawInstruction {
+    type Error = MobileError;
+
+    fn try_from(ix: MobileInstruction) -> Result<Self, Self::Error> {
+        let accounts = ix
+            .accounts
+            .iter()
+            .map(|a| Ok(AccountMeta::new(to_key(&a.pubkey)?, a.is_signer, a.is_writable)))
+            .collect::<Result<Vec<_>, MobileError>>()?;
+        Ok(RawInstruction::new(to_key(&ix.program_id)?, accounts, ix.data))
+    }
+}
+
+/// Compiled instruction with resolved account indices
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileCompiledInstruction {
+    pub program_id_index: u8,
+    pub account_indices: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+/// Decoded view of a serialized transaction
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileTransaction {
+    pub signatures: Vec<Vec<u8>>,
+    pub num_required_signatures: u8,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+    pub account_keys: Vec<Vec<u8>>,
+    pub recent_blockhash: Vec<u8>,
+    pub instructions: Vec<MobileCompiledInstruction>,
+}
+
+impl From<&CompiledTransaction> for MobileTransaction {
+    fn from(tx: &CompiledTransaction) -> Self {
+        let message = &tx.message;
+        Self {
+            signatures: tx.signatures.iter().map(|s| s.to_vec()).collect(),
+            num_required_signatures: message.header.num_required_signatures,
+            num_readonly_signed_accounts: message.header.num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts: message.header.num_readonly_unsigned_accounts,
+            account_keys: message.account_keys.iter().map(|k| k.to_vec()).collect(),
+            recent_blockhash: message.recent_blockhash.to_vec(),
+            instructions: message
+                .instructions
+                .iter()
+                .map(|i| MobileCompiledInstruction {
+                    program_id_index: i.program_id_index,
+                    account_indices: i.account_indices.to_vec(),
+                    data: i.data.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Priority fee strategy
+#[derive(Debug, Clone, Copy, uniffi::Enum)]
+pub enum MobileFeeStrategy {
+    Low,
+    Medium,
+    High,
+    Custom { micro_lamports_per_cu: u64 },
+}
+
+impl From<MobileFeeStrategy> for FeeStrategy {
+    fn from(strategy: MobileFeeStrategy) -> Self {
+        match strategy {
+            MobileFeeStrategy::Low => FeeStrategy::Low,
+            MobileFeeStrategy::Medium => FeeStrategy::Medium,
+            MobileFeeStrategy::High => FeeStrategy::High,
+            MobileFeeStrategy::Custom { micro_lamports_per_cu } => {
+                FeeStrategy::Custom(micro_lamports_per_cu)
+            }
+        }
+    }
+}
+
+/// Fee estimate in lamports
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MobileFeeEstimate {
+    pub base_fee: u64,
+    pub priority_fee_per_cu: u64,
+    pub estimated_compute_units: u32,
+    pub total_cost: u64,
+}
+
+#[derive(Default)]
+struct BuilderState {
+    instructions: Vec<RawInstruction>,
+    payer: Option<[u8; 32]>,
+    recent_blockhash: Option<[u8; 32]>,
+}
+
+/// Transaction builder shared with foreign code
+#[derive(uniffi::Object)]
+pub struct MobileTransactionBuilder {
+    state: Mutex<BuilderState>,
+}
+
+#[uniffi::export]
+impl MobileTransactionBuilder {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(BuilderState::default()),
+        })
+    }
+
+    pub fn set_payer(&self, payer: Vec<u8>) -> Result<(), MobileError> {
+        self.state.lock().unwrap().payer = Some(to_key(&payer)?);
+        Ok(())
+    }
+
+    pub fn set_recent_blockhash(&self, blockhash: Vec<u8>) -> Result<(), MobileError> {
+        self.state.lock().unwrap().recent_blockhash = Some(to_key(&blockhash)?);
+        Ok(())
+    }
+
+    pub fn add_instruction(&self, instruction: MobileInstruction) -> Result<(), MobileError> {
+        let instruction = RawInstruction::try_from(instruction)?;
+        self.state.lock().unwrap().instructions.push(instruction);
+        Ok(())
+    }
+
+    /// Compile and serialize with zeroed signature slots, ready for the wallet to sign
+    pub fn build_unsigned(&self) -> Result<Vec<u8>, MobileError> {
+        let state = self.state.lock().unwrap();
+        let mut builder = TransactionBuilder::new().add_instructions(state.instructions.clone());
+        if let Some(payer) = state.payer {
+            builder = builder.payer(payer);
+        }
+        if let Some(blockhash) = state.recent_blockhash {
+            builder = builder.recent_blockhash(blockhash);
+        }
+        Ok(builder.build_unsigned()?.serialize()?)
+    }
+}
+
+/// Decode a serialized transaction
+#[uniffi::export]
+pub fn decode_transaction(bytes: Vec<u8>) -> Result<MobileTransaction, MobileError> {
+    let tx = CompiledTransaction::deserialize(&bytes)?;
+    Ok(MobileTransaction::from(&tx))
+}
+
+/// Extract the message bytes that signers sign over
+#[uniffi::export]
+pub fn transaction_message_bytes(bytes: Vec<u8>) -> Result<Vec<u8>, MobileError> {
+    Ok(CompiledTransaction::deserialize(&bytes)?.message_bytes()?)
+}
+
+/// Estimate the fee of a serialized transaction
+#[uniffi::export]
+pub fn estimate_fee(
+    bytes: Vec<u8>,
+    strategy: MobileFeeStrategy,
+) -> Result<MobileFeeEstimate, MobileError> {
+    let tx = CompiledTransaction::deserialize(&bytes)?;
+    let estimate = PriorityFeeCalculator::new().estimate_fee(&tx, FeeStrategy::from(strategy))?;
+    Ok(MobileFeeEstimate {
+        base_fee: estimate.base_fee,
+        priority_fee_per_cu: estimate.priority_fee_per_cu,
+        estimated_compute_units: estimate.estimated_compute_units,
+        total_cost: estimate.total_cost,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mobile_builder_roundtrip() {
+        let builder = MobileTransactionBuilder::new();
+        builder.set_payer(vec![1u8; 32]).unwrap();
+        builder.set_recent_blockhash(vec![3u8; 32]).unwrap();
+        builder
+            .add_instruction(MobileInstruction {
+                program_id: vec![2u8; 32],
+                accounts: vec![],
+                data: vec![42],
+            })
+            .unwrap();
+
+        let bytes = builder.build_unsigned().unwrap();
+        let decoded = decode_transaction(bytes.clone()).unwrap();
+        assert_eq!(decoded.num_required_signatures, 1);
+        assert_eq!(decoded.instructions[0].data, vec![42]);
+
+        let estimate = estimate_fee(bytes, MobileFeeStrategy::Low).unwrap();
+        assert_eq!(estimate.base_fee, 5000);
+    }
+
+    #[test]
+    fn test_mobile_rejects_bad_key() {
+        let builder = MobileTransactionBuilder::new();
+        assert!(builder.set_payer(vec![1u8; 31]).is_err());
+    }
+}
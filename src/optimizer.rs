@@ -3,8 +3,18 @@
 //! This module provides various optimization techniques to reduce transaction size,
 //! improve efficiency, and minimize costs.
 
+use crate::convert::legacy_to_v0;
+use crate::cost_model::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT;
+use crate::cpi::CpiRegistry;
 use crate::error::Result;
-use crate::transaction::CompiledTransaction;
+use crate::fee_calculator::compute_budget;
+use crate::serialization::compact_u16_len;
+use crate::transaction::{
+    CompiledInstruction, CompiledMessage, CompiledTransaction, MessageHeader, TransactionBuilder, MAX_TRANSACTION_SIZE,
+};
+use crate::versioned::AddressLookupTable;
+use std::collections::HashSet;
+use std::fmt;
 
 /// Transaction optimization strategies
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -24,40 +34,267 @@ pub struct OptimizationReport {
     pub optimized_size: usize,
     pub bytes_saved: usize,
     pub optimizations_applied: Vec<String>,
+    /// Passes whose output failed simulation or was not cheaper, with reason
+    pub optimizations_rejected: Vec<String>,
+    /// Original indices of instructions removed by accepted passes
+    pub instructions_removed: Vec<usize>,
+    /// Accounts demoted from writable to readonly by accepted passes
+    pub accounts_demoted: Vec<[u8; 32]>,
+    /// Unreferenced accounts dropped by accepted passes
+    pub accounts_removed: Vec<[u8; 32]>,
 }
 
+/// Result of [`TransactionOptimizer::optimize_auto`]
+#[derive(Debug, Clone)]
+pub struct AutoOptimization {
+    pub transaction: CompiledTransaction,
+    pub report: OptimizationReport,
+    /// Suggestions that need program knowledge or a person to apply
+    pub declined: Vec<Suggestion>,
+}
+
+/// Outcome of simulating a transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    pub success: bool,
+    pub units_consumed: u64,
+}
+
+/// Simulator used to verify optimization passes
+pub trait Simulate {
+    fn simulate(&self, transaction: &CompiledTransaction) -> Result<SimulationResult>;
+}
+
+impl<F> Simulate for F
+where
+    F: Fn(&CompiledTransaction) -> Result<SimulationResult>,
+{
+    fn simulate(&self, transaction: &CompiledTransaction) -> Result<SimulationResult> {
+        self(transaction)
+    }
+}
+
+/// Confirms that repeating an instruction has no effect beyond the first
+///
+/// Consulted by the instruction deduplication pass with the invoked
+/// program and the duplicated instruction.
+pub trait IdempotencyPolicy {
+    fn is_idempotent(&self, program_id: &[u8; 32], instruction: &CompiledInstruction) -> bool;
+}
+
+impl<F> IdempotencyPolicy for F
+where
+    F: Fn(&[u8; 32], &CompiledInstruction) -> bool,
+{
+    fn is_idempotent(&self, program_id: &[u8; 32], instruction: &CompiledInstruction) -> bool {
+        self(program_id, instruction)
+    }
+}
+
+const TOKEN: [u8; 32] = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").to_bytes();
+const TOKEN_2022: [u8; 32] = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb").to_bytes();
+
+/// Instructions commonly duplicated by middleware that are safe to drop
+///
+/// Compute budget requests (which the runtime rejects when repeated), memos,
+/// and SPL Token / Token-2022 `Approve` and `ApproveChecked`.
+pub fn known_idempotent(program_id: &[u8; 32], instruction: &CompiledInstruction) -> bool {
+    const MEMO_V1: [u8; 32] = solana_sdk::pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo").to_bytes();
+    const MEMO_V2: [u8; 32] = solana_sdk::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").to_bytes();
+    const APPROVE: u8 = 4;
+    const APPROVE_CHECKED: u8 = 13;
+
+    match *program_id {
+        compute_budget::COMPUTE_BUDGET_PROGRAM_ID | MEMO_V1 | MEMO_V2 => true,
+        TOKEN | TOKEN_2022 => matches!(instruction.data.first(), Some(&APPROVE | &APPROVE_CHECKED)),
+        _ => false,
+    }
+}
+
+/// Structural changes made by a pass, beyond the new transaction itself
+#[derive(Default)]
+struct PassChanges {
+    /// Removed instruction indices, relative to the pass input
+    instructions_removed: Vec<usize>,
+    accounts_demoted: Vec<[u8; 32]>,
+    accounts_removed: Vec<[u8; 32]>,
+}
+
+type Pass = fn(&TransactionOptimizer, CompiledTransaction) -> Result<(CompiledTransaction, PassChanges)>;
+
 /// Transaction optimizer with various optimization techniques
 pub struct TransactionOptimizer {
     strategy: OptimizationStrategy,
+    simulator: Option<Box<dyn Simulate>>,
+    idempotency: Option<Box<dyn IdempotencyPolicy>>,
+    demote_accounts: bool,
+    cpi_registry: Option<CpiRegistry>,
 }
 
 impl TransactionOptimizer {
     pub fn new(strategy: OptimizationStrategy) -> Self {
-        Self { strategy }
+        Self {
+            strategy,
+            simulator: None,
+            idempotency: None,
+            demote_accounts: false,
+            cpi_registry: None,
+        }
+    }
+
+    /// Verify every pass that changes the transaction by simulating it
+    ///
+    /// A changed transaction is only accepted if it simulates successfully,
+    /// uses no more compute units or bytes than before the pass, and
+    /// improves on at least one of compute units, bytes or write locks.
+    pub fn with_simulator(mut self, simulator: impl Simulate + 'static) -> Self {
+        self.simulator = Some(Box::new(simulator));
+        self
+    }
+
+    /// Remove byte-identical duplicate instructions that `policy` confirms
+    /// are idempotent, before the strategy's passes run
+    ///
+    /// [`known_idempotent`] covers the common middleware artifacts.
+    pub fn with_instruction_dedup(mut self, policy: impl IdempotencyPolicy + 'static) -> Self {
+        self.idempotency = Some(Box::new(policy));
+        self
+    }
+
+    /// Demote writable accounts that no instruction can write
+    ///
+    /// Only System and SPL Token / Token-2022 instructions are decoded; an
+    /// account passed to any other instruction is assumed written. The fee
+    /// payer always stays writable.
+    pub fn with_account_demotion(mut self) -> Self {
+        self.demote_accounts = true;
+        self
+    }
+
+    /// Report CPI depth, trace length and loaded-account risks in
+    /// [`Self::analyze`], using the fan-out recorded in `registry`
+    pub fn with_cpi_registry(mut self, registry: CpiRegistry) -> Self {
+        self.cpi_registry = Some(registry);
+        self
     }
 
     /// Optimize a compiled transaction
     pub fn optimize(&self, transaction: CompiledTransaction) -> Result<(CompiledTransaction, OptimizationReport)> {
+        let mut passes: Vec<(&str, Pass)> = Vec::new();
+        if self.idempotency.is_some() {
+            passes.push(("Instruction deduplication", Self::deduplicate_instructions));
+        }
+        if self.demote_accounts {
+            passes.push(("Account demotion", Self::demote_unwritten_accounts));
+        }
+        // Apply optimizations based on strategy
+        passes.extend_from_slice(match self.strategy {
+            OptimizationStrategy::Size => &[("Unused account removal", Self::remove_unused_accounts)],
+            OptimizationStrategy::Cost => &[("Transfer consolidation", Self::consolidate_transfers)],
+            OptimizationStrategy::Balanced => &[
+                ("Unused account removal", Self::remove_unused_accounts),
+                ("Transfer consolidation", Self::consolidate_transfers),
+            ],
+        });
+        self.run_passes(passes, transaction)
+    }
+
+    /// Apply only the passes that preserve semantics without knowing the
+    /// invoked programs, and list what is left for a person to decide
+    ///
+    /// Runs instruction deduplication (only when enabled with
+    /// [`Self::with_instruction_dedup`]), account demotion proven from
+    /// decoded System/Token instructions, and compute budget merging. The
+    /// strategy's passes are not run. Everything still reported by
+    /// [`Self::analyze`] and [`Self::efficiency_report`] afterwards is
+    /// returned as declined, as are repeated compute unit prices: choosing
+    /// one decides what the author pays.
+    pub fn optimize_auto(&self, transaction: CompiledTransaction) -> Result<AutoOptimization> {
+        let mut passes: Vec<(&str, Pass)> = Vec::new();
+        if self.idempotency.is_some() {
+            passes.push(("Instruction deduplication", Self::deduplicate_instructions));
+        }
+        passes.push(("Account demotion", Self::demote_unwritten_accounts));
+        passes.push(("Compute budget merge", Self::merge_compute_budget));
+        let (transaction, report) = self.run_passes(passes, transaction)?;
+
+        let mut declined = self.analyze(&transaction).suggestions;
+        declined.extend(self.efficiency_report(&transaction).deductions.iter().map(Suggestion::from));
+        let message = &transaction.message;
+        let mut seen = HashSet::new();
+        for (index, ix) in message.instructions.iter().enumerate() {
+            if !seen.insert((ix.program_id_index, &ix.account_indices[..], &ix.data[..])) {
+                declined.push(Suggestion {
+                    severity: Severity::Warning,
+                    ..Suggestion::info(
+                        "duplicate-instruction",
+                        &format!("Instruction {} repeats an earlier one; remove it if the program allows", index),
+                    )
+                });
+            }
+        }
+        let prices = message
+            .instructions
+            .iter()
+            .filter(|ix| {
+                message.account_keys.get(ix.program_id_index as usize) == Some(&compute_budget::COMPUTE_BUDGET_PROGRAM_ID)
+                    && compute_budget::parse_compute_unit_price(&ix.data).is_some()
+            })
+            .count();
+        if prices > 1 {
+            declined.push(Suggestion {
+                severity: Severity::Warning,
+                ..Suggestion::info(
+                    "repeated-compute-unit-price",
+                    &format!("{} SetComputeUnitPrice instructions; the runtime rejects repeats, keep the price to pay", prices),
+                )
+            });
+        }
+
+        Ok(AutoOptimization {
+            transaction,
+            report,
+            declined,
+        })
+    }
+
+    /// Run `passes` in order, keeping each change the simulator accepts
+    ///
+    /// Passes that leave the transaction unchanged are not reported.
+    fn run_passes(
+        &self,
+        passes: Vec<(&str, Pass)>,
+        transaction: CompiledTransaction,
+    ) -> Result<(CompiledTransaction, OptimizationReport)> {
         let original_size = transaction.size();
         let mut optimizations_applied = Vec::new();
+        let mut optimizations_rejected = Vec::new();
+        let mut instructions_removed = Vec::new();
+        let mut accounts_demoted = Vec::new();
+        let mut accounts_removed = Vec::new();
+        // Original index of each current instruction
+        let mut original_indices: Vec<usize> = (0..transaction.message.instructions.len()).collect();
 
-        // Apply optimizations based on strategy
-        let optimized_tx = match self.strategy {
-            OptimizationStrategy::Size => {
-                optimizations_applied.push("Account deduplication".to_string());
-                self.deduplicate_accounts(transaction)?
+        let mut optimized_tx = transaction;
+        let mut baseline: Option<SimulationResult> = None;
+        for (name, pass) in passes {
+            let (candidate, changes) = pass(self, optimized_tx.clone())?;
+            if candidate == optimized_tx {
+                continue;
             }
-            OptimizationStrategy::Cost => {
-                optimizations_applied.push("Instruction consolidation".to_string());
-                self.consolidate_instructions(transaction)?
+            match self.verify(&optimized_tx, &candidate, &mut baseline)? {
+                Ok(()) => {
+                    optimizations_applied.push(name.to_string());
+                    optimized_tx = candidate;
+                    for index in changes.instructions_removed.into_iter().rev() {
+                        instructions_removed.push(original_indices.remove(index));
+                    }
+                    accounts_demoted.extend(changes.accounts_demoted);
+                    accounts_removed.extend(changes.accounts_removed);
+                }
+                Err(reason) => optimizations_rejected.push(format!("{}: {}", name, reason)),
             }
-            OptimizationStrategy::Balanced => {
-                optimizations_applied.push("Account deduplication".to_string());
-                optimizations_applied.push("Instruction ordering".to_string());
-                let tx = self.deduplicate_accounts(transaction)?;
-                self.reorder_instructions(tx)?
-            }
-        };
+        }
 
         let optimized_size = optimized_tx.size();
         let bytes_saved = original_size.saturating_sub(optimized_size);
@@ -67,30 +304,318 @@ impl TransactionOptimizer {
             optimized_size,
             bytes_saved,
             optimizations_applied,
+            optimizations_rejected,
+            instructions_removed: {
+                instructions_removed.sort_unstable();
+                instructions_removed
+            },
+            accounts_demoted,
+            accounts_removed,
         };
 
         Ok((optimized_tx, report))
     }
 
-    /// Remove duplicate account references (already handled by compilation, but can optimize further)
-    fn deduplicate_accounts(&self, transaction: CompiledTransaction) -> Result<CompiledTransaction> {
-        // This is typically handled during transaction compilation,
-        // but we can verify and report on it
-        Ok(transaction)
+    /// Decide whether `candidate` may replace `current`
+    ///
+    /// `baseline` caches the simulation of `current` across passes.
+    fn verify(
+        &self,
+        current: &CompiledTransaction,
+        candidate: &CompiledTransaction,
+        baseline: &mut Option<SimulationResult>,
+    ) -> Result<std::result::Result<(), String>> {
+        let Some(simulator) = &self.simulator else {
+            return Ok(Ok(()));
+        };
+
+        let before = match baseline.take() {
+            Some(result) => result,
+            None => simulator.simulate(current)?,
+        };
+        let after = simulator.simulate(candidate)?;
+
+        let verdict = if !after.success {
+            Err("simulation failed".to_string())
+        } else if after.units_consumed > before.units_consumed {
+            Err(format!(
+                "uses {} CUs, up from {}",
+                after.units_consumed, before.units_consumed
+            ))
+        } else if candidate.size() > current.size() {
+            Err(format!("grows to {} bytes from {}", candidate.size(), current.size()))
+        } else if before.success
+            && after.units_consumed == before.units_consumed
+            && candidate.size() == current.size()
+            && write_locks(&candidate.message) >= write_locks(&current.message)
+        {
+            Err("no fewer CUs, bytes or write locks".to_string())
+        } else {
+            Ok(())
+        };
+        *baseline = Some(if verdict.is_ok() { after } else { before });
+        Ok(verdict)
+    }
+
+    /// Drop repeats of byte-identical instructions the idempotency policy
+    /// accepts, keeping the first occurrence
+    ///
+    /// Identical compiled instructions share program, accounts and data, so
+    /// the remaining instructions keep every account in use. Signatures are
+    /// zeroed when anything is removed.
+    fn deduplicate_instructions(&self, mut transaction: CompiledTransaction) -> Result<(CompiledTransaction, PassChanges)> {
+        let Some(policy) = &self.idempotency else {
+            return Ok((transaction, PassChanges::default()));
+        };
+        let message = &transaction.message;
+        let mut seen = HashSet::new();
+        let removed: Vec<usize> = message
+            .instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, ix)| !seen.insert((ix.program_id_index, &ix.account_indices[..], &ix.data[..])))
+            .filter(|(_, ix)| {
+                message
+                    .account_keys
+                    .get(ix.program_id_index as usize)
+                    .is_some_and(|program_id| policy.is_idempotent(program_id, ix))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        if !removed.is_empty() {
+            for &index in removed.iter().rev() {
+                transaction.message.instructions.remove(index);
+            }
+            transaction.signatures.iter_mut().for_each(|signature| *signature = [0u8; 64]);
+        }
+        Ok((
+            transaction,
+            PassChanges {
+                instructions_removed: removed,
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// Mark readonly every writable account that only appears in positions
+    /// the decoded System/Token instructions never write, or as a program
+    ///
+    /// Keys are re-partitioned with their relative order kept and signatures
+    /// are zeroed when anything changes.
+    fn demote_unwritten_accounts(&self, mut transaction: CompiledTransaction) -> Result<(CompiledTransaction, PassChanges)> {
+        let message = &transaction.message;
+        let num_keys = message.account_keys.len();
+
+        // The fee payer is always written
+        let mut written = vec![false; num_keys];
+        if let Some(payer) = written.first_mut() {
+            *payer = true;
+        }
+        for ix in &message.instructions {
+            let positions = message
+                .account_keys
+                .get(ix.program_id_index as usize)
+                .and_then(|program_id| written_positions(program_id, &ix.data));
+            for (position, &index) in ix.account_indices.iter().enumerate() {
+                if positions.is_none_or(|positions| positions.contains(&position)) {
+                    if let Some(flag) = written.get_mut(index as usize) {
+                        *flag = true;
+                    }
+                }
+            }
+        }
+
+        let demoted: Vec<usize> = (0..num_keys).filter(|&i| message.is_writable(i) && !written[i]).collect();
+        if demoted.is_empty() {
+            return Ok((transaction, PassChanges::default()));
+        }
+        let accounts_demoted = demoted.iter().map(|&i| message.account_keys[i]).collect();
+        transaction.message = repartition(message, &demoted);
+        transaction.signatures.iter_mut().for_each(|signature| *signature = [0u8; 64]);
+        Ok((
+            transaction,
+            PassChanges {
+                accounts_demoted,
+                ..Default::default()
+            },
+        ))
+    }
+
+    /// Collapse repeated compute budget limits of the same kind into one
+    ///
+    /// The runtime rejects a transaction that repeats a request, so this
+    /// only changes transactions that would fail. Covers the heap frame,
+    /// compute unit limit and loaded accounts data size requests, whose
+    /// larger values cost nothing extra: the first instruction of each kind
+    /// is kept with the largest value requested. Repeated prices are left
+    /// for the author, since any choice changes the fee, as is the
+    /// deprecated `RequestUnits`.
+    fn merge_compute_budget(&self, mut transaction: CompiledTransaction) -> Result<(CompiledTransaction, PassChanges)> {
+        let message = &mut transaction.message;
+        let is_budget = |ix: &CompiledInstruction| {
+            message.account_keys.get(ix.program_id_index as usize) == Some(&compute_budget::COMPUTE_BUDGET_PROGRAM_ID)
+        };
+        // RequestHeapFrame, SetComputeUnitLimit and
+        // SetLoadedAccountsDataSizeLimit: a tag and one little-endian u32
+        let limit = |ix: &CompiledInstruction| match ix.data[..] {
+            [1 | 2 | 4, a, b, c, d] => Some(u32::from_le_bytes([a, b, c, d])),
+            _ => None,
+        };
+
+        // Tag -> (index kept, index of the largest request)
+        let mut kept: std::collections::HashMap<u8, (usize, usize)> = std::collections::HashMap::new();
+        let mut removed = Vec::new();
+        for (index, ix) in message.instructions.iter().enumerate() {
+            let Some(value) = limit(ix).filter(|_| is_budget(ix)) else {
+                continue;
+            };
+            let tag = ix.data[0];
+            match kept.get_mut(&tag) {
+                Some((_, largest)) => {
+                    if Some(value) > limit(&message.instructions[*largest]) {
+                        *largest = index;
+                    }
+                    removed.push(index);
+                }
+                None => {
+                    kept.insert(tag, (index, index));
+                }
+            }
+        }
+        if removed.is_empty() {
+            return Ok((transaction, PassChanges::default()));
+        }
+
+        for (first, largest) in kept.into_values() {
+            if first != largest {
+                message.instructions[first].data = message.instructions[largest].data.clone();
+            }
+        }
+        for &index in removed.iter().rev() {
+            message.instructions.remove(index);
+        }
+        transaction.signatures.iter_mut().for_each(|signature| *signature = [0u8; 64]);
+        Ok((
+            transaction,
+            PassChanges {
+                instructions_removed: removed,
+                ..Default::default()
+            },
+        ))
     }
 
-    /// Consolidate similar instructions where possible
-    fn consolidate_instructions(&self, transaction: CompiledTransaction) -> Result<CompiledTransaction> {
-        // Instruction consolidation requires understanding program semantics
-        // This is a placeholder for more advanced optimization
-        Ok(transaction)
+    /// Drop non-signer accounts that no instruction references
+    ///
+    /// Such keys only take locks; signers stay, since their signatures are
+    /// required. Signatures are zeroed when anything is removed.
+    fn remove_unused_accounts(&self, mut transaction: CompiledTransaction) -> Result<(CompiledTransaction, PassChanges)> {
+        let message = &transaction.message;
+        let mut used: Vec<bool> = (0..message.account_keys.len()).map(|i| message.is_signer(i)).collect();
+        for ix in &message.instructions {
+            for &index in std::iter::once(&ix.program_id_index).chain(&ix.account_indices) {
+                if let Some(flag) = used.get_mut(index as usize) {
+                    *flag = true;
+                }
+            }
+        }
+        if used.iter().all(|&u| u) {
+            return Ok((transaction, PassChanges::default()));
+        }
+
+        let mut new_index = vec![0u8; used.len()];
+        let mut kept = 0u8;
+        for (i, &u) in used.iter().enumerate() {
+            new_index[i] = kept;
+            kept += u as u8;
+        }
+        let readonly_unsigned = (0..used.len())
+            .filter(|&i| used[i] && !message.is_signer(i) && !message.is_writable(i))
+            .count() as u8;
+        let accounts_removed = (0..used.len()).filter(|&i| !used[i]).map(|i| message.account_keys[i]).collect();
+        transaction.message = CompiledMessage {
+            header: MessageHeader::new(
+                message.header.num_required_signatures,
+                message.header.num_readonly_signed_accounts,
+                readonly_unsigned,
+            ),
+            account_keys: (0..used.len()).filter(|&i| used[i]).map(|i| message.account_keys[i]).collect(),
+            recent_blockhash: message.recent_blockhash,
+            instructions: message
+                .instructions
+                .iter()
+                .map(|ix| CompiledInstruction {
+                    program_id_index: new_index[ix.program_id_index as usize],
+                    account_indices: ix.account_indices.iter().map(|&i| new_index[i as usize]).collect(),
+                    data: ix.data.clone(),
+                })
+                .collect(),
+        };
+        transaction.signatures.iter_mut().for_each(|signature| *signature = [0u8; 64]);
+        Ok((
+            transaction,
+            PassChanges {
+                accounts_removed,
+                ..Default::default()
+            },
+        ))
     }
 
-    /// Reorder instructions for optimal execution
-    fn reorder_instructions(&self, transaction: CompiledTransaction) -> Result<CompiledTransaction> {
-        // Instructions can be reordered if they don't have dependencies
-        // This is a simplified version - real implementation would analyze dependencies
-        Ok(transaction)
+    /// Fold adjacent System transfers between the same two accounts into one
+    ///
+    /// Only consecutive instructions are merged, so nothing runs between
+    /// the transfers being combined; runs whose total overflows are left
+    /// alone. Signatures are zeroed when anything is removed.
+    fn consolidate_transfers(&self, mut transaction: CompiledTransaction) -> Result<(CompiledTransaction, PassChanges)> {
+        const SYSTEM: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+        let message = &mut transaction.message;
+        let lamports = |ix: &CompiledInstruction| {
+            if message.account_keys.get(ix.program_id_index as usize) != Some(&SYSTEM) {
+                return None;
+            }
+            match ix.data[..] {
+                [2, 0, 0, 0, ref amount @ ..] => Some(u64::from_le_bytes(amount.try_into().ok()?)),
+                _ => None,
+            }
+        };
+
+        // First transfer of each run and the lamports it ends up moving
+        let mut heads: Vec<(usize, u64)> = Vec::new();
+        let mut removed: Vec<usize> = Vec::new();
+        for (index, ix) in message.instructions.iter().enumerate() {
+            let Some(amount) = lamports(ix) else {
+                continue;
+            };
+            if let Some((head, total)) = heads.last_mut() {
+                let end = removed.last().map_or(*head, |&r| r.max(*head));
+                if end + 1 == index && message.instructions[*head].account_indices == ix.account_indices {
+                    if let Some(sum) = total.checked_add(amount) {
+                        *total = sum;
+                        removed.push(index);
+                        continue;
+                    }
+                }
+            }
+            heads.push((index, amount));
+        }
+        if removed.is_empty() {
+            return Ok((transaction, PassChanges::default()));
+        }
+
+        for (head, total) in heads {
+            message.instructions[head].data[4..].copy_from_slice(&total.to_le_bytes());
+        }
+        for &index in removed.iter().rev() {
+            message.instructions.remove(index);
+        }
+        transaction.signatures.iter_mut().for_each(|signature| *signature = [0u8; 64]);
+        Ok((
+            transaction,
+            PassChanges {
+                instructions_removed: removed,
+                ..Default::default()
+            },
+        ))
     }
 
     /// Analyze transaction size and suggest optimizations
@@ -111,16 +636,45 @@ impl TransactionOptimizer {
 
         let mut suggestions = Vec::new();
 
+        if size > MAX_TRANSACTION_SIZE {
+            suggestions.push(Suggestion {
+                code: "oversized",
+                severity: Severity::Critical,
+                message: format!(
+                    "Transaction is {} bytes, over the {}-byte packet limit; split it or use lookup tables",
+                    size, MAX_TRANSACTION_SIZE
+                ),
+                estimated_savings_bytes: size - MAX_TRANSACTION_SIZE,
+                estimated_savings_lamports: 0,
+            });
+        }
+
         if num_instructions > 5 {
-            suggestions.push("Consider batching similar operations into fewer instructions".to_string());
+            suggestions.push(Suggestion::info(
+                "batch-instructions",
+                "Consider batching similar operations into fewer instructions",
+            ));
         }
 
         if num_accounts > 20 {
-            suggestions.push("High number of accounts - review if all are necessary".to_string());
+            suggestions.push(Suggestion::info(
+                "many-accounts",
+                "High number of accounts - review if all are necessary",
+            ));
         }
 
         if instruction_data_size > 1000 {
-            suggestions.push("Large instruction data - consider compressing or restructuring".to_string());
+            suggestions.push(Suggestion {
+                severity: Severity::Warning,
+                ..Suggestion::info(
+                    "large-data",
+                    "Large instruction data - consider compressing or restructuring",
+                )
+            });
+        }
+
+        if let Some(registry) = &self.cpi_registry {
+            suggestions.extend(registry.estimate(&transaction.message).suggestions());
         }
 
         TransactionAnalysis {
@@ -132,26 +686,323 @@ impl TransactionOptimizer {
             num_accounts,
             num_instructions,
             suggestions,
+            breakdown: SizeBreakdown::of(transaction),
         }
     }
 
     /// Calculate efficiency score (0-100)
+    ///
+    /// Shorthand for [`Self::efficiency_report`]`.score`.
     pub fn calculate_efficiency_score(&self, transaction: &CompiledTransaction) -> u8 {
-        let analysis = self.analyze(transaction);
-        let size = analysis.total_size as f64;
-        
-        // Baseline expectations
-        let expected_signature_bytes = analysis.num_signatures as f64 * 64.0;
-        let expected_account_bytes = analysis.num_accounts as f64 * 32.0;
-        let expected_instruction_bytes = analysis.num_instructions as f64 * 50.0; // Average instruction size
-        
-        let expected_size = expected_signature_bytes + expected_account_bytes + expected_instruction_bytes + 100.0; // Header overhead
-        
-        let efficiency = (expected_size / size) * 100.0;
-        efficiency.min(100.0) as u8
+        self.efficiency_report(transaction).score
+    }
+
+    /// Score `transaction` against the efficiency rubric
+    ///
+    /// The score starts at 100 and loses [`Deduction::points`] for every
+    /// finding, saturating at 0:
+    ///
+    /// | Deduction | Points | Trigger |
+    /// |---|---|---|
+    /// | [`Deduction::ReadonlyDuplicate`] | 5 | readonly account listed twice in one instruction |
+    /// | [`Deduction::DefaultComputeLimit`] | 15 | no `SetComputeUnitLimit` request |
+    /// | [`Deduction::DataPadding`] | 10 | at least [`PADDING_THRESHOLD`] trailing zero bytes |
+    /// | [`Deduction::MissingLookupTable`] | 15 | at least [`LOOKUP_TABLE_MIN_ACCOUNTS`] table-eligible accounts |
+    /// | [`Deduction::EmptySignatureSlot`] | 10 | all-zero signature for a required signer |
+    pub fn efficiency_report(&self, transaction: &CompiledTransaction) -> EfficiencyReport {
+        let message = &transaction.message;
+        let mut deductions = Vec::new();
+
+        for (instruction, ix) in message.instructions.iter().enumerate() {
+            let mut seen = std::collections::HashMap::new();
+            for &index in ix.account_indices.iter() {
+                *seen.entry(index).or_insert(0usize) += 1;
+            }
+            let mut duplicates: Vec<_> = seen
+                .into_iter()
+                .filter(|&(index, count)| count > 1 && !message.is_writable(index as usize))
+                .collect();
+            duplicates.sort_unstable();
+            for (index, occurrences) in duplicates {
+                deductions.push(Deduction::ReadonlyDuplicate {
+                    instruction,
+                    account: message.account_keys[index as usize],
+                    occurrences,
+                });
+            }
+        }
+
+        let budget = compute_budget::requested_budget(message);
+        if budget.unit_limit.is_none() && budget.num_program_instructions > 0 {
+            deductions.push(Deduction::DefaultComputeLimit {
+                charged_units: budget.num_program_instructions as u64
+                    * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+            });
+        }
+
+        for (instruction, ix) in message.instructions.iter().enumerate() {
+            let padding_bytes = ix.data.iter().rev().take_while(|&&b| b == 0).count();
+            if padding_bytes >= PADDING_THRESHOLD {
+                deductions.push(Deduction::DataPadding { instruction, padding_bytes });
+            }
+        }
+
+        let eligible_accounts = (0..message.account_keys.len())
+            .filter(|&i| !message.is_signer(i) && !message.is_program(i))
+            .count();
+        if eligible_accounts >= LOOKUP_TABLE_MIN_ACCOUNTS {
+            deductions.push(Deduction::MissingLookupTable { eligible_accounts });
+        }
+
+        for (index, signature) in transaction.signatures.iter().enumerate() {
+            if *signature == [0u8; 64] {
+                if let Some(signer) = message.account_keys.get(index) {
+                    deductions.push(Deduction::EmptySignatureSlot { signer: *signer });
+                }
+            }
+        }
+
+        let lost: u32 = deductions.iter().map(|d| d.points() as u32).sum();
+        EfficiencyReport {
+            score: 100u32.saturating_sub(lost) as u8,
+            deductions,
+        }
+    }
+}
+
+/// Number of writable accounts in `message`
+fn write_locks(message: &CompiledMessage) -> usize {
+    (0..message.account_keys.len()).filter(|&i| message.is_writable(i)).count()
+}
+
+/// Copy of `message` with the accounts at `demoted` made readonly
+///
+/// Keys keep their relative order within each signer/writable group.
+fn repartition(message: &CompiledMessage, demoted: &[usize]) -> CompiledMessage {
+    let role = |i: usize| (message.is_signer(i), message.is_writable(i) && !demoted.contains(&i));
+    let mut order: Vec<usize> = (0..message.account_keys.len()).collect();
+    order.sort_by_key(|&i| match role(i) {
+        (true, true) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (false, false) => 3,
+    });
+    let mut new_index = vec![0u8; order.len()];
+    for (new, &old) in order.iter().enumerate() {
+        new_index[old] = new as u8;
+    }
+
+    let count = |signer: bool| order.iter().filter(|&&i| role(i) == (signer, false)).count() as u8;
+    CompiledMessage {
+        header: MessageHeader::new(message.header.num_required_signatures, count(true), count(false)),
+        account_keys: order.iter().map(|&i| message.account_keys[i]).collect(),
+        recent_blockhash: message.recent_blockhash,
+        instructions: message
+            .instructions
+            .iter()
+            .map(|ix| CompiledInstruction {
+                program_id_index: new_index[ix.program_id_index as usize],
+                account_indices: ix.account_indices.iter().map(|&i| new_index[i as usize]).collect(),
+                data: ix.data.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Account positions a decoded System or SPL Token instruction writes
+///
+/// `None` for other programs and unrecognised instructions. Positions past
+/// the listed ones (multisig signers, sysvars) are readonly.
+fn written_positions(program_id: &[u8; 32], data: &[u8]) -> Option<&'static [usize]> {
+    const SYSTEM: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+
+    match *program_id {
+        SYSTEM => {
+            let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+            Some(match tag {
+                // CreateAccount, Transfer, WithdrawNonceAccount
+                0 | 2 | 5 => &[0, 1],
+                // CreateAccountWithSeed
+                3 => &[0, 1],
+                // TransferWithSeed: from, base, to
+                11 => &[0, 2],
+                // Assign, AdvanceNonce, InitializeNonce, AuthorizeNonce, Allocate,
+                // AllocateWithSeed, AssignWithSeed, UpgradeNonceAccount
+                1 | 4 | 6..=10 | 12 => &[0],
+                _ => return None,
+            })
+        }
+        TOKEN | TOKEN_2022 => Some(match data.first()? {
+            // Transfer, MintTo, Burn, CloseAccount, MintToChecked, BurnChecked
+            3 | 7 | 8 | 9 | 14 | 15 => &[0, 1],
+            // TransferChecked: source, mint, destination
+            12 => &[0, 2],
+            // Initialize*, Approve*, Revoke, SetAuthority, Freeze/Thaw,
+            // SyncNative, InitializeImmutableOwner
+            0 | 1 | 2 | 4 | 5 | 6 | 10 | 11 | 13 | 16..=20 | 22 => &[0],
+            // GetAccountDataSize, AmountToUiAmount, UiAmountToAmount
+            21 | 23 | 24 => &[],
+            _ => return None,
+        }),
+        _ => None,
+    }
+}
+
+/// Trailing zero bytes in instruction data that count as padding
+pub const PADDING_THRESHOLD: usize = 16;
+
+/// Table-eligible accounts from which a lookup table pays off
+pub const LOOKUP_TABLE_MIN_ACCOUNTS: usize = 8;
+
+/// Rubric item found by [`TransactionOptimizer::efficiency_report`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Deduction {
+    /// A readonly account is listed more than once by one instruction
+    ReadonlyDuplicate {
+        instruction: usize,
+        account: [u8; 32],
+        occurrences: usize,
+    },
+    /// No compute unit limit is requested, so the default is charged
+    DefaultComputeLimit { charged_units: u64 },
+    /// Instruction data ends in a run of zero bytes
+    DataPadding { instruction: usize, padding_bytes: usize },
+    /// Legacy message whose non-signer, non-program accounts could be looked up
+    MissingLookupTable { eligible_accounts: usize },
+    /// A required signer's signature slot is still zeroed
+    EmptySignatureSlot { signer: [u8; 32] },
+}
+
+impl Deduction {
+    /// Points this item costs
+    pub fn points(&self) -> u8 {
+        match self {
+            Deduction::ReadonlyDuplicate { .. } => 5,
+            Deduction::DefaultComputeLimit { .. } => 15,
+            Deduction::DataPadding { .. } => 10,
+            Deduction::MissingLookupTable { .. } => 15,
+            Deduction::EmptySignatureSlot { .. } => 10,
+        }
+    }
+
+    /// Bytes the fix would save, where it changes the wire size
+    pub fn bytes_saved(&self) -> usize {
+        match self {
+            Deduction::ReadonlyDuplicate { occurrences, .. } => occurrences - 1,
+            Deduction::DataPadding { padding_bytes, .. } => *padding_bytes,
+            // 32-byte keys become 1-byte indices, less the table address and two length prefixes
+            Deduction::MissingLookupTable { eligible_accounts } => {
+                (eligible_accounts * 31).saturating_sub(34)
+            }
+            Deduction::DefaultComputeLimit { .. } | Deduction::EmptySignatureSlot { .. } => 0,
+        }
+    }
+
+    /// Concrete fix for this item
+    pub fn suggestion(&self) -> String {
+        match self {
+            Deduction::ReadonlyDuplicate { instruction, account, occurrences } => format!(
+                "Instruction {} lists readonly account {} {} times; pass it once",
+                instruction,
+                bs58::encode(account).into_string(),
+                occurrences
+            ),
+            Deduction::DefaultComputeLimit { charged_units } => format!(
+                "Add a SetComputeUnitLimit instruction; {} units are reserved by default",
+                charged_units
+            ),
+            Deduction::DataPadding { instruction, padding_bytes } => format!(
+                "Instruction {} data ends in {} zero bytes; trim the padding if the program allows it",
+                instruction, padding_bytes
+            ),
+            Deduction::MissingLookupTable { eligible_accounts } => format!(
+                "Move {} accounts into an address lookup table and send as v0",
+                eligible_accounts
+            ),
+            Deduction::EmptySignatureSlot { signer } => format!(
+                "Sign with {} or drop it as a required signer",
+                bs58::encode(signer).into_string()
+            ),
+        }
+    }
+}
+
+/// How much a [`Suggestion`] matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    /// The transaction will be rejected as it stands
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+/// An improvement found by [`TransactionOptimizer::analyze`]
+///
+/// `code` is stable across releases, so tooling can filter on it and track
+/// how often each suggestion fires. Savings are zero where unknown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub estimated_savings_bytes: usize,
+    pub estimated_savings_lamports: u64,
+}
+
+impl Suggestion {
+    fn info(code: &'static str, message: &str) -> Self {
+        Self {
+            code,
+            severity: Severity::Info,
+            message: message.to_string(),
+            estimated_savings_bytes: 0,
+            estimated_savings_lamports: 0,
+        }
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+impl From<&Deduction> for Suggestion {
+    fn from(deduction: &Deduction) -> Self {
+        let (code, severity) = match deduction {
+            Deduction::ReadonlyDuplicate { .. } => ("readonly-duplicate", Severity::Info),
+            Deduction::DefaultComputeLimit { .. } => ("default-compute-limit", Severity::Warning),
+            Deduction::DataPadding { .. } => ("data-padding", Severity::Info),
+            Deduction::MissingLookupTable { .. } => ("missing-lookup-table", Severity::Info),
+            Deduction::EmptySignatureSlot { .. } => ("empty-signature-slot", Severity::Critical),
+        };
+        Self {
+            code,
+            severity,
+            message: deduction.suggestion(),
+            estimated_savings_bytes: deduction.bytes_saved(),
+            estimated_savings_lamports: 0,
+        }
     }
 }
 
+/// Efficiency score and the deductions behind it
+#[derive(Debug, Clone, PartialEq)]
+pub struct EfficiencyReport {
+    pub score: u8,
+    pub deductions: Vec<Deduction>,
+}
+
 impl Default for TransactionOptimizer {
     fn default() -> Self {
         Self::new(OptimizationStrategy::Balanced)
@@ -168,28 +1019,104 @@ pub struct TransactionAnalysis {
     pub num_signatures: usize,
     pub num_accounts: usize,
     pub num_instructions: usize,
-    pub suggestions: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+    pub breakdown: SizeBreakdown,
 }
 
 impl TransactionAnalysis {
     /// Get size breakdown as percentages
     pub fn size_breakdown(&self) -> SizeBreakdown {
-        let total = self.total_size as f64;
-        SizeBreakdown {
-            signatures_percent: (self.signature_bytes as f64 / total * 100.0) as u8,
-            accounts_percent: (self.account_bytes as f64 / total * 100.0) as u8,
-            instructions_percent: (self.instruction_data_bytes as f64 / total * 100.0) as u8,
-        }
+        self.breakdown.clone()
     }
 }
 
-#[derive(Debug, Clone)]
+/// Serialized size by category
+///
+/// The byte counts add up to `total_bytes`, so [`Self::categories`] covers
+/// the whole transaction. The rounded `*_percent` fields cover only the
+/// three largest parts.
+#[derive(Debug, Clone, PartialEq)]
 pub struct SizeBreakdown {
+    pub total_bytes: usize,
+    pub signature_bytes: usize,
+    pub header_bytes: usize,
+    pub account_key_bytes: usize,
+    pub blockhash_bytes: usize,
+    /// Program ID and account index bytes of every instruction
+    pub instruction_index_bytes: usize,
+    pub instruction_data_bytes: usize,
+    /// Compact-u16 prefixes of every list and byte array
+    pub length_prefix_bytes: usize,
     pub signatures_percent: u8,
     pub accounts_percent: u8,
     pub instructions_percent: u8,
 }
 
+impl SizeBreakdown {
+    pub fn of(transaction: &CompiledTransaction) -> Self {
+        let message = &transaction.message;
+        let mut instruction_index_bytes = 0;
+        let mut instruction_data_bytes = 0;
+        let mut length_prefix_bytes = compact_u16_len(transaction.signatures.len())
+            + compact_u16_len(message.account_keys.len())
+            + compact_u16_len(message.instructions.len());
+        for ix in &message.instructions {
+            instruction_index_bytes += 1 + ix.account_indices.len();
+            instruction_data_bytes += ix.data.len();
+            length_prefix_bytes += compact_u16_len(ix.account_indices.len()) + compact_u16_len(ix.data.len());
+        }
+
+        let mut breakdown = Self {
+            total_bytes: 0,
+            signature_bytes: transaction.signatures.len() * 64,
+            header_bytes: 3,
+            account_key_bytes: message.account_keys.len() * 32,
+            blockhash_bytes: 32,
+            instruction_index_bytes,
+            instruction_data_bytes,
+            length_prefix_bytes,
+            signatures_percent: 0,
+            accounts_percent: 0,
+            instructions_percent: 0,
+        };
+        breakdown.total_bytes = breakdown.categories().iter().map(|(_, bytes, _)| bytes).sum();
+        breakdown.signatures_percent = breakdown.percent(breakdown.signature_bytes) as u8;
+        breakdown.accounts_percent = breakdown.percent(breakdown.account_key_bytes) as u8;
+        breakdown.instructions_percent = breakdown.percent(breakdown.instruction_data_bytes) as u8;
+        breakdown
+    }
+
+    /// Header, blockhash and length prefixes: bytes no content change avoids
+    pub fn structural_overhead(&self) -> usize {
+        self.header_bytes + self.blockhash_bytes + self.length_prefix_bytes
+    }
+
+    /// Share of the transaction taken by `bytes`, in percent
+    pub fn percent(&self, bytes: usize) -> f64 {
+        if self.total_bytes == 0 {
+            return 0.0;
+        }
+        bytes as f64 / self.total_bytes as f64 * 100.0
+    }
+
+    /// (name, bytes, percent) for every category, in wire order apart from
+    /// the length prefixes, which come last
+    pub fn categories(&self) -> Vec<(&'static str, usize, f64)> {
+        [
+            ("signatures", self.signature_bytes),
+            ("header", self.header_bytes),
+            ("account keys", self.account_key_bytes),
+            ("blockhash", self.blockhash_bytes),
+            ("instruction indices", self.instruction_index_bytes),
+            ("instruction data", self.instruction_data_bytes),
+            ("length prefixes", self.length_prefix_bytes),
+        ]
+        .into_iter()
+        .map(|(name, bytes)| (name, bytes, self.percent(bytes)))
+        .collect()
+    }
+}
+
 /// Utility functions for transaction optimization
 pub mod utils {
     use super::*;
@@ -239,6 +1166,94 @@ pub struct TransactionComparison {
     pub account_diff: i32,
 }
 
+/// Accounts a single transaction may lock
+pub const MAX_TX_ACCOUNT_LOCKS: usize = 64;
+
+/// Size and limits of one wire encoding
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingStats {
+    pub size: usize,
+    pub required_signatures: usize,
+    /// Keys carried in the message itself
+    pub static_accounts: usize,
+    /// Keys loaded through lookup tables (always 0 for legacy)
+    pub loaded_accounts: usize,
+    /// Limits the encoding breaks; empty when it can be sent
+    pub violations: Vec<String>,
+}
+
+impl EncodingStats {
+    fn new(size: usize, required_signatures: usize, static_accounts: usize, loaded_accounts: usize) -> Self {
+        let mut violations = Vec::new();
+        if size > MAX_TRANSACTION_SIZE {
+            violations.push(format!("{} bytes exceeds the {} byte limit", size, MAX_TRANSACTION_SIZE));
+        }
+        let accounts = static_accounts + loaded_accounts;
+        if accounts > MAX_TX_ACCOUNT_LOCKS {
+            violations.push(format!("{} accounts exceeds the {} account lock limit", accounts, MAX_TX_ACCOUNT_LOCKS));
+        }
+        Self {
+            size,
+            required_signatures,
+            static_accounts,
+            loaded_accounts,
+            violations,
+        }
+    }
+
+    pub fn fits(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// The same instructions compiled as legacy and as V0 with lookup tables
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingComparison {
+    pub legacy: EncodingStats,
+    pub v0: EncodingStats,
+}
+
+impl EncodingComparison {
+    /// Bytes V0 saves over legacy (negative when V0 is larger)
+    pub fn bytes_saved(&self) -> i64 {
+        self.legacy.size as i64 - self.v0.size as i64
+    }
+
+    /// Whether the lookup tables pay off: V0 is the only encoding that
+    /// fits, or both fit and V0 is smaller
+    pub fn prefers_v0(&self) -> bool {
+        match (self.legacy.fits(), self.v0.fits()) {
+            (false, true) => true,
+            (true, true) => self.bytes_saved() > 0,
+            _ => false,
+        }
+    }
+}
+
+/// Compile `builder` both as legacy and as V0 against `lookup_tables`
+///
+/// Helps decide whether maintaining lookup tables for an instruction set is
+/// worth it. Each non-signer, non-program account is loaded from the first
+/// table holding it, as in [`legacy_to_v0`].
+pub fn compare_encodings(builder: TransactionBuilder, lookup_tables: &[AddressLookupTable]) -> Result<EncodingComparison> {
+    let legacy = builder.build_unsigned()?;
+    let (v0, _) = legacy_to_v0(&legacy, lookup_tables)?;
+    Ok(EncodingComparison {
+        legacy: EncodingStats::new(
+            legacy.size(),
+            legacy.signatures.len(),
+            legacy.message.account_keys.len(),
+            0,
+        ),
+        v0: EncodingStats::new(
+            v0.size(),
+            v0.signatures.len(),
+            v0.message.account_keys.len(),
+            v0.message.num_loaded_accounts(),
+        ),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,6 +1290,19 @@ mod tests {
         assert!(analysis.num_signatures > 0);
         assert!(analysis.num_accounts > 0);
         assert!(analysis.num_instructions > 0);
+        assert!(analysis.suggestions.is_empty());
+
+        let big = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash(blockhash)
+            .add_instruction(InstructionEncoder::new(program_id).data(vec![1; 1300]).build())
+            .build_unsigned()
+            .unwrap();
+        let suggestions = optimizer.analyze(&big).suggestions;
+        let codes: Vec<_> = suggestions.iter().map(|s| (s.code, s.severity)).collect();
+        assert_eq!(codes, vec![("oversized", Severity::Critical), ("large-data", Severity::Warning)]);
+        assert_eq!(suggestions[0].estimated_savings_bytes, big.size() - MAX_TRANSACTION_SIZE);
+        assert!(suggestions[1].to_string().starts_with("warning: Large instruction data"));
     }
 
     #[test]
@@ -349,5 +1377,295 @@ mod tests {
             + breakdown.accounts_percent as u32
             + breakdown.instructions_percent as u32;
         assert!(total <= 100);
+
+        let categories = breakdown.categories();
+        assert_eq!(categories.iter().map(|(_, bytes, _)| bytes).sum::<usize>(), tx.size());
+        assert!((categories.iter().map(|(_, _, pct)| pct).sum::<f64>() - 100.0).abs() < 1e-9);
+        assert_eq!(breakdown.instruction_index_bytes, 2);
+        // Signature, key and instruction counts plus the instruction's two
+        assert_eq!(breakdown.length_prefix_bytes, 5);
+        assert_eq!(breakdown.structural_overhead(), 3 + 32 + 5);
+    }
+
+    #[test]
+    fn test_simulator_rejects_costlier_pass() {
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).append_u8(1).build())
+            .build_unsigned()
+            .unwrap();
+
+        // Passes with nothing to do are neither simulated nor reported
+        let optimizer = TransactionOptimizer::new(OptimizationStrategy::Balanced)
+            .with_simulator(|_: &CompiledTransaction| -> Result<SimulationResult> {
+                panic!("no-op passes should not be simulated")
+            });
+        let (_, report) = optimizer.optimize(tx.clone()).unwrap();
+        assert!(report.optimizations_applied.is_empty());
+
+        let optimizer = TransactionOptimizer::new(OptimizationStrategy::Size)
+            .with_simulator(|t: &CompiledTransaction| {
+                Ok(SimulationResult {
+                    success: true,
+                    units_consumed: 1_000 * t.message.instructions.len() as u64,
+                })
+            });
+        let mut grown = tx.clone();
+        grown.message.instructions.push(grown.message.instructions[0].clone());
+        let mut baseline = None;
+        let verdict = optimizer.verify(&tx, &grown, &mut baseline).unwrap();
+        assert!(verdict.unwrap_err().contains("CUs"));
+        assert!(optimizer.verify(&grown, &tx, &mut None).unwrap().is_ok());
+
+        // A change that is no cheaper, smaller or less locking is declined
+        let mut reordered = grown.clone();
+        reordered.message.instructions[1].data = vec![2];
+        let verdict = optimizer.verify(&grown, &reordered, &mut None).unwrap();
+        assert!(verdict.unwrap_err().contains("no fewer"));
+    }
+
+    #[test]
+    fn test_efficiency_rubric() {
+        let program_id = [2u8; 32];
+        let mut encoder = InstructionEncoder::new(program_id)
+            .readonly([4u8; 32])
+            .readonly([4u8; 32]);
+        for i in 0..LOOKUP_TABLE_MIN_ACCOUNTS as u8 {
+            encoder = encoder.writable([10 + i; 32], false);
+        }
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(encoder.append_u8(7).append_data(&[0u8; PADDING_THRESHOLD]).build())
+            .build_unsigned()
+            .unwrap();
+
+        let report = TransactionOptimizer::default().efficiency_report(&tx);
+        assert_eq!(report.score, 100 - 5 - 15 - 10 - 15 - 10);
+        assert!(matches!(
+            report.deductions[0],
+            Deduction::ReadonlyDuplicate { occurrences: 2, .. }
+        ));
+        assert_eq!(
+            report.deductions[1],
+            Deduction::DefaultComputeLimit { charged_units: 200_000 }
+        );
+        assert_eq!(
+            report.deductions[2],
+            Deduction::DataPadding { instruction: 0, padding_bytes: PADDING_THRESHOLD }
+        );
+        assert_eq!(
+            report.deductions[3],
+            Deduction::MissingLookupTable { eligible_accounts: LOOKUP_TABLE_MIN_ACCOUNTS + 1 }
+        );
+        assert_eq!(report.deductions[4], Deduction::EmptySignatureSlot { signer: [1u8; 32] });
+        assert!(report.deductions.iter().all(|d| !d.suggestion().is_empty()));
+        let padding = Suggestion::from(&report.deductions[2]);
+        assert_eq!((padding.code, padding.estimated_savings_bytes), ("data-padding", PADDING_THRESHOLD));
+    }
+
+    #[test]
+    fn test_instruction_dedup() {
+        let limit = InstructionEncoder::new(compute_budget::COMPUTE_BUDGET_PROGRAM_ID)
+            .data(compute_budget::create_compute_unit_limit_instruction(50_000))
+            .build();
+        let transfer = InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(1).build();
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instructions(vec![limit.clone(), transfer.clone(), limit.clone(), transfer, limit])
+            .build_unsigned()
+            .unwrap();
+
+        // Without a policy nothing is removed
+        let (same, report) = TransactionOptimizer::default().optimize(tx.clone()).unwrap();
+        assert_eq!(same.message.instructions.len(), 5);
+        assert!(report.instructions_removed.is_empty());
+
+        let optimizer = TransactionOptimizer::default().with_instruction_dedup(known_idempotent);
+        let (deduped, report) = optimizer.optimize(tx.clone()).unwrap();
+        assert_eq!(report.instructions_removed, vec![2, 4]);
+        assert_eq!(deduped.message.instructions.len(), 3);
+        assert_eq!(deduped.message.account_keys, tx.message.account_keys);
+        assert_eq!(compute_budget::requested_budget(&deduped.message).unit_limit, Some(50_000));
+
+        let everything = TransactionOptimizer::default()
+            .with_instruction_dedup(|_: &[u8; 32], _: &CompiledInstruction| true);
+        assert_eq!(everything.optimize(tx).unwrap().1.instructions_removed, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_account_demotion() {
+        let (source, mint, destination, owner) = ([5u8; 32], [6u8; 32], [7u8; 32], [8u8; 32]);
+        // Middleware marked the mint and owner writable
+        let transfer_checked = InstructionEncoder::new(TOKEN)
+            .writable(source, false)
+            .writable(mint, false)
+            .writable(destination, false)
+            .signer(owner, true)
+            .append_u8(12)
+            .append_u64(100)
+            .append_u8(6)
+            .build();
+        let unknown = InstructionEncoder::new([2u8; 32]).writable([9u8; 32], false).build();
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instructions(vec![transfer_checked, unknown])
+            .build_unsigned()
+            .unwrap();
+
+        let optimizer = TransactionOptimizer::new(OptimizationStrategy::Size).with_account_demotion();
+        let (demoted, report) = optimizer.optimize(tx.clone()).unwrap();
+        let mut expected = vec![mint, owner];
+        expected.sort_unstable_by_key(|key| tx.message.account_keys.iter().position(|k| k == key));
+        assert_eq!(report.accounts_demoted, expected);
+
+        let message = &demoted.message;
+        let role = |key: &[u8; 32]| {
+            let i = message.account_keys.iter().position(|k| k == key).unwrap();
+            (message.is_signer(i), message.is_writable(i))
+        };
+        assert_eq!(role(&[1u8; 32]), (true, true));
+        assert_eq!(role(&owner), (true, false));
+        assert_eq!(role(&mint), (false, false));
+        assert_eq!(role(&source), (false, true));
+        assert_eq!(role(&[9u8; 32]), (false, true));
+        assert!(message.header.validate(message).is_ok());
+
+        // Instructions still reference the same keys
+        for (before, after) in tx.message.instructions.iter().zip(&message.instructions) {
+            let keys = |m: &CompiledMessage, ix: &CompiledInstruction| -> Vec<[u8; 32]> {
+                ix.account_indices.iter().map(|&i| m.account_keys[i as usize]).collect()
+            };
+            assert_eq!(keys(&tx.message, before), keys(message, after));
+            assert_eq!(before.data, after.data);
+        }
+    }
+
+    #[test]
+    fn test_optimize_auto() {
+        let budget = |data: Vec<u8>| InstructionEncoder::new(compute_budget::COMPUTE_BUDGET_PROGRAM_ID).data(data).build();
+        let transfer = InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(1).build();
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instructions(vec![
+                budget(compute_budget::create_compute_unit_limit_instruction(50_000)),
+                budget(compute_budget::create_compute_unit_price_instruction(10)),
+                transfer.clone(),
+                budget(compute_budget::create_compute_unit_limit_instruction(80_000)),
+                transfer,
+                budget(compute_budget::create_compute_unit_price_instruction(500)),
+                // Deprecated RequestUnits: units and additional fee, two u32s
+                budget([vec![0], 1u32.to_le_bytes().to_vec(), 9u32.to_le_bytes().to_vec()].concat()),
+                budget([vec![0], 2u32.to_le_bytes().to_vec(), 0u32.to_le_bytes().to_vec()].concat()),
+            ])
+            .build_unsigned()
+            .unwrap();
+
+        let auto = TransactionOptimizer::default().optimize_auto(tx).unwrap();
+        assert_eq!(auto.report.instructions_removed, vec![3]);
+        let prices: Vec<_> = auto
+            .transaction
+            .message
+            .instructions
+            .iter()
+            .filter_map(|ix| compute_budget::parse_compute_unit_price(&ix.data))
+            .collect();
+        assert_eq!(prices, vec![10, 500]);
+        // Both RequestUnits survive, each with its own two fields
+        let request_units: Vec<_> =
+            auto.transaction.message.instructions.iter().filter(|ix| ix.data.first() == Some(&0)).collect();
+        assert_eq!(request_units.len(), 2);
+        assert_eq!(request_units[0].data[5..], 9u32.to_le_bytes());
+        assert!(auto.report.optimizations_applied.contains(&"Compute budget merge".to_string()));
+        let budget = compute_budget::requested_budget(&auto.transaction.message);
+        assert_eq!(budget.unit_limit, Some(80_000));
+
+        // The repeated transfer needs program knowledge, so it is only reported
+        let codes: Vec<_> = auto.declined.iter().map(|s| s.code).collect();
+        assert!(codes.contains(&"duplicate-instruction"));
+        assert!(codes.contains(&"repeated-compute-unit-price"));
+        assert!(codes.contains(&"empty-signature-slot"));
+        assert!(!codes.contains(&"default-compute-limit"));
+    }
+
+    #[test]
+    fn test_compare_encodings() {
+        let accounts: Vec<[u8; 32]> = (10..40u8).map(|i| [i; 32]).collect();
+        let builder = TransactionBuilder::new().payer([1u8; 32]).recent_blockhash([3u8; 32]).add_instruction(
+            accounts
+                .iter()
+                .fold(InstructionEncoder::new([2u8; 32]), |ix, key| ix.readonly(*key))
+                .build(),
+        );
+
+        let table = AddressLookupTable::new([9u8; 32], accounts.clone());
+        let comparison = compare_encodings(builder.clone(), &[table]).unwrap();
+        assert_eq!(comparison.legacy.static_accounts, 32);
+        assert_eq!((comparison.v0.static_accounts, comparison.v0.loaded_accounts), (2, 30));
+        assert_eq!(comparison.v0.required_signatures, 1);
+        // 30 keys of 32 bytes become 30 one-byte indexes plus the table entry
+        assert!(comparison.bytes_saved() > 800);
+        assert!(comparison.legacy.fits() && comparison.prefers_v0());
+
+        // Without tables V0 only adds its version byte and lookup length
+        let comparison = compare_encodings(builder, &[]).unwrap();
+        assert_eq!(comparison.bytes_saved(), -2);
+        assert!(!comparison.prefers_v0());
+    }
+
+    #[test]
+    fn test_strategy_passes() {
+        let payer = crate::testkit::pubkey(1);
+        let (to, other) = (crate::testkit::address(2), crate::testkit::address(3));
+        let transfer = |to, lamports| crate::testkit::transfer_instruction(payer, to, lamports);
+        let mut tx = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([3u8; 32])
+            .add_instructions(vec![
+                transfer(to, 1),
+                transfer(to, 2),
+                transfer(other, 3),
+                transfer(to, 4),
+                transfer(to, u64::MAX),
+            ])
+            .build_unsigned()
+            .unwrap();
+        // A readonly key no instruction references
+        let unused = [9u8; 32];
+        tx.message.account_keys.push(unused);
+        tx.message.header.num_readonly_unsigned_accounts += 1;
+
+        let (size, report) = TransactionOptimizer::new(OptimizationStrategy::Size).optimize(tx.clone()).unwrap();
+        assert_eq!(report.optimizations_applied, vec!["Unused account removal".to_string()]);
+        assert_eq!(report.accounts_removed, vec![unused]);
+        assert_eq!(size.message.instructions.len(), 5);
+        assert!(size.message.header.validate(&size.message).is_ok());
+        assert!(!size.message.account_keys.contains(&unused));
+
+        let (cost, report) = TransactionOptimizer::new(OptimizationStrategy::Cost).optimize(tx.clone()).unwrap();
+        assert_eq!(report.optimizations_applied, vec!["Transfer consolidation".to_string()]);
+        // Only the first two are adjacent; the last would overflow
+        assert_eq!(report.instructions_removed, vec![1]);
+        let amounts: Vec<_> =
+            cost.message.instructions.iter().map(|ix| u64::from_le_bytes(ix.data[4..].try_into().unwrap())).collect();
+        assert_eq!(amounts, vec![3, 3, 4, u64::MAX]);
+
+        // The simulator has the last word on each pass
+        let optimizer =
+            TransactionOptimizer::new(OptimizationStrategy::Balanced).with_simulator(|t: &CompiledTransaction| {
+                Ok(SimulationResult {
+                    success: t.message.account_keys.len() > 4,
+                    units_consumed: 150 * t.message.instructions.len() as u64,
+                })
+            });
+        let (balanced, report) = optimizer.optimize(tx).unwrap();
+        assert_eq!(report.optimizations_applied, vec!["Transfer consolidation".to_string()]);
+        assert!(report.optimizations_rejected[0].starts_with("Unused account removal: simulation failed"));
+        assert_eq!(balanced.message.instructions.len(), 4);
+        assert!(balanced.message.account_keys.contains(&unused));
     }
 }
@@ -0,0 +1,280 @@
+//! Fast-path entry points for latency-sensitive callers
+//!
+//! Everything in this module is a stability-guaranteed API: signatures and
+//! allocation behaviour will not change outside a major version bump. The
+//! functions here never allocate when given a buffer with enough capacity,
+//! and [`TransactionView`] reads a serialized legacy transaction in place
+//! without copying keys, signatures or instruction data.
+//!
+//! Benchmarks covering these paths live in `benches/`; run them with
+//! `cargo bench`.
+
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::{FeeEstimate, FeePolicy, PriorityFeeCalculator};
+use crate::serialization::{decode_compact_u16, decode_u8, ByteSerialize};
+use crate::transaction::{CompiledMessage, CompiledTransaction, MessageHeader};
+use crate::versioned::MESSAGE_VERSION_PREFIX;
+use std::io::Cursor;
+
+/// Serialize `transaction` into `buf`, replacing its contents
+pub fn serialize_into(transaction: &CompiledTransaction, buf: &mut Vec<u8>) -> Result<()> {
+    transaction.serialize_into(buf)
+}
+
+/// Serialize the signable message bytes into `buf`, replacing its contents
+pub fn message_bytes_into(message: &CompiledMessage, buf: &mut Vec<u8>) -> Result<()> {
+    buf.clear();
+    buf.reserve(message.byte_size());
+    message.serialize_bytes(buf)
+}
+
+/// Estimate the fee of a serialized transaction
+///
+/// Policies with a fixed price, such as the [`FeeStrategy`] presets, are
+/// priced without decoding; any other policy decodes the message once to
+/// see it.
+///
+/// [`FeeStrategy`]: crate::fee_calculator::FeeStrategy
+pub fn estimate_fee(
+    view: &TransactionView<'_>,
+    calculator: &PriorityFeeCalculator,
+    policy: impl FeePolicy,
+) -> Result<FeeEstimate> {
+    let data_bytes: u32 = view.instructions().map(|i| i.data.len() as u32).sum();
+    let compute_units = calculator.estimate_compute_units_for(
+        view.num_instructions() as u32,
+        view.num_account_keys() as u32,
+        data_bytes,
+    );
+    let price = match policy.fixed_micro_lamports_per_cu() {
+        Some(price) => price,
+        None => policy.micro_lamports_per_cu(&CompiledMessage::deserialize(view.message_bytes())?),
+    };
+    calculator.fee_for(view.num_signatures(), compute_units, price)
+}
+
+/// Zero-copy view over a serialized legacy transaction
+///
+/// The layout is validated once by [`TransactionView::parse`]; accessors
+/// afterwards are plain slice reads.
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionView<'a> {
+    bytes: &'a [u8],
+    num_signatures: usize,
+    signatures_offset: usize,
+    message_offset: usize,
+    num_account_keys: usize,
+    account_keys_offset: usize,
+    blockhash_offset: usize,
+    num_instructions: usize,
+    instructions_offset: usize,
+}
+
+/// Borrowed instruction inside a [`TransactionView`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstructionView<'a> {
+    pub program_id_index: u8,
+    pub account_indices: &'a [u8],
+    pub data: &'a [u8],
+}
+
+impl<'a> TransactionView<'a> {
+    /// Validate the layout of `bytes` and build a view over it
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+
+        let num_signatures = decode_compact_u16(&mut cursor)? as usize;
+        let signatures_offset = cursor.position() as usize;
+        take(&mut cursor, num_signatures * 64)?;
+        let message_offset = cursor.position() as usize;
+
+        if bytes.get(message_offset).is_some_and(|b| b & MESSAGE_VERSION_PREFIX != 0) {
+            return Err(TxAsmError::DeserializationError(
+                "Versioned messages are not supported by TransactionView".to_string(),
+            ));
+        }
+        take(&mut cursor, 3)?;
+
+        let num_account_keys = decode_compact_u16(&mut cursor)? as usize;
+        let account_keys_offset = take(&mut cursor, num_account_keys * 32)?;
+        let blockhash_offset = take(&mut cursor, 32)?;
+
+        let num_instructions = decode_compact_u16(&mut cursor)? as usize;
+        let instructions_offset = cursor.position() as usize;
+        for _ in 0..num_instructions {
+            decode_u8(&mut cursor)?;
+            let num_accounts = decode_compact_u16(&mut cursor)? as usize;
+            take(&mut cursor, num_accounts)?;
+            let data_len = decode_compact_u16(&mut cursor)? as usize;
+            take(&mut cursor, data_len)?;
+        }
+
+        if cursor.position() as usize != bytes.len() {
+            return Err(TxAsmError::DeserializationError(format!(
+                "{} trailing bytes after transaction",
+                bytes.len() - cursor.position() as usize
+            )));
+        }
+
+        Ok(Self {
+            bytes,
+            num_signatures,
+            signatures_offset,
+            message_offset,
+            num_account_keys,
+            account_keys_offset,
+            blockhash_offset,
+            num_instructions,
+            instructions_offset,
+        })
+    }
+
+    pub fn num_signatures(&self) -> usize {
+        self.num_signatures
+    }
+
+    pub fn num_account_keys(&self) -> usize {
+        self.num_account_keys
+    }
+
+    pub fn num_instructions(&self) -> usize {
+        self.num_instructions
+    }
+
+    /// Signature at `index`, if present
+    pub fn signature(&self, index: usize) -> Option<&'a [u8; 64]> {
+        if index >= self.num_signatures {
+            return None;
+        }
+        let start = self.signatures_offset + index * 64;
+        self.bytes[start..start + 64].try_into().ok()
+    }
+
+    /// The signable message bytes
+    pub fn message_bytes(&self) -> &'a [u8] {
+        &self.bytes[self.message_offset..]
+    }
+
+    pub fn header(&self) -> MessageHeader {
+        let h = &self.bytes[self.message_offset..self.message_offset + 3];
+        MessageHeader::new(h[0], h[1], h[2])
+    }
+
+    /// Account key at `index`, if present
+    pub fn account_key(&self, index: usize) -> Option<&'a [u8; 32]> {
+        if index >= self.num_account_keys {
+            return None;
+        }
+        let start = self.account_keys_offset + index * 32;
+        self.bytes[start..start + 32].try_into().ok()
+    }
+
+    pub fn recent_blockhash(&self) -> &'a [u8; 32] {
+        self.bytes[self.blockhash_offset..self.blockhash_offset + 32]
+            .try_into()
+            .expect("blockhash bounds checked in parse")
+    }
+
+    /// Iterate over instructions without copying
+    pub fn instructions(&self) -> impl Iterator<Item = InstructionView<'a>> + 'a {
+        let bytes = self.bytes;
+        let mut cursor = Cursor::new(bytes);
+        cursor.set_position(self.instructions_offset as u64);
+        (0..self.num_instructions).map(move |_| {
+            // Layout was validated in parse, so these reads cannot fail
+            let program_id_index = decode_u8(&mut cursor).expect("validated");
+            let num_accounts = decode_compact_u16(&mut cursor).expect("validated") as usize;
+            let accounts_start = take(&mut cursor, num_accounts).expect("validated");
+            let data_len = decode_compact_u16(&mut cursor).expect("validated") as usize;
+            let data_start = take(&mut cursor, data_len).expect("validated");
+            InstructionView {
+                program_id_index,
+                account_indices: &bytes[accounts_start..accounts_start + num_accounts],
+                data: &bytes[data_start..data_start + data_len],
+            }
+        })
+    }
+}
+
+/// Advance the cursor by `len` bytes, returning the start offset
+fn take(cursor: &mut Cursor<&[u8]>, len: usize) -> Result<usize> {
+    let start = cursor.position() as usize;
+    let available = cursor.get_ref().len();
+    if start + len > available {
+        return Err(TxAsmError::BufferTooSmall {
+            needed: start + len,
+            available,
+        });
+    }
+    cursor.set_position((start + len) as u64);
+    Ok(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::FeeStrategy;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+
+    fn sample() -> CompiledTransaction {
+        let instruction = InstructionEncoder::new([2u8; 32])
+            .writable([5u8; 32], false)
+            .append_u32(7)
+            .build();
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_view_matches_decoded() {
+        let tx = sample();
+        let bytes = tx.serialize().unwrap();
+        let view = TransactionView::parse(&bytes).unwrap();
+
+        assert_eq!(view.header(), tx.message.header);
+        assert_eq!(view.recent_blockhash(), &[3u8; 32]);
+        assert_eq!(view.message_bytes(), tx.message_bytes().unwrap());
+        for (i, key) in tx.message.account_keys.iter().enumerate() {
+            assert_eq!(view.account_key(i), Some(key));
+        }
+        let ix = view.instructions().next().unwrap();
+        assert_eq!(ix.account_indices, &tx.message.instructions[0].account_indices[..]);
+        assert_eq!(ix.data, &tx.message.instructions[0].data[..]);
+
+        let calculator = PriorityFeeCalculator::new();
+        assert_eq!(
+            estimate_fee(&view, &calculator, FeeStrategy::High).unwrap().total_cost,
+            calculator.estimate_fee(&tx, FeeStrategy::High).unwrap().total_cost
+        );
+        let by_keys = |message: &CompiledMessage| message.account_keys.len() as u64 * 1_000;
+        assert_eq!(
+            estimate_fee(&view, &calculator, by_keys).unwrap().priority_fee_per_cu,
+            tx.message.account_keys.len() as u64 * 1_000
+        );
+    }
+
+    #[test]
+    fn test_view_rejects_truncated() {
+        let bytes = sample().serialize().unwrap();
+        assert!(TransactionView::parse(&bytes[..bytes.len() - 1]).is_err());
+
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(TransactionView::parse(&padded).is_err());
+    }
+
+    #[test]
+    fn test_buffer_reuse() {
+        let tx = sample();
+        let mut buf = Vec::with_capacity(1232);
+        message_bytes_into(&tx.message, &mut buf).unwrap();
+        assert_eq!(buf, tx.message_bytes().unwrap());
+        serialize_into(&tx, &mut buf).unwrap();
+        assert_eq!(buf, tx.serialize().unwrap());
+    }
+}
@@ -0,0 +1,416 @@
+//! Compliance policies over compiled messages
+//!
+//! A [`Policy`] inspects a compiled message and returns every
+//! [`PolicyViolation`], naming the instruction and account at fault.
+//! [`AccessList`] is the common case: programs that must never be invoked,
+//! accounts (sanctioned addresses, say) that must never be referenced, and
+//! optionally the only programs that may be invoked at all.
+//! [`SpendingLimits`] caps the transfers a [`BalancePreview`] decodes, per
+//! destination and in aggregate.
+//!
+//! Policies attach to a builder with
+//! [`TransactionBuilder::policy`](crate::transaction::TransactionBuilder::policy),
+//! which then refuses to compile a violating message, or to a
+//! [`PreflightContext`](crate::preflight::PreflightContext) to check
+//! transactions built elsewhere.
+
+use crate::balance::{Asset, BalancePreview};
+use crate::error::{Result, TxAsmError};
+use crate::transaction::CompiledMessage;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Which rule a message broke
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyRule {
+    /// Invokes a blocked program
+    BlockedProgram,
+    /// Invokes a program missing from the allow list
+    ProgramNotAllowed,
+    /// References a blocked account
+    BlockedAccount,
+    /// Sends more of `asset` to one destination than its limit
+    DestinationLimit { asset: Asset, amount: u128, limit: u64 },
+    /// Sends more of `asset` in total than the aggregate limit
+    AggregateLimit { asset: Asset, amount: u128, limit: u64 },
+    /// Instruction whose program or accounts cannot be resolved, so no
+    /// other rule can be checked against it
+    Unresolved(String),
+    /// Rule defined outside this crate
+    Custom(String),
+}
+
+/// One breach of a policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyViolation {
+    /// Offending instruction; `None` for accounts only the header references,
+    /// such as the fee payer
+    pub instruction: Option<usize>,
+    pub account: [u8; 32],
+    pub rule: PolicyRule,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let account = bs58::encode(&self.account).into_string();
+        if let Some(instruction) = self.instruction {
+            write!(f, "instruction {}: ", instruction)?;
+        }
+        match &self.rule {
+            PolicyRule::BlockedProgram => write!(f, "program {} is blocked", account),
+            PolicyRule::ProgramNotAllowed => write!(f, "program {} is not on the allow list", account),
+            PolicyRule::BlockedAccount => write!(f, "account {} is blocked", account),
+            PolicyRule::DestinationLimit { asset, amount, limit } => write!(
+                f,
+                "sends {} {} to {}, over the per-destination limit of {}",
+                amount,
+                asset_name(asset),
+                account,
+                limit
+            ),
+            PolicyRule::AggregateLimit { asset, amount, limit } => write!(
+                f,
+                "sends {} {} in total, over the limit of {}",
+                amount,
+                asset_name(asset),
+                limit
+            ),
+            PolicyRule::Unresolved(reason) => write!(f, "cannot be checked: {}", reason),
+            PolicyRule::Custom(rule) => write!(f, "{} ({})", rule, account),
+        }
+    }
+}
+
+fn asset_name(asset: &Asset) -> String {
+    match asset {
+        Asset::Sol => "lamports".to_string(),
+        Asset::Token(mint) => format!("units of {}", bs58::encode(mint).into_string()),
+    }
+}
+
+/// Check applied to every message before it is accepted
+pub trait Policy: Send + Sync {
+    fn evaluate(&self, message: &CompiledMessage) -> Vec<PolicyViolation>;
+}
+
+impl<F> Policy for F
+where
+    F: Fn(&CompiledMessage) -> Vec<PolicyViolation> + Send + Sync,
+{
+    fn evaluate(&self, message: &CompiledMessage) -> Vec<PolicyViolation> {
+        self(message)
+    }
+}
+
+impl fmt::Debug for dyn Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Policy")
+    }
+}
+
+/// Fail with every violation of `policy`, if any
+pub fn enforce(policy: &dyn Policy, message: &CompiledMessage) -> Result<()> {
+    let violations = policy.evaluate(message);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    Err(TxAsmError::InvalidTransaction(format!(
+        "Policy violation: {}",
+        messages.join("; ")
+    )))
+}
+
+/// Violation for instruction `index`, which `walk` failed to resolve
+fn unresolved(message: &CompiledMessage, index: usize, error: TxAsmError) -> PolicyViolation {
+    let program_id_index = message.instructions[index].program_id_index;
+    PolicyViolation {
+        instruction: Some(index),
+        account: message.account_keys.get(program_id_index as usize).copied().unwrap_or_default(),
+        rule: PolicyRule::Unresolved(error.to_string()),
+    }
+}
+
+/// Allow and block lists of programs and accounts
+#[derive(Debug, Clone, Default)]
+pub struct AccessList {
+    allowed_programs: Option<HashSet<[u8; 32]>>,
+    blocked_programs: HashSet<[u8; 32]>,
+    blocked_accounts: HashSet<[u8; 32]>,
+}
+
+impl AccessList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Permit `program_id`; once any program is allowed, all others are refused
+    pub fn allow_program(mut self, program_id: [u8; 32]) -> Self {
+        self.allowed_programs.get_or_insert_with(HashSet::new).insert(program_id);
+        self
+    }
+
+    pub fn block_program(mut self, program_id: [u8; 32]) -> Self {
+        self.blocked_programs.insert(program_id);
+        self
+    }
+
+    /// Refuse any message referencing `account`, in any role
+    pub fn block_account(mut self, account: [u8; 32]) -> Self {
+        self.blocked_accounts.insert(account);
+        self
+    }
+
+    pub fn block_accounts(mut self, accounts: impl IntoIterator<Item = [u8; 32]>) -> Self {
+        self.blocked_accounts.extend(accounts);
+        self
+    }
+}
+
+impl Policy for AccessList {
+    fn evaluate(&self, message: &CompiledMessage) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        let mut referenced = HashSet::new();
+        for (index, ix) in message.walk().enumerate() {
+            let ix = match ix {
+                Ok(ix) => ix,
+                Err(error) => {
+                    violations.push(unresolved(message, index, error));
+                    continue;
+                }
+            };
+            let violation = |account: &[u8; 32], rule| PolicyViolation {
+                instruction: Some(ix.index),
+                account: *account,
+                rule,
+            };
+            referenced.insert(*ix.program_id);
+            if self.blocked_programs.contains(ix.program_id) {
+                violations.push(violation(ix.program_id, PolicyRule::BlockedProgram));
+            } else if self.allowed_programs.as_ref().is_some_and(|allowed| !allowed.contains(ix.program_id)) {
+                violations.push(violation(ix.program_id, PolicyRule::ProgramNotAllowed));
+            }
+            for account in &ix.accounts {
+                referenced.insert(account.key);
+                if self.blocked_accounts.contains(&account.key) {
+                    violations.push(violation(&account.key, PolicyRule::BlockedAccount));
+                }
+            }
+        }
+        for key in &message.account_keys {
+            if !referenced.contains(key) && self.blocked_accounts.contains(key) {
+                violations.push(PolicyViolation {
+                    instruction: None,
+                    account: *key,
+                    rule: PolicyRule::BlockedAccount,
+                });
+            }
+        }
+        violations
+    }
+}
+
+/// Caps on value sent by the transfers of a message
+///
+/// Every decoded transfer counts, whoever the sender; a message moving
+/// funds between the user's own accounts spends against the limits too.
+/// Each violation names the instruction at which the running total first
+/// went over. A message with instructions that cannot be resolved is
+/// refused outright, naming each of them.
+#[derive(Debug, Clone, Default)]
+pub struct SpendingLimits {
+    preview: BalancePreview,
+    destinations: HashMap<([u8; 32], Asset), u64>,
+    per_destination: HashMap<Asset, u64>,
+    aggregate: HashMap<Asset, u64>,
+}
+
+impl SpendingLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode transfers with `preview`, e.g. one knowing token account mints
+    pub fn preview(mut self, preview: BalancePreview) -> Self {
+        self.preview = preview;
+        self
+    }
+
+    /// Cap what `destination` may receive of `asset`, overriding
+    /// [`Self::per_destination`]
+    pub fn destination(mut self, destination: [u8; 32], asset: Asset, limit: u64) -> Self {
+        self.destinations.insert((destination, asset), limit);
+        self
+    }
+
+    /// Cap what any one destination may receive of `asset`
+    pub fn per_destination(mut self, asset: Asset, limit: u64) -> Self {
+        self.per_destination.insert(asset, limit);
+        self
+    }
+
+    /// Cap the total sent of `asset`
+    pub fn aggregate(mut self, asset: Asset, limit: u64) -> Self {
+        self.aggregate.insert(asset, limit);
+        self
+    }
+}
+
+impl Policy for SpendingLimits {
+    fn evaluate(&self, message: &CompiledMessage) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        let mut received: HashMap<([u8; 32], Asset), u128> = HashMap::new();
+        let mut sent: HashMap<Asset, u128> = HashMap::new();
+        let Ok(transfers) = self.preview.transfers(message) else {
+            return message
+                .walk()
+                .enumerate()
+                .filter_map(|(index, ix)| ix.err().map(|error| unresolved(message, index, error)))
+                .collect();
+        };
+        for transfer in transfers {
+            let key = (transfer.to, transfer.asset);
+            let to_destination = received.entry(key).or_default();
+            let was = *to_destination;
+            *to_destination += transfer.amount as u128;
+            let limit = self.destinations.get(&key).or_else(|| self.per_destination.get(&transfer.asset));
+            if let Some(&limit) = limit {
+                if *to_destination > limit as u128 && was <= limit as u128 {
+                    violations.push(PolicyViolation {
+                        instruction: Some(transfer.instruction),
+                        account: transfer.to,
+                        rule: PolicyRule::DestinationLimit {
+                            asset: transfer.asset,
+                            amount: *to_destination,
+                            limit,
+                        },
+                    });
+                }
+            }
+
+            let total = sent.entry(transfer.asset).or_default();
+            let was = *total;
+            *total += transfer.amount as u128;
+            if let Some(&limit) = self.aggregate.get(&transfer.asset) {
+                if *total > limit as u128 && was <= limit as u128 {
+                    violations.push(PolicyViolation {
+                        instruction: Some(transfer.instruction),
+                        account: transfer.from,
+                        rule: PolicyRule::AggregateLimit {
+                            asset: transfer.asset,
+                            amount: *total,
+                            limit,
+                        },
+                    });
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preflight::{self, CheckKind, CheckStatus, PreflightContext};
+    use crate::testkit;
+    use crate::transaction::TransactionBuilder;
+
+    const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+
+    #[test]
+    fn test_access_list_names_instruction() {
+        let tx = testkit::simple_transfer_tx(1);
+        let sanctioned = testkit::pubkey(2);
+        let violations = AccessList::new().block_account(sanctioned).evaluate(&tx.message);
+        assert_eq!(
+            violations,
+            vec![PolicyViolation {
+                instruction: Some(0),
+                account: sanctioned,
+                rule: PolicyRule::BlockedAccount,
+            }]
+        );
+
+        let allow_other = AccessList::new().allow_program(testkit::address(7));
+        assert_eq!(allow_other.evaluate(&tx.message)[0].rule, PolicyRule::ProgramNotAllowed);
+        assert!(AccessList::new().allow_program(SYSTEM_PROGRAM_ID).evaluate(&tx.message).is_empty());
+
+        let blocked = AccessList::new().block_program(SYSTEM_PROGRAM_ID);
+        let context = PreflightContext::new().policy(&blocked);
+        match preflight::check(&tx, &context).status(CheckKind::Policy) {
+            Some(CheckStatus::Fail(msg)) => assert!(msg.starts_with("instruction 0: program 1111"), "{}", msg),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_spending_limits() {
+        let payer = testkit::pubkey(1);
+        let (a, b) = (testkit::pubkey(2), testkit::pubkey(3));
+        let message = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(payer, a, 400))
+            .add_instruction(testkit::transfer_instruction(payer, b, 400))
+            .add_instruction(testkit::transfer_instruction(payer, a, 400))
+            .compile()
+            .unwrap();
+
+        let limits = SpendingLimits::new()
+            .per_destination(Asset::Sol, 500)
+            .destination(b, Asset::Sol, 100)
+            .aggregate(Asset::Sol, 1_000);
+        let violations = limits.evaluate(&message);
+        assert_eq!(violations.len(), 3);
+        assert_eq!((violations[0].instruction, violations[0].account), (Some(1), b));
+        assert_eq!(
+            violations[1].rule,
+            PolicyRule::DestinationLimit { asset: Asset::Sol, amount: 800, limit: 500 }
+        );
+        assert_eq!(
+            violations[2].to_string(),
+            "instruction 2: sends 1200 lamports in total, over the limit of 1000"
+        );
+        assert!(SpendingLimits::new().aggregate(Asset::Sol, 1_200).evaluate(&message).is_empty());
+    }
+
+    #[test]
+    fn test_builder_refuses_violations() {
+        let payer = testkit::pubkey(1);
+        let builder = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(payer, testkit::pubkey(2), 1));
+        assert!(builder.clone().policy(AccessList::new().allow_program(SYSTEM_PROGRAM_ID)).compile().is_ok());
+
+        // A closure as an in-house rule
+        let err = builder
+            .policy(|message: &CompiledMessage| {
+                vec![PolicyViolation {
+                    instruction: None,
+                    account: message.account_keys[0],
+                    rule: PolicyRule::Custom("payer not onboarded".to_string()),
+                }]
+            })
+            .build_unsigned()
+            .unwrap_err();
+        assert!(err.to_string().contains("payer not onboarded"), "{}", err);
+    }
+
+    #[test]
+    fn test_unresolvable_instruction_is_a_violation() {
+        let mut message = testkit::simple_transfer_tx(1).message;
+        message.instructions[0].program_id_index = 9;
+
+        let violations = AccessList::new().allow_program(SYSTEM_PROGRAM_ID).evaluate(&message);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].instruction, Some(0));
+        assert!(matches!(violations[0].rule, PolicyRule::Unresolved(_)));
+        assert!(violations[0].to_string().starts_with("instruction 0: cannot be checked"));
+
+        let violations = SpendingLimits::new().aggregate(Asset::Sol, u64::MAX).evaluate(&message);
+        assert_eq!(violations.len(), 1);
+        assert!(matches!(violations[0].rule, PolicyRule::Unresolved(_)));
+    }
+}
@@ -0,0 +1,250 @@
+//! Shared pool of reusable builders and buffers
+//!
+//! Services assembling thousands of transactions per second mostly pay for
+//! growing instruction vectors and serialization buffers. A [`BuilderPool`]
+//! hands out pre-warmed [`TransactionBuilder`]s and byte buffers from any
+//! thread and takes them back, cleared but with their capacity intact, when
+//! the guard drops. Paired with a per-thread
+//! [`CompileCache`](crate::cache::CompileCache), whose `serialize_message`
+//! borrows the builder, steady-state builds allocate nothing.
+
+use crate::transaction::{TransactionBuilder, MAX_TRANSACTION_SIZE};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Instruction capacity of pre-warmed builders
+const WARM_INSTRUCTIONS: usize = 8;
+
+/// Reuse counters
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PoolStats {
+    pub builder_reuses: u64,
+    pub builder_allocations: u64,
+    pub buffer_reuses: u64,
+    pub buffer_allocations: u64,
+}
+
+impl PoolStats {
+    /// Fraction of checkouts (builders and buffers) served from the pool
+    pub fn reuse_rate(&self) -> f64 {
+        let reuses = self.builder_reuses + self.buffer_reuses;
+        let total = reuses + self.builder_allocations + self.buffer_allocations;
+        if total == 0 {
+            0.0
+        } else {
+            reuses as f64 / total as f64
+        }
+    }
+}
+
+/// Thread-safe pool of [`TransactionBuilder`]s and serialization buffers
+pub struct BuilderPool {
+    builders: Mutex<Vec<TransactionBuilder>>,
+    buffers: Mutex<Vec<Vec<u8>>>,
+    /// Idle items kept per kind; extras are dropped on return
+    max_idle: usize,
+    builder_reuses: AtomicU64,
+    builder_allocations: AtomicU64,
+    buffer_reuses: AtomicU64,
+    buffer_allocations: AtomicU64,
+}
+
+impl BuilderPool {
+    /// Pool pre-warmed with `size` builders and buffers, keeping at most
+    /// `size` of each idle
+    pub fn new(size: usize) -> Self {
+        Self {
+            builders: Mutex::new((0..size).map(|_| warm_builder()).collect()),
+            buffers: Mutex::new((0..size).map(|_| Vec::with_capacity(MAX_TRANSACTION_SIZE)).collect()),
+            max_idle: size,
+            builder_reuses: AtomicU64::new(0),
+            builder_allocations: AtomicU64::new(0),
+            buffer_reuses: AtomicU64::new(0),
+            buffer_allocations: AtomicU64::new(0),
+        }
+    }
+
+    /// Check out an empty builder
+    pub fn builder(&self) -> PooledBuilder<'_> {
+        let builder = match self.builders.lock().unwrap_or_else(|e| e.into_inner()).pop() {
+            Some(builder) => {
+                self.builder_reuses.fetch_add(1, Ordering::Relaxed);
+                builder
+            }
+            None => {
+                self.builder_allocations.fetch_add(1, Ordering::Relaxed);
+                warm_builder()
+            }
+        };
+        PooledBuilder { pool: self, builder }
+    }
+
+    /// Check out an empty buffer with room for a maximum-size transaction
+    pub fn buffer(&self) -> PooledBuffer<'_> {
+        let buffer = match self.buffers.lock().unwrap_or_else(|e| e.into_inner()).pop() {
+            Some(buffer) => {
+                self.buffer_reuses.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.buffer_allocations.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(MAX_TRANSACTION_SIZE)
+            }
+        };
+        PooledBuffer { pool: self, buffer }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            builder_reuses: self.builder_reuses.load(Ordering::Relaxed),
+            builder_allocations: self.builder_allocations.load(Ordering::Relaxed),
+            buffer_reuses: self.buffer_reuses.load(Ordering::Relaxed),
+            buffer_allocations: self.buffer_allocations.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Idle (builders, buffers) currently in the pool
+    pub fn idle(&self) -> (usize, usize) {
+        (
+            self.builders.lock().unwrap_or_else(|e| e.into_inner()).len(),
+            self.buffers.lock().unwrap_or_else(|e| e.into_inner()).len(),
+        )
+    }
+}
+
+fn warm_builder() -> TransactionBuilder {
+    let mut builder = TransactionBuilder::new();
+    builder.instructions.reserve(WARM_INSTRUCTIONS);
+    builder
+}
+
+/// Builder checked out of a [`BuilderPool`], returned on drop
+pub struct PooledBuilder<'a> {
+    pool: &'a BuilderPool,
+    builder: TransactionBuilder,
+}
+
+impl PooledBuilder<'_> {
+    /// Apply consuming builder methods in place, keeping the allocation
+    pub fn update(&mut self, f: impl FnOnce(TransactionBuilder) -> TransactionBuilder) -> &mut Self {
+        self.builder = f(std::mem::take(&mut self.builder));
+        self
+    }
+}
+
+impl Deref for PooledBuilder<'_> {
+    type Target = TransactionBuilder;
+
+    fn deref(&self) -> &TransactionBuilder {
+        &self.builder
+    }
+}
+
+impl DerefMut for PooledBuilder<'_> {
+    fn deref_mut(&mut self) -> &mut TransactionBuilder {
+        &mut self.builder
+    }
+}
+
+impl Drop for PooledBuilder<'_> {
+    fn drop(&mut self) {
+        let mut builder = std::mem::take(&mut self.builder);
+        builder.instructions.clear();
+        builder.payer = None;
+        builder.recent_blockhash = None;
+        builder.role_merge_policy = Default::default();
+        builder.dedupe_signers = false;
+        builder.additional_signers.clear();
+        builder.limits = Default::default();
+        builder.policies.clear();
+
+        let mut idle = self.pool.builders.lock().unwrap_or_else(|e| e.into_inner());
+        if idle.len() < self.pool.max_idle {
+            idle.push(builder);
+        }
+    }
+}
+
+/// Buffer checked out of a [`BuilderPool`], returned on drop
+pub struct PooledBuffer<'a> {
+    pool: &'a BuilderPool,
+    buffer: Vec<u8>,
+}
+
+impl Deref for PooledBuffer<'_> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer<'_> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+}
+
+impl Drop for PooledBuffer<'_> {
+    fn drop(&mut self) {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.clear();
+
+        let mut idle = self.pool.buffers.lock().unwrap_or_else(|e| e.into_inner());
+        if idle.len() < self.pool.max_idle {
+            idle.push(buffer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CompileCache;
+    use crate::instruction::InstructionEncoder;
+
+    #[test]
+    fn test_pooled_build_reuses_allocations() {
+        let pool = BuilderPool::new(1);
+        let mut cache = CompileCache::new();
+        let ix = InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(1).build();
+
+        let mut allocations = Vec::new();
+        for _ in 0..3 {
+            let mut builder = pool.builder();
+            builder.update(|b| b.payer([1u8; 32]).recent_blockhash([3u8; 32]).add_instruction(ix.clone()));
+            let mut buffer = pool.buffer();
+            cache.serialize_message(&builder, &mut buffer).unwrap();
+            assert_eq!(*buffer, builder.clone().build_unsigned().unwrap().message_bytes().unwrap());
+            allocations.push((builder.instructions.as_ptr(), buffer.as_ptr()));
+        }
+        assert!(allocations.windows(2).all(|w| w[0] == w[1]));
+
+        let stats = pool.stats();
+        assert_eq!((stats.builder_reuses, stats.builder_allocations), (3, 0));
+        assert_eq!(stats.reuse_rate(), 1.0);
+        assert!(pool.builder().instructions.is_empty());
+    }
+
+    #[test]
+    fn test_pool_across_threads() {
+        let pool = BuilderPool::new(2);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        let mut builder = pool.builder();
+                        builder.update(|b| b.payer([1u8; 32]));
+                        let _buffer = pool.buffer();
+                    }
+                });
+            }
+        });
+
+        let stats = pool.stats();
+        assert_eq!(stats.builder_reuses + stats.builder_allocations, 40);
+        let (builders, buffers) = pool.idle();
+        assert!(builders <= 2 && buffers <= 2);
+    }
+}
@@ -0,0 +1,287 @@
+//! Preflight checks before submission
+//!
+//! [`check`] runs every local check a service would want before sending a
+//! transaction (message sanity, size, signatures, blockhash freshness, fee
+//! adequacy and an optional security scan) and collects the outcomes in one
+//! [`PreflightReport`]. Checks whose inputs are missing from the
+//! [`PreflightContext`] are reported as skipped rather than passed.
+
+use crate::fee_calculator::{compute_budget, nearest_rank};
+use crate::policy::Policy;
+use crate::sigverify::verify_strict;
+use crate::transaction::{CompiledTransaction, MAX_TRANSACTION_SIZE};
+
+/// Warn when fewer blocks than this remain before the blockhash expires
+pub const FRESHNESS_WARN_BLOCKS: u64 = 20;
+
+/// Security scan hook: returns one message per finding
+pub type SecurityScan<'a> = &'a dyn Fn(&CompiledTransaction) -> Vec<String>;
+
+/// Inputs for the checks that need chain state
+#[derive(Clone, Copy)]
+pub struct PreflightContext<'a> {
+    /// (current block height, last valid block height of the blockhash)
+    pub block_heights: Option<(u64, u64)>,
+    /// Recent CU prices (microlamports) to compare against
+    pub recent_fees: Option<&'a [u64]>,
+    /// Percentile of `recent_fees` the transaction's CU price should reach
+    pub fee_percentile: u8,
+    pub max_size: usize,
+    pub security_scan: Option<SecurityScan<'a>>,
+    pub policy: Option<&'a dyn Policy>,
+}
+
+impl Default for PreflightContext<'_> {
+    fn default() -> Self {
+        Self {
+            block_heights: None,
+            recent_fees: None,
+            fee_percentile: 50,
+            max_size: MAX_TRANSACTION_SIZE,
+            security_scan: None,
+            policy: None,
+        }
+    }
+}
+
+impl<'a> PreflightContext<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn block_heights(mut self, current: u64, last_valid: u64) -> Self {
+        self.block_heights = Some((current, last_valid));
+        self
+    }
+
+    pub fn recent_fees(mut self, fees: &'a [u64], percentile: u8) -> Self {
+        self.recent_fees = Some(fees);
+        self.fee_percentile = percentile;
+        self
+    }
+
+    pub fn security_scan(mut self, scan: SecurityScan<'a>) -> Self {
+        self.security_scan = Some(scan);
+        self
+    }
+
+    pub fn policy(mut self, policy: &'a dyn Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+}
+
+/// Individual preflight check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckKind {
+    Sanitize,
+    Size,
+    Signatures,
+    BlockhashFreshness,
+    FeeAdequacy,
+    Security,
+    Policy,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckStatus {
+    Pass,
+    Warn(String),
+    Fail(String),
+    Skipped,
+}
+
+/// Outcome of every check, in the order run
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightReport {
+    pub results: Vec<(CheckKind, CheckStatus)>,
+}
+
+impl PreflightReport {
+    /// No check failed (warnings and skips allowed)
+    pub fn passed(&self) -> bool {
+        !self.results.iter().any(|(_, s)| matches!(s, CheckStatus::Fail(_)))
+    }
+
+    pub fn status(&self, kind: CheckKind) -> Option<&CheckStatus> {
+        self.results.iter().find(|(k, _)| *k == kind).map(|(_, s)| s)
+    }
+
+    /// Failure messages, prefixed by check
+    pub fn failures(&self) -> Vec<String> {
+        self.results
+            .iter()
+            .filter_map(|(kind, status)| match status {
+                CheckStatus::Fail(msg) => Some(format!("{:?}: {}", kind, msg)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Run all preflight checks against `transaction`
+pub fn check(transaction: &CompiledTransaction, context: &PreflightContext<'_>) -> PreflightReport {
+    PreflightReport {
+        results: vec![
+            (CheckKind::Sanitize, check_sanitize(transaction)),
+            (CheckKind::Size, check_size(transaction, context.max_size)),
+            (CheckKind::Signatures, check_signatures(transaction)),
+            (CheckKind::BlockhashFreshness, check_freshness(context.block_heights)),
+            (CheckKind::FeeAdequacy, check_fee(transaction, context)),
+            (CheckKind::Security, check_security(transaction, context.security_scan)),
+            (CheckKind::Policy, check_policy(transaction, context.policy)),
+        ],
+    }
+}
+
+fn check_sanitize(transaction: &CompiledTransaction) -> CheckStatus {
+    match transaction.message.header.validate(&transaction.message) {
+        Ok(()) => CheckStatus::Pass,
+        Err(err) => CheckStatus::Fail(err.to_string()),
+    }
+}
+
+fn check_size(transaction: &CompiledTransaction, max_size: usize) -> CheckStatus {
+    let size = transaction.size();
+    if size > max_size {
+        CheckStatus::Fail(format!("{} bytes exceeds {} byte limit", size, max_size))
+    } else {
+        CheckStatus::Pass
+    }
+}
+
+fn check_signatures(transaction: &CompiledTransaction) -> CheckStatus {
+    let signers = transaction.required_signers();
+    if transaction.signatures.len() != signers.len() {
+        return CheckStatus::Fail(format!(
+            "{} signatures for {} required signers",
+            transaction.signatures.len(),
+            signers.len()
+        ));
+    }
+    let message = match transaction.message_bytes() {
+        Ok(message) => message,
+        Err(err) => return CheckStatus::Fail(err.to_string()),
+    };
+
+    let mut problems = Vec::new();
+    for (key, signature) in signers.iter().zip(&transaction.signatures) {
+        let signer = bs58::encode(key).into_string();
+        if *signature == [0u8; 64] {
+            problems.push(format!("{} has not signed", signer));
+        } else if let Err(err) = verify_strict(key, &message, signature) {
+            problems.push(err.to_string());
+        }
+    }
+    if problems.is_empty() {
+        CheckStatus::Pass
+    } else {
+        CheckStatus::Fail(problems.join("; "))
+    }
+}
+
+fn check_freshness(block_heights: Option<(u64, u64)>) -> CheckStatus {
+    let Some((current, last_valid)) = block_heights else {
+        return CheckStatus::Skipped;
+    };
+    if current > last_valid {
+        CheckStatus::Fail(format!(
+            "Blockhash expired at height {}, now {}",
+            last_valid, current
+        ))
+    } else if last_valid - current < FRESHNESS_WARN_BLOCKS {
+        CheckStatus::Warn(format!("Only {} blocks left before expiry", last_valid - current))
+    } else {
+        CheckStatus::Pass
+    }
+}
+
+fn check_fee(transaction: &CompiledTransaction, context: &PreflightContext<'_>) -> CheckStatus {
+    let Some(target) = context
+        .recent_fees
+        .and_then(|fees| nearest_rank(fees, context.fee_percentile))
+    else {
+        return CheckStatus::Skipped;
+    };
+    let price = compute_budget::requested_budget(&transaction.message)
+        .unit_price
+        .unwrap_or(0);
+    if price < target {
+        CheckStatus::Warn(format!(
+            "CU price {} is below the p{} recent price {}",
+            price, context.fee_percentile, target
+        ))
+    } else {
+        CheckStatus::Pass
+    }
+}
+
+fn check_security(transaction: &CompiledTransaction, scan: Option<SecurityScan<'_>>) -> CheckStatus {
+    let Some(scan) = scan else {
+        return CheckStatus::Skipped;
+    };
+    let findings = scan(transaction);
+    if findings.is_empty() {
+        CheckStatus::Pass
+    } else {
+        CheckStatus::Fail(findings.join("; "))
+    }
+}
+
+fn check_policy(transaction: &CompiledTransaction, policy: Option<&dyn Policy>) -> CheckStatus {
+    let Some(policy) = policy else {
+        return CheckStatus::Skipped;
+    };
+    let violations: Vec<String> = policy.evaluate(&transaction.message).iter().map(|v| v.to_string()).collect();
+    if violations.is_empty() {
+        CheckStatus::Pass
+    } else {
+        CheckStatus::Fail(violations.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    fn signed(payer: &Keypair) -> CompiledTransaction {
+        TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).append_u8(1).build())
+            .build_and_sign(&[payer])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_all_checks_pass_or_skip() {
+        let tx = signed(&Keypair::new());
+        let report = check(&tx, &PreflightContext::new());
+        assert!(report.passed());
+        assert_eq!(report.status(CheckKind::Signatures), Some(&CheckStatus::Pass));
+        assert_eq!(report.status(CheckKind::FeeAdequacy), Some(&CheckStatus::Skipped));
+    }
+
+    #[test]
+    fn test_report_collects_problems() {
+        let mut tx = signed(&Keypair::new());
+        tx.signatures[0][0] ^= 1;
+        let fees = [10, 500, 1_000];
+        let scan = |_: &CompiledTransaction| vec!["writable program account".to_string()];
+        let context = PreflightContext::new()
+            .block_heights(120, 100)
+            .recent_fees(&fees, 50)
+            .security_scan(&scan);
+
+        let report = check(&tx, &context);
+        assert!(!report.passed());
+        assert_eq!(report.failures().len(), 3);
+        assert!(matches!(report.status(CheckKind::Signatures), Some(CheckStatus::Fail(_))));
+        assert!(matches!(report.status(CheckKind::FeeAdequacy), Some(CheckStatus::Warn(_))));
+        assert!(matches!(report.status(CheckKind::BlockhashFreshness), Some(CheckStatus::Fail(_))));
+    }
+}
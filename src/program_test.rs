@@ -0,0 +1,84 @@
+//! Running compiled transactions against `solana-program-test`
+//!
+//! [`process`] and [`process_v0`] hand a compiled transaction to a
+//! `BanksClient`, converting through [`crate::interop`] so byte-level-built
+//! transactions run against a local runtime inside `cargo test`:
+//!
+//! ```ignore
+//! let (mut banks_client, payer, blockhash) = ProgramTest::default().start().await;
+//! let tx = build_with_txasm(&payer, blockhash);
+//! txasm::program_test::process(&mut banks_client, &tx).await?;
+//! ```
+//!
+//! Enabled with the `program-test` feature.
+
+use crate::error::{Result, TxAsmError};
+use crate::transaction::CompiledTransaction;
+use crate::versioned::CompiledTransactionV0;
+use solana_program_test::{BanksClient, BanksClientError};
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Process a legacy transaction, failing if it does not execute successfully
+pub async fn process(banks_client: &mut BanksClient, transaction: &CompiledTransaction) -> Result<()> {
+    submit(banks_client, transaction.to_versioned_transaction()?).await
+}
+
+/// Process a V0 transaction whose lookup tables exist in the test bank
+pub async fn process_v0(banks_client: &mut BanksClient, transaction: &CompiledTransactionV0) -> Result<()> {
+    submit(banks_client, transaction.to_versioned_transaction()?).await
+}
+
+async fn submit(banks_client: &mut BanksClient, transaction: VersionedTransaction) -> Result<()> {
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|e: BanksClientError| TxAsmError::SolanaError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::legacy_to_v0;
+    use crate::testkit;
+    use crate::transaction::TransactionBuilder;
+    use solana_program_test::{tokio, ProgramTest};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::Signer;
+
+    #[test]
+    fn test_process_transfers() {
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let (mut banks_client, payer, blockhash) = ProgramTest::default().start().await;
+            let recipient = testkit::address(1);
+            let transfer = |lamports| {
+                TransactionBuilder::new()
+                    .payer_pubkey(&payer.pubkey())
+                    .recent_blockhash(blockhash.to_bytes())
+                    .add_instruction(testkit::transfer_instruction(payer.pubkey().to_bytes(), recipient, lamports))
+            };
+
+            let legacy = transfer(1_000_000).build_and_sign(&[&payer]).unwrap();
+            process(&mut banks_client, &legacy).await.unwrap();
+
+            let (mut v0, _) = legacy_to_v0(&transfer(2_000_000).build_unsigned().unwrap(), &[]).unwrap();
+            let signature = payer.sign_message(&v0.message_bytes().unwrap());
+            v0.signatures[0].copy_from_slice(signature.as_ref());
+            process_v0(&mut banks_client, &v0).await.unwrap();
+
+            let balance = banks_client.get_balance(Pubkey::new_from_array(recipient)).await.unwrap();
+            assert_eq!(balance, 3_000_000);
+
+            let unfunded = testkit::keypair(9);
+            let overdraft = TransactionBuilder::new()
+                .payer_pubkey(&unfunded.pubkey())
+                .recent_blockhash(blockhash.to_bytes())
+                .add_instruction(testkit::transfer_instruction(unfunded.pubkey().to_bytes(), recipient, 1))
+                .build_and_sign(&[&unfunded])
+                .unwrap();
+            assert!(matches!(
+                process(&mut banks_client, &overdraft).await,
+                Err(TxAsmError::SolanaError(_))
+            ));
+        });
+    }
+}
@@ -0,0 +1,11 @@
+//! Instruction builders for well-known programs
+//!
+//! Each submodule encodes one program's instructions with
+//! [`InstructionEncoder`](crate::instruction::InstructionEncoder), matching
+//! the program's account order and data layout byte for byte.
+
+pub mod bpf_loader_upgradeable;
+pub mod bubblegum;
+
+#[cfg(feature = "metaplex")]
+pub mod token_metadata;
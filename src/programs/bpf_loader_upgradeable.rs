@@ -0,0 +1,247 @@
+//! Upgradeable BPF loader instructions
+//!
+//! Builders for the buffer / deploy / upgrade lifecycle, plus the chunking
+//! math deployment tools need: a program is written into a buffer account
+//! with as many `Write` instructions as it takes, each sized to fill one
+//! transaction, then deployed or upgraded from that buffer.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::{InstructionEncoder, RawInstruction};
+use crate::transaction::{TransactionBuilder, MAX_TRANSACTION_SIZE};
+use solana_sdk::pubkey::Pubkey;
+
+/// Upgradeable BPF loader address
+pub const ID: [u8; 32] = solana_sdk::bpf_loader_upgradeable::ID.to_bytes();
+
+/// Buffer account header: state tag + optional authority
+pub const BUFFER_METADATA_SIZE: usize = 37;
+
+/// Program data account header: state tag + slot + optional authority
+pub const PROGRAMDATA_METADATA_SIZE: usize = 45;
+
+/// Program account size: state tag + program data address
+pub const PROGRAM_ACCOUNT_SIZE: usize = 36;
+
+const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+const RENT_SYSVAR_ID: [u8; 32] = solana_sdk::sysvar::rent::ID.to_bytes();
+const CLOCK_SYSVAR_ID: [u8; 32] = solana_sdk::sysvar::clock::ID.to_bytes();
+
+const INITIALIZE_BUFFER: u32 = 0;
+const WRITE: u32 = 1;
+const DEPLOY_WITH_MAX_DATA_LEN: u32 = 2;
+const UPGRADE: u32 = 3;
+const SET_AUTHORITY: u32 = 4;
+const CLOSE: u32 = 5;
+
+/// Buffer account size for a program of `program_len` bytes
+pub fn buffer_len(program_len: usize) -> usize {
+    BUFFER_METADATA_SIZE + program_len
+}
+
+/// Program data account size for `max_data_len` bytes of program
+pub fn programdata_len(max_data_len: usize) -> usize {
+    PROGRAMDATA_METADATA_SIZE + max_data_len
+}
+
+/// Program data address derived from the program address
+pub fn programdata_address(program: &[u8; 32]) -> [u8; 32] {
+    let (address, _) = Pubkey::find_program_address(&[program], &Pubkey::new_from_array(ID));
+    address.to_bytes()
+}
+
+/// Initialize a created buffer account, optionally with a write authority
+pub fn initialize_buffer(buffer: [u8; 32], authority: Option<[u8; 32]>) -> RawInstruction {
+    let encoder = InstructionEncoder::new(ID).writable(buffer, false);
+    let encoder = match authority {
+        Some(authority) => encoder.readonly(authority),
+        None => encoder,
+    };
+    encoder.append_u32(INITIALIZE_BUFFER).build()
+}
+
+/// Write `bytes` into the buffer at `offset` (relative to the program bytes)
+pub fn write(buffer: [u8; 32], authority: [u8; 32], offset: u32, bytes: &[u8]) -> RawInstruction {
+    InstructionEncoder::new(ID)
+        .writable(buffer, false)
+        .signer(authority, false)
+        .append_u32(WRITE)
+        .append_u32(offset)
+        .append_u64(bytes.len() as u64)
+        .append_data(bytes)
+        .build()
+}
+
+/// Largest `Write` payload that fits one transaction paid by `payer`
+///
+/// Measured by compiling a `Write` with an empty payload, as the Solana
+/// CLI does; one byte is held back for the data length prefix growing to
+/// two bytes once the payload passes 127 bytes.
+pub fn max_write_chunk_size(payer: [u8; 32], buffer: [u8; 32], authority: [u8; 32]) -> Result<usize> {
+    let baseline = TransactionBuilder::new()
+        .payer(payer)
+        .recent_blockhash([0u8; 32])
+        .add_instruction(write(buffer, authority, 0, &[]))
+        .build_unsigned()?;
+    Ok(MAX_TRANSACTION_SIZE.saturating_sub(baseline.size()).saturating_sub(1))
+}
+
+/// Split `program` into `Write` instructions of at most `chunk_size` bytes
+pub fn write_chunks(
+    buffer: [u8; 32],
+    authority: [u8; 32],
+    program: &[u8],
+    chunk_size: usize,
+) -> Result<Vec<RawInstruction>> {
+    if chunk_size == 0 {
+        return Err(TxAsmError::InvalidInstruction("Chunk size must be positive".to_string()));
+    }
+    program
+        .chunks(chunk_size)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let offset = u32::try_from(i * chunk_size).map_err(|_| {
+                TxAsmError::InvalidInstruction("Program exceeds u32 offsets".to_string())
+            })?;
+            Ok(write(buffer, authority, offset, chunk))
+        })
+        .collect()
+}
+
+/// Deploy the program in `buffer` to an already created `program` account
+///
+/// The program data account is derived from `program` and funded by `payer`.
+pub fn deploy_with_max_data_len(
+    payer: [u8; 32],
+    program: [u8; 32],
+    buffer: [u8; 32],
+    upgrade_authority: [u8; 32],
+    max_data_len: usize,
+) -> RawInstruction {
+    InstructionEncoder::new(ID)
+        .signer(payer, true)
+        .writable(programdata_address(&program), false)
+        .writable(program, false)
+        .writable(buffer, false)
+        .readonly(RENT_SYSVAR_ID)
+        .readonly(CLOCK_SYSVAR_ID)
+        .readonly(SYSTEM_PROGRAM_ID)
+        .signer(upgrade_authority, false)
+        .append_u32(DEPLOY_WITH_MAX_DATA_LEN)
+        .append_u64(max_data_len as u64)
+        .build()
+}
+
+/// Replace `program`'s code with the contents of `buffer`
+///
+/// The buffer's lamports are sent to `spill`.
+pub fn upgrade(program: [u8; 32], buffer: [u8; 32], upgrade_authority: [u8; 32], spill: [u8; 32]) -> RawInstruction {
+    InstructionEncoder::new(ID)
+        .writable(programdata_address(&program), false)
+        .writable(program, false)
+        .writable(buffer, false)
+        .writable(spill, false)
+        .readonly(RENT_SYSVAR_ID)
+        .readonly(CLOCK_SYSVAR_ID)
+        .signer(upgrade_authority, false)
+        .append_u32(UPGRADE)
+        .build()
+}
+
+/// Change the authority of a buffer or program data account
+///
+/// `None` makes a program immutable; buffers always need an authority.
+pub fn set_authority(account: [u8; 32], current_authority: [u8; 32], new_authority: Option<[u8; 32]>) -> RawInstruction {
+    let encoder = InstructionEncoder::new(ID)
+        .writable(account, false)
+        .signer(current_authority, false);
+    let encoder = match new_authority {
+        Some(authority) => encoder.readonly(authority),
+        None => encoder,
+    };
+    encoder.append_u32(SET_AUTHORITY).build()
+}
+
+/// Close a buffer or program data account, sending its lamports to `recipient`
+///
+/// Closing program data also requires the `program` account.
+pub fn close(
+    account: [u8; 32],
+    recipient: [u8; 32],
+    authority: Option<[u8; 32]>,
+    program: Option<[u8; 32]>,
+) -> RawInstruction {
+    let mut encoder = InstructionEncoder::new(ID)
+        .writable(account, false)
+        .writable(recipient, false);
+    if let Some(authority) = authority {
+        encoder = encoder.signer(authority, false);
+    }
+    if let Some(program) = program {
+        encoder = encoder.writable(program, false);
+    }
+    encoder.append_u32(CLOSE).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::bpf_loader_upgradeable as sdk;
+
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn assert_matches_sdk(ours: RawInstruction, theirs: solana_sdk::instruction::Instruction) {
+        let theirs = RawInstruction::from_sdk_instruction(&theirs.program_id, &theirs.accounts, &theirs.data);
+        assert_eq!(ours.program_id, theirs.program_id);
+        assert_eq!(ours.accounts, theirs.accounts);
+        assert_eq!(ours.data, theirs.data);
+    }
+
+    #[test]
+    fn test_matches_sdk_encoding() {
+        let (buffer, authority, program, spill) = (key(1), key(2), key(3), key(4));
+        assert_matches_sdk(
+            write(buffer.to_bytes(), authority.to_bytes(), 64, &[7, 8, 9]),
+            sdk::write(&buffer, &authority, 64, vec![7, 8, 9]),
+        );
+        assert_matches_sdk(
+            upgrade(program.to_bytes(), buffer.to_bytes(), authority.to_bytes(), spill.to_bytes()),
+            sdk::upgrade(&program, &buffer, &authority, &spill),
+        );
+        assert_matches_sdk(
+            set_authority(buffer.to_bytes(), authority.to_bytes(), Some(spill.to_bytes())),
+            sdk::set_buffer_authority(&buffer, &authority, &spill),
+        );
+        assert_matches_sdk(
+            close(buffer.to_bytes(), spill.to_bytes(), Some(authority.to_bytes()), None),
+            sdk::close(&buffer, &spill, &authority),
+        );
+
+        #[allow(deprecated)]
+        let sdk_deploy = sdk::deploy_with_max_program_len(&key(5), &program, &buffer, &authority, 1, 4096).unwrap();
+        assert_matches_sdk(
+            deploy_with_max_data_len(key(5).to_bytes(), program.to_bytes(), buffer.to_bytes(), authority.to_bytes(), 4096),
+            sdk_deploy.into_iter().last().unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_write_chunks_fill_transactions() {
+        let (payer, buffer, authority) = ([1u8; 32], [2u8; 32], [1u8; 32]);
+        let chunk_size = max_write_chunk_size(payer, buffer, authority).unwrap();
+        let program = vec![0xab; chunk_size * 2 + 10];
+        let chunks = write_chunks(buffer, authority, &program, chunk_size).unwrap();
+        assert_eq!(chunks.len(), 3);
+
+        let full = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([0u8; 32])
+            .add_instruction(chunks[1].clone())
+            .build_unsigned()
+            .unwrap();
+        assert!(full.size() <= MAX_TRANSACTION_SIZE);
+        assert_eq!(&chunks[1].data[4..8], &(chunk_size as u32).to_le_bytes());
+        assert_eq!(buffer_len(program.len()), program.len() + 37);
+    }
+}
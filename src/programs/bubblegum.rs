@@ -0,0 +1,200 @@
+//! Compressed NFT (Bubblegum) instructions
+//!
+//! Compressed NFTs live as leaves of a concurrent merkle tree, so every
+//! transfer or burn carries the leaf's merkle proof as trailing readonly
+//! accounts. The top `canopy_depth` levels of the proof are cached on chain
+//! and must be left out; even then the proof often dominates the
+//! transaction, which is why [`max_proof_len`] reports how much fits in a
+//! legacy transaction (beyond that, put the proof in a lookup table).
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::{AccountMeta, InstructionEncoder, RawInstruction};
+use crate::transaction::{TransactionBuilder, MAX_TRANSACTION_SIZE};
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+
+/// Bubblegum program address
+pub const ID: [u8; 32] = solana_sdk::pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY").to_bytes();
+
+/// SPL account compression program address
+pub const COMPRESSION_PROGRAM_ID: [u8; 32] =
+    solana_sdk::pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK").to_bytes();
+
+/// SPL noop (log wrapper) program address
+pub const NOOP_PROGRAM_ID: [u8; 32] = solana_sdk::pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV").to_bytes();
+
+const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+
+/// Bytes each proof node adds to a transaction: key plus account index
+const PROOF_NODE_SIZE: usize = 33;
+
+/// Leaf being transferred or burned, as returned by the DAS API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeafInfo {
+    /// Current tree root the proof was generated against
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+}
+
+impl LeafInfo {
+    fn encode(&self, name: &str) -> Vec<u8> {
+        let mut data = Vec::with_capacity(8 + 96 + 12);
+        data.extend_from_slice(&Sha256::digest(format!("global:{}", name).as_bytes())[..8]);
+        data.extend_from_slice(&self.root);
+        data.extend_from_slice(&self.data_hash);
+        data.extend_from_slice(&self.creator_hash);
+        data.extend_from_slice(&self.nonce.to_le_bytes());
+        data.extend_from_slice(&self.index.to_le_bytes());
+        data
+    }
+}
+
+/// Tree authority (config) address for `merkle_tree`
+pub fn tree_authority(merkle_tree: &[u8; 32]) -> [u8; 32] {
+    Pubkey::find_program_address(&[merkle_tree], &Pubkey::new_from_array(ID)).0.to_bytes()
+}
+
+/// Drop the proof nodes the tree's canopy already stores
+///
+/// `proof` is ordered leaf to root and may be the full `max_depth` nodes
+/// or already trimmed.
+pub fn trim_proof(proof: &[[u8; 32]], max_depth: usize, canopy_depth: usize) -> Result<&[[u8; 32]]> {
+    let needed = max_depth.checked_sub(canopy_depth).ok_or_else(|| {
+        TxAsmError::InvalidInstruction(format!(
+            "Canopy depth {} exceeds tree depth {}",
+            canopy_depth, max_depth
+        ))
+    })?;
+    if proof.len() < needed {
+        return Err(TxAsmError::InvalidInstruction(format!(
+            "Proof has {} nodes, tree needs {}",
+            proof.len(),
+            needed
+        )));
+    }
+    Ok(&proof[..needed])
+}
+
+/// Accounts shared by transfer and burn, up to the merkle tree
+fn leaf_accounts(merkle_tree: [u8; 32], leaf_owner: [u8; 32], leaf_delegate: [u8; 32], delegate_signs: bool) -> InstructionEncoder {
+    InstructionEncoder::new(ID)
+        .readonly(tree_authority(&merkle_tree))
+        .account(AccountMeta::new_readonly(leaf_owner, !delegate_signs))
+        .account(AccountMeta::new_readonly(leaf_delegate, delegate_signs))
+}
+
+fn with_proof(encoder: InstructionEncoder, proof: &[[u8; 32]]) -> InstructionEncoder {
+    let encoder = encoder
+        .readonly(NOOP_PROGRAM_ID)
+        .readonly(COMPRESSION_PROGRAM_ID)
+        .readonly(SYSTEM_PROGRAM_ID);
+    proof.iter().fold(encoder, |encoder, node| encoder.readonly(*node))
+}
+
+/// Transfer a compressed NFT to `new_leaf_owner`
+///
+/// The owner signs unless `delegate_signs`. `proof` must already be
+/// trimmed to the canopy (see [`trim_proof`]).
+pub fn transfer(
+    merkle_tree: [u8; 32],
+    leaf_owner: [u8; 32],
+    leaf_delegate: [u8; 32],
+    delegate_signs: bool,
+    new_leaf_owner: [u8; 32],
+    leaf: &LeafInfo,
+    proof: &[[u8; 32]],
+) -> RawInstruction {
+    let encoder = leaf_accounts(merkle_tree, leaf_owner, leaf_delegate, delegate_signs)
+        .readonly(new_leaf_owner)
+        .writable(merkle_tree, false);
+    with_proof(encoder, proof).data(leaf.encode("transfer")).build()
+}
+
+/// Burn a compressed NFT
+pub fn burn(
+    merkle_tree: [u8; 32],
+    leaf_owner: [u8; 32],
+    leaf_delegate: [u8; 32],
+    delegate_signs: bool,
+    leaf: &LeafInfo,
+    proof: &[[u8; 32]],
+) -> RawInstruction {
+    let encoder = leaf_accounts(merkle_tree, leaf_owner, leaf_delegate, delegate_signs).writable(merkle_tree, false);
+    with_proof(encoder, proof).data(leaf.encode("burn")).build()
+}
+
+/// Proof nodes a legacy transaction paid by the leaf owner can carry
+/// alongside one transfer
+pub fn max_proof_len(merkle_tree: [u8; 32], leaf_owner: [u8; 32], new_leaf_owner: [u8; 32]) -> Result<usize> {
+    let leaf = LeafInfo {
+        root: [0u8; 32],
+        data_hash: [0u8; 32],
+        creator_hash: [0u8; 32],
+        nonce: 0,
+        index: 0,
+    };
+    let baseline = TransactionBuilder::new()
+        .payer(leaf_owner)
+        .recent_blockhash([0u8; 32])
+        .add_instruction(transfer(merkle_tree, leaf_owner, leaf_owner, false, new_leaf_owner, &leaf, &[]))
+        .build_unsigned()?;
+    Ok(MAX_TRANSACTION_SIZE.saturating_sub(baseline.size()) / PROOF_NODE_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf() -> LeafInfo {
+        LeafInfo {
+            root: [9u8; 32],
+            data_hash: [8u8; 32],
+            creator_hash: [7u8; 32],
+            nonce: 42,
+            index: 42,
+        }
+    }
+
+    #[test]
+    fn test_transfer_layout() {
+        let proof: Vec<[u8; 32]> = (0..14u8).map(|i| [100 + i; 32]).collect();
+        let trimmed = trim_proof(&proof, 14, 10).unwrap();
+        assert_eq!(trimmed.len(), 4);
+        assert!(trim_proof(&proof[..3], 14, 10).is_err());
+        assert!(trim_proof(&proof, 14, 15).is_err());
+
+        let ix = transfer([1u8; 32], [2u8; 32], [2u8; 32], false, [3u8; 32], &leaf(), trimmed);
+        assert_eq!(&ix.data[..8], &[163, 52, 200, 231, 140, 3, 69, 186]);
+        assert_eq!(ix.data.len(), 8 + 96 + 12);
+        assert_eq!(ix.accounts.len(), 8 + 4);
+        assert!(ix.accounts[1].is_signer && !ix.accounts[2].is_signer);
+        assert!(ix.accounts[4].is_writable);
+        assert_eq!(ix.accounts[8].pubkey, proof[0]);
+    }
+
+    #[test]
+    fn test_max_proof_len_fits() {
+        let max = max_proof_len([1u8; 32], [2u8; 32], [3u8; 32]).unwrap();
+        let proof: Vec<[u8; 32]> = (0..max as u8).map(|i| [100 + i; 32]).collect();
+        let tx = |proof: &[[u8; 32]]| {
+            TransactionBuilder::new()
+                .payer([2u8; 32])
+                .recent_blockhash([0u8; 32])
+                .add_instruction(transfer([1u8; 32], [2u8; 32], [2u8; 32], false, [3u8; 32], &leaf(), proof))
+                .build_unsigned()
+                .unwrap()
+        };
+        assert!(tx(&proof).size() <= MAX_TRANSACTION_SIZE);
+
+        let mut longer = proof.clone();
+        longer.push([255u8; 32]);
+        assert!(tx(&longer).size() > MAX_TRANSACTION_SIZE);
+
+        let ix = burn([1u8; 32], [2u8; 32], [4u8; 32], true, &leaf(), &proof);
+        assert_eq!(&ix.data[..8], &[116, 110, 29, 56, 107, 219, 42, 93]);
+        assert!(ix.accounts[2].is_signer);
+    }
+}
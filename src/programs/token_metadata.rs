@@ -0,0 +1,291 @@
+//! Metaplex Token Metadata instructions
+//!
+//! Builders for creating and updating metadata accounts and verifying
+//! collection membership, with the program's Borsh argument layouts and
+//! PDA derivations.
+//!
+//! Enabled with the `metaplex` feature.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::{AccountMeta, InstructionEncoder, RawInstruction};
+use borsh::BorshSerialize;
+use solana_sdk::pubkey::Pubkey;
+
+/// Token Metadata program address
+pub const ID: [u8; 32] = solana_sdk::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s").to_bytes();
+
+const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+
+const UPDATE_METADATA_ACCOUNT_V2: u8 = 15;
+const VERIFY_COLLECTION: u8 = 18;
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+/// Program limits on metadata strings, in bytes
+pub const MAX_NAME_LENGTH: usize = 32;
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+pub const MAX_URI_LENGTH: usize = 200;
+pub const MAX_CREATOR_LIMIT: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize)]
+pub struct Creator {
+    pub address: [u8; 32],
+    pub verified: bool,
+    /// Percentage of royalties, all creators summing to 100
+    pub share: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize)]
+pub enum UseMethod {
+    Burn,
+    Multiple,
+    Single,
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize)]
+pub struct Uses {
+    pub use_method: UseMethod,
+    pub remaining: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize)]
+pub enum CollectionDetails {
+    V1 { size: u64 },
+}
+
+/// Metadata fields set by create and update
+#[derive(Debug, Clone, PartialEq, BorshSerialize)]
+pub struct DataV2 {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub collection: Option<Collection>,
+    pub uses: Option<Uses>,
+}
+
+impl DataV2 {
+    /// Check the limits the program enforces, to fail before sending
+    pub fn validate(&self) -> Result<()> {
+        let too_long = |field: &str, value: &str, max: usize| {
+            if value.len() > max {
+                Err(TxAsmError::InvalidInstruction(format!(
+                    "Metadata {} is {} bytes, max {}",
+                    field,
+                    value.len(),
+                    max
+                )))
+            } else {
+                Ok(())
+            }
+        };
+        too_long("name", &self.name, MAX_NAME_LENGTH)?;
+        too_long("symbol", &self.symbol, MAX_SYMBOL_LENGTH)?;
+        too_long("uri", &self.uri, MAX_URI_LENGTH)?;
+        if self.seller_fee_basis_points > 10_000 {
+            return Err(TxAsmError::InvalidInstruction(
+                "Seller fee exceeds 10000 basis points".to_string(),
+            ));
+        }
+        if let Some(creators) = &self.creators {
+            if creators.len() > MAX_CREATOR_LIMIT {
+                return Err(TxAsmError::InvalidInstruction(format!(
+                    "{} creators exceed the limit of {}",
+                    creators.len(),
+                    MAX_CREATOR_LIMIT
+                )));
+            }
+            let shares: u32 = creators.iter().map(|c| c.share as u32).sum();
+            if !creators.is_empty() && shares != 100 {
+                return Err(TxAsmError::InvalidInstruction(format!(
+                    "Creator shares sum to {}, not 100",
+                    shares
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(BorshSerialize)]
+struct CreateMetadataAccountArgsV3<'a> {
+    data: &'a DataV2,
+    is_mutable: bool,
+    collection_details: Option<CollectionDetails>,
+}
+
+/// Changes applied by [`update_metadata_account_v2`]; `None` leaves a field as is
+#[derive(Debug, Clone, Default, PartialEq, BorshSerialize)]
+pub struct UpdateMetadataAccountArgsV2 {
+    pub data: Option<DataV2>,
+    pub update_authority: Option<[u8; 32]>,
+    pub primary_sale_happened: Option<bool>,
+    pub is_mutable: Option<bool>,
+}
+
+/// Metadata account address for `mint`
+pub fn metadata_address(mint: &[u8; 32]) -> [u8; 32] {
+    find_address(&[b"metadata", &ID, mint])
+}
+
+/// Master edition account address for `mint`
+pub fn master_edition_address(mint: &[u8; 32]) -> [u8; 32] {
+    find_address(&[b"metadata", &ID, mint, b"edition"])
+}
+
+fn find_address(seeds: &[&[u8]]) -> [u8; 32] {
+    Pubkey::find_program_address(seeds, &Pubkey::new_from_array(ID)).0.to_bytes()
+}
+
+fn encode<T: BorshSerialize>(discriminant: u8, args: &T) -> Result<Vec<u8>> {
+    let mut data = vec![discriminant];
+    args.serialize(&mut data)
+        .map_err(|e| TxAsmError::SerializationError(e.to_string()))?;
+    Ok(data)
+}
+
+/// Create the metadata account for `mint`
+///
+/// `update_authority_is_signer` must be set for creators listed as
+/// verified that include the update authority.
+#[allow(clippy::too_many_arguments)]
+pub fn create_metadata_account_v3(
+    mint: [u8; 32],
+    mint_authority: [u8; 32],
+    payer: [u8; 32],
+    update_authority: [u8; 32],
+    update_authority_is_signer: bool,
+    data: &DataV2,
+    is_mutable: bool,
+    collection_details: Option<CollectionDetails>,
+) -> Result<RawInstruction> {
+    data.validate()?;
+    let args = CreateMetadataAccountArgsV3 {
+        data,
+        is_mutable,
+        collection_details,
+    };
+    let update_authority_meta = AccountMeta::new_readonly(update_authority, update_authority_is_signer);
+    Ok(InstructionEncoder::new(ID)
+        .writable(metadata_address(&mint), false)
+        .readonly(mint)
+        .signer(mint_authority, false)
+        .signer(payer, true)
+        .account(update_authority_meta)
+        .readonly(SYSTEM_PROGRAM_ID)
+        .data(encode(CREATE_METADATA_ACCOUNT_V3, &args)?)
+        .build())
+}
+
+/// Update the metadata account of `mint`
+pub fn update_metadata_account_v2(
+    mint: [u8; 32],
+    update_authority: [u8; 32],
+    args: &UpdateMetadataAccountArgsV2,
+) -> Result<RawInstruction> {
+    if let Some(data) = &args.data {
+        data.validate()?;
+    }
+    Ok(InstructionEncoder::new(ID)
+        .writable(metadata_address(&mint), false)
+        .signer(update_authority, false)
+        .data(encode(UPDATE_METADATA_ACCOUNT_V2, args)?)
+        .build())
+}
+
+/// Mark `mint`'s collection as verified by the collection's authority
+///
+/// Pass `collection_authority_record` when a delegate signs instead of the
+/// collection's update authority.
+pub fn verify_collection(
+    mint: [u8; 32],
+    collection_authority: [u8; 32],
+    payer: [u8; 32],
+    collection_mint: [u8; 32],
+    collection_authority_record: Option<[u8; 32]>,
+) -> RawInstruction {
+    let mut encoder = InstructionEncoder::new(ID)
+        .writable(metadata_address(&mint), false)
+        .signer(collection_authority, true)
+        .signer(payer, true)
+        .readonly(collection_mint)
+        .readonly(metadata_address(&collection_mint))
+        .readonly(master_edition_address(&collection_mint));
+    if let Some(record) = collection_authority_record {
+        encoder = encoder.readonly(record);
+    }
+    encoder.append_u8(VERIFY_COLLECTION).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data() -> DataV2 {
+        DataV2 {
+            name: "Ape".to_string(),
+            symbol: "APE".to_string(),
+            uri: "https://x.io/1.json".to_string(),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![Creator {
+                address: [7u8; 32],
+                verified: false,
+                share: 100,
+            }]),
+            collection: None,
+            uses: None,
+        }
+    }
+
+    #[test]
+    fn test_create_metadata_layout() {
+        let ix = create_metadata_account_v3([1u8; 32], [2u8; 32], [3u8; 32], [2u8; 32], true, &data(), true, None)
+            .unwrap();
+        assert_eq!(ix.program_id, ID);
+        assert_eq!(ix.accounts[0].pubkey, metadata_address(&[1u8; 32]));
+        assert!(ix.accounts[4].is_signer);
+
+        let mut expected = vec![CREATE_METADATA_ACCOUNT_V3];
+        for s in ["Ape", "APE", "https://x.io/1.json"] {
+            expected.extend_from_slice(&(s.len() as u32).to_le_bytes());
+            expected.extend_from_slice(s.as_bytes());
+        }
+        expected.extend_from_slice(&500u16.to_le_bytes());
+        expected.extend_from_slice(&[1, 1, 0, 0, 0]);
+        expected.extend_from_slice(&[7u8; 32]);
+        expected.extend_from_slice(&[0, 100]);
+        // collection, uses, is_mutable, collection_details
+        expected.extend_from_slice(&[0, 0, 1, 0]);
+        assert_eq!(&ix.data[..], &expected[..]);
+    }
+
+    #[test]
+    fn test_update_and_verify() {
+        let args = UpdateMetadataAccountArgsV2 {
+            primary_sale_happened: Some(true),
+            ..Default::default()
+        };
+        let ix = update_metadata_account_v2([1u8; 32], [2u8; 32], &args).unwrap();
+        assert_eq!(&ix.data[..], &[UPDATE_METADATA_ACCOUNT_V2, 0, 0, 1, 1, 0]);
+
+        let mut bad = data();
+        bad.name = "x".repeat(MAX_NAME_LENGTH + 1);
+        let args = UpdateMetadataAccountArgsV2 {
+            data: Some(bad),
+            ..Default::default()
+        };
+        assert!(update_metadata_account_v2([1u8; 32], [2u8; 32], &args).is_err());
+
+        let ix = verify_collection([1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32], None);
+        assert_eq!(ix.accounts.len(), 6);
+        assert_eq!(ix.accounts[5].pubkey, master_edition_address(&[4u8; 32]));
+        assert_eq!(&ix.data[..], &[VERIFY_COLLECTION]);
+    }
+}
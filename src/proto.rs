@@ -0,0 +1,316 @@
+//! Protobuf messages for transaction pipelines
+//!
+//! The message types mirror `proto/txasm.proto` as prost-build generates them,
+//! checked in so building needs no `protoc`. Legacy and version 0
+//! transactions share the `Transaction` message, told apart by
+//! `Message.versioned`; converting back validates key, signature and index
+//! widths that protobuf leaves open.
+//!
+//! ```ignore
+//! use prost::Message;
+//!
+//! let bytes = txasm::proto::Transaction::from(&transaction).encode_to_vec();
+//! let decoded: CompiledTransaction = txasm::proto::Transaction::decode(&bytes[..])?.try_into()?;
+//! ```
+
+use crate::error::{Result, TxAsmError};
+use crate::transaction::{self, CompiledTransaction};
+use crate::versioned::{self, CompiledMessageV0, CompiledTransactionV0};
+
+// Generated from proto/txasm.proto (package txasm.v1)
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessageHeader {
+    #[prost(uint32, tag = "1")]
+    pub num_required_signatures: u32,
+    #[prost(uint32, tag = "2")]
+    pub num_readonly_signed_accounts: u32,
+    #[prost(uint32, tag = "3")]
+    pub num_readonly_unsigned_accounts: u32,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CompiledInstruction {
+    #[prost(uint32, tag = "1")]
+    pub program_id_index: u32,
+    /// One byte per account index
+    #[prost(bytes = "vec", tag = "2")]
+    pub account_indices: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MessageAddressTableLookup {
+    #[prost(bytes = "vec", tag = "1")]
+    pub account_key: Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub writable_indexes: Vec<u8>,
+    #[prost(bytes = "vec", tag = "3")]
+    pub readonly_indexes: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Message {
+    /// False for legacy messages, which carry no lookups
+    #[prost(bool, tag = "1")]
+    pub versioned: bool,
+    #[prost(message, optional, tag = "2")]
+    pub header: Option<MessageHeader>,
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub account_keys: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", tag = "4")]
+    pub recent_blockhash: Vec<u8>,
+    #[prost(message, repeated, tag = "5")]
+    pub instructions: Vec<CompiledInstruction>,
+    #[prost(message, repeated, tag = "6")]
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Transaction {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    pub signatures: Vec<Vec<u8>>,
+    #[prost(message, optional, tag = "2")]
+    pub message: Option<Message>,
+}
+
+impl From<&transaction::MessageHeader> for MessageHeader {
+    fn from(header: &transaction::MessageHeader) -> Self {
+        Self {
+            num_required_signatures: header.num_required_signatures.into(),
+            num_readonly_signed_accounts: header.num_readonly_signed_accounts.into(),
+            num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts.into(),
+        }
+    }
+}
+
+impl TryFrom<&MessageHeader> for transaction::MessageHeader {
+    type Error = TxAsmError;
+
+    fn try_from(header: &MessageHeader) -> Result<Self> {
+        Ok(Self::new(
+            byte("num_required_signatures", header.num_required_signatures)?,
+            byte("num_readonly_signed_accounts", header.num_readonly_signed_accounts)?,
+            byte("num_readonly_unsigned_accounts", header.num_readonly_unsigned_accounts)?,
+        ))
+    }
+}
+
+impl From<&transaction::CompiledInstruction> for CompiledInstruction {
+    fn from(ix: &transaction::CompiledInstruction) -> Self {
+        Self {
+            program_id_index: ix.program_id_index.into(),
+            account_indices: ix.account_indices.to_vec(),
+            data: ix.data.clone(),
+        }
+    }
+}
+
+impl TryFrom<CompiledInstruction> for transaction::CompiledInstruction {
+    type Error = TxAsmError;
+
+    fn try_from(ix: CompiledInstruction) -> Result<Self> {
+        Ok(Self {
+            program_id_index: byte("program_id_index", ix.program_id_index)?,
+            account_indices: ix.account_indices.into(),
+            data: ix.data,
+        })
+    }
+}
+
+impl From<&CompiledTransaction> for Transaction {
+    fn from(tx: &CompiledTransaction) -> Self {
+        let message = &tx.message;
+        Self {
+            signatures: tx.signatures.iter().map(|s| s.to_vec()).collect(),
+            message: Some(Message {
+                versioned: false,
+                header: Some((&message.header).into()),
+                account_keys: message.account_keys.iter().map(|k| k.to_vec()).collect(),
+                recent_blockhash: message.recent_blockhash.to_vec(),
+                instructions: message.instructions.iter().map(Into::into).collect(),
+                address_table_lookups: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Transaction> for CompiledTransaction {
+    type Error = TxAsmError;
+
+    fn try_from(tx: Transaction) -> Result<Self> {
+        let (signatures, message) = split(tx)?;
+        if message.versioned || !message.address_table_lookups.is_empty() {
+            return Err(TxAsmError::DecodingError(
+                "Versioned message in legacy transaction".to_string(),
+            ));
+        }
+        Ok(Self {
+            signatures,
+            message: transaction::CompiledMessage {
+                header: required_header(&message)?,
+                account_keys: keys(&message.account_keys)?,
+                recent_blockhash: fixed("recent_blockhash", &message.recent_blockhash)?,
+                instructions: instructions(message.instructions)?,
+            },
+        })
+    }
+}
+
+impl From<&CompiledTransactionV0> for Transaction {
+    fn from(tx: &CompiledTransactionV0) -> Self {
+        let message = &tx.message;
+        Self {
+            signatures: tx.signatures.iter().map(|s| s.to_vec()).collect(),
+            message: Some(Message {
+                versioned: true,
+                header: Some((&message.header).into()),
+                account_keys: message.account_keys.iter().map(|k| k.to_vec()).collect(),
+                recent_blockhash: message.recent_blockhash.to_vec(),
+                instructions: message.instructions.iter().map(Into::into).collect(),
+                address_table_lookups: message
+                    .address_table_lookups
+                    .iter()
+                    .map(|lookup| MessageAddressTableLookup {
+                        account_key: lookup.account_key.to_vec(),
+                        writable_indexes: lookup.writable_indexes.clone(),
+                        readonly_indexes: lookup.readonly_indexes.clone(),
+                    })
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Transaction> for CompiledTransactionV0 {
+    type Error = TxAsmError;
+
+    fn try_from(tx: Transaction) -> Result<Self> {
+        let (signatures, message) = split(tx)?;
+        if !message.versioned {
+            return Err(TxAsmError::DecodingError(
+                "Legacy message in versioned transaction".to_string(),
+            ));
+        }
+        let header = required_header(&message)?;
+        let address_table_lookups = message
+            .address_table_lookups
+            .into_iter()
+            .map(|lookup| {
+                Ok(versioned::MessageAddressTableLookup {
+                    account_key: fixed("lookup account_key", &lookup.account_key)?,
+                    writable_indexes: lookup.writable_indexes,
+                    readonly_indexes: lookup.readonly_indexes,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            signatures,
+            message: CompiledMessageV0 {
+                header,
+                account_keys: keys(&message.account_keys)?,
+                recent_blockhash: fixed("recent_blockhash", &message.recent_blockhash)?,
+                instructions: instructions(message.instructions)?,
+                address_table_lookups,
+            },
+        })
+    }
+}
+
+fn split(tx: Transaction) -> Result<(Vec<[u8; 64]>, Message)> {
+    let signatures = tx
+        .signatures
+        .iter()
+        .map(|s| fixed("signature", s))
+        .collect::<Result<_>>()?;
+    let message = tx
+        .message
+        .ok_or_else(|| TxAsmError::DecodingError("Transaction has no message".to_string()))?;
+    Ok((signatures, message))
+}
+
+fn required_header(message: &Message) -> Result<transaction::MessageHeader> {
+    message
+        .header
+        .as_ref()
+        .ok_or_else(|| TxAsmError::DecodingError("Message has no header".to_string()))?
+        .try_into()
+}
+
+fn keys(keys: &[Vec<u8>]) -> Result<Vec<[u8; 32]>> {
+    keys.iter().map(|k| fixed("account key", k)).collect()
+}
+
+fn instructions(instructions: Vec<CompiledInstruction>) -> Result<Vec<transaction::CompiledInstruction>> {
+    instructions.into_iter().map(TryInto::try_into).collect()
+}
+
+pub(crate) fn fixed<const N: usize>(what: &str, bytes: &[u8]) -> Result<[u8; N]> {
+    bytes.try_into().map_err(|_| {
+        TxAsmError::DecodingError(format!("{} must be {} bytes, got {}", what, N, bytes.len()))
+    })
+}
+
+pub(crate) fn byte(what: &str, value: u32) -> Result<u8> {
+    u8::try_from(value).map_err(|_| TxAsmError::DecodingError(format!("{} ({}) exceeds u8", what, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use prost::Message as _;
+
+    #[test]
+    fn test_legacy_roundtrip() {
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(9).build())
+            .build_unsigned()
+            .unwrap();
+        let encoded = Transaction::from(&tx).encode_to_vec();
+        let decoded: CompiledTransaction = Transaction::decode(&encoded[..]).unwrap().try_into().unwrap();
+        assert_eq!(decoded.serialize().unwrap(), tx.serialize().unwrap());
+
+        // Versioned payloads are not silently read as legacy ones
+        let decoded = Transaction::decode(&encoded[..]).unwrap();
+        assert!(CompiledTransactionV0::try_from(decoded).is_err());
+    }
+
+    #[test]
+    fn test_v0_roundtrip_and_validation() {
+        let tx = CompiledTransactionV0 {
+            message: CompiledMessageV0 {
+                header: transaction::MessageHeader::new(1, 0, 1),
+                account_keys: vec![[1u8; 32], [2u8; 32]],
+                recent_blockhash: [3u8; 32],
+                instructions: vec![transaction::CompiledInstruction {
+                    program_id_index: 1,
+                    account_indices: vec![0, 2].into(),
+                    data: vec![4],
+                }],
+                address_table_lookups: vec![versioned::MessageAddressTableLookup {
+                    account_key: [7u8; 32],
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1],
+                }],
+            },
+            signatures: vec![[5u8; 64]],
+        };
+        let proto = Transaction::from(&tx);
+        let decoded = CompiledTransactionV0::try_from(Transaction::decode(&proto.encode_to_vec()[..]).unwrap()).unwrap();
+        assert_eq!(decoded.serialize().unwrap(), tx.serialize().unwrap());
+
+        let mut short_key = proto.clone();
+        short_key.message.as_mut().unwrap().account_keys[0].pop();
+        let err = CompiledTransactionV0::try_from(short_key).unwrap_err();
+        assert!(err.to_string().contains("account key must be 32 bytes, got 31"));
+
+        let mut wide_index = proto;
+        wide_index.message.as_mut().unwrap().instructions[0].program_id_index = 256;
+        assert!(CompiledTransactionV0::try_from(wide_index).is_err());
+    }
+}
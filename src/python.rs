@@ -0,0 +1,380 @@
+//! Python bindings via PyO3
+//!
+//! Exposes the transaction builder, instruction encoder, fee calculator and
+//! decoders as Python classes, so notebooks can assemble and dissect
+//! transactions with exactly the same byte-level logic as the Rust crate.
+//!
+//! Enabled with the `python` feature. Build the extension module with
+//! `maturin develop`, which builds a cdylib with the `extension-module`
+//! feature as configured in `pyproject.toml`; the library itself stays an
+//! rlib for Rust consumers.
+
+// pyo3's generated wrappers convert `PyResult` into itself
+#![allow(clippy::useless_conversion)]
+
+use crate::error::TxAsmError;
+use crate::fee_calculator::{FeeEstimate, FeeStrategy, PriorityFeeCalculator};
+use crate::instruction::{AccountMeta, InstructionDecoder, InstructionEncoder, RawInstruction};
+use crate::transaction::{CompiledTransaction, TransactionBuilder};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use solana_sdk::signature::Keypair;
+
+impl From<TxAsmError> for PyErr {
+    fn from(err: TxAsmError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
+
+/// Convert a Python `bytes` object into a 32-byte key
+fn to_key(bytes: &[u8]) -> PyResult<[u8; 32]> {
+    bytes.try_into().map_err(|_| {
+        PyValueError::new_err(format!("Expected 32-byte key, got {} bytes", bytes.len()))
+    })
+}
+
+/// Parse a strategy name ("low", "medium", "high") or a custom CU price
+fn to_strategy(strategy: &Bound<'_, PyAny>) -> PyResult<FeeStrategy> {
+    if let Ok(fee) = strategy.extract::<u64>() {
+        return Ok(FeeStrategy::Custom(fee));
+    }
+    match strategy.extract::<String>()?.to_lowercase().as_str() {
+        "low" => Ok(FeeStrategy::Low),
+        "medium" => Ok(FeeStrategy::Medium),
+        "high" => Ok(FeeStrategy::High),
+        other => Err(PyValueError::new_err(format!("Unknown fee strategy: {}", other))),
+    }
+}
+
+/// A built instruction
+#[pyclass(name = "Instruction")]
+#[derive(Clone)]
+pub struct PyInstruction {
+    inner: RawInstruction,
+}
+
+#[pymethods]
+impl PyInstruction {
+    #[getter]
+    fn program_id<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.inner.program_id)
+    }
+
+    #[getter]
+    fn accounts(&self) -> Vec<(Vec<u8>, bool, bool)> {
+        self.inner
+            .accounts
+            .iter()
+            .map(|a| (a.pubkey.to_vec(), a.is_signer, a.is_writable))
+            .collect()
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.inner.data[..])
+    }
+}
+
+/// Instruction encoder with a chainable API mirroring `InstructionEncoder`
+#[pyclass(name = "InstructionEncoder")]
+pub struct PyInstructionEncoder {
+    program_id: [u8; 32],
+    accounts: Vec<AccountMeta>,
+    data: Vec<u8>,
+}
+
+#[pymethods]
+impl PyInstructionEncoder {
+    #[new]
+    fn new(program_id: &[u8]) -> PyResult<Self> {
+        Ok(Self {
+            program_id: to_key(program_id)?,
+            accounts: Vec::new(),
+            data: Vec::new(),
+        })
+    }
+
+    fn signer<'py>(mut slf: PyRefMut<'py, Self>, pubkey: &[u8], is_writable: bool) -> PyResult<PyRefMut<'py, Self>> {
+        slf.accounts.push(AccountMeta::new(to_key(pubkey)?, true, is_writable));
+        Ok(slf)
+    }
+
+    fn writable<'py>(mut slf: PyRefMut<'py, Self>, pubkey: &[u8], is_signer: bool) -> PyResult<PyRefMut<'py, Self>> {
+        slf.accounts.push(AccountMeta::new(to_key(pubkey)?, is_signer, true));
+        Ok(slf)
+    }
+
+    fn readonly<'py>(mut slf: PyRefMut<'py, Self>, pubkey: &[u8]) -> PyResult<PyRefMut<'py, Self>> {
+        slf.accounts.push(AccountMeta::new_readonly(to_key(pubkey)?, false));
+        Ok(slf)
+    }
+
+    fn append_data<'py>(mut slf: PyRefMut<'py, Self>, data: &[u8]) -> PyRefMut<'py, Self> {
+        slf.data.extend_from_slice(data);
+        slf
+    }
+
+    fn append_u8(mut slf: PyRefMut<'_, Self>, value: u8) -> PyRefMut<'_, Self> {
+        slf.data.push(value);
+        slf
+    }
+
+    fn append_u32(mut slf: PyRefMut<'_, Self>, value: u32) -> PyRefMut<'_, Self> {
+        slf.data.extend_from_slice(&value.to_le_bytes());
+        slf
+    }
+
+    fn append_u64(mut slf: PyRefMut<'_, Self>, value: u64) -> PyRefMut<'_, Self> {
+        slf.data.extend_from_slice(&value.to_le_bytes());
+        slf
+    }
+
+    fn build(&self) -> PyInstruction {
+        let inner = InstructionEncoder::new(self.program_id)
+            .accounts(self.accounts.clone())
+            .data(self.data.clone())
+            .build();
+        PyInstruction { inner }
+    }
+}
+
+/// Transaction builder mirroring `TransactionBuilder`
+#[pyclass(name = "TransactionBuilder")]
+#[derive(Default)]
+pub struct PyTransactionBuilder {
+    instructions: Vec<RawInstruction>,
+    payer: Option<[u8; 32]>,
+    recent_blockhash: Option<[u8; 32]>,
+}
+
+impl PyTransactionBuilder {
+    fn to_builder(&self) -> TransactionBuilder {
+        let mut builder = TransactionBuilder::new().add_instructions(self.instructions.clone());
+        if let Some(payer) = self.payer {
+            builder = builder.payer(payer);
+        }
+        if let Some(blockhash) = self.recent_blockhash {
+            builder = builder.recent_blockhash(blockhash);
+        }
+        builder
+    }
+}
+
+#[pymethods]
+impl PyTransactionBuilder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn payer<'py>(mut slf: PyRefMut<'py, Self>, payer: &[u8]) -> PyResult<PyRefMut<'py, Self>> {
+        slf.payer = Some(to_key(payer)?);
+        Ok(slf)
+    }
+
+    fn recent_blockhash<'py>(mut slf: PyRefMut<'py, Self>, blockhash: &[u8]) -> PyResult<PyRefMut<'py, Self>> {
+        slf.recent_blockhash = Some(to_key(blockhash)?);
+        Ok(slf)
+    }
+
+    fn add_instruction<'py>(mut slf: PyRefMut<'py, Self>, instruction: &PyInstruction) -> PyRefMut<'py, Self> {
+        slf.instructions.push(instruction.inner.clone());
+        slf
+    }
+
+    fn build_unsigned(&self) -> PyResult<PyCompiledTransaction> {
+        let inner = self.to_builder().build_unsigned()?;
+        Ok(PyCompiledTransaction { inner })
+    }
+
+    /// Sign with 64-byte secret keys, in signer order
+    fn build_and_sign(&self, secret_keys: Vec<Vec<u8>>) -> PyResult<PyCompiledTransaction> {
+        let keypairs = secret_keys
+            .iter()
+            .map(|bytes| {
+                Keypair::from_bytes(bytes).map_err(|e| PyValueError::new_err(e.to_string()))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let signers: Vec<&Keypair> = keypairs.iter().collect();
+        let inner = self.to_builder().build_and_sign(&signers)?;
+        Ok(PyCompiledTransaction { inner })
+    }
+}
+
+/// A compiled transaction
+#[pyclass(name = "CompiledTransaction")]
+pub struct PyCompiledTransaction {
+    inner: CompiledTransaction,
+}
+
+#[pymethods]
+impl PyCompiledTransaction {
+    #[staticmethod]
+    fn deserialize(bytes: &[u8]) -> PyResult<Self> {
+        Ok(Self {
+            inner: CompiledTransaction::deserialize(bytes)?,
+        })
+    }
+
+    fn serialize<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new_bound(py, &self.inner.serialize()?))
+    }
+
+    fn message_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new_bound(py, &self.inner.message_bytes()?))
+    }
+
+    fn size(&self) -> usize {
+        self.inner.size()
+    }
+
+    #[getter]
+    fn signatures(&self) -> Vec<Vec<u8>> {
+        self.inner.signatures.iter().map(|s| s.to_vec()).collect()
+    }
+
+    #[getter]
+    fn header(&self) -> (u8, u8, u8) {
+        let header = &self.inner.message.header;
+        (
+            header.num_required_signatures,
+            header.num_readonly_signed_accounts,
+            header.num_readonly_unsigned_accounts,
+        )
+    }
+
+    #[getter]
+    fn account_keys(&self) -> Vec<Vec<u8>> {
+        self.inner.message.account_keys.iter().map(|k| k.to_vec()).collect()
+    }
+
+    #[getter]
+    fn recent_blockhash(&self) -> Vec<u8> {
+        self.inner.message.recent_blockhash.to_vec()
+    }
+
+    /// Instructions as (program_id_index, account_indices, data) tuples
+    #[getter]
+    fn instructions(&self) -> Vec<(u8, Vec<u8>, Vec<u8>)> {
+        self.inner
+            .message
+            .instructions
+            .iter()
+            .map(|i| (i.program_id_index, i.account_indices.to_vec(), i.data.clone()))
+            .collect()
+    }
+}
+
+/// Fee estimate returned by `FeeCalculator.estimate_fee`
+#[pyclass(name = "FeeEstimate", get_all)]
+pub struct PyFeeEstimate {
+    base_fee: u64,
+    priority_fee_per_cu: u64,
+    estimated_compute_units: u32,
+    total_cost: u64,
+}
+
+impl From<FeeEstimate> for PyFeeEstimate {
+    fn from(estimate: FeeEstimate) -> Self {
+        Self {
+            base_fee: estimate.base_fee,
+            priority_fee_per_cu: estimate.priority_fee_per_cu,
+            estimated_compute_units: estimate.estimated_compute_units,
+            total_cost: estimate.total_cost,
+        }
+    }
+}
+
+/// Priority fee calculator
+#[pyclass(name = "FeeCalculator")]
+pub struct PyFeeCalculator {
+    inner: PriorityFeeCalculator,
+}
+
+#[pymethods]
+impl PyFeeCalculator {
+    #[new]
+    #[pyo3(signature = (base_fee_per_signature = 5000))]
+    fn new(base_fee_per_signature: u64) -> Self {
+        Self {
+            inner: PriorityFeeCalculator::with_base_fee(base_fee_per_signature),
+        }
+    }
+
+    /// Strategy is "low", "medium", "high" or a custom microlamport price
+    fn estimate_fee(&self, transaction: &PyCompiledTransaction, strategy: &Bound<'_, PyAny>) -> PyResult<PyFeeEstimate> {
+        let strategy = to_strategy(strategy)?;
+        Ok(self.inner.estimate_fee(&transaction.inner, strategy)?.into())
+    }
+
+    fn estimate_compute_units(&self, transaction: &PyCompiledTransaction) -> u32 {
+        self.inner.estimate_compute_units(&transaction.inner)
+    }
+}
+
+/// Decode a single compiled instruction into (program_id_index, account_indices, data)
+#[pyfunction]
+fn decode_instruction(bytes: &[u8]) -> PyResult<(u8, Vec<u8>, Vec<u8>)> {
+    let decoded = InstructionDecoder::decode(bytes)?;
+    Ok((decoded.program_id_index, decoded.account_indices, decoded.data))
+}
+
+#[pymodule]
+fn txasm(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyInstruction>()?;
+    m.add_class::<PyInstructionEncoder>()?;
+    m.add_class::<PyTransactionBuilder>()?;
+    m.add_class::<PyCompiledTransaction>()?;
+    m.add_class::<PyFeeEstimate>()?;
+    m.add_class::<PyFeeCalculator>()?;
+    m.add_function(wrap_pyfunction!(decode_instruction, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn test_python_roundtrip() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new_bound(py, "txasm").unwrap();
+            txasm(&module).unwrap();
+            let locals = PyDict::new_bound(py);
+            locals.set_item("txasm", module).unwrap();
+            py.run_bound(
+                r#"
+ix = txasm.InstructionEncoder(bytes([2] * 32)).writable(bytes([5] * 32), False).append_u64(1000).build()
+tx = txasm.TransactionBuilder().payer(bytes([1] * 32)).recent_blockhash(bytes([3] * 32)).add_instruction(ix).build_unsigned()
+wire = tx.serialize()
+back = txasm.CompiledTransaction.deserialize(wire)
+assert back.serialize() == wire
+assert back.account_keys[0] == [1] * 32
+price = txasm.FeeCalculator().estimate_fee(back, "high").priority_fee_per_cu
+try:
+    txasm.FeeCalculator().estimate_fee(back, "urgent")
+    raise AssertionError("unknown strategy accepted")
+except ValueError:
+    pass
+"#,
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+
+            let expected = TransactionBuilder::new()
+                .payer([1u8; 32])
+                .recent_blockhash([3u8; 32])
+                .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(1000).build())
+                .build_unsigned()
+                .unwrap();
+            let wire: Vec<u8> = locals.get_item("wire").unwrap().unwrap().extract().unwrap();
+            assert_eq!(wire, expected.serialize().unwrap());
+            let price: u64 = locals.get_item("price").unwrap().unwrap().extract().unwrap();
+            assert_eq!(price, 1000);
+        });
+    }
+}
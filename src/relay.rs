@@ -0,0 +1,279 @@
+//! Fee payer relays for gasless transactions
+//!
+//! Octane-style relays pay fees on a user's behalf: the transaction names
+//! the relay as fee payer, the user signs their own slots, and the relay
+//! co-signs slot 0 once it is satisfied with what it is paying for. A
+//! [`FeePayerProvider`] wraps such a service.
+//!
+//! The payer is always account 0, so a transaction can be assembled with
+//! [`TransactionBuilder::build_for_relay`] before a relay is picked and
+//! handed to [`sponsor`], which swaps in the relay's key, collects the
+//! user's signatures and merges the relay's.
+//!
+//! When the relay is known up front, a [`GaslessEnvelope`] carries the
+//! user-signed transaction to it. Both sides check it with
+//! [`relayer_exposure`], a [`SecurityScan`](crate::preflight::SecurityScan)
+//! that flags any instruction able to spend the fee payer's funds.
+
+use crate::error::{Result, TxAsmError};
+use crate::sigverify::verify_strict;
+use crate::transaction::{CompiledTransaction, TransactionBuilder};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use solana_sdk::signature::{Keypair, Signer};
+
+/// Stand-in fee payer used until a relay's key is known
+pub const PLACEHOLDER_FEE_PAYER: [u8; 32] = *b"txasm/fee-payer-placeholder\0\0\0\0\0";
+
+/// A service that pays fees for other signers' transactions
+pub trait FeePayerProvider {
+    /// Account the relay pays fees from
+    fn fee_payer(&self) -> Result<[u8; 32]>;
+
+    /// Sign `transaction` as fee payer and return that signature
+    ///
+    /// The transaction already carries the other signers' signatures, so a
+    /// remote relay can be sent `transaction.serialize()` as is.
+    fn co_sign(&self, transaction: &CompiledTransaction) -> Result<[u8; 64]>;
+}
+
+/// A local keypair acting as its own relay
+impl FeePayerProvider for Keypair {
+    fn fee_payer(&self) -> Result<[u8; 32]> {
+        Ok(self.pubkey().to_bytes())
+    }
+
+    fn co_sign(&self, transaction: &CompiledTransaction) -> Result<[u8; 64]> {
+        Ok(self.sign_message(&transaction.message_bytes()?).into())
+    }
+}
+
+impl TransactionBuilder {
+    /// Compile unsigned with [`PLACEHOLDER_FEE_PAYER`] as payer, for [`sponsor`]
+    pub fn build_for_relay(self) -> Result<CompiledTransaction> {
+        self.payer(PLACEHOLDER_FEE_PAYER).build_unsigned()
+    }
+}
+
+impl CompiledTransaction {
+    /// Replace the fee payer (account 0), clearing every signature
+    ///
+    /// Fails if `payer` already appears elsewhere in the message, since the
+    /// keys must stay unique.
+    pub fn set_fee_payer(&mut self, payer: [u8; 32]) -> Result<()> {
+        let message = &mut self.message;
+        if message.header.num_required_signatures == 0 || message.account_keys.is_empty() {
+            return Err(TxAsmError::InvalidTransaction("Message has no fee payer slot".to_string()));
+        }
+        if message.account_keys[1..].contains(&payer) {
+            return Err(TxAsmError::AccountError(format!(
+                "Fee payer {} is already account {} of the message",
+                bs58::encode(payer).into_string(),
+                message.account_index(&payer).unwrap_or_default()
+            )));
+        }
+        message.account_keys[0] = payer;
+        self.signatures = vec![[0u8; 64]; message.header.num_required_signatures as usize];
+        Ok(())
+    }
+}
+
+/// Have `provider` pay for `transaction` after `signers` sign it
+///
+/// The relay's key replaces whatever payer the transaction was built with,
+/// and the result is checked with [`CompiledTransaction::verify_strict`].
+pub fn sponsor<P: FeePayerProvider + ?Sized>(
+    mut transaction: CompiledTransaction,
+    provider: &P,
+    signers: &[&Keypair],
+) -> Result<CompiledTransaction> {
+    let payer = provider.fee_payer()?;
+    transaction.set_fee_payer(payer)?;
+
+    let message = transaction.message_bytes()?;
+    for signer in signers {
+        let signature = signer.sign_message(&message);
+        transaction.merge_signature(&signer.pubkey().to_bytes(), signature.into())?;
+    }
+    let signature = provider.co_sign(&transaction)?;
+    transaction.merge_signature(&payer, signature)?;
+    transaction.verify_strict()?;
+    Ok(transaction)
+}
+
+/// Instructions that could move the fee payer's funds beyond the fee
+///
+/// Account 0 signs the message, so any instruction that references it, or
+/// invokes it, runs with the fee payer's authority. Returns one finding per
+/// such instruction, and can be passed to
+/// [`PreflightContext::security_scan`](crate::preflight::PreflightContext::security_scan).
+pub fn relayer_exposure(transaction: &CompiledTransaction) -> Vec<String> {
+    let message = &transaction.message;
+    let Some(payer) = message.account_keys.first() else {
+        return Vec::new();
+    };
+    let payer = bs58::encode(payer).into_string();
+    message
+        .instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, ix)| {
+            let program = message.account_keys.get(ix.program_id_index as usize)?;
+            if ix.program_id_index == 0 {
+                Some(format!("Instruction {} invokes the fee payer {} as a program", index, payer))
+            } else if ix.account_indices.contains(&0) {
+                Some(format!(
+                    "Instruction {} ({}) passes the fee payer {} with its signature",
+                    index,
+                    bs58::encode(program).into_string(),
+                    payer
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A transaction paid for by a relayer and signed by everyone else
+///
+/// The relayer's slot (signature 0) stays empty until [`Self::complete`].
+#[derive(Debug, Clone)]
+pub struct GaslessEnvelope {
+    transaction: CompiledTransaction,
+}
+
+impl GaslessEnvelope {
+    /// Compile `builder` with `relayer` as fee payer and sign with `signers`
+    pub fn new(builder: TransactionBuilder, relayer: [u8; 32], signers: &[&Keypair]) -> Result<Self> {
+        let mut transaction = builder.payer(relayer).build_unsigned()?;
+        let message = transaction.message_bytes()?;
+        for signer in signers {
+            let key = signer.pubkey().to_bytes();
+            if key == relayer {
+                return Err(TxAsmError::SignatureError("The relayer signs in complete(), not as a user".to_string()));
+            }
+            transaction.merge_signature(&key, signer.sign_message(&message).into())?;
+        }
+        let envelope = Self { transaction };
+        envelope.validate()?;
+        Ok(envelope)
+    }
+
+    /// Fee payer the envelope is addressed to
+    pub fn relayer(&self) -> [u8; 32] {
+        self.transaction.message.account_keys[0]
+    }
+
+    pub fn transaction(&self) -> &CompiledTransaction {
+        &self.transaction
+    }
+
+    /// The relayer's checks: every user slot holds a valid signature and
+    /// [`relayer_exposure`] finds nothing
+    pub fn validate(&self) -> Result<()> {
+        let findings = relayer_exposure(&self.transaction);
+        if !findings.is_empty() {
+            return Err(TxAsmError::InvalidTransaction(format!(
+                "Relayer funds exposed: {}",
+                findings.join("; ")
+            )));
+        }
+        let signers = self.transaction.required_signers();
+        let message = self.transaction.message_bytes()?;
+        for (slot, key) in signers.iter().enumerate().skip(1) {
+            let signature = self.transaction.signatures.get(slot).ok_or_else(|| {
+                TxAsmError::SignatureError(format!("Missing signature for {}", bs58::encode(key).into_string()))
+            })?;
+            verify_strict(key, &message, signature)?;
+        }
+        Ok(())
+    }
+
+    /// Base64 wire transaction to send to the relayer
+    pub fn to_base64(&self) -> Result<String> {
+        Ok(BASE64.encode(self.transaction.serialize()?))
+    }
+
+    /// Decode and [`validate`](Self::validate) an envelope, relayer side
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(encoded)
+            .map_err(|e| TxAsmError::DecodingError(e.to_string()))?;
+        let envelope = Self {
+            transaction: CompiledTransaction::deserialize(&bytes)?,
+        };
+        envelope.validate()?;
+        Ok(envelope)
+    }
+
+    /// Add the relayer's fee payer signature and verify the result
+    pub fn complete(mut self, signature: [u8; 64]) -> Result<CompiledTransaction> {
+        let relayer = self.relayer();
+        self.transaction.merge_signature(&relayer, signature)?;
+        self.transaction.verify_strict()?;
+        Ok(self.transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit;
+
+    #[test]
+    fn test_sponsored_transfer() {
+        let user = testkit::keypair(1);
+        let relay = testkit::keypair(2);
+        let tx = TransactionBuilder::new()
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(user.pubkey().to_bytes(), testkit::pubkey(3), 5))
+            .build_for_relay()
+            .unwrap();
+        assert_eq!(tx.message.account_keys[0], PLACEHOLDER_FEE_PAYER);
+
+        let sponsored = sponsor(tx, &relay as &dyn FeePayerProvider, &[&user]).unwrap();
+        assert_eq!(sponsored.required_signers(), [relay.pubkey().to_bytes(), user.pubkey().to_bytes()]);
+        assert!(sponsored.is_fully_signed());
+    }
+
+    #[test]
+    fn test_relay_key_must_be_new() {
+        let user = testkit::keypair(1);
+        let tx = TransactionBuilder::new()
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(user.pubkey().to_bytes(), testkit::pubkey(3), 5))
+            .build_for_relay()
+            .unwrap();
+        let err = sponsor(tx.clone(), &user, &[]).unwrap_err();
+        assert!(matches!(err, TxAsmError::AccountError(_)));
+
+        // The user never signed
+        let err = sponsor(tx, &testkit::keypair(2), &[]).unwrap_err();
+        assert!(matches!(err, TxAsmError::SignatureError(_)));
+    }
+
+    #[test]
+    fn test_gasless_envelope() {
+        let user = testkit::keypair(1);
+        let relayer = testkit::keypair(2);
+        let builder = TransactionBuilder::new()
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(user.pubkey().to_bytes(), testkit::pubkey(3), 5));
+        let envelope = GaslessEnvelope::new(builder, relayer.pubkey().to_bytes(), &[&user]).unwrap();
+
+        let received = GaslessEnvelope::from_base64(&envelope.to_base64().unwrap()).unwrap();
+        assert_eq!(received.relayer(), relayer.pubkey().to_bytes());
+        let signature = relayer.co_sign(received.transaction()).unwrap();
+        let tx = received.complete(signature).unwrap();
+        assert!(tx.is_fully_signed());
+
+        // A transfer out of the relayer's account is refused
+        let drain = TransactionBuilder::new()
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(testkit::transfer_instruction(relayer.pubkey().to_bytes(), testkit::pubkey(3), 5));
+        let err = GaslessEnvelope::new(drain, relayer.pubkey().to_bytes(), &[]).unwrap_err();
+        assert!(err.to_string().contains("Instruction 0"));
+        let context = crate::preflight::PreflightContext::new().security_scan(&relayer_exposure);
+        assert!(!crate::preflight::check(&tx, &context).failures().iter().any(|f| f.contains("fee payer")));
+    }
+}
@@ -0,0 +1,11 @@
+//! Renderers for human inspection of transactions
+//!
+//! Each submodule renders the annotated byte layout from
+//! [`CompiledTransaction::layout`](crate::transaction::CompiledTransaction::layout)
+//! for one output medium; [`report`] wraps it in a Markdown or HTML audit
+//! document.
+
+pub mod audit;
+pub mod terminal;
+
+pub use audit::{report, AuditReport};
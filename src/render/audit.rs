@@ -0,0 +1,266 @@
+//! Audit reports for change review
+//!
+//! [`report`] collects a transaction's accounts, size and fee breakdown,
+//! security findings and disassembly into an [`AuditReport`] that renders as
+//! a self-contained Markdown or HTML document, for attaching to review
+//! tickets when a new transaction template is deployed.
+
+use super::terminal::TerminalRenderer;
+use crate::error::Result;
+use crate::fee_calculator::FeeEstimate;
+use crate::optimizer::TransactionAnalysis;
+use crate::token::TokenAmount;
+use crate::transaction::{CompiledTransaction, MAX_TRANSACTION_SIZE};
+
+/// Content of one report section
+enum Body {
+    Table { headers: Vec<&'static str>, rows: Vec<Vec<String>> },
+    List(Vec<String>),
+    Code(String),
+}
+
+/// Audit document for one transaction
+pub struct AuditReport<'a> {
+    transaction: &'a CompiledTransaction,
+    analysis: &'a TransactionAnalysis,
+    fee_estimate: &'a FeeEstimate,
+    title: String,
+    findings: Vec<String>,
+}
+
+/// Start an audit report from an analysis and fee estimate of `transaction`
+pub fn report<'a>(
+    transaction: &'a CompiledTransaction,
+    analysis: &'a TransactionAnalysis,
+    fee_estimate: &'a FeeEstimate,
+) -> AuditReport<'a> {
+    AuditReport {
+        transaction,
+        analysis,
+        fee_estimate,
+        title: "Transaction audit".to_string(),
+        findings: Vec::new(),
+    }
+}
+
+impl<'a> AuditReport<'a> {
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Security findings, e.g. from a preflight scan or summary warnings
+    pub fn findings(mut self, findings: impl IntoIterator<Item = String>) -> Self {
+        self.findings.extend(findings);
+        self
+    }
+
+    pub fn to_markdown(&self) -> Result<String> {
+        let mut out = format!("# {}\n\n", self.title);
+        for (heading, body) in self.sections()? {
+            out.push_str(&format!("## {}\n\n", heading));
+            match body {
+                Body::Table { headers, rows } => {
+                    out.push_str(&format!("| {} |\n", headers.join(" | ")));
+                    out.push_str(&format!("|{}\n", "---|".repeat(headers.len())));
+                    for row in rows {
+                        let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                    }
+                }
+                Body::List(items) => {
+                    for item in items {
+                        out.push_str(&format!("- {}\n", item));
+                    }
+                }
+                Body::Code(code) => out.push_str(&format!("```text\n{}```\n", code)),
+            }
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// HTML page with inline styles and no external resources
+    pub fn to_html(&self) -> Result<String> {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>body{{font-family:sans-serif;max-width:72em;margin:2em auto}}\
+             table{{border-collapse:collapse}}td,th{{border:1px solid #ccc;padding:2px 8px;text-align:left}}\
+             pre{{background:#f6f6f6;padding:1em;overflow-x:auto}}</style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+            title = escape(&self.title)
+        );
+        for (heading, body) in self.sections()? {
+            out.push_str(&format!("<h2>{}</h2>\n", escape(heading)));
+            match body {
+                Body::Table { headers, rows } => {
+                    out.push_str("<table>\n<tr>");
+                    for header in headers {
+                        out.push_str(&format!("<th>{}</th>", escape(header)));
+                    }
+                    out.push_str("</tr>\n");
+                    for row in rows {
+                        out.push_str("<tr>");
+                        for cell in row {
+                            out.push_str(&format!("<td>{}</td>", escape(&cell)));
+                        }
+                        out.push_str("</tr>\n");
+                    }
+                    out.push_str("</table>\n");
+                }
+                Body::List(items) => {
+                    out.push_str("<ul>\n");
+                    for item in items {
+                        out.push_str(&format!("<li>{}</li>\n", escape(&item)));
+                    }
+                    out.push_str("</ul>\n");
+                }
+                Body::Code(code) => out.push_str(&format!("<pre>{}</pre>\n", escape(&code))),
+            }
+        }
+        out.push_str("</body>\n</html>\n");
+        Ok(out)
+    }
+
+    fn sections(&self) -> Result<Vec<(&'static str, Body)>> {
+        let tx = self.transaction;
+        let message = &tx.message;
+        let analysis = self.analysis;
+        let fee = self.fee_estimate;
+
+        let overview = vec![
+            vec![
+                "Signature".to_string(),
+                match tx.signatures.first() {
+                    Some(signature) if *signature != [0u8; 64] => bs58::encode(signature).into_string(),
+                    _ => "(unsigned)".to_string(),
+                },
+            ],
+            vec!["Recent blockhash".to_string(), bs58::encode(message.recent_blockhash).into_string()],
+            vec!["Instructions".to_string(), analysis.num_instructions.to_string()],
+        ];
+
+        let accounts = message
+            .account_keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                vec![
+                    i.to_string(),
+                    bs58::encode(key).into_string(),
+                    yes_no(message.is_signer(i)),
+                    yes_no(message.is_writable(i)),
+                ]
+            })
+            .collect();
+
+        let size = vec![
+            vec!["Signatures".to_string(), analysis.signature_bytes.to_string()],
+            vec!["Account keys".to_string(), analysis.account_bytes.to_string()],
+            vec!["Instruction data".to_string(), analysis.instruction_data_bytes.to_string()],
+            vec![
+                "Total".to_string(),
+                format!("{} of {}", analysis.total_size, MAX_TRANSACTION_SIZE),
+            ],
+        ];
+
+        let lamports = |amount: u64| format!("{} ({} SOL)", amount, TokenAmount::new(amount, 9).to_ui_string());
+        let fees = vec![
+            vec!["Base fee".to_string(), lamports(fee.base_fee)],
+            vec![
+                "Priority fee".to_string(),
+                format!("{} microlamports/CU", fee.priority_fee_per_cu),
+            ],
+            vec!["Estimated compute units".to_string(), fee.estimated_compute_units.to_string()],
+            vec!["Total".to_string(), lamports(fee.total_cost)],
+        ];
+
+        let or_none = |items: &[String]| {
+            if items.is_empty() {
+                vec!["None".to_string()]
+            } else {
+                items.to_vec()
+            }
+        };
+
+        Ok(vec![
+            ("Overview", table(vec!["Field", "Value"], overview)),
+            ("Accounts", table(vec!["#", "Address", "Signer", "Writable"], accounts)),
+            ("Size", table(vec!["Part", "Bytes"], size)),
+            ("Fees", table(vec!["Part", "Amount"], fees)),
+            ("Security findings", Body::List(or_none(&self.findings))),
+            (
+                "Suggestions",
+                Body::List(or_none(&analysis.suggestions.iter().map(|s| s.to_string()).collect::<Vec<_>>())),
+            ),
+            ("Disassembly", Body::Code(TerminalRenderer::new().color(false).render(tx)?)),
+        ])
+    }
+}
+
+fn table(headers: Vec<&'static str>, rows: Vec<Vec<String>>) -> Body {
+    Body::Table { headers, rows }
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::{FeeStrategy, PriorityFeeCalculator};
+    use crate::instruction::InstructionEncoder;
+    use crate::optimizer::TransactionOptimizer;
+    use crate::transaction::TransactionBuilder;
+
+    fn sample() -> CompiledTransaction {
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(7).build())
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let tx = sample();
+        let analysis = TransactionOptimizer::default().analyze(&tx);
+        let fee = PriorityFeeCalculator::new().estimate_fee(&tx, FeeStrategy::Medium).unwrap();
+        let markdown = report(&tx, &analysis, &fee)
+            .title("Vault deposit template")
+            .findings(vec!["Writable account not owned by payer".to_string()])
+            .to_markdown()
+            .unwrap();
+
+        assert!(markdown.starts_with("# Vault deposit template\n"));
+        assert!(markdown.contains(&format!("| 0 | {} | yes | yes |", bs58::encode([1u8; 32]).into_string())));
+        assert!(markdown.contains(&format!("| Total | {} of 1232 |", tx.size())));
+        assert!(markdown.contains("- Writable account not owned by payer\n"));
+        assert!(markdown.contains("## Suggestions\n\n- None\n"));
+        assert!(markdown.contains("```text\n000000  01"));
+    }
+
+    #[test]
+    fn test_html_report_is_escaped() {
+        let tx = sample();
+        let analysis = TransactionOptimizer::default().analyze(&tx);
+        let fee = PriorityFeeCalculator::new().estimate_fee(&tx, FeeStrategy::Low).unwrap();
+        let html = report(&tx, &analysis, &fee)
+            .findings(vec!["<script>alert(1)</script>".to_string()])
+            .to_html()
+            .unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<li>&lt;script&gt;alert(1)&lt;/script&gt;</li>"));
+        assert!(!html.contains("\x1b"));
+        assert!(html.contains("<h2>Disassembly</h2>\n<pre>000000"));
+    }
+}
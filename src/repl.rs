@@ -0,0 +1,251 @@
+//! Line-oriented transaction assembly for the `txasm-repl` binary
+//!
+//! A [`Session`] holds a payer, blockhash, named keys and instructions, and
+//! evaluates one line at a time. Instructions use the [`ix!`](crate::ix)
+//! syntax with account roles `r`, `w`, `s`, `ws` and data fields `u8`,
+//! `u16`, `u32`, `u64`, `i64`, `bool`, `pubkey` and `bytes` (hex):
+//!
+//! ```text
+//! txasm> let vault 5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1
+//! txasm> payer 9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM
+//! txasm> ix 11111111111111111111111111111111 [ws payer, w vault] { u32 2, u64 1000000 }
+//! txasm> fee high
+//! ```
+//!
+//! Enabled with the `repl` feature.
+
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::{FeeStrategy, PriorityFeeCalculator};
+use crate::instruction::{InstructionEncoder, RawInstruction};
+use crate::serialization::{parse_hex, parse_pubkey};
+use crate::transaction::{CompiledTransaction, TransactionBuilder, MAX_TRANSACTION_SIZE};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::collections::BTreeMap;
+
+/// Command reference printed by `help`
+pub const HELP: &str = "\
+let <name> <pubkey>      name a key for later lines (`payer` is bound by `payer`)
+payer <key>              set the fee payer
+blockhash <hash>         set the recent blockhash (default all zeros)
+ix <program> [<role> <key>, ...] { <kind> <value>, ... }
+                         add an instruction; roles r w s ws; kinds u8 u16 u32 u64 i64 bool pubkey bytes
+show                     list instructions
+size                     serialized size against the packet limit
+fee [low|medium|high|<price>]
+                         estimated fee (default medium)
+base64                   unsigned transaction as base64
+undo                     remove the last instruction
+reset                    start over
+help                     this text";
+
+/// REPL state
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    names: BTreeMap<String, [u8; 32]>,
+    payer: Option<[u8; 32]>,
+    blockhash: [u8; 32],
+    instructions: Vec<RawInstruction>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evaluate one line, returning the text to print
+    pub fn eval(&mut self, line: &str) -> Result<String> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command {
+            "" => Ok(String::new()),
+            "help" => Ok(HELP.to_string()),
+            "let" => {
+                let (name, key) = rest
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| syntax("expected `let <name> <pubkey>`"))?;
+                let key = self.key(key.trim())?;
+                self.names.insert(name.to_string(), key);
+                Ok(format!("{} = {}", name, bs58::encode(key).into_string()))
+            }
+            "payer" => {
+                let key = self.key(rest)?;
+                self.payer = Some(key);
+                self.names.insert("payer".to_string(), key);
+                Ok(format!("payer = {}", bs58::encode(key).into_string()))
+            }
+            "blockhash" => {
+                self.blockhash = parse_pubkey(rest)?;
+                Ok(format!("blockhash = {}", rest))
+            }
+            "ix" => {
+                let instruction = self.instruction(rest)?;
+                self.instructions.push(instruction);
+                let added = format!("#{} added", self.instructions.len() - 1);
+                // Size needs a payer, which may come later
+                Ok(match self.size_line() {
+                    Ok(size) => format!("{}, {}", added, size),
+                    Err(_) => added,
+                })
+            }
+            "show" => Ok(self
+                .instructions
+                .iter()
+                .enumerate()
+                .map(|(i, ix)| {
+                    format!(
+                        "#{} {} accounts={} data={}",
+                        i,
+                        bs58::encode(ix.program_id).into_string(),
+                        ix.accounts.len(),
+                        ix.data.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")),
+            "size" => self.size_line(),
+            "fee" => {
+                let strategy = match rest {
+                    "" | "medium" => FeeStrategy::Medium,
+                    "low" => FeeStrategy::Low,
+                    "high" => FeeStrategy::High,
+                    price => FeeStrategy::Custom(price.parse().map_err(|_| syntax("expected low, medium, high or a CU price"))?),
+                };
+                let estimate = PriorityFeeCalculator::new().estimate_fee(&self.build()?, strategy)?;
+                Ok(format!(
+                    "{} lamports (base {}, {} microlamports/CU over ~{} CU)",
+                    estimate.total_cost, estimate.base_fee, estimate.priority_fee_per_cu, estimate.estimated_compute_units
+                ))
+            }
+            "base64" => Ok(BASE64.encode(self.build()?.serialize()?)),
+            "undo" => match self.instructions.pop() {
+                Some(_) => Ok(format!("{} instructions", self.instructions.len())),
+                None => Err(syntax("no instructions to undo")),
+            },
+            "reset" => {
+                *self = Self::default();
+                Ok("reset".to_string())
+            }
+            other => Err(syntax(&format!("unknown command `{}`, try `help`", other))),
+        }
+    }
+
+    /// Compile the current instructions, unsigned
+    pub fn build(&self) -> Result<CompiledTransaction> {
+        let payer = self.payer.ok_or_else(|| syntax("set a payer first"))?;
+        TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash(self.blockhash)
+            .add_instructions(self.instructions.clone())
+            .build_unsigned()
+    }
+
+    fn size_line(&self) -> Result<String> {
+        let size = self.build()?.size();
+        Ok(format!(
+            "{} of {} bytes ({} left)",
+            size,
+            MAX_TRANSACTION_SIZE,
+            MAX_TRANSACTION_SIZE as i64 - size as i64
+        ))
+    }
+
+    /// A name bound with `let` or a base58 address
+    fn key(&self, token: &str) -> Result<[u8; 32]> {
+        match self.names.get(token) {
+            Some(key) => Ok(*key),
+            None => parse_pubkey(token),
+        }
+    }
+
+    /// Parse `<program> [<role> <key>, ...] { <kind> <value>, ... }`
+    fn instruction(&self, text: &str) -> Result<RawInstruction> {
+        let (program, rest) = text.split_once('[').ok_or_else(|| syntax("expected `ix <program> [accounts]`"))?;
+        let (accounts, rest) = rest.split_once(']').ok_or_else(|| syntax("unclosed `[`"))?;
+        let mut encoder = InstructionEncoder::new(self.key(program.trim())?);
+
+        for account in accounts.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            let (role, key) = account
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| syntax(&format!("expected `<role> <key>`, got `{}`", account)))?;
+            let key = self.key(key.trim())?;
+            encoder = match role {
+                "r" => encoder.readonly(key),
+                "w" => encoder.writable(key, false),
+                "s" => encoder.signer(key, false),
+                "ws" => encoder.signer(key, true),
+                other => return Err(syntax(&format!("unknown account role `{}`, expected r, w, s or ws", other))),
+            };
+        }
+
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return Ok(encoder.build());
+        }
+        let fields = rest
+            .strip_prefix('{')
+            .and_then(|r| r.strip_suffix('}'))
+            .ok_or_else(|| syntax("expected `{ data }` after the accounts"))?;
+        for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let (kind, value) = field
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| syntax(&format!("expected `<kind> <value>`, got `{}`", field)))?;
+            let value = value.trim();
+            let bad = || syntax(&format!("invalid {} `{}`", kind, value));
+            encoder = match kind {
+                "u8" => encoder.append_u8(value.parse().map_err(|_| bad())?),
+                "u16" => encoder.append_data(&value.parse::<u16>().map_err(|_| bad())?.to_le_bytes()),
+                "u32" => encoder.append_u32(value.parse().map_err(|_| bad())?),
+                "u64" => encoder.append_u64(value.parse().map_err(|_| bad())?),
+                "i64" => encoder.append_data(&value.parse::<i64>().map_err(|_| bad())?.to_le_bytes()),
+                "bool" => encoder.append_u8(value.parse::<bool>().map_err(|_| bad())? as u8),
+                "pubkey" => encoder.append_data(&self.key(value)?),
+                "bytes" => encoder.append_data(&parse_hex(value).ok_or_else(bad)?),
+                other => return Err(syntax(&format!("unknown data field `{}`", other))),
+            };
+        }
+        Ok(encoder.build())
+    }
+}
+
+fn syntax(message: &str) -> TxAsmError {
+    TxAsmError::InvalidInstruction(message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_assembles_transfer() {
+        let mut session = Session::new();
+        let vault = bs58::encode([5u8; 32]).into_string();
+        session.eval(&format!("let vault {}", vault)).unwrap();
+        session.eval(&format!("payer {}", bs58::encode([1u8; 32]).into_string())).unwrap();
+        let added = session
+            .eval("ix 11111111111111111111111111111111 [ws payer, w vault] { u32 2, u64 1000000 }")
+            .unwrap();
+        assert!(added.starts_with("#0 added"));
+
+        let tx = session.build().unwrap();
+        assert_eq!(tx.message.account_keys[1], [5u8; 32]);
+        assert_eq!(tx.message.instructions[0].data[..4], 2u32.to_le_bytes());
+        assert_eq!(session.eval("size").unwrap(), format!("{} of 1232 bytes ({} left)", tx.size(), 1232 - tx.size()));
+        assert_eq!(BASE64.decode(session.eval("base64").unwrap()).unwrap(), tx.serialize().unwrap());
+        assert!(session.eval("fee high").unwrap().contains("1000 microlamports/CU"));
+
+        session.eval("ix vault [] { bytes 0xdead, bool true }").unwrap();
+        assert_eq!(session.build().unwrap().message.instructions[1].data, vec![0xde, 0xad, 1]);
+        assert_eq!(session.eval("undo").unwrap(), "1 instructions");
+    }
+
+    #[test]
+    fn test_session_errors() {
+        let mut session = Session::new();
+        assert!(session.eval("size").unwrap_err().to_string().contains("set a payer first"));
+        assert!(session.eval("ix 11111111111111111111111111111111 [x payer]").is_err());
+        assert!(session.eval("ix 11111111111111111111111111111111 [] { u8 300 }").is_err());
+        assert!(session.eval("frobnicate").unwrap_err().to_string().contains("help"));
+        assert_eq!(session.eval("  ").unwrap(), "");
+    }
+}
@@ -0,0 +1,190 @@
+//! Replay recorded transactions
+//!
+//! [`Replayer`] reads an [`archive`](crate::archive), re-decodes each
+//! transaction, optionally swaps mainnet signers for test keys and
+//! re-targets a fresh blockhash, then hands the result to a [`ReplaySink`]
+//! such as a sender or simulator. Useful for regression-testing bots
+//! against captured mainnet flows.
+
+use crate::archive::{ArchiveReader, ArchiveRecord};
+use crate::error::{Result, TxAsmError};
+use crate::transaction::CompiledTransaction;
+use solana_sdk::signature::{Keypair, Signer};
+use std::io::Read;
+
+/// Destination for replayed transactions
+pub trait ReplaySink {
+    fn submit(&mut self, record: &ArchiveRecord, transaction: &CompiledTransaction) -> Result<()>;
+}
+
+impl<F> ReplaySink for F
+where
+    F: FnMut(&ArchiveRecord, &CompiledTransaction) -> Result<()>,
+{
+    fn submit(&mut self, record: &ArchiveRecord, transaction: &CompiledTransaction) -> Result<()> {
+        self(record, transaction)
+    }
+}
+
+/// Outcome of a replay run
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Records successfully submitted
+    pub replayed: usize,
+    /// Record index and error for every record that failed
+    pub failures: Vec<(usize, TxAsmError)>,
+}
+
+impl ReplayReport {
+    pub fn total(&self) -> usize {
+        self.replayed + self.failures.len()
+    }
+}
+
+/// Re-decodes, re-signs and re-targets archived transactions
+#[derive(Default)]
+pub struct Replayer {
+    recent_blockhash: Option<[u8; 32]>,
+    /// (original key, replacement signer)
+    signers: Vec<([u8; 32], Keypair)>,
+}
+
+impl Replayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace every transaction's blockhash
+    pub fn recent_blockhash(mut self, blockhash: [u8; 32]) -> Self {
+        self.recent_blockhash = Some(blockhash);
+        self
+    }
+
+    /// Re-sign with `signer` wherever it is a required signer
+    pub fn signer(mut self, signer: Keypair) -> Self {
+        self.signers.push((signer.pubkey().to_bytes(), signer));
+        self
+    }
+
+    /// Substitute `original` with a test key and sign as it
+    pub fn substitute_signer(mut self, original: [u8; 32], signer: Keypair) -> Self {
+        self.signers.push((original, signer));
+        self
+    }
+
+    /// Decode and rewrite a single record
+    ///
+    /// If the message changes, stale signatures are cleared; slots without a
+    /// configured signer are left zeroed.
+    pub fn prepare(&self, record: &ArchiveRecord) -> Result<CompiledTransaction> {
+        let mut tx = record.decode_transaction()?;
+        let mut modified = false;
+
+        for (original, signer) in &self.signers {
+            let replacement = signer.pubkey().to_bytes();
+            if *original == replacement {
+                continue;
+            }
+            for key in tx.message.account_keys.iter_mut().filter(|k| *k == original) {
+                *key = replacement;
+                modified = true;
+            }
+        }
+        if let Some(blockhash) = self.recent_blockhash {
+            modified |= tx.message.recent_blockhash != blockhash;
+            tx.message.recent_blockhash = blockhash;
+        }
+        if modified {
+            tx.signatures.iter_mut().for_each(|s| *s = [0u8; 64]);
+        }
+
+        if !self.signers.is_empty() {
+            let message = tx.message_bytes()?;
+            for (_, signer) in &self.signers {
+                let pubkey = signer.pubkey().to_bytes();
+                if tx.required_signers().contains(&pubkey) {
+                    let signature = signer.sign_message(&message);
+                    tx.merge_signature(&pubkey, signature.into())?;
+                }
+            }
+        }
+        Ok(tx)
+    }
+
+    /// Replay every record in `reader` into `sink`
+    ///
+    /// Per-record decode and submit failures are collected in the report;
+    /// errors reading the archive itself abort the run.
+    pub fn run<R: Read>(
+        &self,
+        reader: ArchiveReader<R>,
+        sink: &mut impl ReplaySink,
+    ) -> Result<ReplayReport> {
+        let mut report = ReplayReport::default();
+        for (index, record) in reader.enumerate() {
+            let record = record?;
+            match self.prepare(&record).and_then(|tx| sink.submit(&record, &tx)) {
+                Ok(()) => report.replayed += 1,
+                Err(err) => report.failures.push((index, err)),
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::ArchiveWriter;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use std::io::Cursor;
+
+    fn recorded(payer: [u8; 32]) -> ArchiveRecord {
+        let instruction = InstructionEncoder::new([2u8; 32]).append_u8(1).build();
+        let tx = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap();
+        ArchiveRecord::from_transaction(&tx).unwrap().slot(7)
+    }
+
+    #[test]
+    fn test_prepare_substitutes_and_signs() {
+        let mainnet_payer = [1u8; 32];
+        let test_key = Keypair::new();
+        let test_pubkey = test_key.pubkey().to_bytes();
+        let replayer = Replayer::new()
+            .recent_blockhash([8u8; 32])
+            .substitute_signer(mainnet_payer, test_key);
+
+        let tx = replayer.prepare(&recorded(mainnet_payer)).unwrap();
+        assert_eq!(tx.message.recent_blockhash, [8u8; 32]);
+        assert!(tx.required_signers().contains(&test_pubkey));
+        assert!(!tx.message.account_keys.contains(&mainnet_payer));
+        assert_ne!(tx.signatures[0], [0u8; 64]);
+    }
+
+    #[test]
+    fn test_run_reports_failures() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        writer.write(&recorded([1u8; 32])).unwrap();
+        writer.write(&ArchiveRecord::new(vec![0xff])).unwrap();
+        writer.write(&recorded([4u8; 32])).unwrap();
+        let reader = ArchiveReader::new(Cursor::new(writer.finish().unwrap())).unwrap();
+
+        let mut slots = Vec::new();
+        let mut sink = |record: &ArchiveRecord, _: &CompiledTransaction| {
+            slots.push(record.slot);
+            Ok(())
+        };
+        let report = Replayer::new().run(reader, &mut sink).unwrap();
+
+        assert_eq!(report.replayed, 2);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].0, 1);
+        assert_eq!(slots, vec![Some(7), Some(7)]);
+    }
+}
@@ -0,0 +1,244 @@
+//! Priority fee RPC response parsers
+//!
+//! Turns the JSON bodies of `getRecentPrioritizationFees` (standard, or
+//! Triton's percentile variant, which returns the same shape) and Helius'
+//! `getPriorityFeeEstimate` into the fee calculator's inputs. Bodies may be
+//! the full JSON-RPC envelope or just its `result`; an `error` member is
+//! returned as [`TxAsmError::FeeCalculationError`].
+//!
+//! [`FeeSource`] is the interface fee providers implement; the `helius` and
+//! `triton` features add adapters that speak those providers' APIs over any
+//! [`RpcTransport`].
+
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::{AccountFeeHistory, FeePolicy, FeeStrategy};
+use crate::transaction::CompiledMessage;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Posts a JSON-RPC request body and returns the response body
+///
+/// Implemented for closures so any HTTP client can be plugged in.
+pub trait RpcTransport {
+    fn post(&self, body: &str) -> Result<String>;
+}
+
+impl<F> RpcTransport for F
+where
+    F: Fn(&str) -> Result<String>,
+{
+    fn post(&self, body: &str) -> Result<String> {
+        self(body)
+    }
+}
+
+/// Provider of CU price recommendations
+pub trait FeeSource {
+    /// CU price (microlamports) of the `strategy` tier for a transaction
+    /// write-locking `accounts`
+    fn preset_fee(&self, accounts: &[[u8; 32]], strategy: FeeStrategy) -> Result<u64>;
+
+    /// CU price `policy` settles on for `message`, quoting tiers for its
+    /// writable accounts
+    fn priority_fee(&self, message: &CompiledMessage, policy: &dyn FeePolicy) -> Result<u64> {
+        let writable = writable_accounts(message);
+        policy.micro_lamports_per_cu_quoted(message, &|strategy| self.preset_fee(&writable, strategy))
+    }
+}
+
+/// Accounts `message` write-locks, the ones fee markets are keyed by
+pub(crate) fn writable_accounts(message: &CompiledMessage) -> Vec<[u8; 32]> {
+    message
+        .account_keys
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| message.is_writable(*i))
+        .map(|(_, key)| *key)
+        .collect()
+}
+
+/// Base58 account list, as fee APIs expect it
+#[cfg(any(feature = "helius", feature = "triton"))]
+pub(crate) fn encode_accounts(accounts: &[[u8; 32]]) -> Vec<String> {
+    accounts.iter().map(|key| bs58::encode(key).into_string()).collect()
+}
+
+/// One slot's entry from `getRecentPrioritizationFees`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrioritizationFee {
+    pub slot: u64,
+    /// CU price in microlamports
+    pub prioritization_fee: u64,
+}
+
+/// Parse a `getRecentPrioritizationFees` response
+pub fn parse_recent_prioritization_fees(body: &str) -> Result<Vec<PrioritizationFee>> {
+    unwrap_rpc(body)
+}
+
+/// CU prices from `fees`, for percentile and outrank calculations
+pub fn prices(fees: &[PrioritizationFee]) -> Vec<u64> {
+    fees.iter().map(|fee| fee.prioritization_fee).collect()
+}
+
+/// Record `fees` against every account the request was made for
+///
+/// `getRecentPrioritizationFees` reports fees paid by transactions locking
+/// any of the requested accounts, so each sample applies to all of them.
+pub fn record_fees(history: &mut AccountFeeHistory, accounts: &[[u8; 32]], fees: &[PrioritizationFee]) {
+    for account in accounts {
+        history.extend(*account, fees.iter().map(|fee| fee.prioritization_fee));
+    }
+}
+
+/// Helius priority fee levels, in microlamports per CU
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriorityFeeLevels {
+    pub min: u64,
+    pub low: u64,
+    pub medium: u64,
+    pub high: u64,
+    pub very_high: u64,
+    pub unsafe_max: u64,
+}
+
+impl PriorityFeeLevels {
+    /// CU price of the `strategy` tier; `Custom` prices pass through unchanged
+    pub fn level(&self, strategy: FeeStrategy) -> u64 {
+        match strategy {
+            FeeStrategy::Low => self.low,
+            FeeStrategy::Medium => self.medium,
+            FeeStrategy::High => self.high,
+            FeeStrategy::Custom(price) => price,
+        }
+    }
+
+    /// CU price `policy` settles on for `message` at these levels
+    pub fn price_for(&self, message: &CompiledMessage, policy: impl FeePolicy) -> Result<u64> {
+        policy.micro_lamports_per_cu_quoted(message, &|strategy| Ok(self.level(strategy)))
+    }
+}
+
+/// Parsed Helius `getPriorityFeeEstimate` result
+///
+/// `estimate` is set for a plain request, `levels` when the request asked
+/// for `includeAllPriorityFeeLevels`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriorityFeeEstimate {
+    pub estimate: Option<u64>,
+    pub levels: Option<PriorityFeeLevels>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawEstimate {
+    priority_fee_estimate: Option<f64>,
+    priority_fee_levels: Option<RawLevels>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawLevels {
+    min: f64,
+    low: f64,
+    medium: f64,
+    high: f64,
+    very_high: f64,
+    unsafe_max: f64,
+}
+
+/// Parse a Helius `getPriorityFeeEstimate` response
+///
+/// Helius reports fractional microlamports; prices are rounded up so a
+/// transaction never bids below the estimate.
+pub fn parse_priority_fee_estimate(body: &str) -> Result<PriorityFeeEstimate> {
+    let raw: RawEstimate = unwrap_rpc(body)?;
+    if raw.priority_fee_estimate.is_none() && raw.priority_fee_levels.is_none() {
+        return Err(TxAsmError::DecodingError(
+            "Response has neither priorityFeeEstimate nor priorityFeeLevels".to_string(),
+        ));
+    }
+    Ok(PriorityFeeEstimate {
+        estimate: raw.priority_fee_estimate.map(round_price),
+        levels: raw.priority_fee_levels.map(|levels| PriorityFeeLevels {
+            min: round_price(levels.min),
+            low: round_price(levels.low),
+            medium: round_price(levels.medium),
+            high: round_price(levels.high),
+            very_high: round_price(levels.very_high),
+            unsafe_max: round_price(levels.unsafe_max),
+        }),
+    })
+}
+
+fn round_price(price: f64) -> u64 {
+    // `as` saturates, and maps NaN to 0
+    price.max(0.0).ceil() as u64
+}
+
+/// Deserialize the `result` of a JSON-RPC envelope, or a bare result
+pub(crate) fn unwrap_rpc<T: DeserializeOwned>(body: &str) -> Result<T> {
+    let value: Value = serde_json::from_str(body).map_err(|e| TxAsmError::DecodingError(e.to_string()))?;
+    let result = match value {
+        Value::Object(mut envelope) if ["jsonrpc", "result", "error"].iter().any(|k| envelope.contains_key(*k)) => {
+            if let Some(error) = envelope.remove("error") {
+                let message = error.get("message").and_then(Value::as_str).unwrap_or("unknown error");
+                return Err(TxAsmError::FeeCalculationError(format!("RPC error: {}", message)));
+            }
+            envelope.remove("result").ok_or_else(|| {
+                TxAsmError::DecodingError("JSON-RPC response has no result".to_string())
+            })?
+        }
+        other => other,
+    };
+    serde_json::from_value(result).map_err(|e| TxAsmError::DecodingError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::PriorityFeeCalculator;
+
+    #[test]
+    fn test_recent_prioritization_fees() {
+        let body = r#"{"jsonrpc":"2.0","result":[
+            {"slot":348125,"prioritizationFee":0},
+            {"slot":348126,"prioritizationFee":1000},
+            {"slot":348127,"prioritizationFee":500}
+        ],"id":1}"#;
+        let fees = parse_recent_prioritization_fees(body).unwrap();
+        assert_eq!(fees[1], PrioritizationFee { slot: 348126, prioritization_fee: 1000 });
+
+        let mut history = AccountFeeHistory::new();
+        record_fees(&mut history, &[[5u8; 32]], &fees);
+        assert_eq!(history.percentile(&[5u8; 32], 100), Some(1000));
+        let price = PriorityFeeCalculator::new().price_to_outrank(&prices(&fees), 1).unwrap();
+        assert_eq!(price, 1001);
+
+        let bare = parse_recent_prioritization_fees(r#"[{"slot":1,"prioritizationFee":7}]"#).unwrap();
+        assert_eq!(prices(&bare), vec![7]);
+        let err = parse_recent_prioritization_fees(
+            r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Too many accounts"},"id":1}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Too many accounts"));
+    }
+
+    #[test]
+    fn test_helius_priority_fee_estimate() {
+        let single = parse_priority_fee_estimate(r#"{"jsonrpc":"2.0","result":{"priorityFeeEstimate":1200.5},"id":"1"}"#)
+            .unwrap();
+        assert_eq!(single.estimate, Some(1201));
+        assert!(single.levels.is_none());
+
+        let body = r#"{"jsonrpc":"2.0","result":{"priorityFeeLevels":{
+            "min":0.0,"low":2.0,"medium":10082.0,"high":100000.0,"veryHigh":1000000.0,"unsafeMax":50000000.0
+        }},"id":"1"}"#;
+        let levels = parse_priority_fee_estimate(body).unwrap().levels.unwrap();
+        assert_eq!(levels.level(FeeStrategy::Medium), 10082);
+        assert_eq!(levels.very_high, 1_000_000);
+        assert!(parse_priority_fee_estimate(r#"{"result":{}}"#).is_err());
+    }
+}
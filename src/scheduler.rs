@@ -0,0 +1,693 @@
+//! Transaction scheduling pipeline
+//!
+//! [`Scheduler`] queues prepared transactions with priorities and
+//! dependencies, stamps them with a recent blockhash or durable nonce,
+//! prices them with the fee calculator, and hands them to a [`Sender`] at no
+//! more than a configured rate per leader window. Transactions whose
+//! blockhash expires before confirmation are handled by the
+//! [`ResubmitPolicy`].
+//!
+//! The scheduler is driven by the caller: feed it slots with
+//! [`Scheduler::tick`] and confirmations with [`Scheduler::confirm`].
+//!
+//! Every send is recorded under its [`IdempotencyKey`], and a job whose
+//! stamped transaction matches another job's send is marked
+//! [`JobStatus::Duplicate`] instead of being sent, so submitting the same
+//! logical transaction twice cannot move funds twice. A job may resend its
+//! own transaction; identical bytes carry the same signature and can only
+//! land once.
+
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::compute_budget::{self, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::fee_calculator::{FeePolicy, PriorityFeeCalculator};
+use crate::instruction::{AccountMeta, RawInstruction};
+use crate::transaction::{CompiledTransaction, TransactionBuilder};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+/// Consecutive slots assigned to one leader
+pub const LEADER_WINDOW_SLOTS: u64 = 4;
+
+/// System program `AdvanceNonceAccount` instruction index
+const ADVANCE_NONCE_ACCOUNT: u32 = 4;
+
+/// Sysvar the nonce advance instruction still requires
+const RECENT_BLOCKHASHES_SYSVAR: Pubkey =
+    solana_sdk::pubkey!("SysvarRecentB1ockHashes11111111111111111111");
+
+/// Identifier of a scheduled job
+pub type JobId = u64;
+
+/// Destination for scheduled transactions
+pub trait Sender {
+    fn send(&mut self, transaction: &CompiledTransaction) -> Result<()>;
+}
+
+impl<F> Sender for F
+where
+    F: FnMut(&CompiledTransaction) -> Result<()>,
+{
+    fn send(&mut self, transaction: &CompiledTransaction) -> Result<()> {
+        self(transaction)
+    }
+}
+
+/// Identity of a logical transaction: its payer, lifetime (recent
+/// blockhash or nonce value) and message hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey(pub [u8; 32]);
+
+impl IdempotencyKey {
+    pub fn new(transaction: &CompiledTransaction) -> Result<Self> {
+        let message = &transaction.message;
+        let payer = message.account_keys.first().ok_or_else(|| {
+            TxAsmError::InvalidTransaction("Message has no fee payer".to_string())
+        })?;
+        let message_hash = Sha256::digest(transaction.message_bytes()?);
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"txasm-idempotency:");
+        hasher.update(payer);
+        hasher.update(message.recent_blockhash);
+        hasher.update(message_hash);
+        Ok(Self(hasher.finalize().into()))
+    }
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", bs58::encode(self.0).into_string())
+    }
+}
+
+/// Bounded map of recently sent [`IdempotencyKey`]s to the job that sent
+/// them, oldest evicted first
+#[derive(Debug, Clone)]
+pub struct IdempotencyStore {
+    keys: HashMap<IdempotencyKey, JobId>,
+    order: VecDeque<IdempotencyKey>,
+    capacity: usize,
+}
+
+impl IdempotencyStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            keys: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record that `job` sent `key`, returning the job that sent it first
+    pub fn insert(&mut self, key: IdempotencyKey, job: JobId) -> Option<JobId> {
+        if let Some(owner) = self.keys.get(&key) {
+            return Some(*owner);
+        }
+        self.keys.insert(key, job);
+        self.order.push_back(key);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.keys.remove(&oldest);
+            }
+        }
+        None
+    }
+
+    /// Job that sent `key`, if it is still remembered
+    pub fn get(&self, key: &IdempotencyKey) -> Option<JobId> {
+        self.keys.get(key).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// What to do with a transaction whose blockhash expired unconfirmed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResubmitPolicy {
+    /// Mark the job expired
+    Drop,
+    /// Re-stamp and resend, up to `max_attempts` sends in total
+    Retry { max_attempts: u32 },
+}
+
+/// Source of the transaction lifetime
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockhashSource {
+    /// Recent blockhash valid through `last_valid_slot`
+    Recent {
+        blockhash: [u8; 32],
+        last_valid_slot: u64,
+    },
+    /// Durable nonce; an `AdvanceNonceAccount` instruction is prepended
+    Nonce {
+        nonce_account: [u8; 32],
+        authority: [u8; 32],
+        nonce_value: [u8; 32],
+    },
+}
+
+/// A prepared transaction awaiting scheduling
+#[derive(Clone)]
+pub struct Job {
+    builder: TransactionBuilder,
+    signers: Vec<Arc<Keypair>>,
+    priority: u64,
+    depends_on: Vec<JobId>,
+}
+
+impl Job {
+    /// `builder` needs a payer; its blockhash is assigned by the scheduler
+    pub fn new(builder: TransactionBuilder, signers: Vec<Arc<Keypair>>) -> Self {
+        Self {
+            builder,
+            signers,
+            priority: 0,
+            depends_on: Vec::new(),
+        }
+    }
+
+    /// Higher priorities are sent first
+    pub fn priority(mut self, priority: u64) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Hold this job until `job` is confirmed
+    pub fn depends_on(mut self, job: JobId) -> Self {
+        self.depends_on.push(job);
+        self
+    }
+}
+
+/// Lifecycle of a scheduled job
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Pending { attempts: u32 },
+    Sent { attempts: u32, last_valid_slot: Option<u64> },
+    Confirmed,
+    Expired,
+    Failed(String),
+    /// Stamped to the same transaction another job already sent
+    Duplicate { of: JobId, key: IdempotencyKey },
+}
+
+/// Scheduler settings
+#[derive(Clone)]
+pub struct SchedulerConfig {
+    /// Sends allowed per leader window
+    pub max_per_leader_window: usize,
+    pub resubmit: ResubmitPolicy,
+    /// Prepend a `SetComputeUnitPrice` instruction priced by this policy
+    pub fee_policy: Option<Arc<dyn FeePolicy>>,
+    /// Sent transactions remembered for duplicate suppression
+    pub idempotency_capacity: usize,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_per_leader_window: 64,
+            resubmit: ResubmitPolicy::Retry { max_attempts: 3 },
+            fee_policy: None,
+            idempotency_capacity: 4096,
+        }
+    }
+}
+
+impl fmt::Debug for SchedulerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SchedulerConfig")
+            .field("max_per_leader_window", &self.max_per_leader_window)
+            .field("resubmit", &self.resubmit)
+            .field("fee_policy", &self.fee_policy.as_ref().map(|_| ".."))
+            .field("idempotency_capacity", &self.idempotency_capacity)
+            .finish()
+    }
+}
+
+/// Jobs affected by one [`Scheduler::tick`]
+#[derive(Debug, Default, PartialEq)]
+pub struct TickReport {
+    pub sent: Vec<JobId>,
+    pub expired: Vec<JobId>,
+    pub failed: Vec<JobId>,
+    pub duplicates: Vec<JobId>,
+}
+
+struct Entry {
+    job: Job,
+    status: JobStatus,
+}
+
+/// Priority/dependency-aware submission queue
+pub struct Scheduler<S: Sender> {
+    sender: S,
+    config: SchedulerConfig,
+    calculator: PriorityFeeCalculator,
+    blockhash: Option<BlockhashSource>,
+    jobs: BTreeMap<JobId, Entry>,
+    next_id: JobId,
+    /// (leader window index, sends in that window)
+    window: (u64, usize),
+    sent_keys: IdempotencyStore,
+}
+
+impl<S: Sender> Scheduler<S> {
+    pub fn new(sender: S, config: SchedulerConfig) -> Self {
+        Self {
+            sender,
+            sent_keys: IdempotencyStore::new(config.idempotency_capacity),
+            config,
+            calculator: PriorityFeeCalculator::new(),
+            blockhash: None,
+            jobs: BTreeMap::new(),
+            next_id: 0,
+            window: (0, 0),
+        }
+    }
+
+    /// Use a custom fee calculator for `fee_policy` pricing
+    pub fn with_calculator(mut self, calculator: PriorityFeeCalculator) -> Self {
+        self.calculator = calculator;
+        self
+    }
+
+    /// Blockhash or nonce stamped on subsequent sends
+    pub fn set_blockhash(&mut self, source: BlockhashSource) {
+        self.blockhash = Some(source);
+    }
+
+    /// Queue a job; its dependencies must already be scheduled
+    pub fn submit(&mut self, job: Job) -> Result<JobId> {
+        if let Some(missing) = job.depends_on.iter().find(|id| !self.jobs.contains_key(id)) {
+            return Err(TxAsmError::InvalidTransaction(format!(
+                "Unknown dependency job {}",
+                missing
+            )));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.jobs.insert(
+            id,
+            Entry {
+                job,
+                status: JobStatus::Pending { attempts: 0 },
+            },
+        );
+        Ok(id)
+    }
+
+    /// Mark a sent job as confirmed, releasing its dependents
+    pub fn confirm(&mut self, id: JobId) -> Result<()> {
+        let entry = self.jobs.get_mut(&id).ok_or_else(|| {
+            TxAsmError::InvalidTransaction(format!("Unknown job {}", id))
+        })?;
+        entry.status = JobStatus::Confirmed;
+        Ok(())
+    }
+
+    pub fn status(&self, id: JobId) -> Option<&JobStatus> {
+        self.jobs.get(&id).map(|e| &e.status)
+    }
+
+    /// Number of jobs not yet confirmed, expired or failed
+    pub fn in_flight(&self) -> usize {
+        self.jobs
+            .values()
+            .filter(|e| matches!(e.status, JobStatus::Pending { .. } | JobStatus::Sent { .. }))
+            .count()
+    }
+
+    /// Advance to `current_slot`: expire stale sends, then send ready jobs
+    pub fn tick(&mut self, current_slot: u64) -> TickReport {
+        let mut report = TickReport::default();
+        self.expire(current_slot, &mut report);
+        self.fail_orphans(&mut report);
+        self.send_ready(current_slot, &mut report);
+        report
+    }
+
+    fn expire(&mut self, current_slot: u64, report: &mut TickReport) {
+        let policy = self.config.resubmit;
+        for (id, entry) in self.jobs.iter_mut() {
+            let JobStatus::Sent { attempts, last_valid_slot: Some(last_valid) } = entry.status else {
+                continue;
+            };
+            if current_slot <= last_valid {
+                continue;
+            }
+            entry.status = match policy {
+                ResubmitPolicy::Retry { max_attempts } if attempts < max_attempts => {
+                    JobStatus::Pending { attempts }
+                }
+                _ => {
+                    report.expired.push(*id);
+                    JobStatus::Expired
+                }
+            };
+        }
+    }
+
+    /// Fail pending jobs whose dependencies can no longer confirm
+    fn fail_orphans(&mut self, report: &mut TickReport) {
+        loop {
+            let orphans: Vec<JobId> = self
+                .jobs
+                .iter()
+                .filter(|(_, e)| matches!(e.status, JobStatus::Pending { .. }))
+                .filter(|(_, e)| {
+                    e.job.depends_on.iter().any(|dep| {
+                        matches!(
+                            self.jobs[dep].status,
+                            JobStatus::Expired | JobStatus::Failed(_)
+                        )
+                    })
+                })
+                .map(|(id, _)| *id)
+                .collect();
+            if orphans.is_empty() {
+                return;
+            }
+            for id in orphans {
+                self.jobs.get_mut(&id).unwrap().status =
+                    JobStatus::Failed("Dependency did not confirm".to_string());
+                report.failed.push(id);
+            }
+        }
+    }
+
+    fn send_ready(&mut self, current_slot: u64, report: &mut TickReport) {
+        let Some(source) = self.blockhash.clone() else {
+            return;
+        };
+        if let BlockhashSource::Recent { last_valid_slot, .. } = source {
+            if current_slot > last_valid_slot {
+                return;
+            }
+        }
+
+        let window = current_slot / LEADER_WINDOW_SLOTS;
+        if self.window.0 != window {
+            self.window = (window, 0);
+        }
+
+        let mut ready: Vec<(u64, JobId)> = self
+            .jobs
+            .iter()
+            .filter(|(_, e)| matches!(e.status, JobStatus::Pending { .. }))
+            .filter(|(_, e)| {
+                e.job
+                    .depends_on
+                    .iter()
+                    .all(|dep| self.jobs[dep].status == JobStatus::Confirmed)
+            })
+            .map(|(id, e)| (e.job.priority, *id))
+            .collect();
+        ready.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        for (_, id) in ready {
+            if self.window.1 >= self.config.max_per_leader_window {
+                break;
+            }
+            let entry = &self.jobs[&id];
+            let JobStatus::Pending { attempts } = entry.status else {
+                continue;
+            };
+
+            let stamped = self
+                .stamp(&entry.job, &source)
+                .and_then(|tx| Ok((IdempotencyKey::new(&tx)?, tx)));
+            let outcome = match stamped {
+                Ok((key, tx)) => match self.sent_keys.get(&key) {
+                    Some(of) if of != id => {
+                        self.jobs.get_mut(&id).unwrap().status = JobStatus::Duplicate { of, key };
+                        report.duplicates.push(id);
+                        continue;
+                    }
+                    _ => self.sender.send(&tx).map(|()| key),
+                },
+                Err(err) => Err(err),
+            };
+            let entry = self.jobs.get_mut(&id).unwrap();
+            match outcome {
+                Ok(key) => {
+                    self.sent_keys.insert(key, id);
+                    let last_valid_slot = match source {
+                        BlockhashSource::Recent { last_valid_slot, .. } => Some(last_valid_slot),
+                        BlockhashSource::Nonce { .. } => None,
+                    };
+                    entry.status = JobStatus::Sent {
+                        attempts: attempts + 1,
+                        last_valid_slot,
+                    };
+                    self.window.1 += 1;
+                    report.sent.push(id);
+                }
+                Err(err) => {
+                    entry.status = JobStatus::Failed(err.to_string());
+                    report.failed.push(id);
+                }
+            }
+        }
+    }
+
+    /// Build and sign `job` against `source`
+    fn stamp(&self, job: &Job, source: &BlockhashSource) -> Result<CompiledTransaction> {
+        let (blockhash, advance) = match source {
+            BlockhashSource::Recent { blockhash, .. } => (*blockhash, None),
+            BlockhashSource::Nonce {
+                nonce_account,
+                authority,
+                nonce_value,
+            } => {
+                let advance = RawInstruction::new(
+                    [0u8; 32],
+                    vec![
+                        AccountMeta::new(*nonce_account, false, true),
+                        AccountMeta::new(RECENT_BLOCKHASHES_SYSVAR.to_bytes(), false, false),
+                        AccountMeta::new(*authority, true, false),
+                    ],
+                    ADVANCE_NONCE_ACCOUNT.to_le_bytes().to_vec(),
+                );
+                (*nonce_value, Some(advance))
+            }
+        };
+
+        let mut builder = job.builder.clone().recent_blockhash(blockhash);
+        if let Some(policy) = &self.config.fee_policy {
+            // Policies price the job as submitted, before the scheduler's own instructions
+            let price = match policy.fixed_micro_lamports_per_cu() {
+                Some(price) => price,
+                None => self.calculator.get_priority_fee(&builder.clone().compile()?, policy.as_ref()),
+            };
+            builder = builder.insert_instruction(
+                0,
+                RawInstruction::new(
+                    COMPUTE_BUDGET_PROGRAM_ID,
+                    vec![],
+                    compute_budget::create_compute_unit_price_instruction(price),
+                ),
+            )?;
+        }
+        if let Some(advance) = advance {
+            // The advance instruction must come first for the nonce to apply
+            builder = builder.insert_instruction(0, advance)?;
+        }
+
+        let mut tx = builder.build_unsigned()?;
+        let message = tx.message_bytes()?;
+        for signer in &job.signers {
+            let signature = signer.sign_message(&message);
+            tx.merge_signature(&signer.pubkey().to_bytes(), signature.into())?;
+        }
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::FeeStrategy;
+    use crate::instruction::InstructionEncoder;
+
+    fn job(payer: &Arc<Keypair>, tag: u8) -> Job {
+        let builder = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .add_instruction(InstructionEncoder::new([2u8; 32]).append_u8(tag).build());
+        Job::new(builder, vec![payer.clone()])
+    }
+
+    fn recent(last_valid_slot: u64) -> BlockhashSource {
+        BlockhashSource::Recent {
+            blockhash: [3u8; 32],
+            last_valid_slot,
+        }
+    }
+
+    #[test]
+    fn test_priority_dependencies_and_throttle() {
+        let payer = Arc::new(Keypair::new());
+        let mut sent = Vec::new();
+        let config = SchedulerConfig {
+            max_per_leader_window: 2,
+            fee_policy: Some(Arc::new(FeeStrategy::High)),
+            ..Default::default()
+        };
+        let mut scheduler = Scheduler::new(
+            |tx: &CompiledTransaction| {
+                assert_ne!(tx.signatures[0], [0u8; 64]);
+                sent.push(tx.message.instructions.len());
+                Ok(())
+            },
+            config,
+        );
+        scheduler.set_blockhash(recent(100));
+
+        let a = scheduler.submit(job(&payer, 1)).unwrap();
+        let b = scheduler.submit(job(&payer, 2).priority(10)).unwrap();
+        let c = scheduler.submit(job(&payer, 3).priority(99).depends_on(a)).unwrap();
+        let d = scheduler.submit(job(&payer, 4)).unwrap();
+        assert!(scheduler.submit(job(&payer, 5).depends_on(42)).is_err());
+
+        // Two per window, highest priority first; c waits on a
+        assert_eq!(scheduler.tick(0).sent, vec![b, a]);
+        assert!(scheduler.tick(1).sent.is_empty());
+        scheduler.confirm(a).unwrap();
+        assert_eq!(scheduler.tick(4).sent, vec![c, d]);
+        assert_eq!(scheduler.in_flight(), 3);
+        drop(scheduler);
+        // Compute unit price instruction was prepended to every send
+        assert!(sent.iter().all(|n| *n == 2));
+    }
+
+    #[test]
+    fn test_fee_policy_sees_job_message() {
+        let payer = Arc::new(Keypair::new());
+        let mut prices = Vec::new();
+        let by_tag = |message: &crate::transaction::CompiledMessage| message.instructions[0].data[0] as u64 * 100;
+        let config = SchedulerConfig {
+            fee_policy: Some(Arc::new(by_tag)),
+            ..Default::default()
+        };
+        let mut scheduler = Scheduler::new(
+            |tx: &CompiledTransaction| {
+                prices.push(compute_budget::parse_compute_unit_price(&tx.message.instructions[0].data));
+                Ok(())
+            },
+            config,
+        );
+        scheduler.set_blockhash(recent(100));
+        scheduler.submit(job(&payer, 3)).unwrap();
+        scheduler.submit(job(&payer, 7)).unwrap();
+        scheduler.tick(0);
+        drop(scheduler);
+        assert_eq!(prices, vec![Some(300), Some(700)]);
+    }
+
+    #[test]
+    fn test_expiry_resubmits_then_fails_dependents() {
+        let payer = Arc::new(Keypair::new());
+        let config = SchedulerConfig {
+            resubmit: ResubmitPolicy::Retry { max_attempts: 2 },
+            ..Default::default()
+        };
+        let mut scheduler = Scheduler::new(|_: &CompiledTransaction| Ok(()), config);
+        scheduler.set_blockhash(recent(10));
+
+        let a = scheduler.submit(job(&payer, 1)).unwrap();
+        let b = scheduler.submit(job(&payer, 2).depends_on(a)).unwrap();
+        assert_eq!(scheduler.tick(5).sent, vec![a]);
+
+        // Expired unconfirmed; resent once a fresh blockhash arrives
+        scheduler.set_blockhash(recent(20));
+        assert_eq!(scheduler.tick(11).sent, vec![a]);
+        assert_eq!(
+            scheduler.status(a),
+            Some(&JobStatus::Sent { attempts: 2, last_valid_slot: Some(20) })
+        );
+
+        let report = scheduler.tick(21);
+        assert_eq!(report.expired, vec![a]);
+        assert_eq!(report.failed, vec![b]);
+    }
+
+    #[test]
+    fn test_durable_nonce_prepends_advance() {
+        let payer = Arc::new(Keypair::new());
+        let mut programs = Vec::new();
+        let mut scheduler = Scheduler::new(
+            |tx: &CompiledTransaction| {
+                let first = &tx.message.instructions[0];
+                programs.push(tx.message.account_keys[first.program_id_index as usize]);
+                assert_eq!(tx.message.recent_blockhash, [7u8; 32]);
+                Ok(())
+            },
+            SchedulerConfig::default(),
+        );
+        scheduler.set_blockhash(BlockhashSource::Nonce {
+            nonce_account: [8u8; 32],
+            authority: payer.pubkey().to_bytes(),
+            nonce_value: [7u8; 32],
+        });
+        let id = scheduler.submit(job(&payer, 1)).unwrap();
+        assert_eq!(scheduler.tick(1_000).sent, vec![id]);
+        // Nonce transactions never expire by slot
+        assert!(scheduler.tick(1_000_000).expired.is_empty());
+        drop(scheduler);
+        assert_eq!(programs, vec![[0u8; 32]]);
+    }
+
+    #[test]
+    fn test_duplicate_submissions_are_suppressed() {
+        let payer = Arc::new(Keypair::new());
+        let mut sends = 0;
+        let mut scheduler = Scheduler::new(
+            |_: &CompiledTransaction| {
+                sends += 1;
+                Ok(())
+            },
+            SchedulerConfig::default(),
+        );
+        scheduler.set_blockhash(recent(100));
+
+        let first = scheduler.submit(job(&payer, 1)).unwrap();
+        let again = scheduler.submit(job(&payer, 1)).unwrap();
+        let other = scheduler.submit(job(&payer, 2)).unwrap();
+        let report = scheduler.tick(0);
+        assert_eq!(report.sent, vec![first, other]);
+        assert_eq!(report.duplicates, vec![again]);
+        assert!(matches!(scheduler.status(again), Some(JobStatus::Duplicate { of, .. }) if *of == first));
+
+        // A fresh blockhash makes a different logical transaction
+        scheduler.confirm(first).unwrap();
+        scheduler.confirm(other).unwrap();
+        scheduler.set_blockhash(BlockhashSource::Recent { blockhash: [4u8; 32], last_valid_slot: 200 });
+        let later = scheduler.submit(job(&payer, 1)).unwrap();
+        assert_eq!(scheduler.tick(101).sent, vec![later]);
+        drop(scheduler);
+        assert_eq!(sends, 3);
+    }
+
+    #[test]
+    fn test_idempotency_store_evicts_oldest() {
+        let mut store = IdempotencyStore::new(2);
+        let keys = [IdempotencyKey([1u8; 32]), IdempotencyKey([2u8; 32]), IdempotencyKey([3u8; 32])];
+        assert_eq!(store.insert(keys[0], 0), None);
+        assert_eq!(store.insert(keys[0], 1), Some(0));
+        assert_eq!(store.insert(keys[1], 1), None);
+        assert_eq!(store.insert(keys[2], 2), None);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(&keys[0]), None);
+        assert_eq!(store.get(&keys[2]), Some(2));
+    }
+}
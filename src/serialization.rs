@@ -54,6 +54,14 @@ pub fn decode_compact_u16(cursor: &mut Cursor<&[u8]>) -> Result<u16> {
         let byte = data[position];
         cursor.set_position(position as u64 + 1);
 
+        // The third byte carries the top two bits only
+        if shift == 14 && byte > 0x03 {
+            return Err(TxAsmError::DeserializationError(if byte & 0x80 != 0 {
+                "Invalid compact-u16 encoding: longer than 3 bytes".to_string()
+            } else {
+                format!("Invalid compact-u16 encoding: value exceeds {}", u16::MAX)
+            }));
+        }
         value |= ((byte & 0x7f) as u16) << shift;
 
         if byte & 0x80 == 0 {
@@ -63,14 +71,35 @@ pub fn decode_compact_u16(cursor: &mut Cursor<&[u8]>) -> Result<u16> {
         shift += 7;
     }
 
-    Err(TxAsmError::DeserializationError(
-        "Invalid compact-u16 encoding".to_string()
-    ))
+    unreachable!("third compact-u16 byte always terminates")
+}
+
+/// Convert a collection length to u16, the widest length a compact-u16 holds
+pub fn checked_len(what: &'static str, len: usize) -> Result<u16> {
+    u16::try_from(len).map_err(|_| TxAsmError::LengthOverflow {
+        what,
+        len,
+        max: u16::MAX as usize,
+    })
+}
+
+/// Encode a collection length as a compact-u16, rejecting lengths above u16
+pub fn encode_len(what: &'static str, len: usize, writer: &mut Vec<u8>) -> Result<()> {
+    encode_compact_u16(checked_len(what, len)?, writer)
+}
+
+/// Encoded length of `value` as a compact-u16
+pub fn compact_u16_len(value: usize) -> usize {
+    match value {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
 }
 
 /// Manual encoding of length-prefixed byte arrays
 pub fn encode_length_prefixed(data: &[u8], writer: &mut Vec<u8>) -> Result<()> {
-    encode_compact_u16(data.len() as u16, writer)?;
+    encode_len("byte array", data.len(), writer)?;
     writer.write_all(data)?;
     Ok(())
 }
@@ -117,6 +146,33 @@ pub fn decode_pubkey(cursor: &mut Cursor<&[u8]>) -> Result<[u8; 32]> {
     Ok(pubkey)
 }
 
+/// Parse hex bytes, with or without a `0x` prefix
+pub(crate) fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Lowercase hex without a prefix
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parse a base58 public key
+pub fn parse_pubkey(encoded: &str) -> Result<[u8; 32]> {
+    let bytes = bs58::decode(encoded)
+        .into_vec()
+        .map_err(|e| TxAsmError::InvalidPublicKey(format!("{}: {}", encoded, e)))?;
+    bytes
+        .try_into()
+        .map_err(|_| TxAsmError::InvalidPublicKey(format!("{} is not 32 bytes", encoded)))
+}
+
 /// Encode a u64 in little-endian format
 pub fn encode_u64(value: u64, writer: &mut Vec<u8>) -> Result<()> {
     writer.write_all(&value.to_le_bytes())?;
@@ -170,7 +226,7 @@ pub mod helpers {
 
     /// Serialize a vector with length prefix
     pub fn serialize_vec<T: ByteSerialize>(items: &[T], writer: &mut Vec<u8>) -> Result<()> {
-        encode_compact_u16(items.len() as u16, writer)?;
+        encode_len("vector", items.len(), writer)?;
         for item in items {
             item.serialize_bytes(writer)?;
         }
@@ -235,4 +291,23 @@ mod tests {
         let decoded = decode_u64(&mut cursor).unwrap();
         assert_eq!(decoded, 0x1234567890ABCDEF);
     }
+
+    #[test]
+    fn test_compact_u16_limits() {
+        let mut buf = Vec::new();
+        encode_compact_u16(u16::MAX, &mut buf).unwrap();
+        assert_eq!(buf, [0xff, 0xff, 0x03]);
+        assert_eq!(decode_compact_u16(&mut Cursor::new(&buf[..])).unwrap(), u16::MAX);
+
+        let err = decode_compact_u16(&mut Cursor::new(&[0xff, 0xff, 0x04][..])).unwrap_err();
+        assert!(err.to_string().contains("exceeds 65535"));
+        let err = decode_compact_u16(&mut Cursor::new(&[0x80, 0x80, 0x80, 0x00][..])).unwrap_err();
+        assert!(err.to_string().contains("longer than 3 bytes"));
+
+        let err = encode_len("account keys", 70_000, &mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            TxAsmError::LengthOverflow { what: "account keys", len: 70_000, max: 65535 }
+        ));
+    }
 }
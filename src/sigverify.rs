@@ -0,0 +1,100 @@
+//! Strict ed25519 signature verification
+//!
+//! The runtime verifies signatures with ed25519-dalek's `verify_strict`,
+//! which rejects small-order keys and `R` points. On top of that,
+//! [`verify_strict`] requires the signature encoding itself to be canonical
+//! (`R.y < p`, `S < L`), so a relayer never accepts, and forwards, a
+//! malleated copy of a signature it has already seen.
+
+use crate::error::{Result, TxAsmError};
+use solana_sdk::signature::Signature;
+
+/// Order of the ed25519 base point, little endian
+const GROUP_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Field prime 2^255 - 19, little endian
+const FIELD_PRIME: [u8; 32] = [
+    0xed, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+];
+
+/// `a < b` for little-endian 256-bit integers
+fn less_than(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().rev().cmp(b.iter().rev()).is_lt()
+}
+
+/// Whether `signature` uses the canonical encoding of both `R` and `S`
+pub fn is_canonical(signature: &[u8; 64]) -> bool {
+    let mut r_y = [0u8; 32];
+    r_y.copy_from_slice(&signature[..32]);
+    r_y[31] &= 0x7f;
+    let mut s = [0u8; 32];
+    s.copy_from_slice(&signature[32..]);
+    less_than(&r_y, &FIELD_PRIME) && less_than(&s, &GROUP_ORDER)
+}
+
+/// Verify `signature` over `message` under the runtime's strict rules,
+/// additionally rejecting non-canonical encodings
+pub fn verify_strict(pubkey: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> Result<()> {
+    let signer = bs58::encode(pubkey).into_string();
+    if !is_canonical(signature) {
+        return Err(TxAsmError::SignatureError(format!(
+            "{} signature is not canonically encoded",
+            signer
+        )));
+    }
+    if !Signature::from(*signature).verify(pubkey, message) {
+        return Err(TxAsmError::SignatureError(format!(
+            "{} signature does not verify",
+            signer
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    /// Add the group order to the little-endian scalar in `s`
+    fn add_group_order(s: &mut [u8]) {
+        let mut carry = 0u16;
+        for (byte, order) in s.iter_mut().zip(GROUP_ORDER) {
+            let sum = *byte as u16 + order as u16 + carry;
+            *byte = sum as u8;
+            carry = sum >> 8;
+        }
+    }
+
+    #[test]
+    fn test_verify_strict() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey().to_bytes();
+        let signature: [u8; 64] = keypair.sign_message(b"hello").into();
+
+        assert!(is_canonical(&signature));
+        assert!(verify_strict(&pubkey, b"hello", &signature).is_ok());
+        assert!(verify_strict(&pubkey, b"world", &signature).is_err());
+    }
+
+    #[test]
+    fn test_malleated_signature_rejected() {
+        let keypair = Keypair::new();
+        let signature: [u8; 64] = keypair.sign_message(b"hello").into();
+
+        // S + L verifies under the plain equation but is a different encoding
+        let mut malleated = signature;
+        add_group_order(&mut malleated[32..]);
+        assert!(!is_canonical(&malleated));
+        let err = verify_strict(&keypair.pubkey().to_bytes(), b"hello", &malleated).unwrap_err();
+        assert!(err.to_string().contains("canonical"));
+
+        let mut high_r = signature;
+        high_r[..32].copy_from_slice(&FIELD_PRIME);
+        assert!(!is_canonical(&high_r));
+    }
+}
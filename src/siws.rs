@@ -0,0 +1,355 @@
+//! Sign-In-With-Solana (SIWS) messages
+//!
+//! Builds the plain-text sign-in payload defined by the SIWS spec (the
+//! Solana flavour of CAIP-122 / EIP-4361), parses it back from text and
+//! verifies the ed25519 signature a wallet returns over it.
+//!
+//! Timestamps are carried as ISO 8601 strings exactly as they appear in the
+//! message; checking them against the current time is left to the caller.
+
+use crate::error::{Result, TxAsmError};
+use solana_sdk::signature::Signature;
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Solana account:";
+
+const FIELD_LABELS: [&str; 8] = [
+    "URI",
+    "Version",
+    "Chain ID",
+    "Nonce",
+    "Issued At",
+    "Expiration Time",
+    "Not Before",
+    "Request ID",
+];
+
+/// Whether `line` opens the field section rather than being a statement
+fn is_field(line: &str) -> bool {
+    line == "Resources:" || line.split_once(": ").is_some_and(|(label, _)| FIELD_LABELS.contains(&label))
+}
+
+/// A SIWS sign-in message
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SiwsMessage {
+    pub domain: String,
+    pub address: [u8; 32],
+    pub statement: Option<String>,
+    pub uri: Option<String>,
+    pub version: Option<String>,
+    pub chain_id: Option<String>,
+    pub nonce: Option<String>,
+    pub issued_at: Option<String>,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+impl SiwsMessage {
+    /// Create a message for `address` signing in to `domain`
+    pub fn new(domain: impl Into<String>, address: [u8; 32]) -> Self {
+        Self {
+            domain: domain.into(),
+            address,
+            version: Some("1".to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn statement(mut self, statement: impl Into<String>) -> Self {
+        self.statement = Some(statement.into());
+        self
+    }
+
+    pub fn uri(mut self, uri: impl Into<String>) -> Self {
+        self.uri = Some(uri.into());
+        self
+    }
+
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Set the chain ID (e.g. "mainnet", "devnet")
+    pub fn chain_id(mut self, chain_id: impl Into<String>) -> Self {
+        self.chain_id = Some(chain_id.into());
+        self
+    }
+
+    pub fn nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Set the issued-at timestamp (ISO 8601)
+    pub fn issued_at(mut self, issued_at: impl Into<String>) -> Self {
+        self.issued_at = Some(issued_at.into());
+        self
+    }
+
+    /// Set the expiration timestamp (ISO 8601)
+    pub fn expiration_time(mut self, expiration_time: impl Into<String>) -> Self {
+        self.expiration_time = Some(expiration_time.into());
+        self
+    }
+
+    /// Set the not-before timestamp (ISO 8601)
+    pub fn not_before(mut self, not_before: impl Into<String>) -> Self {
+        self.not_before = Some(not_before.into());
+        self
+    }
+
+    pub fn request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    pub fn resource(mut self, resource: impl Into<String>) -> Self {
+        self.resources.push(resource.into());
+        self
+    }
+
+    /// Check the fields the spec constrains
+    pub fn validate(&self) -> Result<()> {
+        if self.domain.is_empty() || self.domain.contains('\n') {
+            return Err(TxAsmError::EncodingError("Invalid SIWS domain".to_string()));
+        }
+        if let Some(statement) = &self.statement {
+            if statement.contains('\n') {
+                return Err(TxAsmError::EncodingError(
+                    "SIWS statement must be a single line".to_string(),
+                ));
+            }
+        }
+        if let Some(nonce) = &self.nonce {
+            if nonce.len() < 8 || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(TxAsmError::EncodingError(
+                    "SIWS nonce must be at least 8 alphanumeric characters".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the message text the wallet signs
+    pub fn to_text(&self) -> String {
+        let mut message = format!(
+            "{}{}\n{}",
+            self.domain,
+            HEADER_SUFFIX,
+            bs58::encode(self.address).into_string()
+        );
+
+        if let Some(statement) = &self.statement {
+            message.push_str("\n\n");
+            message.push_str(statement);
+        }
+
+        let mut fields = Vec::new();
+        let optional = [
+            ("URI", &self.uri),
+            ("Version", &self.version),
+            ("Chain ID", &self.chain_id),
+            ("Nonce", &self.nonce),
+            ("Issued At", &self.issued_at),
+            ("Expiration Time", &self.expiration_time),
+            ("Not Before", &self.not_before),
+            ("Request ID", &self.request_id),
+        ];
+        for (label, value) in optional {
+            if let Some(value) = value {
+                fields.push(format!("{}: {}", label, value));
+            }
+        }
+        if !self.resources.is_empty() {
+            fields.push("Resources:".to_string());
+            fields.extend(self.resources.iter().map(|r| format!("- {}", r)));
+        }
+
+        if !fields.is_empty() {
+            message.push_str("\n\n");
+            message.push_str(&fields.join("\n"));
+        }
+        message
+    }
+
+    /// Serialize the message for signing
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        self.validate()?;
+        Ok(self.to_text().into_bytes())
+    }
+
+    /// Parse a message from its text form
+    pub fn parse(text: &str) -> Result<Self> {
+        let malformed = |what: &str| TxAsmError::DecodingError(format!("Malformed SIWS message: {}", what));
+        let mut lines = text.split('\n');
+
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(HEADER_SUFFIX))
+            .ok_or_else(|| malformed("missing header"))?;
+        let address_line = lines.next().ok_or_else(|| malformed("missing address"))?;
+        let address: [u8; 32] = bs58::decode(address_line)
+            .into_vec()?
+            .try_into()
+            .map_err(|_| TxAsmError::InvalidPublicKey(address_line.to_string()))?;
+
+        let mut message = Self {
+            domain: domain.to_string(),
+            address,
+            ..Default::default()
+        };
+
+        let rest: Vec<&str> = lines.collect();
+        let mut index = 0;
+        // A statement is a single line preceded by a blank line and followed
+        // by either the end of the message or another blank line. A lone
+        // line at the end is only read as a field if it looks like one.
+        let has_statement = match rest.len() {
+            0 | 1 => false,
+            2 => !is_field(rest[1]),
+            _ => rest[2].is_empty(),
+        };
+        if has_statement && rest[0].is_empty() {
+            message.statement = Some(rest[1].to_string());
+            index = 2;
+        }
+        if index < rest.len() {
+            if !rest[index].is_empty() {
+                return Err(malformed("expected blank line before fields"));
+            }
+            index += 1;
+        }
+
+        let mut in_resources = false;
+        for line in &rest[index..] {
+            if in_resources {
+                let resource = line.strip_prefix("- ").ok_or_else(|| malformed("bad resource"))?;
+                message.resources.push(resource.to_string());
+                continue;
+            }
+            if *line == "Resources:" {
+                in_resources = true;
+                continue;
+            }
+            let (label, value) = line.split_once(": ").ok_or_else(|| malformed(line))?;
+            let value = Some(value.to_string());
+            match label {
+                "URI" => message.uri = value,
+                "Version" => message.version = value,
+                "Chain ID" => message.chain_id = value,
+                "Nonce" => message.nonce = value,
+                "Issued At" => message.issued_at = value,
+                "Expiration Time" => message.expiration_time = value,
+                "Not Before" => message.not_before = value,
+                "Request ID" => message.request_id = value,
+                _ => return Err(malformed(label)),
+            }
+        }
+
+        Ok(message)
+    }
+
+    /// Verify a wallet's signature over this message
+    pub fn verify(&self, signature: &[u8; 64]) -> Result<()> {
+        let bytes = self.to_bytes()?;
+        if Signature::from(*signature).verify(&self.address, &bytes) {
+            Ok(())
+        } else {
+            Err(TxAsmError::SignatureError(
+                "SIWS signature does not match address".to_string(),
+            ))
+        }
+    }
+
+    /// Verify the signature and that the message targets the expected domain and nonce
+    pub fn verify_for(&self, domain: &str, nonce: &str, signature: &[u8; 64]) -> Result<()> {
+        if self.domain != domain {
+            return Err(TxAsmError::SignatureError(format!(
+                "SIWS domain mismatch: expected {}, got {}",
+                domain, self.domain
+            )));
+        }
+        if self.nonce.as_deref() != Some(nonce) {
+            return Err(TxAsmError::SignatureError("SIWS nonce mismatch".to_string()));
+        }
+        self.verify(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::{Keypair, Signer};
+
+    fn sample(address: [u8; 32]) -> SiwsMessage {
+        SiwsMessage::new("example.com", address)
+            .statement("Sign in to Example")
+            .uri("https://example.com/login")
+            .chain_id("mainnet")
+            .nonce("abc12345")
+            .issued_at("2024-01-01T00:00:00Z")
+            .resource("https://example.com/terms")
+    }
+
+    #[test]
+    fn test_siws_text_format() {
+        let text = sample([1u8; 32]).to_text();
+        let expected = format!(
+            "example.com wants you to sign in with your Solana account:\n{}\n\n\
+             Sign in to Example\n\n\
+             URI: https://example.com/login\nVersion: 1\nChain ID: mainnet\n\
+             Nonce: abc12345\nIssued At: 2024-01-01T00:00:00Z\n\
+             Resources:\n- https://example.com/terms",
+            bs58::encode([1u8; 32]).into_string()
+        );
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn test_siws_parse_roundtrip() {
+        let message = sample([7u8; 32]);
+        assert_eq!(SiwsMessage::parse(&message.to_text()).unwrap(), message);
+
+        let minimal = SiwsMessage {
+            version: None,
+            ..SiwsMessage::new("example.com", [7u8; 32])
+        };
+        assert_eq!(SiwsMessage::parse(&minimal.to_text()).unwrap(), minimal);
+
+        // A statement that looks like a field, with nothing after it
+        let colon = SiwsMessage {
+            version: None,
+            ..SiwsMessage::new("example.com", [7u8; 32]).statement("Terms: accept")
+        };
+        assert_eq!(SiwsMessage::parse(&colon.to_text()).unwrap(), colon);
+
+        let lone_field = SiwsMessage {
+            version: None,
+            ..SiwsMessage::new("example.com", [7u8; 32]).nonce("abc12345")
+        };
+        assert_eq!(SiwsMessage::parse(&lone_field.to_text()).unwrap(), lone_field);
+    }
+
+    #[test]
+    fn test_siws_verify() {
+        let keypair = Keypair::new();
+        let message = sample(keypair.pubkey().to_bytes());
+        let signature = keypair.sign_message(&message.to_bytes().unwrap());
+        let sig: [u8; 64] = signature.into();
+
+        assert!(message.verify(&sig).is_ok());
+        assert!(message.verify_for("example.com", "abc12345", &sig).is_ok());
+        assert!(message.verify_for("evil.com", "abc12345", &sig).is_err());
+
+        let tampered = message.statement("Something else");
+        assert!(tampered.verify(&sig).is_err());
+    }
+
+    #[test]
+    fn test_siws_rejects_short_nonce() {
+        let message = SiwsMessage::new("example.com", [1u8; 32]).nonce("abc");
+        assert!(message.to_bytes().is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Persisted builder state
+//!
+//! Approval workflows often assemble a transaction long before anyone signs
+//! it. A [`BuilderSnapshot`] captures a [`TransactionBuilder`]'s payer,
+//! blockhash, options and instructions in a versioned JSON format (base58
+//! keys, base64 data) that can be stored and restored later, typically with
+//! a fresh blockhash set just before signing.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::{AccountMeta, RawInstruction};
+use crate::serialization::parse_pubkey;
+use crate::transaction::{BuilderLimits, RoleMergePolicy, TransactionBuilder};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+
+/// Current snapshot format version
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Saved account of an instruction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub pubkey: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Saved instruction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstructionSnapshot {
+    pub program_id: String,
+    pub accounts: Vec<AccountSnapshot>,
+    /// Base64 instruction data
+    pub data: String,
+}
+
+/// Saved [`TransactionBuilder`] state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuilderSnapshot {
+    pub version: u32,
+    pub payer: Option<String>,
+    pub recent_blockhash: Option<String>,
+    #[serde(default)]
+    pub role_merge_policy: RoleMergePolicy,
+    #[serde(default)]
+    pub dedupe_signers: bool,
+    /// Base58 keys added with [`TransactionBuilder::additional_signer`]
+    #[serde(default)]
+    pub additional_signers: Vec<String>,
+    #[serde(default)]
+    pub limits: BuilderLimits,
+    pub instructions: Vec<InstructionSnapshot>,
+}
+
+impl BuilderSnapshot {
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(|e| TxAsmError::SerializationError(e.to_string()))
+    }
+
+    /// Parse a snapshot, rejecting versions newer than this crate understands
+    pub fn from_json(json: &str) -> Result<Self> {
+        let snapshot: Self =
+            serde_json::from_str(json).map_err(|e| TxAsmError::DeserializationError(e.to_string()))?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(TxAsmError::DeserializationError(format!(
+                "Snapshot version {} is newer than supported version {}",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+        Ok(snapshot)
+    }
+}
+
+impl TransactionBuilder {
+    /// Capture the builder's current state
+    pub fn snapshot(&self) -> BuilderSnapshot {
+        let encode = |key: &[u8; 32]| bs58::encode(key).into_string();
+        BuilderSnapshot {
+            version: SNAPSHOT_VERSION,
+            payer: self.payer.as_ref().map(encode),
+            recent_blockhash: self.recent_blockhash.as_ref().map(encode),
+            role_merge_policy: self.role_merge_policy,
+            dedupe_signers: self.dedupe_signers,
+            additional_signers: self.additional_signers.iter().map(encode).collect(),
+            limits: self.limits,
+            instructions: self
+                .instructions
+                .iter()
+                .map(|ix| InstructionSnapshot {
+                    program_id: encode(&ix.program_id),
+                    accounts: ix
+                        .accounts
+                        .iter()
+                        .map(|meta| AccountSnapshot {
+                            pubkey: encode(&meta.pubkey),
+                            is_signer: meta.is_signer,
+                            is_writable: meta.is_writable,
+                        })
+                        .collect(),
+                    data: BASE64.encode(&ix.data),
+                })
+                .collect(),
+        }
+    }
+
+    /// Rebuild a builder from a snapshot
+    pub fn restore(snapshot: &BuilderSnapshot) -> Result<Self> {
+        let mut builder = Self::new()
+            .role_merge_policy(snapshot.role_merge_policy)
+            .dedupe_signers(snapshot.dedupe_signers)
+            .limits(snapshot.limits);
+        builder.payer = snapshot.payer.as_deref().map(parse_pubkey).transpose()?;
+        builder.recent_blockhash = snapshot.recent_blockhash.as_deref().map(parse_pubkey).transpose()?;
+        for signer in &snapshot.additional_signers {
+            builder = builder.additional_signer(parse_pubkey(signer)?);
+        }
+        for (index, ix) in snapshot.instructions.iter().enumerate() {
+            let accounts = ix
+                .accounts
+                .iter()
+                .map(|meta| Ok(AccountMeta::new(parse_pubkey(&meta.pubkey)?, meta.is_signer, meta.is_writable)))
+                .collect::<Result<Vec<_>>>()?;
+            let data = BASE64.decode(&ix.data).map_err(|e| {
+                TxAsmError::DecodingError(format!("Instruction {} data: {}", index, e))
+            })?;
+            builder = builder.add_instruction(RawInstruction::new(parse_pubkey(&ix.program_id)?, accounts, data));
+        }
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let builder = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .role_merge_policy(RoleMergePolicy::Error)
+            .add_instruction(
+                InstructionEncoder::new([2u8; 32])
+                    .writable([5u8; 32], false)
+                    .signer([6u8; 32], false)
+                    .append_u64(42)
+                    .build(),
+            );
+        let json = builder.snapshot().to_json().unwrap();
+
+        let restored = TransactionBuilder::restore(&BuilderSnapshot::from_json(&json).unwrap()).unwrap();
+        assert_eq!(restored.snapshot(), builder.snapshot());
+        assert_eq!(restored.role_merge_policy, RoleMergePolicy::Error);
+
+        // Resume next week with a fresh blockhash
+        let tx = restored.recent_blockhash([3u8; 32]).build_unsigned().unwrap();
+        let original = builder.recent_blockhash([3u8; 32]).build_unsigned().unwrap();
+        assert_eq!(tx.serialize().unwrap(), original.serialize().unwrap());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_bad_input() {
+        let mut snapshot = TransactionBuilder::new().payer([1u8; 32]).snapshot();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+        assert!(BuilderSnapshot::from_json(&snapshot.to_json().unwrap()).is_err());
+
+        snapshot.version = SNAPSHOT_VERSION;
+        snapshot.payer = Some("not-a-key".to_string());
+        assert!(TransactionBuilder::restore(&snapshot).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Declarative transaction specifications
+//!
+//! A [`TransactionSpec`] describes a transaction in config: named accounts,
+//! instructions referring to them by name, data laid out as template
+//! [`DataField`]s and optional compute budget settings. Specs load from
+//! JSON, TOML (`toml` feature) or YAML (`yaml` feature):
+//!
+//! ```toml
+//! payer = "treasury"
+//!
+//! [accounts]
+//! treasury = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM"
+//! vault = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1"
+//!
+//! [values]
+//! lamports = 1000000
+//!
+//! [fees]
+//! compute_unit_price = 5000
+//!
+//! [[instructions]]
+//! program = "11111111111111111111111111111111"
+//! accounts = [
+//!   { account = "treasury", signer = true, writable = true },
+//!   { account = "vault", writable = true },
+//! ]
+//! data = [{ u32 = "transfer" }, { u64 = "lamports" }]
+//! ```
+//!
+//! Account references are names from `accounts` or base58 addresses; data
+//! slots bind to `values` (and to `accounts` for pubkey slots). Compiled
+//! specs are checked with the same header sanitizer as preflight.
+
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::compute_budget::{self, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::instruction::RawInstruction;
+use crate::serialization::parse_pubkey;
+use crate::template::{DataField, TemplateBindings, TemplateInstruction, TemplateKey};
+use crate::transaction::{CompiledTransaction, TransactionBuilder};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Compute budget settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeeSpec {
+    pub compute_unit_limit: Option<u32>,
+    /// CU price in microlamports
+    pub compute_unit_price: Option<u64>,
+}
+
+/// One account of an instruction
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountSpec {
+    /// Account name or base58 address
+    pub account: String,
+    #[serde(default)]
+    pub signer: bool,
+    #[serde(default)]
+    pub writable: bool,
+}
+
+/// One instruction
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstructionSpec {
+    /// Program name or base58 address
+    pub program: String,
+    #[serde(default)]
+    pub accounts: Vec<AccountSpec>,
+    #[serde(default)]
+    pub data: Vec<DataField>,
+}
+
+/// Declarative description of a transaction
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionSpec {
+    /// Payer name or base58 address
+    pub payer: String,
+    /// Base58 blockhash; usually supplied at compile time instead
+    #[serde(default)]
+    pub recent_blockhash: Option<String>,
+    /// Account names to base58 addresses
+    #[serde(default)]
+    pub accounts: BTreeMap<String, String>,
+    /// Values for numeric data slots
+    #[serde(default)]
+    pub values: BTreeMap<String, u64>,
+    #[serde(default)]
+    pub fees: FeeSpec,
+    pub instructions: Vec<InstructionSpec>,
+}
+
+impl TransactionSpec {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        serde_yaml::from_str(yaml).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+    }
+
+    /// Resolve an account name or base58 address
+    pub fn key(&self, reference: &str) -> Result<[u8; 32]> {
+        match self.accounts.get(reference) {
+            Some(address) => parse_pubkey(address),
+            None => parse_pubkey(reference).map_err(|_| {
+                TxAsmError::AccountError(format!("'{}' is neither a named account nor an address", reference))
+            }),
+        }
+    }
+
+    /// Instructions with every name and slot bound, compute budget first
+    pub fn instructions(&self) -> Result<Vec<RawInstruction>> {
+        let mut bindings = TemplateBindings::new();
+        for name in self.accounts.keys() {
+            bindings = bindings.key(name.clone(), self.key(name)?);
+        }
+        for (name, value) in &self.values {
+            bindings = bindings.value(name.clone(), *value);
+        }
+
+        let mut instructions = Vec::with_capacity(self.instructions.len() + 2);
+        if let Some(units) = self.fees.compute_unit_limit {
+            instructions.push(RawInstruction::new(
+                COMPUTE_BUDGET_PROGRAM_ID,
+                vec![],
+                compute_budget::create_compute_unit_limit_instruction(units),
+            ));
+        }
+        if let Some(price) = self.fees.compute_unit_price {
+            instructions.push(RawInstruction::new(
+                COMPUTE_BUDGET_PROGRAM_ID,
+                vec![],
+                compute_budget::create_compute_unit_price_instruction(price),
+            ));
+        }
+        for (index, spec) in self.instructions.iter().enumerate() {
+            let mut template = TemplateInstruction::new(TemplateKey::Fixed(self.key(&spec.program)?));
+            for account in &spec.accounts {
+                template = template.account(self.key(&account.account)?, account.signer, account.writable);
+            }
+            template.data = spec.data.clone();
+            let instruction = template.resolve(&bindings).map_err(|e| {
+                TxAsmError::InvalidInstruction(format!("Instruction {}: {}", index, e))
+            })?;
+            instructions.push(instruction);
+        }
+        Ok(instructions)
+    }
+
+    /// Builder holding the spec's payer, instructions and blockhash (if set)
+    pub fn to_builder(&self) -> Result<TransactionBuilder> {
+        let mut builder = TransactionBuilder::new()
+            .payer(self.key(&self.payer)?)
+            .add_instructions(self.instructions()?);
+        if let Some(blockhash) = &self.recent_blockhash {
+            builder = builder.recent_blockhash(parse_pubkey(blockhash)?);
+        }
+        Ok(builder)
+    }
+
+    /// Compile with the spec's own blockhash and sanitize the result
+    pub fn compile(&self) -> Result<CompiledTransaction> {
+        sanitize(self.to_builder()?.build_unsigned()?)
+    }
+
+    /// Compile against `recent_blockhash` and sanitize the result
+    pub fn compile_with_blockhash(&self, recent_blockhash: [u8; 32]) -> Result<CompiledTransaction> {
+        sanitize(self.to_builder()?.recent_blockhash(recent_blockhash).build_unsigned()?)
+    }
+}
+
+fn sanitize(transaction: CompiledTransaction) -> Result<CompiledTransaction> {
+    transaction.message.header.validate(&transaction.message)?;
+    Ok(transaction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::system_instruction;
+
+    fn json() -> String {
+        format!(
+            r#"{{
+                "payer": "treasury",
+                "accounts": {{"treasury": "{}", "vault": "{}"}},
+                "values": {{"transfer": 2, "lamports": 1000000}},
+                "fees": {{"compute_unit_price": 5000}},
+                "instructions": [{{
+                    "program": "11111111111111111111111111111111",
+                    "accounts": [
+                        {{"account": "treasury", "signer": true, "writable": true}},
+                        {{"account": "vault", "writable": true}}
+                    ],
+                    "data": [{{"u32": "transfer"}}, {{"u64": "lamports"}}]
+                }}]
+            }}"#,
+            bs58::encode([1u8; 32]).into_string(),
+            bs58::encode([2u8; 32]).into_string()
+        )
+    }
+
+    #[test]
+    fn test_spec_compiles() {
+        let spec = TransactionSpec::from_json(&json()).unwrap();
+        let tx = spec.compile_with_blockhash([3u8; 32]).unwrap();
+        assert_eq!(tx.message.instructions.len(), 2);
+        assert_eq!(tx.message.account_keys[0], [1u8; 32]);
+
+        let expected = system_instruction::transfer(
+            &Pubkey::new_from_array([1u8; 32]),
+            &Pubkey::new_from_array([2u8; 32]),
+            1_000_000,
+        );
+        assert_eq!(tx.message.instructions[1].data, expected.data);
+        assert_eq!(compute_budget::requested_budget(&tx.message).unit_price, Some(5000));
+
+        // No blockhash in the spec
+        assert!(spec.compile().is_err());
+    }
+
+    #[test]
+    fn test_spec_errors() {
+        let unknown = json().replace("\"vault\", \"writable\"", "\"missing\", \"writable\"");
+        let err = TransactionSpec::from_json(&unknown).unwrap().instructions().unwrap_err();
+        assert!(err.to_string().contains("missing"));
+
+        let unbound = json().replace("\"lamports\": 1000000", "\"other\": 1");
+        assert!(TransactionSpec::from_json(&unbound).unwrap().instructions().is_err());
+        assert!(TransactionSpec::from_json(r#"{"payer":"a","instructions":[],"typo":1}"#).is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_spec_from_toml() {
+        let spec = TransactionSpec::from_toml(
+            r#"
+            payer = "11111111111111111111111111111112"
+            recent_blockhash = "11111111111111111111111111111113"
+
+            [[instructions]]
+            program = "11111111111111111111111111111111"
+            data = [{ bytes = [1, 2] }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(spec.compile().unwrap().message.instructions[0].data, vec![1, 2]);
+    }
+}
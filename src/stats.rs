@@ -0,0 +1,246 @@
+//! Account usage statistics across a transaction corpus
+//!
+//! [`AccountUsage`] tallies how often each account appears in a set of
+//! transactions, how often it is write-locked, and which accounts travel
+//! together. Built from an archive of captured traffic, it answers which
+//! accounts belong in a lookup table ([`AccountUsage::lookup_table_candidates`])
+//! and what CU price the hot accounts have been costing
+//! ([`AccountUsage::fee_history`]).
+
+use crate::archive::ArchiveReader;
+use crate::error::Result;
+use crate::fee_calculator::{compute_budget, AccountFeeHistory};
+use crate::transaction::CompiledTransaction;
+use crate::versioned::AddressLookupTable;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// How one account was used across the corpus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccountStats {
+    /// Transactions referencing the account
+    pub transactions: u64,
+    /// Transactions write-locking it
+    pub writable: u64,
+    /// Transactions it signed
+    pub signer: u64,
+    /// Transactions invoking it as a program
+    pub invoked: u64,
+}
+
+impl AccountStats {
+    /// Share of referencing transactions that write-lock the account
+    pub fn writable_ratio(&self) -> f64 {
+        if self.transactions == 0 {
+            return 0.0;
+        }
+        self.writable as f64 / self.transactions as f64
+    }
+}
+
+/// Per-account frequency, write ratio and co-occurrence over many transactions
+#[derive(Debug, Clone, Default)]
+pub struct AccountUsage {
+    transactions: u64,
+    accounts: HashMap<[u8; 32], AccountStats>,
+    /// Keyed by the pair in ascending byte order
+    co_occurrence: HashMap<([u8; 32], [u8; 32]), u64>,
+    fees: AccountFeeHistory,
+}
+
+impl AccountUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tally every transaction in an archive
+    pub fn from_archive<R: Read>(archive: ArchiveReader<R>) -> Result<Self> {
+        let mut usage = Self::new();
+        for record in archive {
+            usage.record(&record?.decode_transaction()?);
+        }
+        Ok(usage)
+    }
+
+    /// Add one transaction to the tallies
+    pub fn record(&mut self, transaction: &CompiledTransaction) {
+        let message = &transaction.message;
+        let price = compute_budget::requested_budget(message).unit_price;
+        self.transactions += 1;
+
+        for (index, key) in message.account_keys.iter().enumerate() {
+            let stats = self.accounts.entry(*key).or_default();
+            stats.transactions += 1;
+            if message.is_writable(index) {
+                stats.writable += 1;
+                if let Some(price) = price {
+                    self.fees.record(*key, price);
+                }
+            }
+            if message.is_signer(index) {
+                stats.signer += 1;
+            }
+            if message.is_program(index) {
+                stats.invoked += 1;
+            }
+            for other in &message.account_keys[index + 1..] {
+                *self.co_occurrence.entry(pair(key, other)).or_default() += 1;
+            }
+        }
+    }
+
+    /// Number of transactions recorded
+    pub fn transactions(&self) -> u64 {
+        self.transactions
+    }
+
+    pub fn account(&self, key: &[u8; 32]) -> Option<&AccountStats> {
+        self.accounts.get(key)
+    }
+
+    /// Share of transactions referencing `key`
+    pub fn frequency(&self, key: &[u8; 32]) -> f64 {
+        match (self.accounts.get(key), self.transactions) {
+            (Some(stats), total) if total > 0 => stats.transactions as f64 / total as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Transactions referencing both `a` and `b`
+    pub fn co_occurrence(&self, a: &[u8; 32], b: &[u8; 32]) -> u64 {
+        self.co_occurrence.get(&pair(a, b)).copied().unwrap_or_default()
+    }
+
+    /// Accounts seen with `key`, most frequent first
+    pub fn companions(&self, key: &[u8; 32]) -> Vec<([u8; 32], u64)> {
+        let mut companions: Vec<_> = self
+            .co_occurrence
+            .iter()
+            .filter_map(|((a, b), count)| {
+                if a == key {
+                    Some((*b, *count))
+                } else if b == key {
+                    Some((*a, *count))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        companions.sort_by(|x, y| y.1.cmp(&x.1).then(x.0.cmp(&y.0)));
+        companions
+    }
+
+    /// Most write-locked accounts, at most `limit` of them
+    pub fn hot_accounts(&self, limit: usize) -> Vec<([u8; 32], AccountStats)> {
+        let mut hot: Vec<_> = self
+            .accounts
+            .iter()
+            .filter(|(_, stats)| stats.writable > 0)
+            .map(|(key, stats)| (*key, *stats))
+            .collect();
+        hot.sort_by(|x, y| y.1.writable.cmp(&x.1.writable).then(x.0.cmp(&y.0)));
+        hot.truncate(limit);
+        hot
+    }
+
+    /// Accounts worth putting in a lookup table, most frequent first
+    ///
+    /// Only accounts that never signed and were never invoked qualify, since
+    /// those must stay static. `min_frequency` is the share of transactions
+    /// an account must appear in, e.g. 0.1 for 10%.
+    pub fn lookup_table_candidates(&self, min_frequency: f64) -> Vec<[u8; 32]> {
+        let mut candidates: Vec<_> = self
+            .accounts
+            .iter()
+            .filter(|(key, stats)| stats.signer == 0 && stats.invoked == 0 && self.frequency(key) >= min_frequency)
+            .map(|(key, stats)| (*key, stats.transactions))
+            .collect();
+        candidates.sort_by(|x, y| y.1.cmp(&x.1).then(x.0.cmp(&y.0)));
+        candidates.into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// A table at `table_key` holding the top 256 [`Self::lookup_table_candidates`]
+    pub fn recommend_lookup_table(&self, table_key: [u8; 32], min_frequency: f64) -> AddressLookupTable {
+        let mut addresses = self.lookup_table_candidates(min_frequency);
+        addresses.truncate(256);
+        AddressLookupTable::new(table_key, addresses)
+    }
+
+    /// CU prices requested by transactions write-locking each account, for
+    /// [`PriorityFeeCalculator::recommend_for_accounts`](crate::fee_calculator::PriorityFeeCalculator::recommend_for_accounts)
+    pub fn fee_history(&self) -> &AccountFeeHistory {
+        &self.fees
+    }
+}
+
+fn pair(a: &[u8; 32], b: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    if a <= b {
+        (*a, *b)
+    } else {
+        (*b, *a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::{ArchiveRecord, ArchiveWriter};
+    use crate::instruction::{InstructionEncoder, RawInstruction};
+    use crate::testkit;
+    use crate::transaction::TransactionBuilder;
+    use std::io::Cursor;
+
+    fn swap(payer: u64, price: u64) -> CompiledTransaction {
+        TransactionBuilder::new()
+            .payer(testkit::pubkey(payer))
+            .recent_blockhash(testkit::blockhash(1))
+            .add_instruction(RawInstruction::new(
+                compute_budget::COMPUTE_BUDGET_PROGRAM_ID,
+                vec![],
+                compute_budget::create_compute_unit_price_instruction(price),
+            ))
+            .add_instruction(
+                InstructionEncoder::new(testkit::address(1))
+                    .writable(testkit::address(2), false)
+                    .readonly(testkit::address(3))
+                    .build(),
+            )
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_usage_from_archive() {
+        let mut writer = ArchiveWriter::new(Vec::new()).unwrap();
+        for (payer, price) in [(1, 100), (2, 300), (3, 200)] {
+            writer.write(&ArchiveRecord::from_transaction(&swap(payer, price)).unwrap()).unwrap();
+        }
+        writer.write(&ArchiveRecord::from_transaction(&testkit::simple_transfer_tx(4)).unwrap()).unwrap();
+        let archive = ArchiveReader::new(Cursor::new(writer.finish().unwrap())).unwrap();
+        let usage = AccountUsage::from_archive(archive).unwrap();
+
+        assert_eq!(usage.transactions(), 4);
+        let pool = testkit::address(2);
+        assert_eq!(usage.frequency(&pool), 0.75);
+        assert_eq!(usage.account(&pool).unwrap().writable_ratio(), 1.0);
+        assert_eq!(usage.co_occurrence(&pool, &testkit::address(3)), 3);
+        assert_eq!(usage.companions(&pool)[0].1, 3);
+        assert_eq!(usage.hot_accounts(1)[0].0, pool);
+        assert_eq!(usage.fee_history().percentile(&pool, 50), Some(200));
+    }
+
+    #[test]
+    fn test_lookup_table_candidates_stay_static_safe() {
+        let mut usage = AccountUsage::new();
+        for payer in 1..=3 {
+            usage.record(&swap(payer, 1));
+        }
+        // Programs and payers are excluded; the two data accounts qualify
+        let candidates = usage.lookup_table_candidates(0.5);
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.contains(&testkit::address(2)) && candidates.contains(&testkit::address(3)));
+        let table = usage.recommend_lookup_table(testkit::address(9), 0.5);
+        assert_eq!(table.addresses.len(), 2);
+        assert!(usage.lookup_table_candidates(1.5).is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! Borsh and bincode encodings for storage systems
+//!
+//! Compiled transactions, messages and their parts derive
+//! `BorshSerialize`/`BorshDeserialize` and serde's `Serialize`/`Deserialize`,
+//! so they can be embedded in on-chain account data or key-value stores that
+//! standardize on Borsh, or round-tripped through bincode. These encode the
+//! structs themselves, not the wire format: use `serialize`/`deserialize` on
+//! the transaction for bytes the network accepts.
+//!
+//! Borsh has no `SmallVec` support and serde stops at 32-element arrays, so
+//! the field adapters those derives need live here.
+
+use crate::error::{Result, TxAsmError};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Borsh-encode `value`
+pub fn to_borsh<T: BorshSerialize>(value: &T) -> Result<Vec<u8>> {
+    borsh::to_vec(value).map_err(|e| TxAsmError::SerializationError(e.to_string()))
+}
+
+/// Decode a Borsh value, rejecting trailing bytes
+pub fn from_borsh<T: BorshDeserialize>(bytes: &[u8]) -> Result<T> {
+    borsh::from_slice(bytes).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+}
+
+/// bincode-encode `value`
+pub fn to_bincode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    bincode::serialize(value).map_err(|e| TxAsmError::SerializationError(e.to_string()))
+}
+
+/// Decode a bincode value
+pub fn from_bincode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    bincode::deserialize(bytes).map_err(|e| TxAsmError::DeserializationError(e.to_string()))
+}
+
+/// Borsh adapter encoding [`AccountIndices`](crate::transaction::AccountIndices)
+/// as a `Vec<u8>`
+pub(crate) mod account_indices {
+    use crate::transaction::AccountIndices;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use std::io::{Read, Result, Write};
+
+    pub fn serialize<W: Write>(indices: &AccountIndices, writer: &mut W) -> Result<()> {
+        indices.as_slice().serialize(writer)
+    }
+
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<AccountIndices> {
+        Vec::<u8>::deserialize_reader(reader).map(AccountIndices::from_vec)
+    }
+}
+
+/// serde adapter for `Vec<[u8; 64]>`, each signature a 64-element tuple
+pub(crate) mod signatures {
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::ser::SerializeTuple;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt;
+
+    struct Signature([u8; 64]);
+
+    impl Serialize for Signature {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut tuple = serializer.serialize_tuple(64)?;
+            for byte in &self.0 {
+                tuple.serialize_element(byte)?;
+            }
+            tuple.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Signature {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct SignatureVisitor;
+
+            impl<'de> Visitor<'de> for SignatureVisitor {
+                type Value = Signature;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("64 signature bytes")
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Signature, A::Error> {
+                    let mut signature = [0u8; 64];
+                    for (i, byte) in signature.iter_mut().enumerate() {
+                        *byte = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(i, &self))?;
+                    }
+                    Ok(Signature(signature))
+                }
+            }
+
+            deserializer.deserialize_tuple(64, SignatureVisitor)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(signatures: &[[u8; 64]], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(signatures.iter().map(|s| Signature(*s)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[u8; 64]>, D::Error> {
+        Ok(Vec::<Signature>::deserialize(deserializer)?.into_iter().map(|s| s.0).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::{CompiledTransaction, TransactionBuilder};
+    use crate::versioned::{CompiledMessageV0, CompiledTransactionV0, MessageAddressTableLookup};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    #[test]
+    fn test_legacy_roundtrips() {
+        let payer = Keypair::new();
+        let tx = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).append_u64(9).build())
+            .build_and_sign(&[&payer])
+            .unwrap();
+        let wire = tx.serialize().unwrap();
+
+        let borsh_bytes = to_borsh(&tx).unwrap();
+        assert_eq!(from_borsh::<CompiledTransaction>(&borsh_bytes).unwrap().serialize().unwrap(), wire);
+        assert!(from_borsh::<CompiledTransaction>(&[borsh_bytes.as_slice(), &[0]].concat()).is_err());
+
+        let bincode_bytes = to_bincode(&tx).unwrap();
+        assert_eq!(from_bincode::<CompiledTransaction>(&bincode_bytes).unwrap().serialize().unwrap(), wire);
+        // Signatures are fixed-width tuples: one length prefix for the list, none per entry
+        let (count, signature) = bincode_bytes[bincode_bytes.len() - 72..].split_at(8);
+        assert_eq!((count, signature), (&1u64.to_le_bytes()[..], &tx.signatures[0][..]));
+    }
+
+    #[test]
+    fn test_v0_roundtrips() {
+        let tx = CompiledTransactionV0 {
+            message: CompiledMessageV0 {
+                header: crate::transaction::MessageHeader::new(1, 0, 1),
+                account_keys: vec![[1u8; 32], [2u8; 32]],
+                recent_blockhash: [3u8; 32],
+                instructions: vec![crate::transaction::CompiledInstruction {
+                    program_id_index: 1,
+                    account_indices: vec![0, 2].into(),
+                    data: vec![4, 4],
+                }],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: [7u8; 32],
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![],
+                }],
+            },
+            signatures: vec![[5u8; 64]],
+        };
+        let wire = tx.serialize().unwrap();
+        let borsh_bytes = to_borsh(&tx).unwrap();
+        assert_eq!(from_borsh::<CompiledTransactionV0>(&borsh_bytes).unwrap().serialize().unwrap(), wire);
+        let bincode_bytes = to_bincode(&tx).unwrap();
+        assert_eq!(from_bincode::<CompiledTransactionV0>(&bincode_bytes).unwrap().serialize().unwrap(), wire);
+    }
+}
@@ -0,0 +1,177 @@
+//! Continuous decoding of transaction feeds
+//!
+//! [`StreamingDecoder`] turns an async stream of frames, one serialized
+//! transaction each as relayer and searcher feeds deliver them, into a
+//! stream of [`DecodedTx`]. It reads at most `capacity` frames ahead of the
+//! consumer, so a slow consumer stops the upstream from being polled instead
+//! of growing a queue. Each frame decodes independently: a malformed frame or
+//! upstream error yields one `Err` item and the stream carries on.
+//!
+//! Enabled with the `stream` feature.
+
+use crate::error::{Result, TxAsmError};
+use crate::transaction::{CompiledTransaction, DecodeLimits};
+use crate::versioned::{is_versioned, CompiledTransactionV0};
+use futures::stream::Stream;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Frames decoded ahead of the consumer by default
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// A decoded feed transaction
+#[derive(Debug, Clone)]
+pub enum DecodedTx {
+    Legacy(CompiledTransaction),
+    V0(CompiledTransactionV0),
+}
+
+impl DecodedTx {
+    /// Decode wire bytes, dispatching on the message version
+    pub fn decode(bytes: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        if is_versioned(bytes)? {
+            CompiledTransactionV0::deserialize_with_limits(bytes, limits).map(Self::V0)
+        } else {
+            CompiledTransaction::deserialize_with_limits(bytes, limits).map(Self::Legacy)
+        }
+    }
+
+    /// First signature, the transaction's id
+    pub fn signature(&self) -> Option<&[u8; 64]> {
+        match self {
+            Self::Legacy(tx) => tx.signatures.first(),
+            Self::V0(tx) => tx.signatures.first(),
+        }
+    }
+}
+
+/// [`Stream`] of decoded transactions over a stream of frames
+pub struct StreamingDecoder<S> {
+    frames: S,
+    limits: DecodeLimits,
+    capacity: usize,
+    ready: VecDeque<Result<DecodedTx>>,
+    exhausted: bool,
+}
+
+impl<S, B, E> StreamingDecoder<S>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    pub fn new(frames: S) -> Self {
+        Self {
+            frames,
+            limits: DecodeLimits::default(),
+            capacity: DEFAULT_CAPACITY,
+            ready: VecDeque::new(),
+            exhausted: false,
+        }
+    }
+
+    /// Frames decoded ahead of the consumer (at least 1)
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Decoded items waiting for the consumer
+    pub fn queued(&self) -> usize {
+        self.ready.len()
+    }
+}
+
+impl<S, B, E> Stream for StreamingDecoder<S>
+where
+    S: Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: Display,
+{
+    type Item = Result<DecodedTx>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        while !this.exhausted && this.ready.len() < this.capacity {
+            match Pin::new(&mut this.frames).poll_next(cx) {
+                Poll::Ready(Some(Ok(frame))) => this.ready.push_back(DecodedTx::decode(frame.as_ref(), &this.limits)),
+                Poll::Ready(Some(Err(e))) => this
+                    .ready
+                    .push_back(Err(TxAsmError::DeserializationError(format!("Feed error: {}", e)))),
+                Poll::Ready(None) => this.exhausted = true,
+                Poll::Pending => break,
+            }
+        }
+        match this.ready.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if this.exhausted => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.frames.size_hint();
+        let buffered = self.ready.len();
+        (lower + buffered, upper.map(|upper| upper + buffered))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn frame(value: u64) -> Vec<u8> {
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).append_u64(value).build())
+            .build_unsigned()
+            .unwrap()
+            .serialize()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_errors_are_isolated() {
+        let frames: Vec<std::result::Result<Vec<u8>, String>> =
+            vec![Ok(frame(1)), Ok(vec![1, 2, 3]), Err("connection reset".to_string()), Ok(frame(2))];
+        let items: Vec<_> = block_on(StreamingDecoder::new(stream::iter(frames)).collect());
+        assert_eq!(items.len(), 4);
+        assert!(matches!(items[0], Ok(DecodedTx::Legacy(_))));
+        assert!(items[1].is_err());
+        assert!(items[2].as_ref().unwrap_err().to_string().contains("connection reset"));
+        match &items[3] {
+            Ok(DecodedTx::Legacy(tx)) => assert_eq!(tx.message.instructions[0].data, 2u64.to_le_bytes()),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reads_at_most_capacity_ahead() {
+        let pulled = AtomicUsize::new(0);
+        let frames = stream::iter((0..10).map(|i| Ok::<_, String>(frame(i)))).inspect(|_| {
+            pulled.fetch_add(1, Ordering::Relaxed);
+        });
+        let mut decoder = StreamingDecoder::new(frames).capacity(3);
+        block_on(async {
+            for consumed in 1..=10 {
+                assert!(decoder.next().await.unwrap().is_ok());
+                assert!(pulled.load(Ordering::Relaxed) <= consumed + 2);
+                assert!(decoder.queued() <= 2);
+            }
+            assert!(decoder.next().await.is_none());
+        });
+    }
+}
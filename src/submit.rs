@@ -0,0 +1,175 @@
+//! Classification of `sendTransaction` failures
+//!
+//! RPC nodes report send failures as a JSON-RPC `error` whose `data.err`
+//! holds the runtime's `TransactionError`, or only as a message string
+//! from some proxies. [`SubmitError`] names the cases retry policies care
+//! about so they can branch on [`SubmitError::is_retryable`] instead of
+//! parsing strings.
+
+use crate::error::{Result, TxAsmError};
+use serde_json::Value;
+use thiserror::Error;
+
+/// JSON-RPC code for a failed preflight simulation
+pub const SEND_TRANSACTION_PREFLIGHT_FAILURE: i64 = -32002;
+
+/// JSON-RPC code for a node that is behind the cluster
+pub const NODE_UNHEALTHY: i64 = -32005;
+
+/// Why a transaction submission failed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SubmitError {
+    /// The blockhash expired or the node has not seen it yet
+    #[error("Blockhash not found")]
+    BlockhashNotFound,
+
+    /// The same transaction already landed
+    #[error("Transaction already processed")]
+    AlreadyProcessed,
+
+    #[error("Insufficient funds for fee")]
+    InsufficientFundsForFee,
+
+    #[error("Node is unhealthy{}", .slots_behind.map(|n| format!(" ({} slots behind)", n)).unwrap_or_default())]
+    NodeUnhealthy { slots_behind: Option<u64> },
+
+    /// An instruction failed in simulation; `code` is set for custom
+    /// program errors and `reason` holds the runtime's error name
+    #[error("Instruction {ix} failed: {reason}")]
+    PreflightFailure { ix: u8, code: Option<u32>, reason: String },
+
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+}
+
+impl SubmitError {
+    /// Whether sending again (with a fresh blockhash where needed) can
+    /// succeed without changing the transaction's contents
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SubmitError::BlockhashNotFound | SubmitError::NodeUnhealthy { .. })
+    }
+
+    /// Classify a JSON-RPC `error` object
+    pub fn from_rpc_error(error: &Value) -> Self {
+        let code = error.get("code").and_then(Value::as_i64).unwrap_or_default();
+        let message = error.get("message").and_then(Value::as_str).unwrap_or_default();
+        let data = error.get("data");
+
+        if code == NODE_UNHEALTHY {
+            return SubmitError::NodeUnhealthy {
+                slots_behind: data.and_then(|d| d.get("numSlotsBehind")).and_then(Value::as_u64),
+            };
+        }
+        if let Some(err) = data.and_then(|d| d.get("err")).filter(|e| !e.is_null()) {
+            if let Some(classified) = Self::from_transaction_error(err) {
+                return classified;
+            }
+        }
+        Self::from_message(message).unwrap_or_else(|| SubmitError::Rpc {
+            code,
+            message: message.to_string(),
+        })
+    }
+
+    /// Classify a serialized `TransactionError`, e.g. `"BlockhashNotFound"`
+    /// or `{"InstructionError":[1,{"Custom":6001}]}`
+    pub fn from_transaction_error(err: &Value) -> Option<Self> {
+        match err {
+            Value::String(name) => match name.as_str() {
+                "BlockhashNotFound" => Some(SubmitError::BlockhashNotFound),
+                "AlreadyProcessed" => Some(SubmitError::AlreadyProcessed),
+                "InsufficientFundsForFee" => Some(SubmitError::InsufficientFundsForFee),
+                _ => None,
+            },
+            Value::Object(map) => {
+                let detail = map.get("InstructionError")?.as_array()?;
+                let ix = u8::try_from(detail.first()?.as_u64()?).ok()?;
+                let (code, reason) = match detail.get(1)? {
+                    Value::String(name) => (None, name.clone()),
+                    other => {
+                        let code = other.get("Custom").and_then(Value::as_u64).and_then(|c| u32::try_from(c).ok());
+                        (code, other.to_string())
+                    }
+                };
+                Some(SubmitError::PreflightFailure { ix, code, reason })
+            }
+            _ => None,
+        }
+    }
+
+    /// Classify from an error message alone, for transports that drop `data`
+    pub fn from_message(message: &str) -> Option<Self> {
+        let lower = message.to_ascii_lowercase();
+        if lower.contains("blockhash not found") {
+            Some(SubmitError::BlockhashNotFound)
+        } else if lower.contains("already been processed") || lower.contains("already processed") {
+            Some(SubmitError::AlreadyProcessed)
+        } else if lower.contains("insufficient funds for fee") {
+            Some(SubmitError::InsufficientFundsForFee)
+        } else if lower.contains("node is unhealthy") || lower.contains("node is behind") {
+            Some(SubmitError::NodeUnhealthy { slots_behind: None })
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse a `sendTransaction` response into the transaction signature
+///
+/// Failures reported by the node come back as [`TxAsmError::Submit`].
+pub fn parse_send_transaction(body: &str) -> Result<[u8; 64]> {
+    let value: Value = serde_json::from_str(body).map_err(|e| TxAsmError::DecodingError(e.to_string()))?;
+    if let Some(error) = value.get("error") {
+        return Err(SubmitError::from_rpc_error(error).into());
+    }
+    let signature = value
+        .get("result")
+        .and_then(Value::as_str)
+        .ok_or_else(|| TxAsmError::DecodingError("sendTransaction response has no result".to_string()))?;
+    let bytes = bs58::decode(signature).into_vec()?;
+    bytes
+        .try_into()
+        .map_err(|_| TxAsmError::DecodingError(format!("Invalid signature {}", signature)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_rpc_errors() {
+        let body = r#"{"jsonrpc":"2.0","error":{"code":-32002,"message":"Transaction simulation failed: Error processing Instruction 1: custom program error: 0x1771","data":{"err":{"InstructionError":[1,{"Custom":6001}]},"logs":[]}},"id":1}"#;
+        let err = parse_send_transaction(body).unwrap_err();
+        let TxAsmError::Submit(submit) = err else {
+            panic!("expected a submit error, got {:?}", err);
+        };
+        assert_eq!(submit, SubmitError::PreflightFailure { ix: 1, code: Some(6001), reason: r#"{"Custom":6001}"#.to_string() });
+        assert!(!submit.is_retryable());
+
+        let unhealthy = serde_json::json!({"code": -32005, "message": "Node is behind by 42 slots", "data": {"numSlotsBehind": 42}});
+        let err = SubmitError::from_rpc_error(&unhealthy);
+        assert_eq!(err, SubmitError::NodeUnhealthy { slots_behind: Some(42) });
+        assert!(err.is_retryable());
+        assert_eq!(err.to_string(), "Node is unhealthy (42 slots behind)");
+
+        let expired = serde_json::json!({"code": -32002, "message": "x", "data": {"err": "BlockhashNotFound"}});
+        assert!(SubmitError::from_rpc_error(&expired).is_retryable());
+        let bare = serde_json::json!({"code": -32002, "message": "Transaction simulation failed: Attempt to debit an account but found no record of a prior credit."});
+        assert!(matches!(SubmitError::from_rpc_error(&bare), SubmitError::Rpc { code: -32002, .. }));
+    }
+
+    #[test]
+    fn test_message_fallback_and_success() {
+        assert_eq!(
+            SubmitError::from_message("Transaction simulation failed: Blockhash not found"),
+            Some(SubmitError::BlockhashNotFound)
+        );
+        assert_eq!(
+            SubmitError::from_message("This transaction has already been processed"),
+            Some(SubmitError::AlreadyProcessed)
+        );
+        let signature = bs58::encode([7u8; 64]).into_string();
+        let body = format!(r#"{{"jsonrpc":"2.0","result":"{}","id":1}}"#, signature);
+        assert_eq!(parse_send_transaction(&body).unwrap(), [7u8; 64]);
+    }
+}
@@ -0,0 +1,319 @@
+//! Human-readable transaction summaries
+//!
+//! [`summarize`] turns a compiled transaction into short lines such as
+//! `Transfer 1.5 SOL to 9xQe…` or `Approve USDC delegate 4Nd1… — WARNING
+//! unlimited`, for wallet confirmation prompts and alert messages. System
+//! and SPL Token instructions are decoded built in and vote program
+//! instructions are named; a [`SummaryRegistry`] supplies token symbols,
+//! address labels, describers for other programs and an optional security
+//! scan (the same shape as the
+//! [`preflight`](crate::preflight) scan) whose findings become warnings.
+
+use crate::accounts::VOTE_PROGRAM_ID;
+use crate::classify::vote_instruction_name;
+use crate::token::TokenAmount;
+use crate::transaction::CompiledTransaction;
+use std::collections::HashMap;
+
+const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+const TOKEN: [u8; 32] = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").to_bytes();
+const TOKEN_2022: [u8; 32] = solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb").to_bytes();
+
+/// Describes one instruction from its account keys and data, `None` to fall
+/// back to the generic line
+pub type Describer = Box<dyn Fn(&[[u8; 32]], &[u8]) -> Option<String>>;
+
+/// Security findings for a whole transaction
+pub type Scanner = Box<dyn Fn(&CompiledTransaction) -> Vec<String>>;
+
+/// Token symbols, address labels and program describers used by [`summarize`]
+#[derive(Default)]
+pub struct SummaryRegistry {
+    tokens: HashMap<[u8; 32], (String, u8)>,
+    token_accounts: HashMap<[u8; 32], [u8; 32]>,
+    labels: HashMap<[u8; 32], String>,
+    describers: HashMap<[u8; 32], Describer>,
+    security_scan: Option<Scanner>,
+}
+
+impl SummaryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name a mint so amounts render as e.g. `10 USDC`
+    pub fn token(mut self, mint: [u8; 32], symbol: &str, decimals: u8) -> Self {
+        self.tokens.insert(mint, (symbol.to_string(), decimals));
+        self
+    }
+
+    /// Associate a token account with its mint, for instructions that do not
+    /// pass the mint
+    pub fn token_account(mut self, account: [u8; 32], mint: [u8; 32]) -> Self {
+        self.token_accounts.insert(account, mint);
+        self
+    }
+
+    /// Show `name` instead of the shortened address
+    pub fn label(mut self, address: [u8; 32], name: &str) -> Self {
+        self.labels.insert(address, name.to_string());
+        self
+    }
+
+    /// Describe instructions of `program_id`
+    pub fn program<F>(mut self, program_id: [u8; 32], describer: F) -> Self
+    where
+        F: Fn(&[[u8; 32]], &[u8]) -> Option<String> + 'static,
+    {
+        self.describers.insert(program_id, Box::new(describer));
+        self
+    }
+
+    /// Append this scan's findings to every summary's warnings
+    pub fn security_scan<F>(mut self, scan: F) -> Self
+    where
+        F: Fn(&CompiledTransaction) -> Vec<String> + 'static,
+    {
+        self.security_scan = Some(Box::new(scan));
+        self
+    }
+
+    /// Label or shortened base58 address, e.g. `9xQe…`
+    pub fn name(&self, address: &[u8; 32]) -> String {
+        match self.labels.get(address) {
+            Some(label) => label.clone(),
+            None => {
+                let encoded = bs58::encode(address).into_string();
+                format!("{}…", &encoded[..4])
+            }
+        }
+    }
+
+    /// `amount` of `mint`, or raw base units when the mint is unknown
+    fn amount(&self, mint: Option<&[u8; 32]>, raw: u64) -> String {
+        match mint.and_then(|mint| self.tokens.get(mint)) {
+            Some((symbol, decimals)) => format!("{} {}", TokenAmount::new(raw, *decimals).to_ui_string(), symbol),
+            None => format!("{} base units", raw),
+        }
+    }
+
+    fn symbol(&self, mint: Option<&[u8; 32]>) -> String {
+        match mint {
+            Some(mint) => self.tokens.get(mint).map(|(symbol, _)| symbol.clone()).unwrap_or_else(|| self.name(mint)),
+            None => "token".to_string(),
+        }
+    }
+}
+
+/// Summary lines and warnings for one transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TxSummary {
+    /// One line per instruction, compute budget instructions omitted
+    pub lines: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl TxSummary {
+    pub fn has_warnings(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+}
+
+impl std::fmt::Display for TxSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "WARNING: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// Summarize `transaction` for a confirmation prompt
+pub fn summarize(transaction: &CompiledTransaction, registry: &SummaryRegistry) -> TxSummary {
+    let message = &transaction.message;
+    let mut summary = TxSummary::default();
+    for instruction in &message.instructions {
+        let Some(program_id) = message.account_keys.get(instruction.program_id_index as usize) else {
+            summary.lines.push("Invalid instruction".to_string());
+            continue;
+        };
+        if *program_id == crate::fee_calculator::compute_budget::COMPUTE_BUDGET_PROGRAM_ID {
+            continue;
+        }
+        let accounts: Vec<[u8; 32]> = instruction
+            .account_indices
+            .iter()
+            .filter_map(|&index| message.account_keys.get(index as usize).copied())
+            .collect();
+        let data = &instruction.data;
+
+        let line = registry
+            .describers
+            .get(program_id)
+            .and_then(|describe| describe(&accounts, data))
+            .or_else(|| match *program_id {
+                SYSTEM_PROGRAM_ID => describe_system(registry, &accounts, data),
+                TOKEN | TOKEN_2022 => describe_token(registry, &accounts, data, &mut summary.warnings),
+                VOTE_PROGRAM_ID => Some(format!(
+                    "Vote program {} for {}",
+                    vote_instruction_name(data)?,
+                    registry.name(accounts.first()?)
+                )),
+                _ => None,
+            })
+            .unwrap_or_else(|| {
+                format!("Call {} with {} accounts", registry.name(program_id), accounts.len())
+            });
+        summary.lines.push(line);
+    }
+    if let Some(scan) = &registry.security_scan {
+        summary.warnings.extend(scan(transaction));
+    }
+    summary
+}
+
+fn describe_system(registry: &SummaryRegistry, accounts: &[[u8; 32]], data: &[u8]) -> Option<String> {
+    let tag = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+    let lamports = u64::from_le_bytes(data.get(4..12)?.try_into().ok()?);
+    match tag {
+        // Transfer: [from, to]
+        2 => Some(format!(
+            "Transfer {} SOL to {}",
+            TokenAmount::new(lamports, 9).to_ui_string(),
+            registry.name(accounts.get(1)?)
+        )),
+        _ => None,
+    }
+}
+
+fn describe_token(
+    registry: &SummaryRegistry,
+    accounts: &[[u8; 32]],
+    data: &[u8],
+    warnings: &mut Vec<String>,
+) -> Option<String> {
+    let (&tag, rest) = data.split_first()?;
+    let amount = rest.get(..8).and_then(|bytes| bytes.try_into().ok()).map(u64::from_le_bytes);
+    let mint_of = |account: &[u8; 32]| registry.token_accounts.get(account);
+    match tag {
+        // Transfer: [source, destination, owner]
+        3 => Some(format!(
+            "Transfer {} to {}",
+            registry.amount(mint_of(accounts.first()?), amount?),
+            registry.name(accounts.get(1)?)
+        )),
+        // TransferChecked: [source, mint, destination, owner]
+        12 => Some(format!(
+            "Transfer {} to {}",
+            registry.amount(accounts.get(1), amount?),
+            registry.name(accounts.get(2)?)
+        )),
+        // Approve: [source, delegate, owner]; ApproveChecked: [source, mint, delegate, owner]
+        4 | 13 => {
+            let (mint, delegate) = if tag == 4 {
+                (mint_of(accounts.first()?), accounts.get(1)?)
+            } else {
+                (accounts.get(1), accounts.get(2)?)
+            };
+            let symbol = registry.symbol(mint);
+            let amount = amount?;
+            if amount == u64::MAX {
+                warnings.push(format!("Unlimited {} approval to {}", symbol, registry.name(delegate)));
+                Some(format!("Approve {} delegate {} — WARNING unlimited", symbol, registry.name(delegate)))
+            } else {
+                Some(format!(
+                    "Approve {} delegate {} for {}",
+                    symbol,
+                    registry.name(delegate),
+                    registry.amount(mint, amount)
+                ))
+            }
+        }
+        // Revoke: [source, owner]
+        5 => Some(format!("Revoke delegate of {}", registry.name(accounts.first()?))),
+        // CloseAccount: [account, destination, owner]
+        9 => Some(format!(
+            "Close token account {}, rent to {}",
+            registry.name(accounts.first()?),
+            registry.name(accounts.get(1)?)
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::TransactionBuilder;
+
+    const USDC: [u8; 32] = [40u8; 32];
+
+    fn tx(instructions: Vec<crate::instruction::RawInstruction>) -> CompiledTransaction {
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instructions(instructions)
+            .build_unsigned()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_summarize_transfers_and_approvals() {
+        let registry = SummaryRegistry::new()
+            .token(USDC, "USDC", 6)
+            .token_account([41u8; 32], USDC)
+            .label([50u8; 32], "Jupiter");
+        let transaction = tx(vec![
+            InstructionEncoder::new(SYSTEM_PROGRAM_ID)
+                .signer([1u8; 32], true)
+                .writable([9u8; 32], false)
+                .append_u32(2)
+                .append_u64(1_500_000_000)
+                .build(),
+            InstructionEncoder::new(TOKEN)
+                .writable([41u8; 32], false)
+                .readonly([50u8; 32])
+                .signer([1u8; 32], false)
+                .append_u8(4)
+                .append_u64(u64::MAX)
+                .build(),
+            InstructionEncoder::new(TOKEN)
+                .writable([41u8; 32], false)
+                .readonly(USDC)
+                .writable([42u8; 32], false)
+                .signer([1u8; 32], false)
+                .append_u8(12)
+                .append_u64(2_500_000)
+                .append_u8(6)
+                .build(),
+        ]);
+        let summary = summarize(&transaction, &registry);
+
+        let to = bs58::encode([9u8; 32]).into_string();
+        assert_eq!(summary.lines[0], format!("Transfer 1.5 SOL to {}…", &to[..4]));
+        assert_eq!(summary.lines[1], "Approve USDC delegate Jupiter — WARNING unlimited");
+        assert!(summary.lines[2].starts_with("Transfer 2.5 USDC to "));
+        assert_eq!(summary.warnings, vec!["Unlimited USDC approval to Jupiter"]);
+    }
+
+    #[test]
+    fn test_registry_describers_and_scan() {
+        let program = [60u8; 32];
+        let registry = SummaryRegistry::new()
+            .program(program, |_, data| (data.first() == Some(&7)).then(|| "Stake into vault".to_string()))
+            .security_scan(|tx| vec![format!("{} instructions", tx.message.instructions.len())]);
+        let transaction = tx(vec![
+            InstructionEncoder::new(program).append_u8(7).build(),
+            InstructionEncoder::new(program).append_u8(8).writable([9u8; 32], false).build(),
+        ]);
+        let summary = summarize(&transaction, &registry);
+
+        assert_eq!(summary.lines[0], "Stake into vault");
+        assert!(summary.lines[1].starts_with("Call ") && summary.lines[1].ends_with(" with 1 accounts"));
+        assert!(summary.to_string().ends_with("WARNING: 2 instructions\n"));
+    }
+}
@@ -0,0 +1,696 @@
+//! Transaction templates with late-bound placeholders
+//!
+//! A template is compiled once: account ordering, the header, instruction
+//! indices and all static data bytes are resolved up front. Filling it only
+//! copies the precompiled bytes and writes the bound values into their slots,
+//! which keeps hot-path assembly to a handful of copies.
+//!
+//! Keys are ordered by role and then by slot (fixed keys by value,
+//! placeholders by name), so two bindings of the same template always produce
+//! the same layout. Binding a placeholder to a key that is already present in
+//! the message is rejected, since it would change that layout.
+//!
+//! Data slots are the only mutable bytes of an instruction. Each precompiled
+//! instruction carries a checksum over everything outside its slots, which
+//! [`TransactionTemplate::fill`] and [`TransactionTemplate::fill_in_place`]
+//! check after patching, so a bad slot offset or a message the template did
+//! not produce fails instead of silently corrupting unrelated bytes.
+
+use crate::error::{Result, TxAsmError};
+use crate::instruction::{AccountMeta, RawInstruction};
+use crate::transaction::{
+    checked_u8, AccountIndices, CompiledInstruction, CompiledMessage, MessageHeader,
+    MAX_ACCOUNT_KEYS,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// An account key that is either known now or bound at fill time
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TemplateKey {
+    Fixed([u8; 32]),
+    Placeholder(String),
+}
+
+impl TemplateKey {
+    pub fn fixed(key: [u8; 32]) -> Self {
+        TemplateKey::Fixed(key)
+    }
+
+    pub fn placeholder(name: impl Into<String>) -> Self {
+        TemplateKey::Placeholder(name.into())
+    }
+}
+
+impl From<[u8; 32]> for TemplateKey {
+    fn from(key: [u8; 32]) -> Self {
+        TemplateKey::Fixed(key)
+    }
+}
+
+/// A segment of instruction data
+///
+/// Deserializes from `{"bytes": [..]}` or `{"u64": "name"}`-style objects.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataField {
+    /// Static bytes
+    Bytes(Vec<u8>),
+    /// Little-endian u8 bound by name
+    U8(String),
+    /// Little-endian u16 bound by name
+    U16(String),
+    /// Little-endian u32 bound by name
+    U32(String),
+    /// Little-endian u64 bound by name
+    U64(String),
+    /// 32-byte pubkey bound by name
+    Pubkey(String),
+}
+
+impl DataField {
+    fn len(&self) -> usize {
+        match self {
+            DataField::Bytes(bytes) => bytes.len(),
+            DataField::U8(_) => 1,
+            DataField::U16(_) => 2,
+            DataField::U32(_) => 4,
+            DataField::U64(_) => 8,
+            DataField::Pubkey(_) => 32,
+        }
+    }
+}
+
+/// Account reference within a template instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateAccount {
+    pub key: TemplateKey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// Instruction with placeholder accounts and data fields
+#[derive(Debug, Clone)]
+pub struct TemplateInstruction {
+    pub program_id: TemplateKey,
+    pub accounts: Vec<TemplateAccount>,
+    pub data: Vec<DataField>,
+}
+
+impl TemplateInstruction {
+    pub fn new(program_id: impl Into<TemplateKey>) -> Self {
+        Self {
+            program_id: program_id.into(),
+            accounts: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Add an account
+    pub fn account(mut self, key: impl Into<TemplateKey>, is_signer: bool, is_writable: bool) -> Self {
+        self.accounts.push(TemplateAccount {
+            key: key.into(),
+            is_signer,
+            is_writable,
+        });
+        self
+    }
+
+    /// Append static bytes to the data
+    pub fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.data.push(DataField::Bytes(bytes.to_vec()));
+        self
+    }
+
+    /// Append a u8 slot
+    pub fn u8_slot(mut self, name: impl Into<String>) -> Self {
+        self.data.push(DataField::U8(name.into()));
+        self
+    }
+
+    /// Append a u16 slot
+    pub fn u16_slot(mut self, name: impl Into<String>) -> Self {
+        self.data.push(DataField::U16(name.into()));
+        self
+    }
+
+    /// Append a u32 slot
+    pub fn u32_slot(mut self, name: impl Into<String>) -> Self {
+        self.data.push(DataField::U32(name.into()));
+        self
+    }
+
+    /// Append a u64 slot
+    pub fn u64_slot(mut self, name: impl Into<String>) -> Self {
+        self.data.push(DataField::U64(name.into()));
+        self
+    }
+
+    /// Append a pubkey slot
+    pub fn pubkey_slot(mut self, name: impl Into<String>) -> Self {
+        self.data.push(DataField::Pubkey(name.into()));
+        self
+    }
+
+    /// Bind every placeholder and slot, producing a plain instruction
+    pub fn resolve(&self, bindings: &TemplateBindings) -> Result<RawInstruction> {
+        let resolve_key = |key: &TemplateKey| match key {
+            TemplateKey::Fixed(key) => Ok(*key),
+            TemplateKey::Placeholder(name) => bindings.get_key(name),
+        };
+
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|account| {
+                Ok(AccountMeta::new(resolve_key(&account.key)?, account.is_signer, account.is_writable))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut data = Vec::with_capacity(self.data.iter().map(DataField::len).sum());
+        for field in &self.data {
+            match field {
+                DataField::Bytes(bytes) => data.extend_from_slice(bytes),
+                field => {
+                    let slot = DataSlot {
+                        offset: data.len(),
+                        field: field.clone(),
+                    };
+                    data.resize(data.len() + field.len(), 0);
+                    write_slot(&mut data, &slot, bindings)?;
+                }
+            }
+        }
+
+        Ok(RawInstruction::new(resolve_key(&self.program_id)?, accounts, data))
+    }
+}
+
+/// Values bound into a template at fill time
+#[derive(Debug, Clone, Default)]
+pub struct TemplateBindings {
+    keys: HashMap<String, [u8; 32]>,
+    values: HashMap<String, u64>,
+    recent_blockhash: Option<[u8; 32]>,
+}
+
+impl TemplateBindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind a key placeholder (or pubkey data slot)
+    pub fn key(mut self, name: impl Into<String>, key: [u8; 32]) -> Self {
+        self.keys.insert(name.into(), key);
+        self
+    }
+
+    /// Bind an integer data slot
+    pub fn value(mut self, name: impl Into<String>, value: u64) -> Self {
+        self.values.insert(name.into(), value);
+        self
+    }
+
+    pub fn recent_blockhash(mut self, blockhash: [u8; 32]) -> Self {
+        self.recent_blockhash = Some(blockhash);
+        self
+    }
+
+    /// Update a key binding in place (for reuse across fills)
+    pub fn set_key(&mut self, name: &str, key: [u8; 32]) {
+        self.keys.insert(name.to_string(), key);
+    }
+
+    /// Update a value binding in place (for reuse across fills)
+    pub fn set_value(&mut self, name: &str, value: u64) {
+        self.values.insert(name.to_string(), value);
+    }
+
+    pub fn set_recent_blockhash(&mut self, blockhash: [u8; 32]) {
+        self.recent_blockhash = Some(blockhash);
+    }
+
+    fn get_key(&self, name: &str) -> Result<[u8; 32]> {
+        self.keys.get(name).copied().ok_or_else(|| missing(name))
+    }
+
+    fn get_value(&self, name: &str) -> Result<u64> {
+        self.values.get(name).copied().ok_or_else(|| missing(name))
+    }
+}
+
+fn missing(name: &str) -> TxAsmError {
+    TxAsmError::InvalidTransaction(format!("Missing template binding '{}'", name))
+}
+
+/// A data slot resolved to its byte offset in the precompiled data
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataSlot {
+    pub offset: usize,
+    pub field: DataField,
+}
+
+/// Precompiled instruction with data slots to patch
+#[derive(Debug, Clone)]
+struct PrecompiledInstruction {
+    program_id_index: u8,
+    account_indices: AccountIndices,
+    data: Vec<u8>,
+    slots: Vec<DataSlot>,
+    /// [`static_checksum`] of `data`
+    checksum: u64,
+}
+
+impl PrecompiledInstruction {
+    fn verify(&self, index: usize, data: &[u8]) -> Result<()> {
+        if data.len() != self.data.len() || static_checksum(data, &self.slots) != self.checksum {
+            return Err(TxAsmError::EncodingError(format!(
+                "Static data of instruction {} does not match the template",
+                index
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// FNV-1a over the bytes of `data` outside every slot
+fn static_checksum(data: &[u8], slots: &[DataSlot]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    let mut slots = slots.iter().peekable();
+    let mut offset = 0;
+    while offset < data.len() {
+        if let Some(slot) = slots.next_if(|slot| slot.offset == offset) {
+            offset += slot.field.len();
+            continue;
+        }
+        hash = (hash ^ data[offset] as u64).wrapping_mul(0x0100_0000_01b3);
+        offset += 1;
+    }
+    hash
+}
+
+/// A compiled transaction template
+#[derive(Debug, Clone)]
+pub struct TransactionTemplate {
+    header: MessageHeader,
+    keys: Vec<TemplateKey>,
+    instructions: Vec<PrecompiledInstruction>,
+}
+
+impl TransactionTemplate {
+    /// Precompile a template with the given fee payer
+    pub fn compile(payer: impl Into<TemplateKey>, instructions: Vec<TemplateInstruction>) -> Result<Self> {
+        let payer = payer.into();
+        if instructions.is_empty() {
+            return Err(TxAsmError::InvalidTransaction(
+                "No instructions provided".to_string(),
+            ));
+        }
+
+        // Merge roles per key; the payer is a writable signer
+        let mut roles: HashMap<TemplateKey, (bool, bool)> = HashMap::new();
+        roles.insert(payer.clone(), (true, true));
+        for ix in &instructions {
+            roles.entry(ix.program_id.clone()).or_insert((false, false));
+            for account in &ix.accounts {
+                let entry = roles.entry(account.key.clone()).or_insert((false, false));
+                entry.0 |= account.is_signer;
+                entry.1 |= account.is_writable;
+            }
+        }
+
+        let mut keys: Vec<(TemplateKey, bool, bool)> = roles
+            .into_iter()
+            .map(|(key, (is_signer, is_writable))| (key, is_signer, is_writable))
+            .collect();
+        keys.sort_by(|a, b| {
+            let priority = |(key, is_signer, is_writable): &(TemplateKey, bool, bool)| {
+                let category = match (*is_signer, *is_writable) {
+                    (true, true) => 0,
+                    (true, false) => 1,
+                    (false, true) => 2,
+                    (false, false) => 3,
+                };
+                (*key != payer, category)
+            };
+            priority(a).cmp(&priority(b)).then_with(|| a.0.cmp(&b.0))
+        });
+
+        if keys.len() > MAX_ACCOUNT_KEYS {
+            return Err(TxAsmError::AccountIndexOverflow {
+                what: "account keys",
+                value: keys.len(),
+                max: MAX_ACCOUNT_KEYS,
+            });
+        }
+
+        let header = MessageHeader::new(
+            checked_u8("required signatures", keys.iter().filter(|(_, s, _)| *s).count())?,
+            checked_u8("readonly signed accounts", keys.iter().filter(|(_, s, w)| *s && !*w).count())?,
+            checked_u8("readonly unsigned accounts", keys.iter().filter(|(_, s, w)| !*s && !*w).count())?,
+        );
+
+        let index_of: HashMap<&TemplateKey, u8> = keys
+            .iter()
+            .enumerate()
+            .map(|(i, (key, _, _))| (key, i as u8))
+            .collect();
+
+        let precompiled = instructions
+            .iter()
+            .map(|ix| {
+                let mut data = Vec::with_capacity(ix.data.iter().map(DataField::len).sum());
+                let mut slots = Vec::new();
+                for field in &ix.data {
+                    match field {
+                        DataField::Bytes(bytes) => data.extend_from_slice(bytes),
+                        other => {
+                            slots.push(DataSlot {
+                                offset: data.len(),
+                                field: other.clone(),
+                            });
+                            data.resize(data.len() + other.len(), 0);
+                        }
+                    }
+                }
+                PrecompiledInstruction {
+                    program_id_index: index_of[&ix.program_id],
+                    account_indices: ix.accounts.iter().map(|a| index_of[&a.key]).collect(),
+                    checksum: static_checksum(&data, &slots),
+                    data,
+                    slots,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            header,
+            keys: keys.into_iter().map(|(key, _, _)| key).collect(),
+            instructions: precompiled,
+        })
+    }
+
+    /// Names of all key placeholders and data slots
+    pub fn placeholders(&self) -> Vec<&str> {
+        let keys = self.keys.iter().filter_map(|k| match k {
+            TemplateKey::Placeholder(name) => Some(name.as_str()),
+            TemplateKey::Fixed(_) => None,
+        });
+        let slots = self.instructions.iter().flat_map(|ix| ix.slots.iter()).map(|slot| match &slot.field {
+            DataField::U8(name)
+            | DataField::U16(name)
+            | DataField::U32(name)
+            | DataField::U64(name)
+            | DataField::Pubkey(name) => name.as_str(),
+            DataField::Bytes(_) => "",
+        });
+        keys.chain(slots).collect()
+    }
+
+    /// Data slots of the instruction at `index`, with their byte offsets
+    pub fn data_slots(&self, index: usize) -> Option<&[DataSlot]> {
+        self.instructions.get(index).map(|ix| ix.slots.as_slice())
+    }
+
+    /// Instantiate the template into a compiled message
+    pub fn fill(&self, bindings: &TemplateBindings) -> Result<CompiledMessage> {
+        let recent_blockhash = bindings.recent_blockhash.ok_or_else(|| missing("recent_blockhash"))?;
+
+        self.check_keys(bindings)?;
+        let mut account_keys = vec![[0u8; 32]; self.keys.len()];
+        self.write_keys(&mut account_keys, bindings)?;
+
+        let instructions = self
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(index, ix)| {
+                let mut data = ix.data.clone();
+                for slot in &ix.slots {
+                    write_slot(&mut data, slot, bindings)?;
+                }
+                ix.verify(index, &data)?;
+                Ok(CompiledInstruction {
+                    program_id_index: ix.program_id_index,
+                    account_indices: ix.account_indices.clone(),
+                    data,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(CompiledMessage {
+            header: self.header.clone(),
+            account_keys,
+            recent_blockhash,
+            instructions,
+        })
+    }
+
+    /// Re-bind a message previously produced by [`Self::fill`], patching
+    /// keys, blockhash and data slots in place without allocating
+    ///
+    /// The message's static data, every binding and the uniqueness of the
+    /// resulting keys are checked before anything is written, so on error
+    /// the message is left untouched. Static data is checked again
+    /// afterwards.
+    pub fn fill_in_place(&self, message: &mut CompiledMessage, bindings: &TemplateBindings) -> Result<()> {
+        let recent_blockhash = bindings.recent_blockhash.ok_or_else(|| missing("recent_blockhash"))?;
+        if message.header != self.header
+            || message.account_keys.len() != self.keys.len()
+            || message.instructions.len() != self.instructions.len()
+        {
+            return Err(TxAsmError::InvalidTransaction(
+                "Message was not produced by this template".to_string(),
+            ));
+        }
+        for (index, (ix, compiled)) in self.instructions.iter().zip(&message.instructions).enumerate() {
+            ix.verify(index, &compiled.data)?;
+        }
+        self.check_keys(bindings)?;
+        for slot in self.instructions.iter().flat_map(|ix| &ix.slots) {
+            check_slot(slot, bindings)?;
+        }
+
+        self.write_keys(&mut message.account_keys, bindings)?;
+        message.recent_blockhash = recent_blockhash;
+        for (index, (ix, compiled)) in self.instructions.iter().zip(&mut message.instructions).enumerate() {
+            for slot in &ix.slots {
+                write_slot(&mut compiled.data, slot, bindings)?;
+            }
+            ix.verify(index, &compiled.data)?;
+        }
+        Ok(())
+    }
+
+    /// Check every key placeholder is bound and no binding duplicates
+    /// another key
+    ///
+    /// A pairwise scan rather than a set, so checking needs no allocation.
+    /// Fixed keys are distinct by construction, so only pairs involving a
+    /// placeholder are compared.
+    fn check_keys(&self, bindings: &TemplateBindings) -> Result<()> {
+        for (i, key) in self.keys.iter().enumerate() {
+            let resolved = resolve_key(key, bindings)?;
+            for earlier in &self.keys[..i] {
+                let both_fixed = matches!((key, earlier), (TemplateKey::Fixed(_), TemplateKey::Fixed(_)));
+                if !both_fixed && resolve_key(earlier, bindings)? == resolved {
+                    return Err(TxAsmError::AccountError(format!(
+                        "Binding duplicates account {} already in the template",
+                        bs58::encode(resolved).into_string()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve every key into `account_keys`; call [`Self::check_keys`] first
+    fn write_keys(&self, account_keys: &mut [[u8; 32]], bindings: &TemplateBindings) -> Result<()> {
+        for (slot, key) in account_keys.iter_mut().zip(&self.keys) {
+            *slot = resolve_key(key, bindings)?;
+        }
+        Ok(())
+    }
+}
+
+fn resolve_key(key: &TemplateKey, bindings: &TemplateBindings) -> Result<[u8; 32]> {
+    match key {
+        TemplateKey::Fixed(key) => Ok(*key),
+        TemplateKey::Placeholder(name) => bindings.get_key(name),
+    }
+}
+
+/// Check a slot's binding exists and fits the field width, without writing
+fn check_slot(slot: &DataSlot, bindings: &TemplateBindings) -> Result<()> {
+    let fits = |name: &str, max: u64| {
+        let value = bindings.get_value(name)?;
+        if value > max {
+            return Err(TxAsmError::EncodingError(format!("Value {} does not fit slot '{}'", value, name)));
+        }
+        Ok(())
+    };
+    match &slot.field {
+        DataField::Bytes(_) => Ok(()),
+        DataField::U8(name) => fits(name, u8::MAX as u64),
+        DataField::U16(name) => fits(name, u16::MAX as u64),
+        DataField::U32(name) => fits(name, u32::MAX as u64),
+        DataField::U64(name) => fits(name, u64::MAX),
+        DataField::Pubkey(name) => bindings.get_key(name).map(|_| ()),
+    }
+}
+
+/// Write a bound value into its slot, checking it fits the field width
+fn write_slot(data: &mut [u8], slot: &DataSlot, bindings: &TemplateBindings) -> Result<()> {
+    let overflow = |name: &str, value: u64| {
+        TxAsmError::EncodingError(format!("Value {} does not fit slot '{}'", value, name))
+    };
+    let offset = slot.offset;
+    match &slot.field {
+        DataField::Bytes(_) => {}
+        DataField::U8(name) => {
+            let value = bindings.get_value(name)?;
+            let value = u8::try_from(value).map_err(|_| overflow(name, value))?;
+            data[offset] = value;
+        }
+        DataField::U16(name) => {
+            let value = bindings.get_value(name)?;
+            let value = u16::try_from(value).map_err(|_| overflow(name, value))?;
+            data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+        }
+        DataField::U32(name) => {
+            let value = bindings.get_value(name)?;
+            let value = u32::try_from(value).map_err(|_| overflow(name, value))?;
+            data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        DataField::U64(name) => {
+            let value = bindings.get_value(name)?;
+            data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        }
+        DataField::Pubkey(name) => {
+            data[offset..offset + 32].copy_from_slice(&bindings.get_key(name)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+    use crate::serialization::ByteSerialize;
+    use crate::transaction::TransactionBuilder;
+
+    fn transfer_template() -> TransactionTemplate {
+        let ix = TemplateInstruction::new([0u8; 32])
+            .account(TemplateKey::placeholder("payer"), true, true)
+            .account(TemplateKey::placeholder("recipient"), false, true)
+            .bytes(&2u32.to_le_bytes())
+            .u64_slot("lamports");
+        TransactionTemplate::compile(TemplateKey::placeholder("payer"), vec![ix]).unwrap()
+    }
+
+    #[test]
+    fn test_template_fill_matches_builder() {
+        let template = transfer_template();
+        let bindings = TemplateBindings::new()
+            .key("payer", [1u8; 32])
+            .key("recipient", [5u8; 32])
+            .value("lamports", 1_000)
+            .recent_blockhash([3u8; 32]);
+        let filled = template.fill(&bindings).unwrap();
+
+        let expected = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(
+                InstructionEncoder::new([0u8; 32])
+                    .signer([1u8; 32], true)
+                    .writable([5u8; 32], false)
+                    .append_u32(2)
+                    .append_u64(1_000)
+                    .build(),
+            )
+            .compile()
+            .unwrap();
+
+        let mut filled_bytes = Vec::new();
+        let mut expected_bytes = Vec::new();
+        filled.serialize_bytes(&mut filled_bytes).unwrap();
+        expected.serialize_bytes(&mut expected_bytes).unwrap();
+        assert_eq!(filled_bytes, expected_bytes);
+    }
+
+    #[test]
+    fn test_template_reports_missing_and_duplicate_bindings() {
+        let template = transfer_template();
+        let mut placeholders = template.placeholders();
+        placeholders.sort();
+        assert_eq!(placeholders, vec!["lamports", "payer", "recipient"]);
+
+        let partial = TemplateBindings::new().key("payer", [1u8; 32]).recent_blockhash([3u8; 32]);
+        assert!(template.fill(&partial).is_err());
+
+        let duplicate = partial.key("recipient", [1u8; 32]).value("lamports", 1);
+        assert!(matches!(template.fill(&duplicate), Err(TxAsmError::AccountError(_))));
+    }
+
+    #[test]
+    fn test_template_slot_offsets_and_overflow() {
+        let ix = TemplateInstruction::new([2u8; 32]).bytes(&[7]).u8_slot("small").u64_slot("big");
+        let template = TransactionTemplate::compile([1u8; 32], vec![ix]).unwrap();
+        let slots = template.data_slots(0).unwrap();
+        assert_eq!(slots[0].offset, 1);
+        assert_eq!(slots[1].offset, 2);
+
+        let bindings = TemplateBindings::new()
+            .value("small", 300)
+            .value("big", 1)
+            .recent_blockhash([3u8; 32]);
+        assert!(template.fill(&bindings).is_err());
+    }
+
+    #[test]
+    fn test_fill_in_place_guards_static_bytes() {
+        let template = transfer_template();
+        let bindings = TemplateBindings::new()
+            .key("payer", [1u8; 32])
+            .key("recipient", [5u8; 32])
+            .value("lamports", 1_000)
+            .recent_blockhash([3u8; 32]);
+        let mut message = template.fill(&bindings).unwrap();
+
+        let rebound = bindings.clone().value("lamports", 2_000).recent_blockhash([4u8; 32]);
+        template.fill_in_place(&mut message, &rebound).unwrap();
+        assert_eq!(message, template.fill(&rebound).unwrap());
+
+        // The transfer tag sits outside every slot
+        message.instructions[0].data[0] = 3;
+        let err = template.fill_in_place(&mut message, &rebound).unwrap_err();
+        assert!(err.to_string().contains("instruction 0"));
+        assert_eq!(message.recent_blockhash, [4u8; 32]);
+    }
+
+    #[test]
+    fn test_failed_fill_in_place_leaves_message_unchanged() {
+        let template = transfer_template();
+        let bindings = TemplateBindings::new()
+            .key("payer", [1u8; 32])
+            .key("recipient", [5u8; 32])
+            .value("lamports", 1_000)
+            .recent_blockhash([3u8; 32]);
+        let mut message = template.fill(&bindings).unwrap();
+        let original = message.clone();
+
+        // Recipient collides with the payer: rejected before any key is written
+        let duplicate = bindings.clone().key("recipient", [1u8; 32]).recent_blockhash([4u8; 32]);
+        assert!(template.fill_in_place(&mut message, &duplicate).is_err());
+        assert_eq!(message, original);
+
+        let unbound = TemplateBindings::new()
+            .key("payer", [7u8; 32])
+            .key("recipient", [8u8; 32])
+            .recent_blockhash([4u8; 32]);
+        assert!(template.fill_in_place(&mut message, &unbound).is_err());
+        assert_eq!(message, original);
+    }
+}
@@ -0,0 +1,124 @@
+//! Deterministic fixtures for tests
+//!
+//! Keypairs, addresses and blockhashes derived from a `u64` seed, canned
+//! program IDs, and small transactions whose bytes depend only on the seed.
+//! Ed25519 signing is deterministic, so [`simple_transfer_tx`] with the same
+//! seed serializes to the same bytes on every run and machine.
+//!
+//! Enabled with the `testkit` feature (typically as a dev-dependency).
+
+use crate::instruction::{InstructionEncoder, RawInstruction};
+use crate::transaction::{CompiledTransaction, TransactionBuilder};
+use sha2::{Digest, Sha256};
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+
+pub const SYSTEM_PROGRAM_ID: [u8; 32] = solana_sdk::system_program::ID.to_bytes();
+pub const TOKEN_PROGRAM_ID: [u8; 32] = solana_sdk::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").to_bytes();
+pub const TOKEN_2022_PROGRAM_ID: [u8; 32] =
+    solana_sdk::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb").to_bytes();
+pub const MEMO_PROGRAM_ID: [u8; 32] = solana_sdk::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").to_bytes();
+pub use crate::fee_calculator::compute_budget::COMPUTE_BUDGET_PROGRAM_ID;
+
+/// Lamports moved by [`simple_transfer_tx`]
+pub const TRANSFER_LAMPORTS: u64 = 1_000_000;
+
+/// 32 bytes derived from a domain tag and seed
+fn derive(domain: &str, seed: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"txasm-testkit:");
+    hasher.update(domain.as_bytes());
+    hasher.update(seed.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Keypair derived from `seed`
+pub fn keypair(seed: u64) -> Keypair {
+    keypair_from_seed(&derive("keypair", seed)).expect("32-byte seed")
+}
+
+/// Address of [`keypair`]`(seed)`
+pub fn pubkey(seed: u64) -> [u8; 32] {
+    keypair(seed).pubkey().to_bytes()
+}
+
+/// Address with no known private key, e.g. for a made-up program or mint
+pub fn address(seed: u64) -> [u8; 32] {
+    derive("address", seed)
+}
+
+pub fn blockhash(seed: u64) -> [u8; 32] {
+    derive("blockhash", seed)
+}
+
+/// System transfer of `lamports` between two addresses
+pub fn transfer_instruction(from: [u8; 32], to: [u8; 32], lamports: u64) -> RawInstruction {
+    InstructionEncoder::new(SYSTEM_PROGRAM_ID)
+        .signer(from, true)
+        .writable(to, false)
+        .append_u32(2)
+        .append_u64(lamports)
+        .build()
+}
+
+/// Signed transfer of [`TRANSFER_LAMPORTS`] from [`keypair`]`(seed)` to
+/// [`pubkey`]`(seed + 1)` against [`blockhash`]`(seed)`
+pub fn simple_transfer_tx(seed: u64) -> CompiledTransaction {
+    let payer = keypair(seed);
+    TransactionBuilder::new()
+        .payer_pubkey(&payer.pubkey())
+        .recent_blockhash(blockhash(seed))
+        .add_instruction(transfer_instruction(
+            payer.pubkey().to_bytes(),
+            pubkey(seed.wrapping_add(1)),
+            TRANSFER_LAMPORTS,
+        ))
+        .build_and_sign(&[&payer])
+        .expect("fixture transaction compiles")
+}
+
+/// Unsigned transaction of `count` memo instructions, for size and
+/// instruction-count tests
+pub fn memo_tx(seed: u64, count: usize) -> CompiledTransaction {
+    let payer = pubkey(seed);
+    let memos = (0..count)
+        .map(|i| {
+            InstructionEncoder::new(MEMO_PROGRAM_ID)
+                .signer(payer, false)
+                .data(format!("memo {}", i).into_bytes())
+                .build()
+        })
+        .collect();
+    TransactionBuilder::new()
+        .payer(payer)
+        .recent_blockhash(blockhash(seed))
+        .add_instructions(memos)
+        .build_unsigned()
+        .expect("fixture transaction compiles")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures_are_deterministic() {
+        let a = simple_transfer_tx(7).serialize().unwrap();
+        assert_eq!(a, simple_transfer_tx(7).serialize().unwrap());
+        assert_ne!(a, simple_transfer_tx(8).serialize().unwrap());
+        assert_eq!(keypair(7).to_bytes(), keypair(7).to_bytes());
+        assert_ne!(address(7), pubkey(7));
+
+        let tx = simple_transfer_tx(7);
+        tx.verify_strict().unwrap();
+        assert_eq!(tx.message.account_keys[..2], [pubkey(7), pubkey(8)]);
+        assert_eq!(tx.message.recent_blockhash, blockhash(7));
+    }
+
+    #[test]
+    fn test_memo_tx() {
+        let tx = memo_tx(1, 3);
+        assert_eq!(tx.message.instructions.len(), 3);
+        assert_eq!(tx.message.instructions[2].data, b"memo 2");
+        assert_eq!(tx.signatures, vec![[0u8; 64]]);
+    }
+}
@@ -0,0 +1,276 @@
+//! Leader-aware submission timing
+//!
+//! [`SubmissionAdvisor`] walks the leader schedule forward from the current
+//! slot and recommends when to broadcast and which leaders' TPUs to target,
+//! so a transaction reaches a leader before its blockhash expires. It
+//! complements the [`scheduler`](crate::scheduler) and its
+//! [`Sender`](crate::scheduler::Sender), which decide what to send; the
+//! advisor decides when and where.
+
+use crate::error::{Result, TxAsmError};
+use crate::serialization::parse_pubkey;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+/// Slot leaders for a contiguous slot range
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderSchedule {
+    first_slot: u64,
+    leaders: Vec<[u8; 32]>,
+}
+
+impl LeaderSchedule {
+    /// `leaders[i]` leads slot `first_slot + i`
+    pub fn new(first_slot: u64, leaders: Vec<[u8; 32]>) -> Self {
+        Self { first_slot, leaders }
+    }
+
+    /// Parse a `getLeaderSchedule` result, which maps each identity to the
+    /// slot indices it leads relative to `epoch_start_slot`
+    pub fn from_rpc_json(json: &str, epoch_start_slot: u64) -> Result<Self> {
+        let value: Value = serde_json::from_str(json).map_err(|e| TxAsmError::DecodingError(e.to_string()))?;
+        let schedule = value.get("result").unwrap_or(&value);
+        let entries = schedule
+            .as_object()
+            .ok_or_else(|| TxAsmError::DecodingError("Leader schedule is not an object".to_string()))?;
+
+        let mut slots: Vec<Option<[u8; 32]>> = Vec::new();
+        for (identity, indices) in entries {
+            let leader = parse_pubkey(identity)?;
+            let indices = indices
+                .as_array()
+                .ok_or_else(|| TxAsmError::DecodingError(format!("Slots for {} are not an array", identity)))?;
+            for index in indices {
+                let index = index
+                    .as_u64()
+                    .ok_or_else(|| TxAsmError::DecodingError(format!("Invalid slot index {}", index)))?
+                    as usize;
+                if index >= slots.len() {
+                    slots.resize(index + 1, None);
+                }
+                slots[index] = Some(leader);
+            }
+        }
+        let leaders = slots
+            .into_iter()
+            .enumerate()
+            .map(|(index, leader)| {
+                leader.ok_or_else(|| TxAsmError::DecodingError(format!("No leader for slot index {}", index)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self::new(epoch_start_slot, leaders))
+    }
+
+    pub fn first_slot(&self) -> u64 {
+        self.first_slot
+    }
+
+    /// Last slot the schedule covers
+    pub fn last_slot(&self) -> Option<u64> {
+        (self.leaders.len() as u64).checked_sub(1).map(|last| self.first_slot + last)
+    }
+
+    pub fn leader_at(&self, slot: u64) -> Option<[u8; 32]> {
+        let index = usize::try_from(slot.checked_sub(self.first_slot)?).ok()?;
+        self.leaders.get(index).copied()
+    }
+
+    /// Consecutive slots led by the same leader, starting with the window
+    /// that contains `slot`
+    pub fn windows_from(&self, slot: u64) -> impl Iterator<Item = LeaderWindow> + '_ {
+        let mut next = slot.max(self.first_slot);
+        // Start from the beginning of the window containing `slot`
+        if let Some(leader) = self.leader_at(next) {
+            while next > self.first_slot && self.leader_at(next - 1) == Some(leader) {
+                next -= 1;
+            }
+        }
+        std::iter::from_fn(move || {
+            let leader = self.leader_at(next)?;
+            let start = next;
+            while self.leader_at(next + 1) == Some(leader) {
+                next += 1;
+            }
+            next += 1;
+            Some(LeaderWindow {
+                leader,
+                start_slot: start,
+                end_slot: next - 1,
+            })
+        })
+    }
+}
+
+/// Consecutive slots led by one validator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeaderWindow {
+    pub leader: [u8; 32],
+    pub start_slot: u64,
+    /// Inclusive
+    pub end_slot: u64,
+}
+
+/// A leader to send to
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderTarget {
+    pub window: LeaderWindow,
+    /// The leader's TPU address, when known
+    pub tpu: Option<SocketAddr>,
+}
+
+/// When and where to broadcast
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmissionAdvice {
+    /// Broadcast once this slot is reached (the current slot means now)
+    pub send_at_slot: u64,
+    /// Leaders to send to, in window order
+    pub targets: Vec<LeaderTarget>,
+    /// Slots between the first target window opening and blockhash expiry
+    pub slots_of_validity: u64,
+}
+
+impl SubmissionAdvice {
+    pub fn send_now(&self, current_slot: u64) -> bool {
+        current_slot >= self.send_at_slot
+    }
+}
+
+/// Recommends broadcast timing and TPU targets from a leader schedule
+#[derive(Debug, Clone)]
+pub struct SubmissionAdvisor {
+    schedule: LeaderSchedule,
+    tpus: HashMap<[u8; 32], SocketAddr>,
+    lead_slots: u64,
+    fanout: usize,
+}
+
+impl SubmissionAdvisor {
+    /// Targets the next 2 distinct leaders, sending 1 slot ahead of each window
+    pub fn new(schedule: LeaderSchedule) -> Self {
+        Self {
+            schedule,
+            tpus: HashMap::new(),
+            lead_slots: 1,
+            fanout: 2,
+        }
+    }
+
+    /// TPU addresses by leader identity, e.g. from `getClusterNodes`
+    pub fn tpus(mut self, tpus: HashMap<[u8; 32], SocketAddr>) -> Self {
+        self.tpus = tpus;
+        self
+    }
+
+    /// Slots of network latency to send ahead of a leader's window
+    pub fn lead_slots(mut self, lead_slots: u64) -> Self {
+        self.lead_slots = lead_slots;
+        self
+    }
+
+    /// Distinct upcoming leaders to target (at least 1)
+    pub fn fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout.max(1);
+        self
+    }
+
+    pub fn schedule(&self) -> &LeaderSchedule {
+        &self.schedule
+    }
+
+    /// Advice for a transaction whose blockhash is valid through `last_valid_slot`
+    ///
+    /// The current leader is targeted only while more than `lead_slots` of
+    /// its window remain. Fails when no leader window opens before expiry or
+    /// the schedule does not cover `current_slot`.
+    pub fn advise(&self, current_slot: u64, last_valid_slot: u64) -> Result<SubmissionAdvice> {
+        if self.schedule.leader_at(current_slot).is_none() {
+            return Err(TxAsmError::InvalidTransaction(format!(
+                "Leader schedule does not cover slot {}",
+                current_slot
+            )));
+        }
+
+        let mut targets: Vec<LeaderTarget> = Vec::with_capacity(self.fanout);
+        for window in self.schedule.windows_from(current_slot) {
+            if window.start_slot > last_valid_slot || targets.len() == self.fanout {
+                break;
+            }
+            let reachable = window.end_slot >= current_slot + self.lead_slots;
+            if reachable && targets.iter().all(|t| t.window.leader != window.leader) {
+                targets.push(LeaderTarget {
+                    window,
+                    tpu: self.tpus.get(&window.leader).copied(),
+                });
+            }
+        }
+
+        let first = targets.first().ok_or_else(|| {
+            TxAsmError::InvalidTransaction(format!(
+                "Blockhash expires at slot {} before another leader window opens",
+                last_valid_slot
+            ))
+        })?;
+        let opens = first.window.start_slot.max(current_slot);
+        Ok(SubmissionAdvice {
+            send_at_slot: first.window.start_slot.saturating_sub(self.lead_slots).max(current_slot),
+            slots_of_validity: last_valid_slot.saturating_sub(opens),
+            targets,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> LeaderSchedule {
+        // Leaders A, B, A, C in 4-slot windows from slot 100
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        LeaderSchedule::new(100, [a, b, a, c].iter().flat_map(|l| [*l; 4]).collect())
+    }
+
+    #[test]
+    fn test_advice_targets_distinct_upcoming_leaders() {
+        let tpu: SocketAddr = "10.0.0.2:8003".parse().unwrap();
+        let advisor = SubmissionAdvisor::new(schedule()).tpus(HashMap::from([([2u8; 32], tpu)]));
+
+        // Early in A's window: send now to A, then B
+        let advice = advisor.advise(101, 200).unwrap();
+        assert!(advice.send_now(101));
+        let leaders: Vec<_> = advice.targets.iter().map(|t| t.window.leader).collect();
+        assert_eq!(leaders, vec![[1u8; 32], [2u8; 32]]);
+        assert_eq!(advice.targets[1].tpu, Some(tpu));
+
+        // Last slot of A's window: too late for A, wait one slot less than B's start
+        let advice = advisor.advise(103, 200).unwrap();
+        assert_eq!(advice.targets[0].window.start_slot, 104);
+        assert_eq!(advice.send_at_slot, 103);
+        // A's next window still counts, since its current one was skipped
+        assert_eq!(advice.targets[1].window, LeaderWindow { leader: [1u8; 32], start_slot: 108, end_slot: 111 });
+
+        let advice = advisor.clone().lead_slots(3).advise(101, 200).unwrap();
+        assert_eq!(advice.targets[0].window.start_slot, 104);
+        assert_eq!(advice.send_at_slot, 101);
+    }
+
+    #[test]
+    fn test_expiry_and_schedule_parsing() {
+        let advisor = SubmissionAdvisor::new(schedule()).fanout(3);
+        let advice = advisor.advise(102, 105).unwrap();
+        assert_eq!(advice.targets.len(), 2);
+        assert_eq!(advice.slots_of_validity, 3);
+        assert!(advisor.advise(103, 103).is_err());
+        assert!(advisor.advise(500, 600).is_err());
+
+        let a = bs58::encode([1u8; 32]).into_string();
+        let b = bs58::encode([2u8; 32]).into_string();
+        let json = format!(r#"{{"jsonrpc":"2.0","result":{{"{a}":[0,1,4],"{b}":[2,3]}},"id":1}}"#);
+        let parsed = LeaderSchedule::from_rpc_json(&json, 1000).unwrap();
+        assert_eq!(parsed.last_slot(), Some(1004));
+        assert_eq!(parsed.leader_at(1003), Some([2u8; 32]));
+        let windows: Vec<_> = parsed.windows_from(1001).map(|w| (w.start_slot, w.end_slot)).collect();
+        assert_eq!(windows, vec![(1000, 1001), (1002, 1003), (1004, 1004)]);
+        assert!(LeaderSchedule::from_rpc_json(&format!(r#"{{"{a}":[0,2]}}"#), 0).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Decimal-aware token amounts
+//!
+//! SPL token instructions take amounts in base units, while users and
+//! configs speak in UI units ("1.5 USDC"). [`TokenAmount`] keeps the raw
+//! value and its mint's decimals together and converts between the two
+//! exactly, with no floating point and no silent truncation.
+
+use crate::error::{Result, TxAsmError};
+use std::fmt;
+
+/// A raw token amount together with its mint's decimals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TokenAmount {
+    /// Amount in base units
+    pub raw: u64,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(raw: u64, decimals: u8) -> Self {
+        Self { raw, decimals }
+    }
+
+    /// Parse a UI amount such as `"1.5"` for a mint with `decimals`
+    ///
+    /// Fails rather than rounds when the string has more fractional digits
+    /// than the mint supports, and when the result overflows `u64`.
+    pub fn from_ui(ui: &str, decimals: u8) -> Result<Self> {
+        let invalid = |reason: &str| TxAsmError::DecodingError(format!("Invalid token amount '{}': {}", ui, reason));
+
+        let (whole, fraction) = ui.trim().split_once('.').unwrap_or((ui.trim(), ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid("no digits"));
+        }
+        if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(invalid("expected digits with an optional decimal point"));
+        }
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.len() > decimals as usize {
+            return Err(invalid(&format!("more than {} decimal places", decimals)));
+        }
+
+        // Digits beyond u128 are an overflow too, so parse failures map to it
+        let parse = |digits: &str| if digits.is_empty() { Some(0u128) } else { digits.parse().ok() };
+        let raw = pow10(decimals)
+            .zip(parse(whole))
+            .and_then(|(scale, whole)| whole.checked_mul(scale))
+            .zip(parse(fraction).zip(pow10(decimals - fraction.len() as u8)))
+            // `fraction * scale` is below 10^decimals, which fit above
+            .and_then(|(whole, (fraction, scale))| whole.checked_add(fraction * scale))
+            .and_then(|raw| u64::try_from(raw).ok())
+            .ok_or_else(|| invalid("exceeds u64"))?;
+        Ok(Self { raw, decimals })
+    }
+
+    /// UI string with trailing fractional zeros trimmed, e.g. `"1.5"`
+    pub fn to_ui_string(&self) -> String {
+        let decimals = self.decimals as usize;
+        if decimals == 0 {
+            return self.raw.to_string();
+        }
+        let digits = format!("{:0>width$}", self.raw, width = decimals + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - decimals);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{}.{}", whole, fraction)
+        }
+    }
+
+    /// Sum of two amounts of the same mint
+    pub fn checked_add(self, other: Self) -> Result<Self> {
+        self.same_decimals(&other)?;
+        self.raw
+            .checked_add(other.raw)
+            .map(|raw| Self::new(raw, self.decimals))
+            .ok_or_else(|| TxAsmError::EncodingError(format!("{} + {} overflows u64", self, other)))
+    }
+
+    /// Difference of two amounts of the same mint
+    pub fn checked_sub(self, other: Self) -> Result<Self> {
+        self.same_decimals(&other)?;
+        self.raw
+            .checked_sub(other.raw)
+            .map(|raw| Self::new(raw, self.decimals))
+            .ok_or_else(|| TxAsmError::EncodingError(format!("{} - {} underflows", self, other)))
+    }
+
+    /// The same value expressed with `decimals`, if exactly representable
+    pub fn rescale(self, decimals: u8) -> Result<Self> {
+        Self::from_ui(&self.to_ui_string(), decimals)
+    }
+
+    fn same_decimals(&self, other: &Self) -> Result<()> {
+        if self.decimals != other.decimals {
+            return Err(TxAsmError::EncodingError(format!(
+                "Token amounts have different decimals ({} and {})",
+                self.decimals, other.decimals
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_ui_string())
+    }
+}
+
+fn pow10(exponent: u8) -> Option<u128> {
+    10u128.checked_pow(exponent as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ui_round_trip() {
+        let amount = TokenAmount::from_ui("1.5", 6).unwrap();
+        assert_eq!(amount.raw, 1_500_000);
+        assert_eq!(amount.to_ui_string(), "1.5");
+        assert_eq!(TokenAmount::from_ui("0.000001", 6).unwrap().raw, 1);
+        assert_eq!(TokenAmount::from_ui(".25", 2).unwrap().raw, 25);
+        assert_eq!(TokenAmount::from_ui("42", 0).unwrap().to_string(), "42");
+        assert_eq!(TokenAmount::new(5, 9).to_string(), "0.000000005");
+        assert_eq!(TokenAmount::new(2_000_000_000, 9).to_string(), "2");
+        assert_eq!(TokenAmount::from_ui("1.50", 1).unwrap().raw, 15);
+
+        assert!(TokenAmount::from_ui("1.2345", 2).is_err());
+        assert!(TokenAmount::from_ui("18446744073709551616", 0).is_err());
+        assert!(TokenAmount::from_ui("1", 20).is_err());
+        assert!(TokenAmount::from_ui("-1", 6).is_err());
+        assert!(TokenAmount::from_ui(".", 6).is_err());
+    }
+
+    #[test]
+    fn test_checked_math() {
+        let a = TokenAmount::from_ui("1.5", 6).unwrap();
+        let b = TokenAmount::from_ui("0.25", 6).unwrap();
+        assert_eq!(a.checked_add(b).unwrap().to_string(), "1.75");
+        assert_eq!(a.checked_sub(b).unwrap().raw, 1_250_000);
+        assert!(b.checked_sub(a).is_err());
+        assert!(a.checked_add(TokenAmount::new(1, 9)).is_err());
+
+        assert_eq!(a.rescale(9).unwrap().raw, 1_500_000_000);
+        assert!(TokenAmount::new(1, 9).rescale(6).is_err());
+    }
+}
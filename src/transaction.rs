@@ -3,29 +3,146 @@
 //! This module provides comprehensive transaction construction capabilities,
 //! including signature handling, account management, and message compilation.
 
+use crate::diff::AccountRole;
 use crate::error::{Result, TxAsmError};
-use crate::instruction::RawInstruction;
+use crate::instruction::{AccountMeta, RawInstruction};
+use crate::policy::{self, Policy};
+use crate::simd;
 use crate::serialization::{
-    ByteSerialize, encode_compact_u16, encode_pubkey, encode_u8,
+    ByteDeserialize, ByteSerialize, encode_len, encode_pubkey, encode_u8,
     decode_compact_u16, decode_pubkey, decode_u8,
 };
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
 use solana_sdk::{
     hash::Hash,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
+use std::ops::Range;
+use std::sync::Arc;
 
-/// Transaction version
+/// Maximum number of account keys a message can address with u8 indices
+pub const MAX_ACCOUNT_KEYS: usize = 256;
+
+/// Maximum serialized transaction size (the network packet payload)
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+/// Caps on counts read from untrusted bytes, checked before allocating
+///
+/// Decoded lengths are attacker-controlled, so every count is compared with
+/// its cap before any buffer is sized from it. The defaults admit anything
+/// that fits in a [`MAX_TRANSACTION_SIZE`] packet.
 #[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodeLimits {
+    pub max_signatures: usize,
+    pub max_account_keys: usize,
+    pub max_instructions: usize,
+    /// Account indices per instruction
+    pub max_instruction_accounts: usize,
+    /// Data bytes per instruction
+    pub max_data_len: usize,
+    pub max_address_table_lookups: usize,
+    /// Writable or readonly indexes per address table lookup
+    pub max_lookup_indexes: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_signatures: MAX_TRANSACTION_SIZE / 64,
+            max_account_keys: MAX_TRANSACTION_SIZE / 32,
+            // Smallest instruction: program index and two empty lengths
+            max_instructions: MAX_TRANSACTION_SIZE / 3,
+            max_instruction_accounts: MAX_TRANSACTION_SIZE,
+            max_data_len: MAX_TRANSACTION_SIZE,
+            // Smallest lookup: table key and two empty lengths
+            max_address_table_lookups: MAX_TRANSACTION_SIZE / 34,
+            max_lookup_indexes: MAX_TRANSACTION_SIZE,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// No caps beyond the u16 range of compact-u16 lengths
+    pub fn unlimited() -> Self {
+        let max = u16::MAX as usize;
+        Self {
+            max_signatures: max,
+            max_account_keys: max,
+            max_instructions: max,
+            max_instruction_accounts: max,
+            max_data_len: max,
+            max_address_table_lookups: max,
+            max_lookup_indexes: max,
+        }
+    }
+
+    /// Read a compact-u16 count and reject it when above `max`
+    pub(crate) fn read(&self, cursor: &mut Cursor<&[u8]>, what: &'static str, max: usize) -> Result<usize> {
+        let len = decode_compact_u16(cursor)? as usize;
+        if len > max {
+            return Err(TxAsmError::LengthOverflow { what, len, max });
+        }
+        Ok(len)
+    }
+}
+
+/// Signature vector normalized to the header's `num_required_signatures`
+///
+/// Reported by the tolerant decoders when a partially built transaction from
+/// another tool carries the wrong number of signature slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureRepair {
+    /// Missing slots were appended as zeroed (unsigned) signatures
+    Padded { found: usize, required: usize },
+    /// Surplus slots were dropped; `dropped_signed` of them were non-zero
+    Truncated { found: usize, required: usize, dropped_signed: usize },
+}
+
+impl std::fmt::Display for SignatureRepair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureRepair::Padded { found, required } => {
+                write!(f, "padded {} signatures to the {} the header requires", found, required)
+            }
+            SignatureRepair::Truncated { found, required, dropped_signed } => write!(
+                f,
+                "truncated {} signatures to the {} the header requires, dropping {} non-zero",
+                found, required, dropped_signed
+            ),
+        }
+    }
+}
+
+/// Pad or truncate `signatures` to `required` slots
+pub(crate) fn normalize_signatures(signatures: &mut Vec<[u8; 64]>, required: usize) -> Option<SignatureRepair> {
+    let found = signatures.len();
+    if found < required {
+        signatures.resize(required, [0u8; 64]);
+        Some(SignatureRepair::Padded { found, required })
+    } else if found > required {
+        let dropped_signed = signatures.drain(required..).filter(|s| !simd::is_zero_signature(s)).count();
+        Some(SignatureRepair::Truncated { found, required, dropped_signed })
+    } else {
+        None
+    }
+}
+
+/// Transaction version
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TransactionVersion {
     Legacy,
     V0,
 }
 
 /// Message header containing account metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct MessageHeader {
     pub num_required_signatures: u8,
     pub num_readonly_signed_accounts: u8,
@@ -46,6 +163,50 @@ impl MessageHeader {
     }
 }
 
+impl MessageHeader {
+    /// Check that the header is consistent with the message's keys and instructions
+    pub fn validate(&self, message: &CompiledMessage) -> Result<()> {
+        let num_keys = message.account_keys.len();
+        let num_signers = self.num_required_signatures as usize;
+        let invalid = |msg: String| Err(TxAsmError::InvalidTransaction(msg));
+
+        if num_signers == 0 {
+            return invalid("Header requires no signatures; the fee payer must sign".to_string());
+        }
+        if num_signers > num_keys {
+            return invalid(format!(
+                "Header requires {} signatures but message has {} keys",
+                num_signers, num_keys
+            ));
+        }
+        if self.num_readonly_signed_accounts as usize >= num_signers {
+            return invalid(format!(
+                "{} readonly signers leave no writable fee payer among {} signers",
+                self.num_readonly_signed_accounts, num_signers
+            ));
+        }
+        if self.num_readonly_unsigned_accounts as usize > num_keys - num_signers {
+            return invalid(format!(
+                "{} readonly unsigned accounts exceed the {} unsigned keys",
+                self.num_readonly_unsigned_accounts,
+                num_keys - num_signers
+            ));
+        }
+        for (i, ix) in message.instructions.iter().enumerate() {
+            let out_of_range = std::iter::once(&ix.program_id_index)
+                .chain(ix.account_indices.iter())
+                .find(|&&index| index as usize >= num_keys);
+            if let Some(index) = out_of_range {
+                return invalid(format!(
+                    "Instruction {} references account index {} but message has {} keys",
+                    i, index, num_keys
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 impl ByteSerialize for MessageHeader {
     fn serialize_bytes(&self, writer: &mut Vec<u8>) -> Result<()> {
         encode_u8(self.num_required_signatures, writer)?;
@@ -59,8 +220,52 @@ impl ByteSerialize for MessageHeader {
     }
 }
 
+/// One of the four role regions of a message's account keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountRegion {
+    WritableSigner,
+    ReadonlySigner,
+    WritableUnsigned,
+    ReadonlyUnsigned,
+}
+
+/// Region boundaries of a message's account keys, derived from its header
+///
+/// Messages built by [`TransactionBuilder`] always place the fee payer at
+/// index 0 and lay the regions out in field order, each sorted by key bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageLayout {
+    pub writable_signers: Range<usize>,
+    pub readonly_signers: Range<usize>,
+    pub writable_unsigned: Range<usize>,
+    pub readonly_unsigned: Range<usize>,
+}
+
+impl MessageLayout {
+    /// Index of the fee payer, if the message has a signer at all
+    pub fn fee_payer(&self) -> Option<usize> {
+        (!self.writable_signers.is_empty()).then_some(0)
+    }
+
+    /// Region holding account `index`, or `None` past the last key
+    pub fn region(&self, index: usize) -> Option<AccountRegion> {
+        [
+            (&self.writable_signers, AccountRegion::WritableSigner),
+            (&self.readonly_signers, AccountRegion::ReadonlySigner),
+            (&self.writable_unsigned, AccountRegion::WritableUnsigned),
+            (&self.readonly_unsigned, AccountRegion::ReadonlyUnsigned),
+        ]
+        .into_iter()
+        .find(|(range, _)| range.contains(&index))
+        .map(|(_, region)| region)
+    }
+}
+
 /// Compiled message ready for signing
-#[derive(Debug, Clone)]
+///
+/// Every field maps one-to-one onto the wire format, so `==` and `Hash`
+/// agree with comparing [`serialize`](Self::serialize) output.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct CompiledMessage {
     pub header: MessageHeader,
     pub account_keys: Vec<[u8; 32]>,
@@ -68,22 +273,235 @@ pub struct CompiledMessage {
     pub instructions: Vec<CompiledInstruction>,
 }
 
+impl CompiledMessage {
+    /// Serialize the message, i.e. the bytes signers sign
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(self.byte_size());
+        self.serialize_bytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Decode a standalone message, rejecting trailing bytes
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        Self::deserialize_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    /// [`Self::deserialize`] with explicit caps on decoded counts
+    pub fn deserialize_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        let message = Self::decode(&mut cursor, limits)?;
+        if (cursor.position() as usize) != bytes.len() {
+            return Err(TxAsmError::DeserializationError(format!(
+                "{} trailing bytes after message",
+                bytes.len() - cursor.position() as usize
+            )));
+        }
+        Ok(message)
+    }
+
+    /// Base64 message bytes, the payload of a wallet's `signMessage`
+    pub fn to_base64(&self) -> Result<String> {
+        Ok(BASE64.encode(self.serialize()?))
+    }
+
+    /// Decode a base64 message produced by [`Self::to_base64`]
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(encoded)
+            .map_err(|e| TxAsmError::DecodingError(e.to_string()))?;
+        Self::deserialize(&bytes)
+    }
+
+    /// Whether the account at `index` must sign
+    pub fn is_signer(&self, index: usize) -> bool {
+        index < self.header.num_required_signatures as usize
+    }
+
+    /// Whether the account at `index` is write-locked
+    pub fn is_writable(&self, index: usize) -> bool {
+        let num_keys = self.account_keys.len();
+        let num_signers = self.header.num_required_signatures as usize;
+        if index >= num_keys {
+            return false;
+        }
+        if index < num_signers {
+            index < num_signers.saturating_sub(self.header.num_readonly_signed_accounts as usize)
+        } else {
+            index < num_keys.saturating_sub(self.header.num_readonly_unsigned_accounts as usize)
+        }
+    }
+
+    /// Position of `pubkey` in the account keys, `None` if absent or past
+    /// the last index an instruction can reference
+    pub fn account_index(&self, pubkey: &[u8; 32]) -> Option<u8> {
+        self.account_keys
+            .iter()
+            .position(|key| key == pubkey)
+            .and_then(|i| u8::try_from(i).ok())
+    }
+
+    /// Region boundaries implied by the header, clamped to the key count
+    pub fn message_layout(&self) -> MessageLayout {
+        let num_keys = self.account_keys.len();
+        let num_signers = (self.header.num_required_signatures as usize).min(num_keys);
+        let readonly_signers = (self.header.num_readonly_signed_accounts as usize).min(num_signers);
+        let readonly_unsigned = (self.header.num_readonly_unsigned_accounts as usize).min(num_keys - num_signers);
+        MessageLayout {
+            writable_signers: 0..num_signers - readonly_signers,
+            readonly_signers: num_signers - readonly_signers..num_signers,
+            writable_unsigned: num_signers..num_keys - readonly_unsigned,
+            readonly_unsigned: num_keys - readonly_unsigned..num_keys,
+        }
+    }
+
+    /// Whether the account at `index` is invoked as a program
+    pub fn is_program(&self, index: usize) -> bool {
+        self.instructions
+            .iter()
+            .any(|ix| ix.program_id_index as usize == index)
+    }
+
+    /// Key and role of the account at `index`
+    pub fn account_role(&self, index: usize) -> Option<AccountRole> {
+        self.account_keys.get(index).map(|key| AccountRole {
+            index,
+            key: *key,
+            is_signer: self.is_signer(index),
+            is_writable: self.is_writable(index),
+        })
+    }
+
+    /// Every instruction with its program and accounts resolved from indices
+    ///
+    /// An instruction referencing an index past the key list yields an
+    /// error; iteration continues with the next one.
+    pub fn walk(&self) -> impl Iterator<Item = Result<ResolvedInstruction<'_>>> + '_ {
+        self.instructions.iter().enumerate().map(move |(index, ix)| {
+            let out_of_range = |what: &str, i: u8| {
+                TxAsmError::InvalidInstruction(format!(
+                    "Instruction {} {} index {} out of range for {} keys",
+                    index,
+                    what,
+                    i,
+                    self.account_keys.len()
+                ))
+            };
+            let program_id = self
+                .account_keys
+                .get(ix.program_id_index as usize)
+                .ok_or_else(|| out_of_range("program", ix.program_id_index))?;
+            let accounts = ix
+                .account_indices
+                .iter()
+                .map(|&i| self.account_role(i as usize).ok_or_else(|| out_of_range("account", i)))
+                .collect::<Result<_>>()?;
+            Ok(ResolvedInstruction {
+                index,
+                program_id,
+                accounts,
+                data: &ix.data,
+            })
+        })
+    }
+
+    /// Recompute the header from account roles, reordering keys if needed
+    ///
+    /// Keys listed in `roles` take the given signer/writable flags; all other
+    /// keys keep the role implied by the current header. Keys are stably
+    /// regrouped into protocol order and instruction indices are remapped.
+    /// Returns `true` if the message changed.
+    pub fn repair_header(&mut self, roles: &[AccountMeta]) -> Result<bool> {
+        let role_of = |index: usize, key: &[u8; 32]| {
+            roles
+                .iter()
+                .find(|meta| meta.pubkey == *key)
+                .map(|meta| (meta.is_signer, meta.is_writable))
+                .unwrap_or((self.is_signer(index), self.is_writable(index)))
+        };
+        let mut entries: Vec<(usize, bool, bool)> = self
+            .account_keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let (is_signer, is_writable) = role_of(i, key);
+                (i, is_signer, is_writable)
+            })
+            .collect();
+
+        entries.sort_by_key(|(_, is_signer, is_writable)| match (*is_signer, *is_writable) {
+            (true, true) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (false, false) => 3,
+        });
+
+        let mut new_index = vec![0u8; entries.len()];
+        for (new, (old, _, _)) in entries.iter().enumerate() {
+            new_index[*old] = checked_u8("account index", new)?;
+        }
+
+        let header = MessageHeader::new(
+            checked_u8("required signatures", entries.iter().filter(|(_, s, _)| *s).count())?,
+            checked_u8("readonly signed accounts", entries.iter().filter(|(_, s, w)| *s && !*w).count())?,
+            checked_u8("readonly unsigned accounts", entries.iter().filter(|(_, s, w)| !*s && !*w).count())?,
+        );
+
+        let changed = header != self.header || entries.iter().enumerate().any(|(new, (old, _, _))| new != *old);
+        if !changed {
+            return Ok(false);
+        }
+
+        let remap = |index: u8| {
+            new_index.get(index as usize).copied().ok_or_else(|| {
+                TxAsmError::InvalidInstruction(format!("Account index {} out of range", index))
+            })
+        };
+        for ix in &mut self.instructions {
+            ix.program_id_index = remap(ix.program_id_index)?;
+            for index in &mut ix.account_indices {
+                *index = remap(*index)?;
+            }
+        }
+        self.account_keys = entries.iter().map(|(old, _, _)| self.account_keys[*old]).collect();
+        self.header = header;
+        Ok(true)
+    }
+}
+
+/// Account indices of a compiled instruction, stored inline for typical sizes
+pub type AccountIndices = SmallVec<[u8; 16]>;
+
+/// An instruction as yielded by [`CompiledMessage::walk`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedInstruction<'a> {
+    /// Position in the message
+    pub index: usize,
+    pub program_id: &'a [u8; 32],
+    /// Accounts in instruction order, each with its message-wide role
+    pub accounts: Vec<AccountRole>,
+    pub data: &'a [u8],
+}
+
 /// Compiled instruction with resolved account indices
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct CompiledInstruction {
     pub program_id_index: u8,
-    pub account_indices: Vec<u8>,
+    #[borsh(
+        serialize_with = "crate::storage::account_indices::serialize",
+        deserialize_with = "crate::storage::account_indices::deserialize"
+    )]
+    pub account_indices: AccountIndices,
     pub data: Vec<u8>,
 }
 
 impl ByteSerialize for CompiledInstruction {
     fn serialize_bytes(&self, writer: &mut Vec<u8>) -> Result<()> {
         encode_u8(self.program_id_index, writer)?;
-        encode_compact_u16(self.account_indices.len() as u16, writer)?;
+        encode_len("instruction accounts", self.account_indices.len(), writer)?;
         for &index in &self.account_indices {
             encode_u8(index, writer)?;
         }
-        encode_compact_u16(self.data.len() as u16, writer)?;
+        encode_len("instruction data", self.data.len(), writer)?;
         writer.extend_from_slice(&self.data);
         Ok(())
     }
@@ -95,13 +513,76 @@ impl ByteSerialize for CompiledInstruction {
     }
 }
 
+impl ByteDeserialize for CompiledMessage {
+    fn deserialize_bytes(cursor: &mut Cursor<&[u8]>) -> Result<Self> {
+        Self::decode(cursor, &DecodeLimits::default())
+    }
+}
+
+impl CompiledMessage {
+    fn decode(cursor: &mut Cursor<&[u8]>, limits: &DecodeLimits) -> Result<Self> {
+        // Decode message header
+        let num_required_signatures = decode_u8(cursor)?;
+        let num_readonly_signed_accounts = decode_u8(cursor)?;
+        let num_readonly_unsigned_accounts = decode_u8(cursor)?;
+        let header = MessageHeader::new(
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        );
+        
+        // Decode account keys
+        let num_account_keys = limits.read(cursor, "account keys", limits.max_account_keys)?;
+        let account_keys = simd::decode_keys(cursor, num_account_keys)?;
+        
+        // Decode recent blockhash
+        let recent_blockhash = decode_pubkey(cursor)?;
+        
+        // Decode instructions
+        let num_instructions = limits.read(cursor, "instructions", limits.max_instructions)?;
+        let mut instructions = Vec::with_capacity(num_instructions);
+        for _ in 0..num_instructions {
+            let program_id_index = decode_u8(cursor)?;
+            let num_accounts = limits.read(cursor, "instruction accounts", limits.max_instruction_accounts)?;
+            let mut account_indices = AccountIndices::with_capacity(num_accounts);
+            for _ in 0..num_accounts {
+                account_indices.push(decode_u8(cursor)?);
+            }
+            let data_len = limits.read(cursor, "instruction data", limits.max_data_len)?;
+            let position = cursor.position() as usize;
+            let data_bytes = cursor.get_ref();
+            if position + data_len > data_bytes.len() {
+                return Err(TxAsmError::BufferTooSmall {
+                    needed: position + data_len,
+                    available: data_bytes.len(),
+                });
+            }
+            let data = data_bytes[position..position + data_len].to_vec();
+            cursor.set_position((position + data_len) as u64);
+            
+            instructions.push(CompiledInstruction {
+                program_id_index,
+                account_indices,
+                data,
+            });
+        }
+        
+        Ok(CompiledMessage {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        })
+    }
+}
+
 impl ByteSerialize for CompiledMessage {
     fn serialize_bytes(&self, writer: &mut Vec<u8>) -> Result<()> {
         // Serialize header
         self.header.serialize_bytes(writer)?;
         
         // Serialize account keys
-        encode_compact_u16(self.account_keys.len() as u16, writer)?;
+        encode_len("account keys", self.account_keys.len(), writer)?;
         for key in &self.account_keys {
             encode_pubkey(key, writer)?;
         }
@@ -110,7 +591,7 @@ impl ByteSerialize for CompiledMessage {
         encode_pubkey(&self.recent_blockhash, writer)?;
         
         // Serialize instructions
-        encode_compact_u16(self.instructions.len() as u16, writer)?;
+        encode_len("instructions", self.instructions.len(), writer)?;
         for instruction in &self.instructions {
             instruction.serialize_bytes(writer)?;
         }
@@ -119,45 +600,68 @@ impl ByteSerialize for CompiledMessage {
     }
 
     fn byte_size(&self) -> usize {
-        let keys_len_size = if self.account_keys.len() <= 0x7f { 1 } else { 2 };
-        let instructions_len_size = if self.instructions.len() <= 0x7f { 1 } else { 2 };
-        
-        self.header.byte_size()
-            + keys_len_size
-            + (self.account_keys.len() * 32)
-            + 32  // blockhash
-            + instructions_len_size
-            + self.instructions.iter().map(|i| i.byte_size()).sum::<usize>()
+        message_body_size(&self.header, &self.account_keys, &self.instructions)
     }
 }
 
+/// Serialized size of a header, keys, blockhash and instructions, the body
+/// legacy and V0 messages share
+pub(crate) fn message_body_size(
+    header: &MessageHeader,
+    account_keys: &[[u8; 32]],
+    instructions: &[CompiledInstruction],
+) -> usize {
+    let keys_len_size = if account_keys.len() <= 0x7f { 1 } else { 2 };
+    let instructions_len_size = if instructions.len() <= 0x7f { 1 } else { 2 };
+
+    header.byte_size()
+        + keys_len_size
+        + (account_keys.len() * 32)
+        + 32  // blockhash
+        + instructions_len_size
+        + instructions.iter().map(|i| i.byte_size()).sum::<usize>()
+}
+
 /// A fully compiled transaction ready for signing and sending
-#[derive(Debug, Clone)]
+///
+/// Equality and hashing cover the signatures too, matching the serialized
+/// bytes; use [`semantically_equal`](Self::semantically_equal) to compare
+/// only what was signed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct CompiledTransaction {
     pub message: CompiledMessage,
+    #[serde(with = "crate::storage::signatures")]
     pub signatures: Vec<[u8; 64]>,
 }
 
 impl CompiledTransaction {
     /// Serialize the entire transaction to bytes
     pub fn serialize(&self) -> Result<Vec<u8>> {
-        let mut bytes = Vec::new();
-        
+        let mut bytes = Vec::with_capacity(self.size());
+        self.serialize_into(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Serialize into a reusable scratch buffer, replacing its contents
+    pub fn serialize_into(&self, bytes: &mut Vec<u8>) -> Result<()> {
+        bytes.clear();
+        bytes.reserve(self.size());
+
         // Serialize signatures
-        encode_compact_u16(self.signatures.len() as u16, &mut bytes)?;
+        encode_len("signatures", self.signatures.len(), bytes)?;
         for sig in &self.signatures {
             bytes.extend_from_slice(sig);
         }
         
         // Serialize message
-        self.message.serialize_bytes(&mut bytes)?;
+        self.message.serialize_bytes(bytes)?;
         
-        Ok(bytes)
+        Ok(())
     }
 
     /// Get the serialized message (for signing)
     pub fn message_bytes(&self) -> Result<Vec<u8>> {
-        let mut bytes = Vec::new();
+        let mut bytes = Vec::with_capacity(self.message.byte_size());
         self.message.serialize_bytes(&mut bytes)?;
         Ok(bytes)
     }
@@ -170,157 +674,193 @@ impl CompiledTransaction {
 
     /// Decode a transaction from bytes
     pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        Self::deserialize_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    /// [`Self::deserialize`] with explicit caps on decoded counts
+    pub fn deserialize_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self> {
         let mut cursor = Cursor::new(bytes);
         
         // Decode signatures
-        let num_signatures = decode_compact_u16(&mut cursor)? as usize;
-        let mut signatures = Vec::with_capacity(num_signatures);
-        for _ in 0..num_signatures {
-            let position = cursor.position() as usize;
-            let data = cursor.get_ref();
-            if position + 64 > data.len() {
-                return Err(TxAsmError::BufferTooSmall {
-                    needed: position + 64,
-                    available: data.len(),
-                });
-            }
-            let mut sig = [0u8; 64];
-            sig.copy_from_slice(&data[position..position + 64]);
-            signatures.push(sig);
-            cursor.set_position((position + 64) as u64);
-        }
-        
-        // Decode message header
-        let num_required_signatures = decode_u8(&mut cursor)?;
-        let num_readonly_signed_accounts = decode_u8(&mut cursor)?;
-        let num_readonly_unsigned_accounts = decode_u8(&mut cursor)?;
-        let header = MessageHeader::new(
-            num_required_signatures,
-            num_readonly_signed_accounts,
-            num_readonly_unsigned_accounts,
-        );
-        
-        // Decode account keys
-        let num_account_keys = decode_compact_u16(&mut cursor)? as usize;
-        let mut account_keys = Vec::with_capacity(num_account_keys);
-        for _ in 0..num_account_keys {
-            account_keys.push(decode_pubkey(&mut cursor)?);
-        }
-        
-        // Decode recent blockhash
-        let recent_blockhash = decode_pubkey(&mut cursor)?;
-        
-        // Decode instructions
-        let num_instructions = decode_compact_u16(&mut cursor)? as usize;
-        let mut instructions = Vec::with_capacity(num_instructions);
-        for _ in 0..num_instructions {
-            let program_id_index = decode_u8(&mut cursor)?;
-            let num_accounts = decode_compact_u16(&mut cursor)? as usize;
-            let mut account_indices = Vec::with_capacity(num_accounts);
-            for _ in 0..num_accounts {
-                account_indices.push(decode_u8(&mut cursor)?);
-            }
-            let data_len = decode_compact_u16(&mut cursor)? as usize;
-            let position = cursor.position() as usize;
-            let data_bytes = cursor.get_ref();
-            let data = data_bytes[position..position + data_len].to_vec();
-            cursor.set_position((position + data_len) as u64);
-            
-            instructions.push(CompiledInstruction {
-                program_id_index,
-                account_indices,
-                data,
-            });
-        }
+        let num_signatures = limits.read(&mut cursor, "signatures", limits.max_signatures)?;
+        let signatures = simd::decode_signatures(&mut cursor, num_signatures)?;
         
-        Ok(CompiledTransaction {
-            message: CompiledMessage {
-                header,
-                account_keys,
-                recent_blockhash,
-                instructions,
-            },
-            signatures,
-        })
-    }
-}
+        let message = CompiledMessage::decode(&mut cursor, limits)?;
 
-/// Transaction builder with fluent API
-pub struct TransactionBuilder {
-    instructions: Vec<RawInstruction>,
-    payer: Option<[u8; 32]>,
-    recent_blockhash: Option<[u8; 32]>,
-}
-
-impl TransactionBuilder {
-    pub fn new() -> Self {
-        Self {
-            instructions: Vec::new(),
-            payer: None,
-            recent_blockhash: None,
-        }
+        Ok(CompiledTransaction { message, signatures })
     }
 
-    /// Set the fee payer
-    pub fn payer(mut self, payer: [u8; 32]) -> Self {
-        self.payer = Some(payer);
-        self
+    /// [`Self::deserialize`], normalizing a signature vector whose length
+    /// disagrees with the header and reporting the repair
+    pub fn deserialize_tolerant(bytes: &[u8]) -> Result<(Self, Option<SignatureRepair>)> {
+        let mut transaction = Self::deserialize(bytes)?;
+        let required = transaction.message.header.num_required_signatures as usize;
+        let repair = normalize_signatures(&mut transaction.signatures, required);
+        Ok((transaction, repair))
     }
 
-    /// Set the fee payer from Pubkey
-    pub fn payer_pubkey(mut self, payer: &Pubkey) -> Self {
-        self.payer = Some(payer.to_bytes());
-        self
+    /// Same message, whatever the signatures hold
+    pub fn semantically_equal(&self, other: &Self) -> bool {
+        self.message == other.message
     }
 
-    /// Set the recent blockhash
-    pub fn recent_blockhash(mut self, blockhash: [u8; 32]) -> Self {
-        self.recent_blockhash = Some(blockhash);
-        self
+    /// Account keys that must sign, in signature-slot order
+    pub fn required_signers(&self) -> &[[u8; 32]] {
+        let num_required = self.message.header.num_required_signatures as usize;
+        &self.message.account_keys[..num_required.min(self.message.account_keys.len())]
     }
 
-    /// Set recent blockhash from Hash
-    pub fn recent_blockhash_hash(mut self, blockhash: &Hash) -> Self {
-        self.recent_blockhash = Some(blockhash.to_bytes());
-        self
+    /// Signature slots still zeroed or missing, as (slot, signer)
+    pub fn unsigned_slots(&self) -> Vec<(usize, [u8; 32])> {
+        self.required_signers()
+            .iter()
+            .enumerate()
+            .filter(|(slot, _)| self.signatures.get(*slot).is_none_or(simd::is_zero_signature))
+            .map(|(slot, key)| (slot, *key))
+            .collect()
     }
 
-    /// Add an instruction
-    pub fn add_instruction(mut self, instruction: RawInstruction) -> Self {
-        self.instructions.push(instruction);
-        self
+    /// Every required signer has a non-zero signature (not verified)
+    pub fn is_fully_signed(&self) -> bool {
+        self.signatures.len() == self.message.header.num_required_signatures as usize
+            && self.unsigned_slots().is_empty()
     }
 
-    /// Add multiple instructions
-    pub fn add_instructions(mut self, instructions: Vec<RawInstruction>) -> Self {
-        self.instructions.extend(instructions);
-        self
+    /// Signature placed by `pubkey`, if it is a required signer and has signed
+    pub fn signature_for(&self, pubkey: &[u8; 32]) -> Option<&[u8; 64]> {
+        let slot = simd::find_key(self.required_signers(), pubkey)?;
+        self.signatures.get(slot).filter(|sig| !simd::is_zero_signature(sig))
     }
 
-    /// Compile the transaction into a message
-    pub fn compile(self) -> Result<CompiledMessage> {
-        let payer = self.payer.ok_or_else(|| {
-            TxAsmError::InvalidTransaction("Payer not set".to_string())
-        })?;
+    /// Verify every signature slot under strict, canonical ed25519 rules
+    ///
+    /// Use this before relaying externally signed transactions; see
+    /// [`crate::sigverify`].
+    pub fn verify_strict(&self) -> Result<()> {
+        let signers = self.required_signers();
+        if self.signatures.len() != signers.len() {
+            return Err(TxAsmError::SignatureError(format!(
+                "{} signatures for {} required signers",
+                self.signatures.len(),
+                signers.len()
+            )));
+        }
+        let message = self.message_bytes()?;
+        for (key, signature) in signers.iter().zip(&self.signatures) {
+            crate::sigverify::verify_strict(key, &message, signature)?;
+        }
+        Ok(())
+    }
 
-        let recent_blockhash = self.recent_blockhash.ok_or_else(|| {
-            TxAsmError::InvalidTransaction("Recent blockhash not set".to_string())
-        })?;
+    /// Export as the payload browser wallet adapters expect for `signTransaction`
+    ///
+    /// The transaction is serialized with all-zero signature slots.
+    pub fn to_wallet_payload(&self) -> Result<WalletPayload> {
+        let unsigned = CompiledTransaction {
+            message: self.message.clone(),
+            signatures: vec![[0u8; 64]; self.message.header.num_required_signatures as usize],
+        };
 
-        if self.instructions.is_empty() {
-            return Err(TxAsmError::InvalidTransaction(
-                "No instructions provided".to_string(),
+        Ok(WalletPayload {
+            transaction: BASE64.encode(unsigned.serialize()?),
+            required_signers: self
+                .required_signers()
+                .iter()
+                .map(|key| bs58::encode(key).into_string())
+                .collect(),
+            version: TransactionVersion::Legacy,
+        })
+    }
+
+    /// Place a signature in the slot belonging to `pubkey`
+    pub fn merge_signature(&mut self, pubkey: &[u8; 32], signature: [u8; 64]) -> Result<()> {
+        let slot = self
+            .required_signers()
+            .iter()
+            .position(|key| key == pubkey)
+            .ok_or_else(|| {
+                TxAsmError::SignatureError(format!(
+                    "{} is not a required signer",
+                    bs58::encode(pubkey).into_string()
+                ))
+            })?;
+
+        let num_required = self.message.header.num_required_signatures as usize;
+        if self.signatures.len() < num_required {
+            self.signatures.resize(num_required, [0u8; 64]);
+        }
+        self.signatures[slot] = signature;
+        Ok(())
+    }
+
+    /// Merge the signatures from a wallet-signed base64 transaction
+    ///
+    /// The wallet's message must be byte-identical to ours; only non-zero
+    /// signatures are copied over.
+    pub fn merge_wallet_signed(&mut self, signed_base64: &str) -> Result<()> {
+        let bytes = BASE64
+            .decode(signed_base64)
+            .map_err(|e| TxAsmError::DecodingError(e.to_string()))?;
+        let signed = CompiledTransaction::deserialize(&bytes)?;
+
+        if signed.message_bytes()? != self.message_bytes()? {
+            return Err(TxAsmError::SignatureError(
+                "Wallet returned a different message".to_string(),
             ));
         }
 
+        let signers: Vec<[u8; 32]> = self.required_signers().to_vec();
+        for (key, signature) in signers.iter().zip(signed.signatures.iter()) {
+            if *signature != [0u8; 64] {
+                self.merge_signature(key, *signature)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Unsigned transaction in the shape wallet adapters accept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletPayload {
+    /// Base64 serialized transaction with zeroed signatures
+    pub transaction: String,
+    /// Base58 pubkeys that must sign, in slot order
+    pub required_signers: Vec<String>,
+    pub version: TransactionVersion,
+}
+
+/// Account ordering, header and instruction indices for a set of instructions
+///
+/// This is everything `compile()` derives from the account keys alone; the
+/// blockhash and instruction data are filled in afterwards.
+#[derive(Debug, Clone)]
+pub(crate) struct AccountLayout {
+    pub(crate) header: MessageHeader,
+    pub(crate) account_keys: Vec<[u8; 32]>,
+    /// (program_id_index, account_indices) per instruction
+    pub(crate) indices: Vec<(u8, AccountIndices)>,
+}
+
+impl AccountLayout {
+    pub(crate) fn build(
+        payer: [u8; 32],
+        additional_signers: &[[u8; 32]],
+        instructions: &[RawInstruction],
+    ) -> Result<Self> {
         // Collect all unique account keys
         let mut account_keys_map: HashMap<[u8; 32], (bool, bool)> = HashMap::new();
         
         // Payer is always first and writable signer
         account_keys_map.insert(payer, (true, true));
 
+        // Forced signers keep whatever writability the instructions give them
+        for signer in additional_signers {
+            account_keys_map.entry(*signer).or_insert((true, false)).0 = true;
+        }
+
         // Process all instructions
-        for instruction in &self.instructions {
+        for instruction in instructions {
             // Add program ID as readonly
             account_keys_map
                 .entry(instruction.program_id)
@@ -337,43 +877,66 @@ impl TransactionBuilder {
             }
         }
 
-        // Sort accounts: writable signers, readonly signers, writable non-signers, readonly non-signers
-        let mut account_keys: Vec<([u8; 32], bool, bool)> = account_keys_map
-            .into_iter()
-            .map(|(key, (is_signer, is_writable))| (key, is_signer, is_writable))
-            .collect();
-
-        account_keys.sort_by_key(|(key, is_signer, is_writable)| {
-            let priority = match (*is_signer, *is_writable) {
+        // Lay accounts out in the protocol's four regions. Invariants the
+        // runtime relies on, which `MessageLayout` reports back:
+        // - the payer is index 0, at the head of the writable signers
+        // - all signers come before all non-signers, so a signature slot's
+        //   index is also its account index
+        // - readonly signers close the signer region and readonly
+        //   non-signers close the message, which is what lets the header
+        //   describe roles with two counts
+        // Within a region keys are sorted by bytes, so layouts are stable.
+        let mut regions: [Vec<[u8; 32]>; 4] = Default::default();
+        for (key, (is_signer, is_writable)) in account_keys_map {
+            if key == payer {
+                continue;
+            }
+            let region = match (is_signer, is_writable) {
                 (true, true) => 0,
                 (true, false) => 1,
                 (false, true) => 2,
                 (false, false) => 3,
             };
-            (priority, *key)
-        });
+            regions[region].push(key);
+        }
+        for region in &mut regions {
+            region.sort_unstable();
+        }
+        let [writable_signers, readonly_signers, writable_unsigned, readonly_unsigned] = regions;
+
+        let num_signers = 1 + writable_signers.len() + readonly_signers.len();
+        let num_readonly_signers = readonly_signers.len();
+        let num_readonly_unsigned = readonly_unsigned.len();
+        let account_keys: Vec<[u8; 32]> = std::iter::once(payer)
+            .chain(writable_signers)
+            .chain(readonly_signers)
+            .chain(writable_unsigned)
+            .chain(readonly_unsigned)
+            .collect();
+
+        if account_keys.len() > MAX_ACCOUNT_KEYS {
+            return Err(TxAsmError::AccountIndexOverflow {
+                what: "account keys",
+                value: account_keys.len(),
+                max: MAX_ACCOUNT_KEYS,
+            });
+        }
 
         // Create account key index map
         let account_index_map: HashMap<[u8; 32], u8> = account_keys
             .iter()
             .enumerate()
-            .map(|(i, (key, _, _))| (*key, i as u8))
-            .collect();
-
-        // Build header
-        let num_writable_signers = account_keys.iter().filter(|(_, s, w)| *s && *w).count() as u8;
-        let num_readonly_signers = account_keys.iter().filter(|(_, s, w)| *s && !*w).count() as u8;
-        let num_readonly_unsigned = account_keys.iter().filter(|(_, s, w)| !*s && !*w).count() as u8;
+            .map(|(i, key)| Ok((*key, checked_u8("account index", i)?)))
+            .collect::<Result<_>>()?;
 
         let header = MessageHeader::new(
-            num_writable_signers + num_readonly_signers,
-            num_readonly_signers,
-            num_readonly_unsigned,
+            checked_u8("required signatures", num_signers)?,
+            checked_u8("readonly signed accounts", num_readonly_signers)?,
+            checked_u8("readonly unsigned accounts", num_readonly_unsigned)?,
         );
 
-        // Compile instructions
-        let compiled_instructions: Vec<CompiledInstruction> = self
-            .instructions
+        // Resolve instruction indices
+        let indices = instructions
             .iter()
             .map(|instruction| {
                 let program_id_index = *account_index_map
@@ -382,7 +945,7 @@ impl TransactionBuilder {
                         TxAsmError::InvalidInstruction("Program ID not found in accounts".to_string())
                     })?;
 
-                let account_indices: Vec<u8> = instruction
+                let account_indices = instruction
                     .accounts
                     .iter()
                     .map(|account| {
@@ -390,24 +953,600 @@ impl TransactionBuilder {
                             TxAsmError::InvalidInstruction("Account not found in accounts".to_string())
                         })
                     })
-                    .collect::<Result<Vec<_>>>()?;
+                    .collect::<Result<AccountIndices>>()?;
 
-                Ok(CompiledInstruction {
-                    program_id_index,
-                    account_indices,
-                    data: instruction.data.clone(),
-                })
+                Ok((program_id_index, account_indices))
             })
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(CompiledMessage {
+        Ok(Self {
             header,
-            account_keys: account_keys.into_iter().map(|(key, _, _)| key).collect(),
+            account_keys,
+            indices,
+        })
+    }
+
+    pub(crate) fn index_map(&self) -> HashMap<[u8; 32], u8> {
+        // build() caps the key count at MAX_ACCOUNT_KEYS, so indices fit
+        self.account_keys.iter().enumerate().map(|(i, key)| (*key, i as u8)).collect()
+    }
+
+    /// Assemble a message, taking instruction data from `instructions`
+    pub(crate) fn to_message(
+        &self,
+        recent_blockhash: [u8; 32],
+        instructions: &[RawInstruction],
+    ) -> CompiledMessage {
+        CompiledMessage {
+            header: self.header.clone(),
+            account_keys: self.account_keys.clone(),
             recent_blockhash,
-            instructions: compiled_instructions,
+            instructions: self
+                .indices
+                .iter()
+                .zip(instructions)
+                .map(|((program_id_index, account_indices), instruction)| CompiledInstruction {
+                    program_id_index: *program_id_index,
+                    account_indices: account_indices.clone(),
+                    data: instruction.data.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Assemble a message, moving owned instruction data instead of copying it
+    pub(crate) fn into_message(
+        self,
+        recent_blockhash: [u8; 32],
+        instructions: Vec<RawInstruction>,
+    ) -> CompiledMessage {
+        CompiledMessage {
+            header: self.header,
+            account_keys: self.account_keys,
+            recent_blockhash,
+            instructions: self
+                .indices
+                .into_iter()
+                .zip(instructions)
+                .map(|((program_id_index, account_indices), instruction)| CompiledInstruction {
+                    program_id_index,
+                    account_indices,
+                    data: instruction.data.into_owned(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Account passed with different roles by different instructions
+///
+/// Compilation silently promotes the account to the strongest role, which
+/// is often an assembly mistake.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RoleConflict {
+    Writable {
+        account: [u8; 32],
+        writable_in: Vec<usize>,
+        readonly_in: Vec<usize>,
+    },
+    Signer {
+        account: [u8; 32],
+        signer_in: Vec<usize>,
+        non_signer_in: Vec<usize>,
+    },
+    /// The fee payer, always writable, passed readonly
+    Payer {
+        account: [u8; 32],
+        readonly_in: Vec<usize>,
+    },
+}
+
+impl std::fmt::Display for RoleConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoleConflict::Writable { account, writable_in, readonly_in } => write!(
+                f,
+                "{} is writable in instructions {:?} but readonly in {:?}",
+                bs58::encode(account).into_string(),
+                writable_in,
+                readonly_in
+            ),
+            RoleConflict::Signer { account, signer_in, non_signer_in } => write!(
+                f,
+                "{} is a signer in instructions {:?} but not in {:?}",
+                bs58::encode(account).into_string(),
+                signer_in,
+                non_signer_in
+            ),
+            RoleConflict::Payer { account, readonly_in } => write!(
+                f,
+                "{} is the fee payer but readonly in instructions {:?}",
+                bs58::encode(account).into_string(),
+                readonly_in
+            ),
+        }
+    }
+}
+
+/// How compilation treats an account passed with different roles
+///
+/// The account is always compiled with its strongest role; the policy
+/// decides whether that promotion is silent, reported or refused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleMergePolicy {
+    #[default]
+    PromoteSilently,
+    /// Promote and report the conflicts in [`CompileOutput::warnings`]
+    PromoteWithWarning,
+    /// Fail compilation with [`TxAsmError::AccountError`]
+    Error,
+}
+
+/// In-house caps on a builder, stricter than the protocol's limits
+///
+/// Unset caps are not checked. [`TransactionBuilder::try_add_instruction`],
+/// [`insert_instruction`](TransactionBuilder::insert_instruction) and
+/// [`replace_instruction`](TransactionBuilder::replace_instruction) reject
+/// an instruction that breaks them, and compilation rejects a builder that
+/// does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuilderLimits {
+    /// Distinct accounts, including the payer and invoked programs
+    pub max_accounts: Option<usize>,
+    pub max_instructions: Option<usize>,
+    /// Data bytes of any one instruction
+    pub max_instruction_data: Option<usize>,
+}
+
+impl BuilderLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_accounts(mut self, max: usize) -> Self {
+        self.max_accounts = Some(max);
+        self
+    }
+
+    pub fn max_instructions(mut self, max: usize) -> Self {
+        self.max_instructions = Some(max);
+        self
+    }
+
+    pub fn max_instruction_data(mut self, max: usize) -> Self {
+        self.max_instruction_data = Some(max);
+        self
+    }
+
+    /// Every cap the given builder contents break
+    fn violations(
+        &self,
+        payer: Option<&[u8; 32]>,
+        additional_signers: &[[u8; 32]],
+        instructions: &[RawInstruction],
+    ) -> Vec<LimitViolation> {
+        let mut violations = Vec::new();
+        if let Some(max) = self.max_accounts {
+            let accounts: HashSet<&[u8; 32]> = payer
+                .into_iter()
+                .chain(additional_signers)
+                .chain(instructions.iter().flat_map(|ix| {
+                    std::iter::once(&ix.program_id).chain(ix.accounts.iter().map(|meta| &meta.pubkey))
+                }))
+                .collect();
+            if accounts.len() > max {
+                violations.push(LimitViolation::Accounts { count: accounts.len(), max });
+            }
+        }
+        if let Some(max) = self.max_instructions {
+            if instructions.len() > max {
+                violations.push(LimitViolation::Instructions { count: instructions.len(), max });
+            }
+        }
+        if let Some(max) = self.max_instruction_data {
+            for (instruction, ix) in instructions.iter().enumerate() {
+                if ix.data.len() > max {
+                    violations.push(LimitViolation::InstructionData { instruction, len: ix.data.len(), max });
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// A [`BuilderLimits`] cap that a builder breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitViolation {
+    Accounts { count: usize, max: usize },
+    Instructions { count: usize, max: usize },
+    InstructionData { instruction: usize, len: usize, max: usize },
+}
+
+impl std::fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitViolation::Accounts { count, max } => write!(f, "{} accounts exceed the limit of {}", count, max),
+            LimitViolation::Instructions { count, max } => {
+                write!(f, "{} instructions exceed the limit of {}", count, max)
+            }
+            LimitViolation::InstructionData { instruction, len, max } => write!(
+                f,
+                "instruction {} has {} data bytes, over the limit of {}",
+                instruction, len, max
+            ),
+        }
+    }
+}
+
+/// Message compiled by [`TransactionBuilder::compile_with_warnings`]
+#[derive(Debug, Clone)]
+pub struct CompileOutput {
+    pub message: CompiledMessage,
+    /// Role promotions, under [`RoleMergePolicy::PromoteWithWarning`]
+    pub warnings: Vec<RoleConflict>,
+    /// Final position of every account key
+    pub account_indices: HashMap<[u8; 32], u8>,
+}
+
+/// Why a writable account could be passed readonly instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DemotionReason {
+    /// The account is invoked as a program, and programs are never written
+    InvokedProgram,
+    /// The account is a sysvar, which transactions cannot write
+    Sysvar,
+}
+
+/// Pre-compile findings from [`TransactionBuilder::analyze`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuilderAnalysis {
+    pub role_conflicts: Vec<RoleConflict>,
+    /// (first, duplicate) instruction index pairs with identical program, accounts and data
+    pub duplicate_instructions: Vec<(usize, usize)>,
+    /// Accounts marked writable that could be demoted to readonly
+    pub demotable: Vec<([u8; 32], DemotionReason)>,
+}
+
+impl BuilderAnalysis {
+    pub fn is_clean(&self) -> bool {
+        self.role_conflicts.is_empty()
+            && self.duplicate_instructions.is_empty()
+            && self.demotable.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct AccountUses {
+    signer_in: Vec<usize>,
+    non_signer_in: Vec<usize>,
+    writable_in: Vec<usize>,
+    readonly_in: Vec<usize>,
+}
+
+/// Transaction builder with fluent API
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    pub(crate) instructions: Vec<RawInstruction>,
+    pub(crate) payer: Option<[u8; 32]>,
+    pub(crate) recent_blockhash: Option<[u8; 32]>,
+    pub(crate) role_merge_policy: RoleMergePolicy,
+    pub(crate) dedupe_signers: bool,
+    pub(crate) additional_signers: Vec<[u8; 32]>,
+    pub(crate) limits: BuilderLimits,
+    pub(crate) policies: Vec<Arc<dyn Policy>>,
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self {
+            instructions: Vec::new(),
+            payer: None,
+            recent_blockhash: None,
+            role_merge_policy: RoleMergePolicy::default(),
+            dedupe_signers: false,
+            additional_signers: Vec::new(),
+            limits: BuilderLimits::default(),
+            policies: Vec::new(),
+        }
+    }
+
+    /// Set the fee payer
+    pub fn payer(mut self, payer: [u8; 32]) -> Self {
+        self.payer = Some(payer);
+        self
+    }
+
+    /// Set the fee payer from Pubkey
+    pub fn payer_pubkey(mut self, payer: &Pubkey) -> Self {
+        self.payer = Some(payer.to_bytes());
+        self
+    }
+
+    /// Set the recent blockhash
+    pub fn recent_blockhash(mut self, blockhash: [u8; 32]) -> Self {
+        self.recent_blockhash = Some(blockhash);
+        self
+    }
+
+    /// Set recent blockhash from Hash
+    pub fn recent_blockhash_hash(mut self, blockhash: &Hash) -> Self {
+        self.recent_blockhash = Some(blockhash.to_bytes());
+        self
+    }
+
+    /// Set how accounts passed with different roles are merged
+    pub fn role_merge_policy(mut self, policy: RoleMergePolicy) -> Self {
+        self.role_merge_policy = policy;
+        self
+    }
+
+    /// Drop repeated keypairs in [`build_and_sign`](Self::build_and_sign)
+    /// instead of rejecting them. Account keys are unique in a compiled
+    /// message, so each key is only ever asked for one signature.
+    pub fn dedupe_signers(mut self, dedupe: bool) -> Self {
+        self.dedupe_signers = dedupe;
+        self
+    }
+
+    /// Require a signature from `signer` even if no instruction marks it as
+    /// one, e.g. for co-sign policies or guard programs that check the
+    /// signer set. The account is readonly unless an instruction writes it.
+    pub fn additional_signer(mut self, signer: [u8; 32]) -> Self {
+        if !self.additional_signers.contains(&signer) {
+            self.additional_signers.push(signer);
+        }
+        self
+    }
+
+    /// Enforce in-house limits on this builder
+    pub fn limits(mut self, limits: BuilderLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Refuse to compile messages that violate `policy`
+    ///
+    /// Policies accumulate; every one must pass.
+    pub fn policy(mut self, policy: impl Policy + 'static) -> Self {
+        self.policies.push(Arc::new(policy));
+        self
+    }
+
+    /// Fail if `message` violates any of the builder's policies
+    pub(crate) fn check_policies(&self, message: &CompiledMessage) -> Result<()> {
+        self.policies.iter().try_for_each(|policy| policy::enforce(policy.as_ref(), message))
+    }
+
+    /// Add an instruction
+    ///
+    /// Limits set with [`Self::limits`] are checked at compile time; use
+    /// [`Self::try_add_instruction`] to check them now.
+    pub fn add_instruction(mut self, instruction: RawInstruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Add an instruction, failing if it breaks the builder's limits
+    pub fn try_add_instruction(mut self, instruction: RawInstruction) -> Result<Self> {
+        self.instructions.push(instruction);
+        self.check_limits()?;
+        Ok(self)
+    }
+
+    /// Add multiple instructions
+    pub fn add_instructions(mut self, instructions: Vec<RawInstruction>) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    /// Insert an instruction at `index`, shifting later instructions back
+    pub fn insert_instruction(mut self, index: usize, instruction: RawInstruction) -> Result<Self> {
+        if index > self.instructions.len() {
+            return Err(self.index_error(index));
+        }
+        self.instructions.insert(index, instruction);
+        self.check_limits()?;
+        Ok(self)
+    }
+
+    /// Replace the instruction at `index`
+    pub fn replace_instruction(mut self, index: usize, instruction: RawInstruction) -> Result<Self> {
+        match self.instructions.get_mut(index) {
+            Some(slot) => *slot = instruction,
+            None => return Err(self.index_error(index)),
+        }
+        self.check_limits()?;
+        Ok(self)
+    }
+
+    /// Remove the instruction at `index`
+    pub fn remove_instruction(mut self, index: usize) -> Result<Self> {
+        if index >= self.instructions.len() {
+            return Err(self.index_error(index));
+        }
+        self.instructions.remove(index);
+        Ok(self)
+    }
+
+    /// Pending instructions, in order
+    pub fn instructions(&self) -> &[RawInstruction] {
+        &self.instructions
+    }
+
+    /// Check the pending instructions for assembly mistakes before compiling
+    ///
+    /// Reports accounts passed with conflicting roles, instructions repeated
+    /// verbatim, and writable accounts that can be demoted to readonly.
+    pub fn analyze(&self) -> BuilderAnalysis {
+        let mut order: Vec<[u8; 32]> = Vec::new();
+        let mut uses: HashMap<[u8; 32], AccountUses> = HashMap::new();
+        // The payer compiles writable whatever the instructions ask for
+        if let Some(payer) = self.payer {
+            order.push(payer);
+            uses.insert(payer, AccountUses::default());
+        }
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            for meta in &instruction.accounts {
+                let entry = uses.entry(meta.pubkey).or_insert_with(|| {
+                    order.push(meta.pubkey);
+                    AccountUses::default()
+                });
+                let signer = if meta.is_signer { &mut entry.signer_in } else { &mut entry.non_signer_in };
+                signer.push(index);
+                let writable = if meta.is_writable { &mut entry.writable_in } else { &mut entry.readonly_in };
+                writable.push(index);
+            }
+        }
+
+        let mut analysis = BuilderAnalysis::default();
+        for account in order {
+            let entry = uses.remove(&account).unwrap_or_default();
+            if Some(account) == self.payer && !entry.readonly_in.is_empty() {
+                analysis.role_conflicts.push(RoleConflict::Payer {
+                    account,
+                    readonly_in: entry.readonly_in.clone(),
+                });
+            } else if !entry.writable_in.is_empty() && !entry.readonly_in.is_empty() {
+                analysis.role_conflicts.push(RoleConflict::Writable {
+                    account,
+                    writable_in: entry.writable_in.clone(),
+                    readonly_in: entry.readonly_in,
+                });
+            }
+            if !entry.signer_in.is_empty() && !entry.non_signer_in.is_empty() {
+                analysis.role_conflicts.push(RoleConflict::Signer {
+                    account,
+                    signer_in: entry.signer_in,
+                    non_signer_in: entry.non_signer_in,
+                });
+            }
+            if entry.writable_in.is_empty() {
+                continue;
+            }
+            if self.instructions.iter().any(|ix| ix.program_id == account) {
+                analysis.demotable.push((account, DemotionReason::InvokedProgram));
+            } else if solana_sdk::sysvar::is_sysvar_id(&Pubkey::new_from_array(account)) {
+                analysis.demotable.push((account, DemotionReason::Sysvar));
+            }
+        }
+
+        for (later, instruction) in self.instructions.iter().enumerate() {
+            let first = self.instructions[..later].iter().position(|earlier| {
+                earlier.program_id == instruction.program_id
+                    && earlier.accounts == instruction.accounts
+                    && earlier.data == instruction.data
+            });
+            if let Some(first) = first {
+                analysis.duplicate_instructions.push((first, later));
+            }
+        }
+        analysis
+    }
+
+    /// Caps from [`Self::limits`] that the builder currently breaks
+    pub fn limit_violations(&self) -> Vec<LimitViolation> {
+        self.limits
+            .violations(self.payer.as_ref(), &self.additional_signers, &self.instructions)
+    }
+
+    fn check_limits(&self) -> Result<()> {
+        let violations = self.limit_violations();
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let messages: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        Err(TxAsmError::InvalidTransaction(format!(
+            "Builder limits exceeded: {}",
+            messages.join("; ")
+        )))
+    }
+
+    /// Payer and blockhash, checking that there is something to compile
+    /// within the builder's limits
+    pub(crate) fn require_parts(&self) -> Result<([u8; 32], [u8; 32])> {
+        let payer = self.payer.ok_or_else(|| {
+            TxAsmError::InvalidTransaction("Payer not set".to_string())
+        })?;
+
+        let recent_blockhash = self.recent_blockhash.ok_or_else(|| {
+            TxAsmError::InvalidTransaction("Recent blockhash not set".to_string())
+        })?;
+
+        if self.instructions.is_empty() {
+            return Err(TxAsmError::InvalidTransaction(
+                "No instructions provided".to_string(),
+            ));
+        }
+        self.check_limits()?;
+
+        Ok((payer, recent_blockhash))
+    }
+
+    /// Readonly-to-writable promotions to report under the merge policy, or
+    /// the policy error
+    ///
+    /// Signer mismatches are left to [`Self::analyze`]: passing a signer as
+    /// a plain account elsewhere is ordinary.
+    pub(crate) fn role_warnings(&self) -> Result<Vec<RoleConflict>> {
+        if self.role_merge_policy == RoleMergePolicy::PromoteSilently {
+            return Ok(Vec::new());
+        }
+        let conflicts: Vec<RoleConflict> = self
+            .analyze()
+            .role_conflicts
+            .into_iter()
+            .filter(|c| !matches!(c, RoleConflict::Signer { .. }))
+            .collect();
+        if self.role_merge_policy == RoleMergePolicy::Error && !conflicts.is_empty() {
+            let messages: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+            return Err(TxAsmError::AccountError(format!(
+                "Conflicting account roles: {}",
+                messages.join("; ")
+            )));
+        }
+        Ok(conflicts)
+    }
+
+    fn index_error(&self, index: usize) -> TxAsmError {
+        TxAsmError::InvalidTransaction(format!(
+            "Instruction index {} out of range ({} instructions)",
+            index,
+            self.instructions.len()
+        ))
+    }
+
+    /// Compile the transaction into a message
+    pub fn compile(self) -> Result<CompiledMessage> {
+        self.compile_with_warnings().map(|output| output.message)
+    }
+
+    /// Compile the transaction, reporting role promotions per the merge policy
+    pub fn compile_with_warnings(self) -> Result<CompileOutput> {
+        let (payer, recent_blockhash) = self.require_parts()?;
+        let warnings = self.role_warnings()?;
+        let layout = AccountLayout::build(payer, &self.additional_signers, &self.instructions)?;
+        let account_indices = layout.index_map();
+        let message = layout.into_message(recent_blockhash, self.instructions);
+        self.policies.iter().try_for_each(|policy| policy::enforce(policy.as_ref(), &message))?;
+        Ok(CompileOutput {
+            message,
+            warnings,
+            account_indices,
         })
     }
 
+    /// Where each account key will land in the compiled message
+    ///
+    /// Positions depend only on the payer, signers and account metas, never
+    /// on instruction data, so this can be called before filling in data
+    /// that embeds account indices. Replacing an instruction's data later
+    /// leaves the map valid.
+    pub fn account_indices(&self) -> Result<HashMap<[u8; 32], u8>> {
+        let (payer, _) = self.require_parts()?;
+        Ok(AccountLayout::build(payer, &self.additional_signers, &self.instructions)?.index_map())
+    }
+
     /// Compile and create an unsigned transaction
     pub fn build_unsigned(self) -> Result<CompiledTransaction> {
         let message = self.compile()?;
@@ -419,6 +1558,19 @@ impl TransactionBuilder {
 
     /// Compile and sign the transaction
     pub fn build_and_sign(self, signers: &[&Keypair]) -> Result<CompiledTransaction> {
+        let mut unique: Vec<&Keypair> = Vec::with_capacity(signers.len());
+        for signer in signers {
+            let pubkey = signer.pubkey();
+            if unique.iter().any(|seen| seen.pubkey() == pubkey) {
+                if self.dedupe_signers {
+                    continue;
+                }
+                return Err(TxAsmError::SignatureError(format!("Duplicate signer {}", pubkey)));
+            }
+            unique.push(signer);
+        }
+        let signers = unique;
+
         let message = self.compile()?;
         let message_bytes = {
             let mut bytes = Vec::new();
@@ -448,6 +1600,15 @@ impl TransactionBuilder {
     }
 }
 
+/// Narrow a count or index to u8, reporting which field overflowed
+pub(crate) fn checked_u8(what: &'static str, value: usize) -> Result<u8> {
+    u8::try_from(value).map_err(|_| TxAsmError::AccountIndexOverflow {
+        what,
+        value,
+        max: u8::MAX as usize,
+    })
+}
+
 impl Default for TransactionBuilder {
     fn default() -> Self {
         Self::new()
@@ -459,6 +1620,20 @@ mod tests {
     use super::*;
     use crate::instruction::InstructionEncoder;
 
+    fn builder_with_accounts(num_accounts: usize) -> TransactionBuilder {
+        let accounts = (0..num_accounts)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                key[..8].copy_from_slice(&(i as u64 + 100).to_le_bytes());
+                AccountMeta::new_readonly(key, false)
+            })
+            .collect();
+        TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(RawInstruction::new([2u8; 32], accounts, vec![]))
+    }
+
     #[test]
     fn test_message_header() {
         let header = MessageHeader::new(2, 1, 3);
@@ -512,4 +1687,515 @@ mod tests {
         let decoded = CompiledTransaction::deserialize(&bytes).unwrap();
         assert_eq!(decoded.message.account_keys.len(), tx.message.account_keys.len());
     }
+
+    #[test]
+    fn test_wallet_payload_roundtrip() {
+        let payer = Keypair::new();
+        let instruction = InstructionEncoder::new([2u8; 32]).append_u8(1).build();
+
+        let mut tx = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap();
+
+        let payload = tx.to_wallet_payload().unwrap();
+        assert_eq!(payload.required_signers, vec![payer.pubkey().to_string()]);
+        assert_eq!(payload.version, TransactionVersion::Legacy);
+
+        // Simulate the wallet signing the payload
+        let mut signed = CompiledTransaction::deserialize(&BASE64.decode(&payload.transaction).unwrap()).unwrap();
+        let signature = payer.sign_message(&signed.message_bytes().unwrap());
+        signed.signatures[0].copy_from_slice(signature.as_ref());
+        let signed_base64 = BASE64.encode(signed.serialize().unwrap());
+
+        tx.merge_wallet_signed(&signed_base64).unwrap();
+        assert_eq!(tx.signatures[0], signed.signatures[0]);
+        assert!(tx.merge_signature(&[9u8; 32], [1u8; 64]).is_err());
+    }
+
+    #[test]
+    fn test_message_account_roles() {
+        let payer = [1u8; 32];
+        let program_id = [2u8; 32];
+        let instruction = InstructionEncoder::new(program_id)
+            .writable([4u8; 32], false)
+            .readonly([5u8; 32])
+            .build();
+
+        let message = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .compile()
+            .unwrap();
+
+        let index_of = |key: [u8; 32]| message.account_keys.iter().position(|k| *k == key).unwrap();
+        assert!(message.is_signer(index_of(payer)) && message.is_writable(index_of(payer)));
+        assert!(!message.is_signer(index_of([4u8; 32])) && message.is_writable(index_of([4u8; 32])));
+        assert!(!message.is_writable(index_of([5u8; 32])));
+        assert!(message.is_program(index_of(program_id)));
+    }
+
+    #[test]
+    fn test_compile_account_limit_boundary() {
+        // payer + program + 254 accounts = 256 keys, the last addressable index is 255
+        let message = builder_with_accounts(254).compile().unwrap();
+        assert_eq!(message.account_keys.len(), MAX_ACCOUNT_KEYS);
+        assert!(message.instructions[0].account_indices.contains(&255));
+
+        let err = builder_with_accounts(255).compile().unwrap_err();
+        assert!(matches!(
+            err,
+            TxAsmError::AccountIndexOverflow { value: 257, max: 256, .. }
+        ));
+    }
+
+    #[test]
+    fn test_header_validate_and_repair() {
+        let instruction = InstructionEncoder::new([2u8; 32])
+            .writable([4u8; 32], false)
+            .readonly([5u8; 32])
+            .build();
+        let mut message = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .compile()
+            .unwrap();
+        assert!(message.header.validate(&message).is_ok());
+        assert!(!message.repair_header(&[]).unwrap());
+
+        let bad = MessageHeader::new(1, 1, 0);
+        assert!(bad.validate(&message).is_err());
+        let bad = MessageHeader::new(1, 0, 9);
+        assert!(bad.validate(&message).is_err());
+
+        // Splice in a co-signer: [5u8; 32] becomes a readonly signer
+        let original = message.clone();
+        assert!(message.repair_header(&[AccountMeta::new_readonly([5u8; 32], true)]).unwrap());
+        assert_eq!(message.header, MessageHeader::new(2, 1, 1));
+        assert_eq!(message.account_keys[1], [5u8; 32]);
+        assert!(message.header.validate(&message).is_ok());
+
+        // Instructions still point at the same keys
+        let resolve = |m: &CompiledMessage| -> Vec<[u8; 32]> {
+            m.instructions[0].account_indices.iter().map(|&i| m.account_keys[i as usize]).collect()
+        };
+        assert_eq!(resolve(&message), resolve(&original));
+    }
+
+    #[test]
+    fn test_builder_instruction_editing() {
+        let ix = |tag: u8| InstructionEncoder::new([2u8; 32]).append_u8(tag).build();
+        let builder = TransactionBuilder::new()
+            .add_instruction(ix(1))
+            .add_instruction(ix(2))
+            .insert_instruction(0, ix(0))
+            .unwrap()
+            .replace_instruction(2, ix(9))
+            .unwrap()
+            .remove_instruction(1)
+            .unwrap();
+
+        let tags: Vec<u8> = builder.instructions().iter().map(|i| i.data[0]).collect();
+        assert_eq!(tags, vec![0, 9]);
+
+        let builder = builder.insert_instruction(2, ix(3)).unwrap();
+        assert_eq!(builder.instructions().len(), 3);
+        assert!(TransactionBuilder::new().remove_instruction(0).is_err());
+        assert!(TransactionBuilder::new().insert_instruction(1, ix(0)).is_err());
+        assert!(TransactionBuilder::new().replace_instruction(0, ix(0)).is_err());
+    }
+
+    #[test]
+    fn test_static_data_and_scratch_buffer() {
+        static DATA: [u8; 3] = [7, 8, 9];
+        let instruction = RawInstruction::new([2u8; 32], vec![], &DATA[..]);
+        assert!(matches!(instruction.data, std::borrow::Cow::Borrowed(_)));
+
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap();
+        assert_eq!(tx.message.instructions[0].data, DATA);
+
+        let mut scratch = vec![0xff; 4];
+        tx.serialize_into(&mut scratch).unwrap();
+        assert_eq!(scratch, tx.serialize().unwrap());
+        assert_eq!(scratch.len(), tx.size());
+    }
+
+    #[test]
+    fn test_builder_analyze() {
+        let vault = [5u8; 32];
+        let program = [2u8; 32];
+        let clock = solana_sdk::sysvar::clock::id().to_bytes();
+        let deposit = InstructionEncoder::new(program)
+            .writable(vault, false)
+            .writable(clock, false)
+            .append_u8(1)
+            .build();
+        let read = InstructionEncoder::new([4u8; 32])
+            .readonly(vault)
+            .writable(program, false)
+            .build();
+
+        let builder = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .add_instruction(deposit.clone())
+            .add_instruction(read)
+            .add_instruction(deposit);
+        let analysis = builder.analyze();
+
+        assert_eq!(
+            analysis.role_conflicts,
+            vec![RoleConflict::Writable {
+                account: vault,
+                writable_in: vec![0, 2],
+                readonly_in: vec![1],
+            }]
+        );
+        assert_eq!(analysis.duplicate_instructions, vec![(0, 2)]);
+        assert_eq!(
+            analysis.demotable,
+            vec![(clock, DemotionReason::Sysvar), (program, DemotionReason::InvokedProgram)]
+        );
+        assert!(!analysis.is_clean());
+    }
+
+    #[test]
+    fn test_role_merge_policy() {
+        let builder = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).readonly([5u8; 32]).build())
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).build());
+
+        let silent = builder.clone().compile_with_warnings().unwrap();
+        assert!(silent.warnings.is_empty());
+
+        let warned = builder
+            .clone()
+            .role_merge_policy(RoleMergePolicy::PromoteWithWarning)
+            .compile_with_warnings()
+            .unwrap();
+        assert_eq!(warned.message.account_keys, silent.message.account_keys);
+        assert_eq!(warned.warnings.len(), 1);
+
+        let err = builder.role_merge_policy(RoleMergePolicy::Error).compile().unwrap_err();
+        assert!(matches!(err, TxAsmError::AccountError(_)));
+
+        // A signer passed as a plain account elsewhere is not a promotion
+        let authority = [6u8; 32];
+        let signer_mix = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).signer(authority, false).build())
+            .add_instruction(InstructionEncoder::new([2u8; 32]).readonly(authority).build());
+        assert_eq!(signer_mix.analyze().role_conflicts.len(), 1);
+        let output = signer_mix
+            .role_merge_policy(RoleMergePolicy::PromoteWithWarning)
+            .compile_with_warnings()
+            .unwrap();
+        assert!(output.warnings.is_empty());
+
+        // The payer is compiled writable even when passed readonly
+        let readonly_payer = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).readonly([1u8; 32]).build())
+            .role_merge_policy(RoleMergePolicy::PromoteWithWarning);
+        let output = readonly_payer.clone().compile_with_warnings().unwrap();
+        assert_eq!(output.warnings, vec![RoleConflict::Payer { account: [1u8; 32], readonly_in: vec![0] }]);
+        assert!(output.message.is_writable(0));
+        assert!(readonly_payer.role_merge_policy(RoleMergePolicy::Error).compile().is_err());
+    }
+
+    #[test]
+    fn test_message_base64_roundtrip() {
+        let payer = Keypair::new();
+        let mut tx = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).append_u64(7).build())
+            .build_unsigned()
+            .unwrap();
+
+        // A wallet signs the bare message and returns only the signature
+        let encoded = tx.message.to_base64().unwrap();
+        let message = CompiledMessage::from_base64(&encoded).unwrap();
+        assert_eq!(message.serialize().unwrap(), tx.message_bytes().unwrap());
+        let signature = payer.sign_message(&message.serialize().unwrap());
+        tx.merge_signature(&payer.pubkey().to_bytes(), signature.into()).unwrap();
+        assert!(signature.verify(payer.pubkey().as_ref(), &tx.message_bytes().unwrap()));
+
+        let mut trailing = message.serialize().unwrap();
+        trailing.push(0);
+        assert!(CompiledMessage::deserialize(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_signature_slots() {
+        let payer = Keypair::new();
+        let cosigner = Keypair::new();
+        let instruction = InstructionEncoder::new([2u8; 32])
+            .signer(cosigner.pubkey().to_bytes(), false)
+            .build();
+        let mut tx = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([3u8; 32])
+            .add_instruction(instruction)
+            .build_unsigned()
+            .unwrap();
+        assert_eq!(tx.unsigned_slots().len(), 2);
+
+        let signature = payer.sign_message(&tx.message_bytes().unwrap());
+        tx.merge_signature(&payer.pubkey().to_bytes(), signature.into()).unwrap();
+        assert!(!tx.is_fully_signed());
+        assert_eq!(tx.unsigned_slots(), vec![(1, cosigner.pubkey().to_bytes())]);
+        assert!(tx.signature_for(&payer.pubkey().to_bytes()).is_some());
+        assert!(tx.signature_for(&cosigner.pubkey().to_bytes()).is_none());
+
+        let signature = cosigner.sign_message(&tx.message_bytes().unwrap());
+        tx.merge_signature(&cosigner.pubkey().to_bytes(), signature.into()).unwrap();
+        assert!(tx.is_fully_signed());
+        assert!(tx.verify_strict().is_ok());
+        tx.signatures[1][0] ^= 1;
+        assert!(tx.verify_strict().is_err());
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        // One signature, header, no keys, blockhash, then a claim of 65535 instructions
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&[0u8; 64]);
+        bytes.extend_from_slice(&[1, 0, 0, 0]);
+        bytes.extend_from_slice(&[3u8; 32]);
+        bytes.extend_from_slice(&[0xff, 0xff, 0x03]);
+        let err = CompiledTransaction::deserialize(&bytes).unwrap_err();
+        assert!(matches!(err, TxAsmError::LengthOverflow { what: "instructions", len: 65535, .. }));
+        let err = CompiledTransaction::deserialize_with_limits(&bytes, &DecodeLimits::unlimited()).unwrap_err();
+        assert!(matches!(err, TxAsmError::DeserializationError(_) | TxAsmError::BufferTooSmall { .. }));
+
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).data(vec![7; 40]).build())
+            .build_unsigned()
+            .unwrap();
+        let bytes = tx.serialize().unwrap();
+        assert!(CompiledTransaction::deserialize(&bytes).is_ok());
+        let strict = DecodeLimits {
+            max_data_len: 32,
+            ..DecodeLimits::default()
+        };
+        let err = CompiledTransaction::deserialize_with_limits(&bytes, &strict).unwrap_err();
+        assert!(matches!(err, TxAsmError::LengthOverflow { what: "instruction data", len: 40, max: 32 }));
+    }
+
+    #[test]
+    fn test_tolerant_decode_repairs_signature_count() {
+        let mut tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).signer([4u8; 32], false).build())
+            .build_unsigned()
+            .unwrap();
+        let (_, repair) = CompiledTransaction::deserialize_tolerant(&tx.serialize().unwrap()).unwrap();
+        assert_eq!(repair, None);
+
+        tx.signatures = vec![[9u8; 64]];
+        let (fixed, repair) = CompiledTransaction::deserialize_tolerant(&tx.serialize().unwrap()).unwrap();
+        assert_eq!(repair, Some(SignatureRepair::Padded { found: 1, required: 2 }));
+        assert_eq!(fixed.signatures, vec![[9u8; 64], [0u8; 64]]);
+
+        tx.signatures = vec![[9u8; 64], [0u8; 64], [8u8; 64]];
+        let (fixed, repair) = CompiledTransaction::deserialize_tolerant(&tx.serialize().unwrap()).unwrap();
+        assert_eq!(repair, Some(SignatureRepair::Truncated { found: 3, required: 2, dropped_signed: 1 }));
+        assert_eq!(fixed.signatures.len(), 2);
+        assert!(repair.unwrap().to_string().contains("dropping 1 non-zero"));
+    }
+
+    #[test]
+    fn test_duplicate_signers() {
+        let payer = Keypair::new();
+        let same_key = Keypair::from_bytes(&payer.to_bytes()).unwrap();
+        let builder = TransactionBuilder::new()
+            .payer_pubkey(&payer.pubkey())
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).signer(payer.pubkey().to_bytes(), true).build());
+
+        let err = builder.clone().build_and_sign(&[&payer, &same_key]).unwrap_err();
+        assert!(err.to_string().contains(&payer.pubkey().to_string()));
+
+        let tx = builder.dedupe_signers(true).build_and_sign(&[&payer, &same_key]).unwrap();
+        assert_eq!(tx.signatures.len(), 1);
+        assert!(tx.verify_strict().is_ok());
+    }
+
+    #[test]
+    fn test_additional_signer() {
+        let guard = [9u8; 32];
+        let vault = [5u8; 32];
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .additional_signer(guard)
+            .additional_signer(vault)
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable(vault, false).readonly(guard).build())
+            .build_unsigned()
+            .unwrap();
+
+        let message = &tx.message;
+        assert_eq!(message.header.num_required_signatures, 3);
+        assert_eq!(message.header.num_readonly_signed_accounts, 1);
+        assert_eq!(tx.signatures.len(), 3);
+        let index = |key| message.account_keys.iter().position(|k| *k == key).unwrap();
+        assert!(message.is_signer(index(guard)) && !message.is_writable(index(guard)));
+        assert!(message.is_signer(index(vault)) && message.is_writable(index(vault)));
+    }
+
+    #[test]
+    fn test_message_layout_regions() {
+        // Keys chosen so that plain byte order would interleave the regions
+        let payer = [0xffu8; 32];
+        let readonly_signer = [1u8; 32];
+        let writable_signer = [2u8; 32];
+        let program = [3u8; 32];
+        let writable = [4u8; 32];
+        let message = TransactionBuilder::new()
+            .payer(payer)
+            .recent_blockhash([9u8; 32])
+            .add_instruction(
+                InstructionEncoder::new(program)
+                    .signer(readonly_signer, false)
+                    .writable(writable, false)
+                    .signer(writable_signer, true)
+                    .build(),
+            )
+            .compile()
+            .unwrap();
+
+        assert_eq!(message.account_keys, vec![payer, writable_signer, readonly_signer, writable, program]);
+        let layout = message.message_layout();
+        assert_eq!(layout.writable_signers, 0..2);
+        assert_eq!(layout.readonly_signers, 2..3);
+        assert_eq!(layout.writable_unsigned, 3..4);
+        assert_eq!(layout.readonly_unsigned, 4..5);
+        assert_eq!(layout.fee_payer(), Some(0));
+        assert_eq!(layout.region(2), Some(AccountRegion::ReadonlySigner));
+        assert_eq!(layout.region(5), None);
+        for index in 0..5 {
+            let region = layout.region(index).unwrap();
+            assert_eq!(
+                matches!(region, AccountRegion::WritableSigner | AccountRegion::ReadonlySigner),
+                message.is_signer(index)
+            );
+            assert_eq!(
+                matches!(region, AccountRegion::WritableSigner | AccountRegion::WritableUnsigned),
+                message.is_writable(index)
+            );
+        }
+    }
+
+    #[test]
+    fn test_account_indices_before_data() {
+        let vault = [5u8; 32];
+        let builder = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable(vault, false).build());
+        let indices = builder.account_indices().unwrap();
+
+        // Embed the vault's index in the data, as some programs expect
+        let builder = builder
+            .replace_instruction(
+                0,
+                InstructionEncoder::new([2u8; 32]).writable(vault, false).append_u8(indices[&vault]).build(),
+            )
+            .unwrap();
+        let output = builder.compile_with_warnings().unwrap();
+        assert_eq!(output.account_indices, indices);
+        assert_eq!(output.message.account_index(&vault), Some(indices[&vault]));
+        assert_eq!(output.message.instructions[0].data, vec![indices[&vault]]);
+        assert_eq!(output.message.account_index(&[7u8; 32]), None);
+
+        // Decoded messages may carry more keys than a u8 index reaches
+        let mut oversized = output.message.clone();
+        oversized.account_keys = (0..300u16)
+            .map(|i| {
+                let mut key = [9u8; 32];
+                key[..2].copy_from_slice(&i.to_le_bytes());
+                key
+            })
+            .collect();
+        assert_eq!(oversized.account_index(&oversized.account_keys[255]), Some(255));
+        assert_eq!(oversized.account_index(&oversized.account_keys[260]), None);
+    }
+
+    #[test]
+    fn test_equality_matches_bytes() {
+        let signed = crate::testkit::simple_transfer_tx(1);
+        let decoded = CompiledTransaction::deserialize(&signed.serialize().unwrap()).unwrap();
+        assert_eq!(decoded, signed);
+
+        let mut unsigned = signed.clone();
+        unsigned.signatures = vec![[0u8; 64]];
+        assert_ne!(unsigned, signed);
+        assert!(unsigned.semantically_equal(&signed));
+        assert!(!crate::testkit::simple_transfer_tx(2).semantically_equal(&signed));
+
+        let seen: std::collections::HashSet<CompiledTransaction> = [signed.clone(), decoded, unsigned].into_iter().collect();
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn test_walk_resolves_accounts() {
+        let mut tx = crate::testkit::simple_transfer_tx(1);
+        let walked: Vec<_> = tx.message.walk().collect::<Result<_>>().unwrap();
+        assert_eq!(walked.len(), 1);
+        let transfer = &walked[0];
+        assert_eq!(*transfer.program_id, solana_sdk::system_program::ID.to_bytes());
+        assert_eq!(transfer.accounts.len(), 2);
+        assert!(transfer.accounts[0].is_signer && transfer.accounts[0].is_writable);
+        assert_eq!(transfer.accounts[1].key, crate::testkit::pubkey(2));
+        assert!(!transfer.accounts[1].is_signer && transfer.accounts[1].is_writable);
+        assert_eq!(transfer.data, &tx.message.instructions[0].data[..]);
+
+        tx.message.instructions[0].account_indices[1] = 9;
+        let mut walk = tx.message.walk();
+        assert!(matches!(walk.next(), Some(Err(TxAsmError::InvalidInstruction(_)))));
+        assert!(walk.next().is_none());
+    }
+
+    #[test]
+    fn test_builder_limits() {
+        let ix = |program: u8, len: usize| InstructionEncoder::new([program; 32]).data(vec![0; len]).build();
+        let limits = BuilderLimits::new().max_accounts(3).max_instructions(2).max_instruction_data(8);
+        let builder = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([9u8; 32])
+            .limits(limits)
+            .try_add_instruction(ix(2, 8))
+            .unwrap();
+        assert!(builder.clone().try_add_instruction(ix(2, 9)).is_err());
+        assert!(builder.clone().insert_instruction(0, ix(2, 9)).is_err());
+
+        // Added unchecked, caught at compile time
+        let builder = builder.add_instruction(ix(3, 1)).add_instruction(ix(4, 1));
+        assert_eq!(
+            builder.limit_violations(),
+            vec![
+                LimitViolation::Accounts { count: 4, max: 3 },
+                LimitViolation::Instructions { count: 3, max: 2 },
+            ]
+        );
+        let err = builder.clone().compile().unwrap_err().to_string();
+        assert!(err.contains("4 accounts exceed the limit of 3"), "{}", err);
+        assert!(builder.remove_instruction(2).unwrap().compile().is_ok());
+    }
 }
@@ -0,0 +1,131 @@
+//! Triton priority fee adapter
+//!
+//! Implements [`FeeSource`] over Triton's `getRecentPrioritizationFees`
+//! extension, which takes a `percentile` (in basis points) and returns the
+//! fee at that percentile for each recent slot. The recommendation is the
+//! median across slots, so one busy slot doesn't set the price.
+//!
+//! Enabled with the `triton` feature.
+
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::{nearest_rank, FeeStrategy};
+use crate::rpc_fees::{encode_accounts, parse_recent_prioritization_fees, prices, FeeSource, RpcTransport};
+use serde_json::json;
+
+/// [`FeeSource`] backed by Triton's percentile `getRecentPrioritizationFees`
+pub struct TritonFeeSource<T> {
+    transport: T,
+    /// Percentile in basis points for Low, Medium and High
+    percentiles: [u16; 3],
+}
+
+impl<T: RpcTransport> TritonFeeSource<T> {
+    /// `transport` posts to a Triton RPC endpoint
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            percentiles: [2_500, 5_000, 7_500],
+        }
+    }
+
+    /// Percentiles (basis points, at most 10000) used for Low, Medium and High
+    pub fn percentiles(mut self, low: u16, medium: u16, high: u16) -> Self {
+        self.percentiles = [low, medium, high];
+        self
+    }
+
+    /// JSON-RPC request body for `accounts` at `percentile` basis points
+    pub fn request(accounts: &[[u8; 32]], percentile: u16) -> String {
+        json!({
+            "jsonrpc": "2.0",
+            "id": "txasm",
+            "method": "getRecentPrioritizationFees",
+            "params": [encode_accounts(accounts), { "percentile": percentile }],
+        })
+        .to_string()
+    }
+
+    /// Median across recent slots of the per-slot fee at `percentile`
+    pub fn fee_at(&self, accounts: &[[u8; 32]], percentile: u16) -> Result<u64> {
+        if percentile > 10_000 {
+            return Err(TxAsmError::FeeCalculationError(format!(
+                "Percentile {} exceeds 10000 basis points",
+                percentile
+            )));
+        }
+        let response = self.transport.post(&Self::request(accounts, percentile))?;
+        let fees = parse_recent_prioritization_fees(&response)?;
+        Ok(nearest_rank(&prices(&fees), 50).unwrap_or(0))
+    }
+}
+
+impl<T: RpcTransport> FeeSource for TritonFeeSource<T> {
+    fn preset_fee(&self, accounts: &[[u8; 32]], strategy: FeeStrategy) -> Result<u64> {
+        let percentile = match strategy {
+            FeeStrategy::Low => self.percentiles[0],
+            FeeStrategy::Medium => self.percentiles[1],
+            FeeStrategy::High => self.percentiles[2],
+            FeeStrategy::Custom(price) => return Ok(price),
+        };
+        self.fee_at(accounts, percentile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fee_calculator::{FeePolicy, PriorityFeeCalculator};
+    use crate::instruction::InstructionEncoder;
+    use crate::transaction::{CompiledMessage, TransactionBuilder};
+
+    #[test]
+    fn test_triton_fee_source() {
+        let transport = |body: &str| -> Result<String> {
+            let fee = if body.contains("\"percentile\":7500") { 900 } else { 100 };
+            Ok(format!(
+                r#"{{"jsonrpc":"2.0","result":[
+                    {{"slot":1,"prioritizationFee":{}}},
+                    {{"slot":2,"prioritizationFee":{}}},
+                    {{"slot":3,"prioritizationFee":5}}
+                ],"id":"txasm"}}"#,
+                fee, fee
+            ))
+        };
+        let source = TritonFeeSource::new(transport);
+        assert_eq!(source.preset_fee(&[[5u8; 32]], FeeStrategy::High).unwrap(), 900);
+        assert_eq!(source.preset_fee(&[[5u8; 32]], FeeStrategy::Low).unwrap(), 100);
+        assert!(source.fee_at(&[], 10_001).is_err());
+
+        let tx = TransactionBuilder::new()
+            .payer([1u8; 32])
+            .recent_blockhash([3u8; 32])
+            .add_instruction(InstructionEncoder::new([2u8; 32]).writable([5u8; 32], false).build())
+            .build_unsigned()
+            .unwrap();
+        let estimate = PriorityFeeCalculator::new()
+            .estimate_fee_from_source(&tx, &source, FeeStrategy::High)
+            .unwrap();
+        assert_eq!(estimate.priority_fee_per_cu, 900);
+
+        // Bid the high tier only for the hot account
+        struct HotAccount([u8; 32]);
+        impl FeePolicy for HotAccount {
+            fn micro_lamports_per_cu(&self, _message: &CompiledMessage) -> u64 {
+                1
+            }
+
+            fn micro_lamports_per_cu_quoted(
+                &self,
+                message: &CompiledMessage,
+                quote: &dyn Fn(FeeStrategy) -> Result<u64>,
+            ) -> Result<u64> {
+                let tier = if message.account_keys.contains(&self.0) { FeeStrategy::High } else { FeeStrategy::Low };
+                quote(tier)
+            }
+        }
+        assert_eq!(source.priority_fee(&tx.message, &HotAccount([5u8; 32])).unwrap(), 900);
+        assert_eq!(source.priority_fee(&tx.message, &HotAccount([6u8; 32])).unwrap(), 100);
+        let closure = |_: &CompiledMessage| 42;
+        assert_eq!(source.priority_fee(&tx.message, &closure).unwrap(), 42);
+    }
+}
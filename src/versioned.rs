@@ -0,0 +1,440 @@
+//! Version 0 messages with address lookup tables
+//!
+//! V0 messages extend the legacy layout with a version prefix byte and a list
+//! of address table lookups. Accounts loaded through tables follow the static
+//! keys: first every table's writable entries, then every table's readonly
+//! entries, in lookup order.
+
+use crate::error::{Result, TxAsmError};
+use crate::serialization::{
+    ByteSerialize, decode_compact_u16, decode_pubkey, decode_u8, encode_len,
+    encode_pubkey, encode_u8,
+};
+use crate::simd;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use crate::transaction::{
+    message_body_size, normalize_signatures, CompiledInstruction, DecodeLimits, MessageHeader, SignatureRepair,
+};
+use std::io::Cursor;
+
+/// High bit set on the first message byte marks a versioned message
+pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
+
+/// An on-chain address lookup table's contents
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct AddressLookupTable {
+    pub key: [u8; 32],
+    pub addresses: Vec<[u8; 32]>,
+}
+
+impl AddressLookupTable {
+    pub fn new(key: [u8; 32], addresses: Vec<[u8; 32]>) -> Self {
+        Self { key, addresses }
+    }
+
+    /// Index of `address` within the table, if present and addressable by u8
+    pub fn index_of(&self, address: &[u8; 32]) -> Option<u8> {
+        self.addresses
+            .iter()
+            .position(|a| a == address)
+            .and_then(|i| u8::try_from(i).ok())
+    }
+}
+
+/// Reference to entries of one lookup table
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct MessageAddressTableLookup {
+    pub account_key: [u8; 32],
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+impl ByteSerialize for MessageAddressTableLookup {
+    fn serialize_bytes(&self, writer: &mut Vec<u8>) -> Result<()> {
+        encode_pubkey(&self.account_key, writer)?;
+        encode_len("lookup writable indexes", self.writable_indexes.len(), writer)?;
+        writer.extend_from_slice(&self.writable_indexes);
+        encode_len("lookup readonly indexes", self.readonly_indexes.len(), writer)?;
+        writer.extend_from_slice(&self.readonly_indexes);
+        Ok(())
+    }
+
+    fn byte_size(&self) -> usize {
+        let writable_len_size = if self.writable_indexes.len() <= 0x7f { 1 } else { 2 };
+        let readonly_len_size = if self.readonly_indexes.len() <= 0x7f { 1 } else { 2 };
+        32 + writable_len_size
+            + self.writable_indexes.len()
+            + readonly_len_size
+            + self.readonly_indexes.len()
+    }
+}
+
+/// Addresses loaded from lookup tables, in message order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadedAddresses {
+    pub writable: Vec<[u8; 32]>,
+    pub readonly: Vec<[u8; 32]>,
+}
+
+impl LoadedAddresses {
+    pub fn len(&self) -> usize {
+        self.writable.len() + self.readonly.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writable.is_empty() && self.readonly.is_empty()
+    }
+}
+
+/// Compiled version 0 message
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct CompiledMessageV0 {
+    pub header: MessageHeader,
+    /// Static account keys stored in the message itself
+    pub account_keys: Vec<[u8; 32]>,
+    pub recent_blockhash: [u8; 32],
+    pub instructions: Vec<CompiledInstruction>,
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl CompiledMessageV0 {
+    /// Number of accounts loaded through lookup tables
+    pub fn num_loaded_accounts(&self) -> usize {
+        self.address_table_lookups
+            .iter()
+            .map(|l| l.writable_indexes.len() + l.readonly_indexes.len())
+            .sum()
+    }
+
+    /// Total number of addressable accounts (static + loaded)
+    pub fn total_accounts(&self) -> usize {
+        self.account_keys.len() + self.num_loaded_accounts()
+    }
+
+    /// Resolve the lookups against the given tables
+    pub fn resolve(&self, tables: &[AddressLookupTable]) -> Result<LoadedAddresses> {
+        let mut loaded = LoadedAddresses::default();
+        for lookup in &self.address_table_lookups {
+            let table = tables
+                .iter()
+                .find(|t| t.key == lookup.account_key)
+                .ok_or_else(|| {
+                    TxAsmError::AccountError(format!(
+                        "Lookup table {} not provided",
+                        bs58::encode(lookup.account_key).into_string()
+                    ))
+                })?;
+            let fetch = |index: &u8| {
+                table.addresses.get(*index as usize).copied().ok_or_else(|| {
+                    TxAsmError::AccountError(format!(
+                        "Index {} out of range for lookup table {}",
+                        index,
+                        bs58::encode(table.key).into_string()
+                    ))
+                })
+            };
+            for index in &lookup.writable_indexes {
+                loaded.writable.push(fetch(index)?);
+            }
+            for index in &lookup.readonly_indexes {
+                loaded.readonly.push(fetch(index)?);
+            }
+        }
+        Ok(loaded)
+    }
+
+    /// Whether the account at `index` must sign
+    pub fn is_signer(&self, index: usize) -> bool {
+        index < self.header.num_required_signatures as usize
+    }
+
+    /// Whether the account at `index` is write-locked, counting loaded accounts
+    pub fn is_writable(&self, index: usize) -> bool {
+        let num_static = self.account_keys.len();
+        if index < num_static {
+            let num_signers = self.header.num_required_signatures as usize;
+            return if index < num_signers {
+                index < num_signers.saturating_sub(self.header.num_readonly_signed_accounts as usize)
+            } else {
+                index < num_static.saturating_sub(self.header.num_readonly_unsigned_accounts as usize)
+            };
+        }
+        let num_loaded_writable: usize = self
+            .address_table_lookups
+            .iter()
+            .map(|l| l.writable_indexes.len())
+            .sum();
+        index < num_static + num_loaded_writable
+    }
+
+    fn deserialize_from(cursor: &mut Cursor<&[u8]>, limits: &DecodeLimits) -> Result<Self> {
+        let prefix = decode_u8(cursor)?;
+        if prefix & MESSAGE_VERSION_PREFIX == 0 {
+            return Err(TxAsmError::DeserializationError(
+                "Not a versioned message".to_string(),
+            ));
+        }
+        let version = prefix & !MESSAGE_VERSION_PREFIX;
+        if version != 0 {
+            return Err(TxAsmError::DeserializationError(format!(
+                "Unsupported message version {}",
+                version
+            )));
+        }
+
+        let header = MessageHeader::new(decode_u8(cursor)?, decode_u8(cursor)?, decode_u8(cursor)?);
+
+        let num_account_keys = limits.read(cursor, "account keys", limits.max_account_keys)?;
+        let account_keys = simd::decode_keys(cursor, num_account_keys)?;
+
+        let recent_blockhash = decode_pubkey(cursor)?;
+
+        let num_instructions = limits.read(cursor, "instructions", limits.max_instructions)?;
+        let mut instructions = Vec::with_capacity(num_instructions);
+        for _ in 0..num_instructions {
+            let program_id_index = decode_u8(cursor)?;
+            let num_accounts = limits.read(cursor, "instruction accounts", limits.max_instruction_accounts)?;
+            let account_indices = take_bytes(cursor, num_accounts)?.into();
+            let data_len = limits.read(cursor, "instruction data", limits.max_data_len)?;
+            let data = take_bytes(cursor, data_len)?;
+            instructions.push(CompiledInstruction {
+                program_id_index,
+                account_indices,
+                data,
+            });
+        }
+
+        let num_lookups = limits.read(cursor, "address table lookups", limits.max_address_table_lookups)?;
+        let mut address_table_lookups = Vec::with_capacity(num_lookups);
+        for _ in 0..num_lookups {
+            let account_key = decode_pubkey(cursor)?;
+            // Index lists are copied only once their bytes are known present
+            let num_writable = limits.read(cursor, "lookup writable indexes", limits.max_lookup_indexes)?;
+            let writable_indexes = take_bytes(cursor, num_writable)?;
+            let num_readonly = limits.read(cursor, "lookup readonly indexes", limits.max_lookup_indexes)?;
+            let readonly_indexes = take_bytes(cursor, num_readonly)?;
+            address_table_lookups.push(MessageAddressTableLookup {
+                account_key,
+                writable_indexes,
+                readonly_indexes,
+            });
+        }
+
+        Ok(Self {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+        })
+    }
+}
+
+/// Read `len` bytes, checking they are present before copying
+fn take_bytes(cursor: &mut Cursor<&[u8]>, len: usize) -> Result<Vec<u8>> {
+    let position = cursor.position() as usize;
+    let data = cursor.get_ref();
+    if position + len > data.len() {
+        return Err(TxAsmError::BufferTooSmall {
+            needed: position + len,
+            available: data.len(),
+        });
+    }
+    let bytes = data[position..position + len].to_vec();
+    cursor.set_position((position + len) as u64);
+    Ok(bytes)
+}
+
+impl ByteSerialize for CompiledMessageV0 {
+    fn serialize_bytes(&self, writer: &mut Vec<u8>) -> Result<()> {
+        encode_u8(MESSAGE_VERSION_PREFIX, writer)?;
+        self.header.serialize_bytes(writer)?;
+
+        encode_len("account keys", self.account_keys.len(), writer)?;
+        for key in &self.account_keys {
+            encode_pubkey(key, writer)?;
+        }
+
+        encode_pubkey(&self.recent_blockhash, writer)?;
+
+        encode_len("instructions", self.instructions.len(), writer)?;
+        for instruction in &self.instructions {
+            instruction.serialize_bytes(writer)?;
+        }
+
+        encode_len("address table lookups", self.address_table_lookups.len(), writer)?;
+        for lookup in &self.address_table_lookups {
+            lookup.serialize_bytes(writer)?;
+        }
+        Ok(())
+    }
+
+    fn byte_size(&self) -> usize {
+        let lookups_len_size = if self.address_table_lookups.len() <= 0x7f { 1 } else { 2 };
+        1 + message_body_size(&self.header, &self.account_keys, &self.instructions)
+            + lookups_len_size
+            + self
+                .address_table_lookups
+                .iter()
+                .map(|l| l.byte_size())
+                .sum::<usize>()
+    }
+}
+
+/// A compiled version 0 transaction
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct CompiledTransactionV0 {
+    pub message: CompiledMessageV0,
+    #[serde(with = "crate::storage::signatures")]
+    pub signatures: Vec<[u8; 64]>,
+}
+
+impl CompiledTransactionV0 {
+    /// Serialize the entire transaction to bytes
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        encode_len("signatures", self.signatures.len(), &mut bytes)?;
+        for sig in &self.signatures {
+            bytes.extend_from_slice(sig);
+        }
+        self.message.serialize_bytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Get the serialized message (for signing)
+    pub fn message_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.message.serialize_bytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Same message, whatever the signatures hold
+    pub fn semantically_equal(&self, other: &Self) -> bool {
+        self.message == other.message
+    }
+
+    /// Calculate transaction size in bytes
+    pub fn size(&self) -> usize {
+        let sigs_len_size = if self.signatures.len() <= 0x7f { 1 } else { 2 };
+        sigs_len_size + (self.signatures.len() * 64) + self.message.byte_size()
+    }
+
+    /// Decode a V0 transaction from bytes
+    pub fn deserialize(bytes: &[u8]) -> Result<Self> {
+        Self::deserialize_with_limits(bytes, &DecodeLimits::default())
+    }
+
+    /// [`Self::deserialize`] with explicit caps on decoded counts
+    pub fn deserialize_with_limits(bytes: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+
+        let num_signatures = limits.read(&mut cursor, "signatures", limits.max_signatures)?;
+        let signatures = simd::decode_signatures(&mut cursor, num_signatures)?;
+
+        let message = CompiledMessageV0::deserialize_from(&mut cursor, limits)?;
+        Ok(Self { message, signatures })
+    }
+
+    /// [`Self::deserialize`], normalizing a signature vector whose length
+    /// disagrees with the header and reporting the repair
+    pub fn deserialize_tolerant(bytes: &[u8]) -> Result<(Self, Option<SignatureRepair>)> {
+        let mut transaction = Self::deserialize(bytes)?;
+        let required = transaction.message.header.num_required_signatures as usize;
+        let repair = normalize_signatures(&mut transaction.signatures, required);
+        Ok((transaction, repair))
+    }
+}
+
+/// Whether serialized transaction bytes carry a versioned message
+pub fn is_versioned(bytes: &[u8]) -> Result<bool> {
+    let mut cursor = Cursor::new(bytes);
+    let num_signatures = decode_compact_u16(&mut cursor)? as usize;
+    let offset = cursor.position() as usize + num_signatures * 64;
+    bytes
+        .get(offset)
+        .map(|b| b & MESSAGE_VERSION_PREFIX != 0)
+        .ok_or(TxAsmError::BufferTooSmall {
+            needed: offset + 1,
+            available: bytes.len(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_message() -> CompiledMessageV0 {
+        CompiledMessageV0 {
+            header: MessageHeader::new(1, 0, 1),
+            account_keys: vec![[1u8; 32], [2u8; 32]],
+            recent_blockhash: [3u8; 32],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                account_indices: vec![0, 2, 3].into(),
+                data: vec![9, 9],
+            }],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: [7u8; 32],
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_v0_roundtrip() {
+        let tx = CompiledTransactionV0 {
+            message: sample_message(),
+            signatures: vec![[5u8; 64]],
+        };
+        let bytes = tx.serialize().unwrap();
+        assert_eq!(bytes.len(), tx.size());
+        assert!(is_versioned(&bytes).unwrap());
+
+        let decoded = CompiledTransactionV0::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.message.address_table_lookups, tx.message.address_table_lookups);
+        assert_eq!(decoded.serialize().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_v0_resolve_and_roles() {
+        let message = sample_message();
+        let table = AddressLookupTable::new([7u8; 32], vec![[10u8; 32], [11u8; 32]]);
+        let loaded = message.resolve(&[table]).unwrap();
+        assert_eq!(loaded.writable, vec![[10u8; 32]]);
+        assert_eq!(loaded.readonly, vec![[11u8; 32]]);
+
+        assert!(message.is_writable(0));
+        assert!(!message.is_writable(1));
+        assert!(message.is_writable(2));
+        assert!(!message.is_writable(3));
+        assert!(message.resolve(&[]).is_err());
+    }
+
+    #[test]
+    fn test_v0_decode_limits_lookup_indexes() {
+        // One signature, V0 prefix, header, no keys, blockhash, no
+        // instructions, then one lookup claiming 65535 writable indexes
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&[0u8; 64]);
+        bytes.extend_from_slice(&[MESSAGE_VERSION_PREFIX, 1, 0, 0, 0]);
+        bytes.extend_from_slice(&[3u8; 32]);
+        bytes.extend_from_slice(&[0, 1]);
+        bytes.extend_from_slice(&[7u8; 32]);
+        bytes.extend_from_slice(&[0xff, 0xff, 0x03]);
+        let err = CompiledTransactionV0::deserialize(&bytes).unwrap_err();
+        assert!(matches!(err, TxAsmError::LengthOverflow { what: "lookup writable indexes", len: 65535, .. }));
+
+        let tx = CompiledTransactionV0 {
+            message: sample_message(),
+            signatures: vec![[5u8; 64]],
+        };
+        let strict = DecodeLimits {
+            max_lookup_indexes: 0,
+            ..DecodeLimits::default()
+        };
+        let err = CompiledTransactionV0::deserialize_with_limits(&tx.serialize().unwrap(), &strict).unwrap_err();
+        assert!(matches!(err, TxAsmError::LengthOverflow { what: "lookup writable indexes", len: 1, max: 0 }));
+    }
+}
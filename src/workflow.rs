@@ -0,0 +1,228 @@
+//! Multi-transaction workflows
+//!
+//! A [`Workflow`] is a set of steps, each a group of instructions that must
+//! execute together, with "runs after" dependencies between them (create an
+//! ATA, then transfer into it). [`Workflow::build`] packs the steps into as
+//! few transactions as the size limit allows, in an order that respects
+//! every dependency, and applies the shared payer, blockhash and CU price.
+
+use crate::error::{Result, TxAsmError};
+use crate::fee_calculator::compute_budget::{self, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::instruction::RawInstruction;
+use crate::transaction::{CompiledTransaction, TransactionBuilder, MAX_TRANSACTION_SIZE};
+
+/// Identifier of a workflow step
+pub type StepId = usize;
+
+#[derive(Debug, Clone)]
+struct Step {
+    instructions: Vec<RawInstruction>,
+    after: Vec<StepId>,
+}
+
+/// One transaction produced by a workflow
+#[derive(Debug, Clone)]
+pub struct WorkflowTransaction {
+    /// Steps packed into this transaction, in execution order
+    pub steps: Vec<StepId>,
+    /// Indices of earlier transactions that must land first
+    pub depends_on: Vec<usize>,
+    pub transaction: CompiledTransaction,
+}
+
+/// Dependency-ordered multi-transaction builder
+#[derive(Debug, Clone)]
+pub struct Workflow {
+    payer: [u8; 32],
+    steps: Vec<Step>,
+    max_transaction_size: usize,
+    compute_unit_price: Option<u64>,
+}
+
+impl Workflow {
+    pub fn new(payer: [u8; 32]) -> Self {
+        Self {
+            payer,
+            steps: Vec::new(),
+            max_transaction_size: MAX_TRANSACTION_SIZE,
+            compute_unit_price: None,
+        }
+    }
+
+    /// Override the per-transaction size limit
+    pub fn max_transaction_size(mut self, bytes: usize) -> Self {
+        self.max_transaction_size = bytes;
+        self
+    }
+
+    /// Prepend a `SetComputeUnitPrice` instruction to every transaction
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Add a step with no dependencies
+    pub fn step(&mut self, instructions: Vec<RawInstruction>) -> StepId {
+        self.steps.push(Step {
+            instructions,
+            after: Vec::new(),
+        });
+        self.steps.len() - 1
+    }
+
+    /// Add a step that must run after every step in `after`
+    pub fn step_after(&mut self, instructions: Vec<RawInstruction>, after: &[StepId]) -> Result<StepId> {
+        if let Some(unknown) = after.iter().find(|id| **id >= self.steps.len()) {
+            return Err(TxAsmError::InvalidInstruction(format!(
+                "Unknown workflow step {}",
+                unknown
+            )));
+        }
+        self.steps.push(Step {
+            instructions,
+            after: after.to_vec(),
+        });
+        Ok(self.steps.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Pack the steps into unsigned transactions in submission order
+    ///
+    /// Dependencies can only point at earlier steps, so step order is already
+    /// a valid execution order; a dependency is satisfied either by running
+    /// later in the same transaction or by landing in a later transaction.
+    pub fn build(&self, recent_blockhash: [u8; 32]) -> Result<Vec<WorkflowTransaction>> {
+        let mut packed: Vec<WorkflowTransaction> = Vec::new();
+        let mut placement: Vec<usize> = Vec::with_capacity(self.steps.len());
+        let mut current: Vec<StepId> = Vec::new();
+        let mut current_tx: Option<CompiledTransaction> = None;
+
+        for id in 0..self.steps.len() {
+            let mut candidate = current.clone();
+            candidate.push(id);
+            match self.compile(&candidate, recent_blockhash)? {
+                Some(tx) => {
+                    current = candidate;
+                    current_tx = Some(tx);
+                }
+                None if !current.is_empty() => {
+                    self.flush(&mut packed, &mut current, &mut current_tx, &placement);
+                    current_tx = self.compile(&[id], recent_blockhash)?;
+                    if current_tx.is_none() {
+                        return Err(self.oversized(id));
+                    }
+                    current.push(id);
+                }
+                None => return Err(self.oversized(id)),
+            }
+            placement.push(packed.len());
+        }
+        self.flush(&mut packed, &mut current, &mut current_tx, &placement);
+        Ok(packed)
+    }
+
+    fn flush(
+        &self,
+        packed: &mut Vec<WorkflowTransaction>,
+        current: &mut Vec<StepId>,
+        current_tx: &mut Option<CompiledTransaction>,
+        placement: &[usize],
+    ) {
+        let Some(transaction) = current_tx.take() else {
+            return;
+        };
+        let index = packed.len();
+        let mut depends_on: Vec<usize> = current
+            .iter()
+            .flat_map(|id| self.steps[*id].after.iter())
+            .map(|dep| placement[*dep])
+            .filter(|tx| *tx != index)
+            .collect();
+        depends_on.sort_unstable();
+        depends_on.dedup();
+        packed.push(WorkflowTransaction {
+            steps: std::mem::take(current),
+            depends_on,
+            transaction,
+        });
+    }
+
+    /// Compile `steps` into one transaction, or `None` if it is too large
+    fn compile(&self, steps: &[StepId], recent_blockhash: [u8; 32]) -> Result<Option<CompiledTransaction>> {
+        let mut builder = TransactionBuilder::new()
+            .payer(self.payer)
+            .recent_blockhash(recent_blockhash);
+        if let Some(price) = self.compute_unit_price {
+            builder = builder.add_instruction(RawInstruction::new(
+                COMPUTE_BUDGET_PROGRAM_ID,
+                vec![],
+                compute_budget::create_compute_unit_price_instruction(price),
+            ));
+        }
+        for id in steps {
+            builder = builder.add_instructions(self.steps[*id].instructions.clone());
+        }
+        match builder.build_unsigned() {
+            Ok(tx) if tx.size() <= self.max_transaction_size => Ok(Some(tx)),
+            Ok(_) | Err(TxAsmError::AccountIndexOverflow { .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn oversized(&self, id: StepId) -> TxAsmError {
+        TxAsmError::InvalidTransaction(format!(
+            "Workflow step {} does not fit in a {} byte transaction",
+            id, self.max_transaction_size
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::InstructionEncoder;
+
+    fn ix(tag: u8, data_len: usize) -> RawInstruction {
+        InstructionEncoder::new([2u8; 32])
+            .writable([10 + tag; 32], false)
+            .append_data(&vec![tag; data_len])
+            .build()
+    }
+
+    #[test]
+    fn test_packs_small_steps_together() {
+        let mut workflow = Workflow::new([1u8; 32]).compute_unit_price(1_000);
+        let create = workflow.step(vec![ix(1, 10)]);
+        workflow.step_after(vec![ix(2, 10)], &[create]).unwrap();
+
+        let txs = workflow.build([3u8; 32]).unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].steps, vec![0, 1]);
+        assert_eq!(txs[0].transaction.message.instructions.len(), 3);
+        assert!(workflow.step_after(vec![], &[7]).is_err());
+    }
+
+    #[test]
+    fn test_splits_on_size_and_tracks_dependencies() {
+        let mut workflow = Workflow::new([1u8; 32]);
+        let a = workflow.step(vec![ix(1, 600)]);
+        let b = workflow.step(vec![ix(2, 600)]);
+        workflow.step_after(vec![ix(3, 600)], &[a, b]).unwrap();
+
+        let txs = workflow.build([3u8; 32]).unwrap();
+        assert_eq!(txs.len(), 3);
+        assert_eq!(txs[2].steps, vec![2]);
+        assert_eq!(txs[2].depends_on, vec![0, 1]);
+        assert!(txs.iter().all(|t| t.transaction.size() <= MAX_TRANSACTION_SIZE));
+
+        workflow.step(vec![ix(4, 1300)]);
+        assert!(workflow.build([3u8; 32]).is_err());
+    }
+}
@@ -0,0 +1,358 @@
+//! Yellowstone gRPC transaction stream adapter
+//!
+//! Converts Geyser `SubscribeUpdateTransaction` updates into
+//! [`CompiledTransaction`]s plus slot and status metadata, so analysis code
+//! written against txasm types runs unchanged on live firehose data.
+//!
+//! [`pb`] mirrors the subset of Yellowstone's `geyser.proto` and
+//! `solana-storage.proto` the adapter reads, with upstream field numbers, so
+//! raw update bytes decode directly and unknown fields are skipped. Updates
+//! received as `yellowstone-grpc-proto` types can be passed to
+//! [`decode_update`] via their `encode_to_vec()` bytes.
+//!
+//! Enabled with the `yellowstone` feature.
+
+use crate::convert::v0_to_legacy;
+use crate::error::{Result, TxAsmError};
+use crate::proto::{byte, fixed};
+use crate::transaction::{CompiledInstruction, CompiledMessage, CompiledTransaction, MessageHeader};
+use crate::versioned::{CompiledMessageV0, CompiledTransactionV0, LoadedAddresses, MessageAddressTableLookup};
+use prost::Message as _;
+
+/// Subset of the Yellowstone protobuf messages
+pub mod pb {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdateTransaction {
+        #[prost(message, optional, tag = "1")]
+        pub transaction: Option<SubscribeUpdateTransactionInfo>,
+        #[prost(uint64, tag = "2")]
+        pub slot: u64,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdateTransactionInfo {
+        #[prost(bytes = "vec", tag = "1")]
+        pub signature: Vec<u8>,
+        #[prost(bool, tag = "2")]
+        pub is_vote: bool,
+        #[prost(message, optional, tag = "3")]
+        pub transaction: Option<Transaction>,
+        #[prost(message, optional, tag = "4")]
+        pub meta: Option<TransactionStatusMeta>,
+        #[prost(uint64, tag = "5")]
+        pub index: u64,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Transaction {
+        #[prost(bytes = "vec", repeated, tag = "1")]
+        pub signatures: Vec<Vec<u8>>,
+        #[prost(message, optional, tag = "2")]
+        pub message: Option<Message>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Message {
+        #[prost(message, optional, tag = "1")]
+        pub header: Option<MessageHeader>,
+        #[prost(bytes = "vec", repeated, tag = "2")]
+        pub account_keys: Vec<Vec<u8>>,
+        #[prost(bytes = "vec", tag = "3")]
+        pub recent_blockhash: Vec<u8>,
+        #[prost(message, repeated, tag = "4")]
+        pub instructions: Vec<CompiledInstruction>,
+        #[prost(bool, tag = "5")]
+        pub versioned: bool,
+        #[prost(message, repeated, tag = "6")]
+        pub address_table_lookups: Vec<MessageAddressTableLookup>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MessageHeader {
+        #[prost(uint32, tag = "1")]
+        pub num_required_signatures: u32,
+        #[prost(uint32, tag = "2")]
+        pub num_readonly_signed_accounts: u32,
+        #[prost(uint32, tag = "3")]
+        pub num_readonly_unsigned_accounts: u32,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CompiledInstruction {
+        #[prost(uint32, tag = "1")]
+        pub program_id_index: u32,
+        #[prost(bytes = "vec", tag = "2")]
+        pub accounts: Vec<u8>,
+        #[prost(bytes = "vec", tag = "3")]
+        pub data: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MessageAddressTableLookup {
+        #[prost(bytes = "vec", tag = "1")]
+        pub account_key: Vec<u8>,
+        #[prost(bytes = "vec", tag = "2")]
+        pub writable_indexes: Vec<u8>,
+        #[prost(bytes = "vec", tag = "3")]
+        pub readonly_indexes: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TransactionStatusMeta {
+        #[prost(message, optional, tag = "1")]
+        pub err: Option<TransactionError>,
+        #[prost(uint64, tag = "2")]
+        pub fee: u64,
+        #[prost(string, repeated, tag = "6")]
+        pub log_messages: Vec<String>,
+        #[prost(bytes = "vec", repeated, tag = "12")]
+        pub loaded_writable_addresses: Vec<Vec<u8>>,
+        #[prost(bytes = "vec", repeated, tag = "13")]
+        pub loaded_readonly_addresses: Vec<Vec<u8>>,
+        #[prost(uint64, optional, tag = "16")]
+        pub compute_units_consumed: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TransactionError {
+        /// bincode-encoded `TransactionError`
+        #[prost(bytes = "vec", tag = "1")]
+        pub err: Vec<u8>,
+    }
+}
+
+/// Execution status reported alongside a streamed transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamedMeta {
+    /// bincode-encoded `TransactionError`, when the transaction failed
+    pub err: Option<Vec<u8>>,
+    pub fee: u64,
+    pub compute_units_consumed: Option<u64>,
+    pub log_messages: Vec<String>,
+    pub loaded_addresses: LoadedAddresses,
+}
+
+impl StreamedMeta {
+    pub fn succeeded(&self) -> bool {
+        self.err.is_none()
+    }
+}
+
+/// One transaction from the stream
+#[derive(Debug, Clone)]
+pub struct StreamedTransaction {
+    pub slot: u64,
+    /// Position within the block
+    pub index: u64,
+    pub signature: [u8; 64],
+    pub is_vote: bool,
+    /// Legacy form; version 0 lookups are resolved from the meta's loaded
+    /// addresses, so signatures over the original message are not carried
+    pub transaction: CompiledTransaction,
+    /// The transaction as sent, for version 0 messages
+    pub versioned: Option<CompiledTransactionV0>,
+    pub meta: Option<StreamedMeta>,
+}
+
+/// Decode a raw `SubscribeUpdateTransaction` protobuf payload
+pub fn decode_update(bytes: &[u8]) -> Result<StreamedTransaction> {
+    pb::SubscribeUpdateTransaction::decode(bytes)
+        .map_err(|e| TxAsmError::DecodingError(e.to_string()))?
+        .try_into()
+}
+
+impl TryFrom<pb::SubscribeUpdateTransaction> for StreamedTransaction {
+    type Error = TxAsmError;
+
+    fn try_from(update: pb::SubscribeUpdateTransaction) -> Result<Self> {
+        let info = update
+            .transaction
+            .ok_or_else(|| missing("SubscribeUpdateTransaction.transaction"))?;
+        let tx = info.transaction.ok_or_else(|| missing("SubscribeUpdateTransactionInfo.transaction"))?;
+        let meta = info.meta.map(StreamedMeta::try_from).transpose()?;
+
+        let signatures = tx
+            .signatures
+            .iter()
+            .map(|s| fixed("signature", s))
+            .collect::<Result<Vec<[u8; 64]>>>()?;
+        let message = tx.message.ok_or_else(|| missing("Transaction.message"))?;
+        let header = message.header.as_ref().ok_or_else(|| missing("Message.header"))?;
+        let header = MessageHeader::new(
+            byte("num_required_signatures", header.num_required_signatures)?,
+            byte("num_readonly_signed_accounts", header.num_readonly_signed_accounts)?,
+            byte("num_readonly_unsigned_accounts", header.num_readonly_unsigned_accounts)?,
+        );
+        let account_keys = message
+            .account_keys
+            .iter()
+            .map(|k| fixed("account key", k))
+            .collect::<Result<_>>()?;
+        let recent_blockhash = fixed("recent_blockhash", &message.recent_blockhash)?;
+        let instructions = message
+            .instructions
+            .into_iter()
+            .map(|ix| {
+                Ok(CompiledInstruction {
+                    program_id_index: byte("program_id_index", ix.program_id_index)?,
+                    account_indices: ix.accounts.into(),
+                    data: ix.data,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let (transaction, versioned) = if message.versioned {
+            let address_table_lookups = message
+                .address_table_lookups
+                .into_iter()
+                .map(|lookup| {
+                    Ok(MessageAddressTableLookup {
+                        account_key: fixed("lookup account_key", &lookup.account_key)?,
+                        writable_indexes: lookup.writable_indexes,
+                        readonly_indexes: lookup.readonly_indexes,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            let v0 = CompiledTransactionV0 {
+                message: CompiledMessageV0 {
+                    header,
+                    account_keys,
+                    recent_blockhash,
+                    instructions,
+                    address_table_lookups,
+                },
+                signatures,
+            };
+            let loaded = meta
+                .as_ref()
+                .map(|meta| &meta.loaded_addresses)
+                .ok_or_else(|| missing("meta (loaded addresses for a version 0 message)"))?;
+            let (legacy, _) = v0_to_legacy(&v0, loaded)?;
+            (legacy, Some(v0))
+        } else {
+            let legacy = CompiledTransaction {
+                message: CompiledMessage {
+                    header,
+                    account_keys,
+                    recent_blockhash,
+                    instructions,
+                },
+                signatures,
+            };
+            (legacy, None)
+        };
+
+        Ok(Self {
+            slot: update.slot,
+            index: info.index,
+            signature: fixed("signature", &info.signature)?,
+            is_vote: info.is_vote,
+            transaction,
+            versioned,
+            meta,
+        })
+    }
+}
+
+impl TryFrom<pb::TransactionStatusMeta> for StreamedMeta {
+    type Error = TxAsmError;
+
+    fn try_from(meta: pb::TransactionStatusMeta) -> Result<Self> {
+        let addresses = |keys: &[Vec<u8>]| -> Result<Vec<[u8; 32]>> {
+            keys.iter().map(|k| fixed("loaded address", k)).collect()
+        };
+        Ok(Self {
+            err: meta.err.map(|e| e.err),
+            fee: meta.fee,
+            compute_units_consumed: meta.compute_units_consumed,
+            log_messages: meta.log_messages,
+            loaded_addresses: LoadedAddresses {
+                writable: addresses(&meta.loaded_writable_addresses)?,
+                readonly: addresses(&meta.loaded_readonly_addresses)?,
+            },
+        })
+    }
+}
+
+fn missing(field: &str) -> TxAsmError {
+    TxAsmError::DecodingError(format!("Stream update is missing {}", field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(versioned: bool, meta: Option<pb::TransactionStatusMeta>) -> pb::SubscribeUpdateTransaction {
+        pb::SubscribeUpdateTransaction {
+            slot: 42,
+            transaction: Some(pb::SubscribeUpdateTransactionInfo {
+                signature: vec![5; 64],
+                is_vote: false,
+                index: 3,
+                meta,
+                transaction: Some(pb::Transaction {
+                    signatures: vec![vec![5; 64]],
+                    message: Some(pb::Message {
+                        header: Some(pb::MessageHeader {
+                            num_required_signatures: 1,
+                            num_readonly_signed_accounts: 0,
+                            num_readonly_unsigned_accounts: 1,
+                        }),
+                        account_keys: vec![vec![1; 32], vec![2; 32]],
+                        recent_blockhash: vec![3; 32],
+                        instructions: vec![pb::CompiledInstruction {
+                            program_id_index: 1,
+                            accounts: if versioned { vec![0, 2] } else { vec![0] },
+                            data: vec![9],
+                        }],
+                        versioned,
+                        address_table_lookups: if versioned {
+                            vec![pb::MessageAddressTableLookup {
+                                account_key: vec![7; 32],
+                                writable_indexes: vec![0],
+                                readonly_indexes: vec![],
+                            }]
+                        } else {
+                            vec![]
+                        },
+                    }),
+                }),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_decode_legacy_update() {
+        let meta = pb::TransactionStatusMeta {
+            fee: 5000,
+            compute_units_consumed: Some(150),
+            log_messages: vec!["Program log: hi".to_string()],
+            ..Default::default()
+        };
+        let streamed = decode_update(&update(false, Some(meta)).encode_to_vec()).unwrap();
+        assert_eq!((streamed.slot, streamed.index), (42, 3));
+        assert!(streamed.versioned.is_none());
+        assert_eq!(streamed.transaction.message.account_keys, vec![[1u8; 32], [2u8; 32]]);
+        assert_eq!(streamed.transaction.signatures, vec![[5u8; 64]]);
+        let meta = streamed.meta.unwrap();
+        assert!(meta.succeeded());
+        assert_eq!((meta.fee, meta.compute_units_consumed), (5000, Some(150)));
+    }
+
+    #[test]
+    fn test_decode_v0_update_resolves_lookups() {
+        let meta = pb::TransactionStatusMeta {
+            err: Some(pb::TransactionError { err: vec![1, 0, 0, 0] }),
+            loaded_writable_addresses: vec![vec![8; 32]],
+            ..Default::default()
+        };
+        let streamed: StreamedTransaction = update(true, Some(meta)).try_into().unwrap();
+        assert_eq!(streamed.versioned.unwrap().message.address_table_lookups.len(), 1);
+        let message = &streamed.transaction.message;
+        let loaded = message.account_keys.iter().position(|k| *k == [8u8; 32]).unwrap();
+        assert!(message.is_writable(loaded));
+        assert!(!streamed.meta.unwrap().succeeded());
+
+        assert!(StreamedTransaction::try_from(update(true, None)).is_err());
+    }
+}